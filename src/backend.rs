@@ -1,16 +1,26 @@
-use crate::history::History;
+use crate::clock::{Clock, SystemClock};
+use crate::history::{History, HistoryRecord};
+use crate::hooks::{run as run_hook, HookEvent};
+use crate::i18n::tr;
+use crate::import::TogglEntry;
+use crate::palette::Palette;
+use crate::profiles::data_dir;
+use crate::settings::{Settings, WindowState};
+use crate::storage::{
+    new_storage, open_storage, sorted_map, write_atomically, RonStorage, Storage, StorageKind,
+};
+use crate::util::{round_duration, start_of_day, start_of_week, RoundingMode};
 use std::cmp::Ordering;
 
-use std::collections::HashMap;
-use std::fs::File;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::hash::Hash;
-use std::io::{Read, Write};
 use std::path::Path;
 
 use std::sync::{Arc, Mutex};
 
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
+use chrono::{DateTime, Local, NaiveDate};
 use rand::{thread_rng, Rng};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
@@ -26,15 +36,44 @@ pub enum WorkingMode {
 pub struct WorkingProgress {
     subject: Arc<Mutex<Subject>>,
     session_id: Uuid,
-    previous_tick: SystemTime,
+    /// Monotonic time of the previous tick, used to accumulate durations.
+    previous_tick: Instant,
+    /// Wall clock time of the previous tick, used for record timestamps and suspend detection.
+    previous_wall_tick: SystemTime,
+    started_at: DateTime<Local>,
+    /// The start hook ran, which waits until the session is long enough to be kept.
+    announced: bool,
 }
 
 impl WorkingProgress {
-    fn start(subject: Arc<Mutex<Subject>>, session_id: Uuid) -> Self {
+    fn start(subject: Arc<Mutex<Subject>>, session_id: Uuid, clock: &dyn Clock) -> Self {
         Self {
             subject,
             session_id,
-            previous_tick: SystemTime::now(),
+            previous_tick: clock.instant(),
+            previous_wall_tick: clock.now(),
+            started_at: clock.local_now(),
+            announced: false,
+        }
+    }
+}
+
+/// Phase of the pomodoro cycle together with the moment it ends.
+#[derive(Default, Copy, Clone, PartialEq)]
+pub enum PomodoroState {
+    #[default]
+    Off,
+    Work(Instant),
+    Break(Instant),
+}
+
+impl PomodoroState {
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        match self {
+            PomodoroState::Off => None,
+            PomodoroState::Work(ends_at) | PomodoroState::Break(ends_at) => {
+                Some(ends_at.saturating_duration_since(now))
+            }
         }
     }
 }
@@ -43,15 +82,223 @@ pub trait PreferVariant {
     fn get_prefer() -> Self;
 }
 
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug, Default)]
+pub enum GoalPeriod {
+    #[default]
+    Day,
+    Week,
+}
+
+/// Target amount of tracked time per day or week.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq)]
+pub struct Goal {
+    pub(crate) period: GoalPeriod,
+    pub(crate) minutes: u32,
+}
+
+impl Goal {
+    /// What the goal adds up to over a span of `days` days.
+    pub fn for_days(&self, days: i64) -> chrono::Duration {
+        let minutes = self.minutes as i64 * days;
+
+        match self.period {
+            GoalPeriod::Day => chrono::Duration::minutes(minutes),
+            GoalPeriod::Week => chrono::Duration::minutes(minutes / 7),
+        }
+    }
+}
+
+/// How each session is rounded before it is billed.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum BillingRounding {
+    #[default]
+    None,
+    UpTo5Minutes,
+    UpTo15Minutes,
+}
+
+impl BillingRounding {
+    pub const ALL: [BillingRounding; 3] = [
+        BillingRounding::None,
+        BillingRounding::UpTo5Minutes,
+        BillingRounding::UpTo15Minutes,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BillingRounding::None => tr!("Exact"),
+            BillingRounding::UpTo5Minutes => tr!("Up to 5 min"),
+            BillingRounding::UpTo15Minutes => tr!("Up to 15 min"),
+        }
+    }
+
+    fn apply(self, duration: chrono::Duration) -> chrono::Duration {
+        let minutes = match self {
+            BillingRounding::None => 0,
+            BillingRounding::UpTo5Minutes => 5,
+            BillingRounding::UpTo15Minutes => 15,
+        };
+
+        round_duration(duration, RoundingMode { minutes, up: true })
+    }
+}
+
+/// Billed time of one project within a range, `amount` is `None` without a rate.
+pub struct BillingLine {
+    pub(crate) name: String,
+    pub(crate) duration: chrono::Duration,
+    pub(crate) rate: Option<f64>,
+    pub(crate) currency: String,
+    pub(crate) amount: Option<f64>,
+}
+
+/// Billing lines sorted by name and the grand total of every currency.
+pub struct Billing {
+    pub(crate) lines: Vec<BillingLine>,
+    pub(crate) totals: Vec<(String, f64)>,
+}
+
+/// What a column row shows of a container, without its children.
+pub struct ContainerRow<K> {
+    pub(crate) id: K,
+    pub(crate) name: String,
+    pub(crate) is_deleted: bool,
+    pub(crate) color: (u8, u8, u8),
+    pub(crate) goal: Option<Goal>,
+    pub(crate) is_archived: bool,
+}
+
+/// Deleted entry as listed in the trash.
+pub struct TrashEntry {
+    pub(crate) path: EntryPath,
+    /// Names from the project down to the entry.
+    pub(crate) label: String,
+    pub(crate) deleted_at: Option<SystemTime>,
+}
+
+/// Inconsistency in the data, left behind by hand edits or old bugs.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Issue {
+    /// Record pointing at a project, sub project or subject that does not exist.
+    MissingEntry { record: Uuid },
+    /// Record ending before it starts.
+    NegativeRecord { record: Uuid },
+    /// Container whose selection is a missing or deleted entry, `None` for the chain itself.
+    DanglingSelection {
+        tree: Tree,
+        container: Option<EntryPath>,
+    },
+    /// Subject whose duration is not the sum of its records.
+    DurationMismatch {
+        path: EntryPath,
+        stored: Duration,
+        history: Duration,
+    },
+}
+
+/// Where records pointing at missing entries are moved to keep their time.
+const RECOVERED_PATH: [&str; 3] = ["Recovered", "Recovered", "Missing subject"];
+
+/// Live ticks and record ends drift apart by milliseconds, only more counts as a mismatch.
+const DURATION_TOLERANCE: Duration = Duration::from_secs(60);
+
+/// Which of the two chains an entry belongs to.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Tree {
+    Time,
+    Todo,
+}
+
+/// Ids leading to a project, sub project or subject within a chain.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum EntryPath {
+    Project(Uuid),
+    SubProject(Uuid, Uuid),
+    Subject(Uuid, Uuid, Uuid),
+}
+
+impl EntryPath {
+    pub fn id(&self) -> Uuid {
+        match *self {
+            EntryPath::Project(id)
+            | EntryPath::SubProject(_, id)
+            | EntryPath::Subject(_, _, id) => id,
+        }
+    }
+
+    pub fn parent(&self) -> Option<EntryPath> {
+        match *self {
+            EntryPath::Project(_) => None,
+            EntryPath::SubProject(p, _) => Some(EntryPath::Project(p)),
+            EntryPath::Subject(p, s, _) => Some(EntryPath::SubProject(p, s)),
+        }
+    }
+
+    /// Where this entry, found inside an entry being moved, ends up once that one goes under
+    /// `parent`.
+    fn rebase(self, parent: EntryPath) -> EntryPath {
+        match (self, parent) {
+            (EntryPath::SubProject(_, s), EntryPath::Project(p)) => EntryPath::SubProject(p, s),
+            (EntryPath::Subject(_, s, x), EntryPath::Project(p)) => EntryPath::Subject(p, s, x),
+            (EntryPath::Subject(_, _, x), EntryPath::SubProject(p, s)) => {
+                EntryPath::Subject(p, s, x)
+            }
+            (path, _) => path,
+        }
+    }
+
+    pub fn contains(&self, other: &EntryPath) -> bool {
+        let mut path = Some(*other);
+
+        while let Some(v) = path {
+            if v == *self {
+                return true;
+            }
+
+            path = v.parent();
+        }
+
+        false
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(bound(serialize = "T: Serialize, K: Serialize + Ord"))]
 pub struct PContainer<T, K: Eq + Hash> {
     pub(crate) id: K,
     pub(crate) name: String,
     pub(crate) created_at: SystemTime,
     pub(crate) is_deleted: bool,
+    /// When it went to the trash, `None` for entries deleted before this was kept.
+    #[serde(default)]
+    pub(crate) deleted_at: Option<SystemTime>,
     pub(crate) color: (u8, u8, u8),
+    #[serde(serialize_with = "sorted_map")]
     pub(crate) inner: HashMap<K, T>,
     pub(crate) current_inner_id: Option<K>,
+    #[serde(default)]
+    pub(crate) goal: Option<Goal>,
+    /// Hidden from the columns and not selectable, but still part of statistics.
+    #[serde(default)]
+    pub(crate) is_archived: bool,
+    /// Per hour, only used by the billing report of time projects.
+    #[serde(default)]
+    pub(crate) hourly_rate: Option<f64>,
+    #[serde(default)]
+    pub(crate) currency: String,
+    /// Timeline color of a time sub project instead of its project's, unused elsewhere.
+    #[serde(default)]
+    pub(crate) color_override: Option<(u8, u8, u8)>,
+}
+
+impl<T: Clone, K: Eq + Hash + Clone> PContainer<T, K> {
+    /// The entry without its children, for storage that keeps each level apart.
+    pub(crate) fn without_inner(&self) -> Self {
+        Self {
+            inner: HashMap::new(),
+            ..self.clone()
+        }
+    }
 }
 
 impl<
@@ -66,19 +313,36 @@ impl<
             name: name.to_string(),
             created_at: SystemTime::now(),
             is_deleted: false,
+            deleted_at: None,
             color: (rng.gen(), rng.gen(), rng.gen()),
             inner: HashMap::new(),
             current_inner_id: None,
+            goal: None,
+            is_archived: false,
+            hourly_rate: None,
+            currency: String::new(),
+            color_override: None,
+        }
+    }
+
+    pub(crate) fn row(&self) -> ContainerRow<K> {
+        ContainerRow {
+            id: self.id,
+            name: self.name.clone(),
+            is_deleted: self.is_deleted,
+            color: self.color,
+            goal: self.goal,
+            is_archived: self.is_archived,
         }
     }
 
-    pub(crate) fn get_inner_sorted<F>(&self, sort_f: F) -> Vec<T>
+    pub(crate) fn get_inner_sorted<F>(&self, mut sort_f: F) -> Vec<&T>
     where
         F: FnMut(&T, &T) -> Ordering,
     {
-        let mut c: Vec<T> = self.inner.values().cloned().collect();
+        let mut c: Vec<&T> = self.inner.values().collect();
 
-        c.sort_by(sort_f);
+        c.sort_by(|a, b| sort_f(a, b));
 
         c
     }
@@ -122,10 +386,111 @@ impl PreferVariant for IdType {
     }
 }
 
-pub type ProjectChain =
-    PContainer<PContainer<PContainer<Arc<Mutex<Subject>>, IdType>, IdType>, IdType>;
-pub type TodoChain =
-    PContainer<PContainer<PContainer<Arc<Mutex<TodoSubject>>, IdType>, IdType>, IdType>;
+pub type SubProject = PContainer<Arc<Mutex<Subject>>, IdType>;
+pub type Project = PContainer<SubProject, IdType>;
+pub type ProjectChain = PContainer<Project, IdType>;
+pub type TodoSubProject = PContainer<Arc<Mutex<TodoSubject>>, IdType>;
+pub type TodoProject = PContainer<TodoSubProject, IdType>;
+pub type TodoChain = PContainer<TodoProject, IdType>;
+
+/// Changes made within this long of the previous save are written together.
+const DIRTY_SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// The wall clock moving this much further than the monotonic one between two ticks means
+/// the machine was suspended with the timer running.
+const SUSPEND_GAP: Duration = Duration::from_secs(2 * 60);
+
+/// Records ending this far in the future mean the system clock was set back.
+const CLOCK_TOLERANCE: Duration = Duration::from_secs(5 * 60);
+
+/// The session counter carries over a restart only when the last record ended this recently.
+const SESSION_CARRY_GAP: Duration = Duration::from_secs(5 * 60);
+
+/// Subject breaks are tracked under when pomodoro breaks are tracked.
+const BREAK_SUBJECT: &str = "Break";
+
+pub const MISSING_COLOR: (u8, u8, u8) = (128, 128, 128);
+
+/// Time tracked today per project, sub project and subject.
+#[derive(Default)]
+pub struct TodayTimes {
+    pub(crate) projects: HashMap<Uuid, Duration>,
+    pub(crate) sub_projects: HashMap<Uuid, Duration>,
+    pub(crate) subjects: HashMap<Uuid, Duration>,
+}
+
+/// Outcome of an import as shown before running it.
+#[derive(Default)]
+pub struct ImportPreview {
+    pub(crate) entries: usize,
+    pub(crate) duplicates: usize,
+    /// Entries of archived projects, which are left alone.
+    pub(crate) archived: usize,
+    pub(crate) projects: usize,
+    pub(crate) sub_projects: usize,
+    pub(crate) subjects: usize,
+}
+
+/// Display names of the chain a history record points at, with placeholders for removed entries.
+pub struct RecordPath {
+    pub(crate) project: String,
+    pub(crate) sub_project: String,
+    pub(crate) subject: String,
+    pub(crate) color: (u8, u8, u8),
+}
+
+/// Whether a session started at `now` continues the one that ended at `ended`, instead of
+/// the session counter starting over.
+fn continues_session(ended: Option<DateTime<Local>>, now: DateTime<Local>) -> bool {
+    ended.is_some_and(|v| {
+        now.signed_duration_since(v)
+            .to_std()
+            .is_ok_and(|v| v < SESSION_CARRY_GAP)
+    })
+}
+
+/// Names and color of what `record` was tracked on, placeholders for entries removed since.
+pub fn record_path(projects: &ProjectChain, record: &HistoryRecord) -> RecordPath {
+    let project = projects.inner.get(&record.project_id);
+    let sub_project = project.and_then(|v| v.inner.get(&record.sub_project_id));
+    let subject = sub_project.and_then(|v| v.inner.get(&record.subject_id));
+
+    RecordPath {
+        project: project.map_or_else(|| tr!("<deleted project>").to_string(), |v| v.name.clone()),
+        sub_project: sub_project.map_or_else(
+            || tr!("<deleted sub project>").to_string(),
+            |v| v.name.clone(),
+        ),
+        subject: subject.map_or_else(
+            || tr!("<deleted subject>").to_string(),
+            |v| v.lock().unwrap().name.clone(),
+        ),
+        color: most_specific_color(project, sub_project, subject),
+    }
+}
+
+/// Colors of the live projects of a chain.
+fn chain_colors<T>(chain: &PContainer<PContainer<T, IdType>, IdType>) -> Vec<(u8, u8, u8)> {
+    chain
+        .inner
+        .values()
+        .filter(|v| !v.is_deleted)
+        .map(|v| v.color)
+        .collect()
+}
+
+/// Timeline color of a subject, sub project or project, the first of them that has one.
+fn most_specific_color(
+    project: Option<&Project>,
+    sub_project: Option<&SubProject>,
+    subject: Option<&Arc<Mutex<Subject>>>,
+) -> (u8, u8, u8) {
+    subject
+        .and_then(|v| v.lock().unwrap().color)
+        .or_else(|| sub_project.and_then(|v| v.color_override))
+        .or_else(|| project.map(|v| v.color))
+        .unwrap_or(MISSING_COLOR)
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct Backend {
@@ -134,33 +499,154 @@ pub struct Backend {
     #[serde(skip)]
     pub(crate) working_mode: WorkingMode,
     #[serde(skip)]
+    pub(crate) pomodoro: PomodoroState,
+    #[serde(skip)]
     pub(crate) dirty: bool,
+    /// Where the data is saved, `None` while a save is in progress.
+    #[serde(skip)]
+    storage: Option<Box<dyn Storage>>,
+    /// Set while data written elsewhere waits to be reconciled with this, so saving does not
+    /// overwrite it.
+    #[serde(skip)]
+    pub(crate) saves_held: bool,
+    /// Tracked time per project and sub project id, `None` when it needs recomputing.
+    #[serde(skip)]
+    totals: Option<HashMap<Uuid, Duration>>,
+    /// Open and total live todos per todo project and sub project, `None` when it needs
+    /// recomputing.
+    #[serde(skip)]
+    todo_counts: Option<HashMap<Uuid, (usize, usize)>>,
+    /// Set when a session was cut at its last tick because the machine was suspended.
+    #[serde(skip)]
+    pub(crate) interrupted_at: Option<DateTime<Local>>,
+    /// Why the latest save failed, until one succeeds.
+    #[serde(skip)]
+    pub(crate) save_error: Option<String>,
+    /// How far the clock is behind the newest record, when it is behind at all.
+    #[serde(skip)]
+    pub(crate) clock_behind: Option<chrono::Duration>,
     pub(crate) current_session_duration: Duration,
     pub(crate) last_session_subject_id: Uuid,
-    last_save: SystemTime,
+    pub(crate) last_save: SystemTime,
     pub(crate) history: History,
+    #[serde(default)]
+    pub(crate) scratchpad: String,
+    /// Day the notes were last seen on, they move to the journal once it is over.
+    #[serde(default)]
+    pub(crate) scratchpad_day: Option<NaiveDate>,
+    /// Notes of past days, by the day they were written on.
+    #[serde(default)]
+    pub(crate) journal: BTreeMap<NaiveDate, String>,
+    /// Day the end of day summary was last shown on, it shows at most once a day.
+    #[serde(default)]
+    pub(crate) end_of_day_shown: Option<NaiveDate>,
+    /// Record of a session started from the command line. Nothing ticks it, its end is set
+    /// when it stops or when the window takes it over.
+    #[serde(default)]
+    pub(crate) detached_session: Option<Uuid>,
+    #[serde(default)]
+    pub(crate) settings: Settings,
+    #[serde(default)]
+    pub(crate) window_state: WindowState,
+    #[serde(skip, default = "system_clock")]
+    clock: Box<dyn Clock>,
+}
+
+fn system_clock() -> Box<dyn Clock> {
+    Box::new(SystemClock)
 }
 
 impl Backend {
-    pub fn load() -> Self {
-        let config = Path::new("./data.ron");
-
-        if config.exists() {
-            if let Ok(mut file) = File::open("./data.ron") {
-                let mut contents = String::new();
-                if file.read_to_string(&mut contents).is_ok() {
-                    if let Ok(data) = ron::from_str::<Backend>(&contents) {
-                        return data;
-                    }
-                }
+    /// Loads the data of the profile in use, empty when there is none yet. Fails when the
+    /// data exists and cannot be read, so nothing saves empty data over it.
+    pub fn try_load() -> Result<Self, String> {
+        Self::load_from(open_storage()?)
+    }
+
+    /// Loads an encrypted data file, failing with
+    /// [`WRONG_PASSPHRASE`](crate::crypto::WRONG_PASSPHRASE) when `passphrase` does not open it.
+    pub fn unlock(passphrase: &str) -> Result<Self, String> {
+        Self::load_from(Box::new(RonStorage::with_passphrase(
+            &data_dir(),
+            passphrase,
+        )))
+    }
+
+    pub(crate) fn load_from(mut storage: Box<dyn Storage>) -> Result<Self, String> {
+        let mut backend = storage.load()?.unwrap_or_default();
+
+        if let Some(store) = storage.history_store() {
+            backend.history.open_archive(store);
+        }
+
+        backend.storage = Some(storage);
+
+        // Enough for today, the current week and the last days.
+        let today = backend.now().date_naive();
+        let week_start = start_of_week(today, backend.settings.first_day_of_week);
+
+        backend
+            .history
+            .load_range(week_start.min(today - chrono::Duration::days(7)), today);
+
+        // A session started from the command line may have started long ago.
+        if let Some(id) = backend.detached_session {
+            if backend.history.get(&id).is_none() {
+                backend.history.load_all();
             }
         }
 
-        Self::default()
+        if !continues_session(backend.history.latest_end(), backend.now()) {
+            backend.current_session_duration = Duration::ZERO;
+        }
+
+        Ok(backend)
+    }
+
+    /// The time by the clock of the backend.
+    pub fn now(&self) -> DateTime<Local> {
+        self.clock.local_now()
+    }
+
+    pub fn pomodoro_remaining(&self) -> Option<Duration> {
+        self.pomodoro.remaining(self.clock.instant())
     }
 
     pub fn dirty(&mut self) {
         self.dirty = true;
+        self.totals = None;
+        self.todo_counts = None;
+    }
+
+    /// Moves the notes of a day that is over to the end of its journal entry and clears
+    /// them, when daily notes are on.
+    pub fn roll_scratchpad(&mut self) {
+        let today = self.clock.local_now().date_naive();
+
+        let Some(day) = self.scratchpad_day.replace(today) else {
+            self.dirty();
+
+            return;
+        };
+
+        if day == today {
+            return;
+        }
+
+        self.dirty();
+
+        if day > today || !self.settings.daily_notes || self.scratchpad.trim().is_empty() {
+            return;
+        }
+
+        let notes = std::mem::take(&mut self.scratchpad);
+        let entry = self.journal.entry(day).or_default();
+
+        if !entry.is_empty() {
+            entry.push('\n');
+        }
+
+        entry.push_str(notes.trim_end());
     }
 
     pub fn get_current_subject(&self) -> Option<Arc<Mutex<Subject>>> {
@@ -175,17 +661,11 @@ impl Backend {
         None
     }
 
-    pub fn get_current_sub_project(&self) -> Option<&PContainer<Arc<Mutex<Subject>>, IdType>> {
-        let Some(current_project) = self.projects.get_current() else {
-            return None;
-        };
-
-        current_project.get_current()
+    pub fn get_current_sub_project(&self) -> Option<&SubProject> {
+        self.projects.get_current()?.get_current()
     }
 
-    pub fn get_current_project(
-        &self,
-    ) -> Option<&PContainer<PContainer<Arc<Mutex<Subject>>, IdType>, IdType>> {
+    pub fn get_current_project(&self) -> Option<&Project> {
         self.projects.get_current()
     }
 
@@ -209,35 +689,40 @@ impl Backend {
         current_project.set_current(sub_project_key);
     }
 
+    /// Archived projects cannot be selected for tracking.
     pub fn set_current_project(&mut self, project_key: Option<Uuid>) {
-        self.projects.set_current(project_key)
-    }
-
-    pub fn get_current_todo_subject(&self) -> Option<Arc<Mutex<TodoSubject>>> {
-        if let Some(project) = self.todos.get_current() {
-            if let Some(sub_project) = project.get_current() {
-                if let Some(subject) = sub_project.get_current() {
-                    return Some(subject.clone());
-                }
+        if let Some(key) = &project_key {
+            if self.projects.inner.get(key).is_some_and(|v| v.is_archived) {
+                return;
             }
         }
 
-        None
+        self.projects.set_current(project_key)
     }
 
-    pub fn get_current_todo_sub_project(
-        &self,
-    ) -> Option<&PContainer<Arc<Mutex<TodoSubject>>, IdType>> {
-        let Some(current_project) = self.todos.get_current() else {
-            return None;
+    pub fn set_project_archived(&mut self, key: &Uuid, archived: bool) {
+        if archived {
+            self.stop_tracking_inside(EntryPath::Project(*key));
+        }
+
+        let Some(project) = self.projects.inner.get_mut(key) else {
+            return;
         };
 
-        current_project.get_current()
+        project.is_archived = archived;
+
+        if archived {
+            unselect(&mut self.projects, *key);
+        }
+
+        self.dirty();
     }
 
-    pub fn get_current_todo_project(
-        &self,
-    ) -> Option<&PContainer<PContainer<Arc<Mutex<TodoSubject>>, IdType>, IdType>> {
+    pub fn get_current_todo_sub_project(&self) -> Option<&TodoSubProject> {
+        self.todos.get_current()?.get_current()
+    }
+
+    pub fn get_current_todo_project(&self) -> Option<&TodoProject> {
         self.todos.get_current()
     }
 
@@ -253,269 +738,4269 @@ impl Backend {
         self.todos.set_current(project_key)
     }
 
-    pub(crate) fn dump(&mut self) {
-        let mut file = File::create("./data.ron").unwrap();
-        file.write_all(
-            ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
-                .unwrap()
-                .as_bytes(),
-        )
-        .unwrap();
-        self.last_save = SystemTime::now();
-        self.dirty = false;
+    pub(crate) fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
     }
 
-    pub fn update_time(&mut self) {
-        if let WorkingMode::InProgress(progress) = &mut self.working_mode {
-            let duration = SystemTime::now()
-                .duration_since(progress.previous_tick)
-                .unwrap();
+    fn save(&mut self) -> Result<(), String> {
+        self.last_save = self.clock.now();
 
-            progress.previous_tick = SystemTime::now();
+        self.history.write_months(self.now().date_naive());
 
-            self.current_session_duration += duration;
+        let Some(mut storage) = self.storage.take() else {
+            return Ok(());
+        };
 
-            progress.subject.lock().unwrap().duration += duration;
+        let saved = storage.save(self);
+        self.storage = Some(storage);
 
-            self.history.update(progress.session_id);
+        saved
+    }
 
-            if SystemTime::now().duration_since(self.last_save).unwrap() > Duration::from_secs(10) {
-                self.dump();
-            }
+    /// Hands a snapshot to the storage. A save failing here or on the writing thread leaves
+    /// the data dirty, so it is retried shortly, and is kept in `save_error`.
+    pub(crate) fn dump(&mut self) {
+        if self.saves_held {
+            return;
         }
 
-        if self.dirty {
-            self.dump();
+        match self.save() {
+            Ok(()) => self.dirty = false,
+            Err(e) => {
+                eprintln!("{e}");
+                self.save_error = Some(e);
+            }
         }
     }
 
-    pub fn get_project_time(&self, key: &Uuid) -> Option<Duration> {
-        if let Some(project) = self.projects.inner.get(key) {
-            return Some(
-                project
-                    .inner
-                    .values()
-                    .fold(Duration::default(), |s, inner| {
-                        s + inner
-                            .inner
-                            .values()
-                            .fold(Duration::default(), |v, iv| v + iv.lock().unwrap().duration)
-                    }),
-            );
-        }
-
-        None
-    }
+    /// Takes in how the writes handed to another thread went.
+    fn check_saves(&mut self) {
+        let Some(outcome) = self.storage.as_mut().and_then(|v| v.save_outcome()) else {
+            return;
+        };
 
-    pub fn get_sub_project_time(&self, key: &Uuid) -> Option<Duration> {
-        if let Some(project) = self.projects.get_current() {
-            if let Some(sub_project) = project.inner.get(key) {
-                return Some(
-                    sub_project
-                        .inner
-                        .values()
-                        .fold(Duration::default(), |v, iv| v + iv.lock().unwrap().duration),
-                );
+        match outcome {
+            Ok(()) => self.save_error = None,
+            Err(e) => {
+                self.save_error = Some(e);
+                self.dirty = true;
             }
         }
+    }
 
-        None
+    /// Whether there are changes the data file lacks. A running session always has some.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.dirty || matches!(self.working_mode, WorkingMode::InProgress(_))
     }
 
-    pub fn get_current_work_name(&self) -> String {
-        if let Some(project) = self.projects.get_current() {
-            if let Some(sub_project) = project.get_current() {
-                if let Some(subject) = sub_project.get_current() {
-                    return format!(
-                        " {}/{}/{}",
-                        project.name,
-                        sub_project.name,
-                        subject.lock().unwrap().name,
-                    );
-                }
-            }
+    /// Saves pending changes and waits for every write to finish.
+    pub fn flush(&mut self) {
+        if self.has_unsaved_changes() {
+            self.dump();
         }
 
-        "None".to_string()
+        if let Some(storage) = &mut self.storage {
+            storage.flush();
+        }
     }
-    pub fn add_todo_project(&mut self, name: &str) {
-        let project = PContainer::new(name);
 
-        self.todos.inner.insert(project.id, project);
-
-        self.dirty();
+    pub fn changed_outside(&mut self) -> bool {
+        self.storage.as_mut().is_some_and(|v| v.changed_outside())
     }
 
-    pub fn add_todo_sub_project(&mut self, name: &str) {
-        let Some(project) = self.todos.get_current_mut() else {
-            return;
+    /// The data as it is in the storage now, after it [changed outside](Self::changed_outside).
+    pub fn reload(&self) -> Result<Self, String> {
+        let Some(storage) = &self.storage else {
+            return Err("the data has no storage to load from".to_string());
         };
 
-        let sub_project = PContainer::new(name);
-
-        project.inner.insert(sub_project.id, sub_project);
+        Self::load_from(storage.reopen()?)
+    }
 
-        self.dirty();
+    pub fn storage_kind(&self) -> Option<StorageKind> {
+        self.storage.as_ref().map(|v| v.kind())
     }
 
-    pub fn add_todo_subject(&mut self, name: &str) {
-        let Some(project) = self.todos.get_current_mut() else {
-            return;
-        };
+    pub fn encrypted(&self) -> bool {
+        self.storage.as_ref().is_some_and(|v| v.encrypted())
+    }
 
-        let Some(sub_project) = project.get_current_mut() else {
-            return;
+    /// Encrypts the data file with `passphrase`, or decrypts it when `None`, and rewrites it
+    /// right away.
+    pub fn set_passphrase(&mut self, passphrase: Option<&str>) -> Result<(), String> {
+        let Some(storage) = &mut self.storage else {
+            return Ok(());
         };
 
-        let subject = TodoSubject::create(name);
+        storage.set_passphrase(passphrase)?;
 
-        sub_project
-            .inner
-            .insert(subject.id, Arc::new(Mutex::new(subject)));
+        self.save()?;
+        self.flush();
 
-        self.dirty();
+        Ok(())
     }
 
-    pub fn add_project(&mut self, name: &str) {
-        let project = PContainer::new(name);
+    /// Copies everything into a new storage of `kind`, which is used from then on, and moves
+    /// the data of the previous one aside.
+    pub fn convert_storage(&mut self, kind: StorageKind) -> Result<(), String> {
+        if self.storage_kind() == Some(kind) {
+            return Ok(());
+        }
 
-        self.projects.inner.insert(project.id, project);
+        if self.encrypted() {
+            return Err("turn off the encryption before converting the data".to_string());
+        }
 
-        self.dirty();
-    }
+        let mut storage = new_storage(kind)?;
 
-    pub fn add_sub_project(&mut self, name: &str) {
-        let Some(project) = self.projects.get_current_mut() else {
-            return;
+        let Some(store) = storage.history_store() else {
+            return Err(format!("cannot keep the history in the {}", kind.label()));
         };
 
-        let sub_project = PContainer::new(name);
+        self.history.load_all();
+        self.history.open_archive(store);
 
-        project.inner.insert(sub_project.id, sub_project);
+        let mut previous = self.storage.replace(storage);
 
-        self.dirty();
+        self.save()?;
+        self.flush();
+
+        if let Some(previous) = &mut previous {
+            previous.flush();
+            previous.retire()?;
+        }
+
+        Ok(())
     }
 
-    pub fn add_subject(&mut self, name: &str) {
-        let Some(project) = self.projects.get_current_mut() else {
-            return;
-        };
+    pub(crate) fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        let data = self.to_ron().map_err(std::io::Error::other)?;
 
-        let Some(sub_project) = project.get_current_mut() else {
+        write_atomically(path, &data)
+    }
+
+    /// Compares the clock against the newest record and remembers if it is behind.
+    pub fn check_clock(&mut self) {
+        let Some(latest) = self.history.latest_end() else {
             return;
         };
 
-        let subject = Subject::create(name);
+        let behind = latest.signed_duration_since(self.now());
 
-        sub_project
-            .inner
-            .insert(subject.id, Arc::new(Mutex::new(subject)));
+        if behind.to_std().is_ok_and(|v| v > CLOCK_TOLERANCE) {
+            self.clock_behind = Some(behind);
+        }
+    }
 
-        self.dirty();
+    /// Adds the time since the previous tick to the running session and its subject, and
+    /// ends its record now. Returns false, adding nothing, when the time since is a suspend.
+    fn accumulate_time(&mut self) -> bool {
+        let WorkingMode::InProgress(progress) = &mut self.working_mode else {
+            return false;
+        };
+
+        let now = self.clock.instant();
+        let wall_now = self.clock.now();
+
+        let duration = now.duration_since(progress.previous_tick);
+        let wall_gap = wall_now
+            .duration_since(progress.previous_wall_tick)
+            .unwrap_or_default();
+
+        // The monotonic clock stands still while the system sleeps, so suspends are only
+        // visible on the wall clock running ahead of it. A long gap on both only means no
+        // frame ran, like while the window is hidden, and all of it was worked.
+        if wall_gap.saturating_sub(duration) > SUSPEND_GAP {
+            return false;
+        }
+
+        progress.previous_tick = now;
+        progress.previous_wall_tick = wall_now;
+
+        if !matches!(self.pomodoro, PomodoroState::Break(_)) {
+            self.current_session_duration += duration;
+        }
+
+        progress.subject.lock().unwrap().duration += duration;
+        self.totals = None;
+
+        self.history
+            .update(progress.session_id, DateTime::from(wall_now));
+        progress.session_id = self.history.split_days(progress.session_id);
+
+        true
     }
 
-    pub fn start_subject(&mut self) {
-        let Some(project) = self.projects.get_current_mut() else {
+    pub fn update_time(&mut self) {
+        self.check_saves();
+
+        if let WorkingMode::InProgress(progress) = &self.working_mode {
+            let last_tick = DateTime::from(progress.previous_wall_tick);
+
+            if !self.accumulate_time() {
+                self.pause_at(last_tick);
+                self.interrupted_at = Some(last_tick);
+
+                return;
+            }
+
+            let WorkingMode::InProgress(progress) = &mut self.working_mode else {
+                return;
+            };
+
+            let min = chrono::Duration::seconds(self.settings.min_session_seconds as i64);
+
+            if !progress.announced
+                && DateTime::<Local>::from(progress.previous_wall_tick)
+                    .signed_duration_since(progress.started_at)
+                    >= min
+            {
+                progress.announced = true;
+
+                if let Some(record) = self.history.get(&progress.session_id) {
+                    run_hook(
+                        &self.settings.on_start_command,
+                        HookEvent::Start,
+                        &record_path(&self.projects, record),
+                    );
+                }
+            }
+
+            if self
+                .clock
+                .now()
+                .duration_since(self.last_save)
+                .unwrap_or_default()
+                > Duration::from_secs(self.settings.save_period_seconds)
+            {
+                self.dump();
+            }
+        }
+
+        if self.dirty
+            && self
+                .clock
+                .now()
+                .duration_since(self.last_save)
+                .unwrap_or_default()
+                > DIRTY_SAVE_DELAY
+        {
+            self.dump();
+        }
+    }
+
+    /// How long the UI may sleep before the next frame: short while tracking so the timer
+    /// keeps moving, long when idle.
+    pub fn repaint_period(&self) -> Duration {
+        match self.working_mode {
+            WorkingMode::InProgress(_) => Duration::from_millis(self.settings.tracking_repaint_ms),
+            WorkingMode::Idle if self.dirty => DIRTY_SAVE_DELAY,
+            WorkingMode::Idle => Duration::from_secs(self.settings.idle_repaint_seconds),
+        }
+    }
+
+    /// Recomputes project and sub project totals if anything changed since the last call.
+    pub fn refresh_totals(&mut self) {
+        if self.totals.is_some() {
+            return;
+        }
+
+        let mut totals = HashMap::new();
+
+        for project in self.projects.inner.values() {
+            let mut project_total = Duration::default();
+
+            for sub_project in project.inner.values() {
+                let total = sub_project
+                    .inner
+                    .values()
+                    .fold(Duration::default(), |v, iv| v + iv.lock().unwrap().duration);
+
+                project_total += total;
+                totals.insert(sub_project.id, total);
+            }
+
+            totals.insert(project.id, project_total);
+        }
+
+        self.totals = Some(totals);
+    }
+
+    /// Recomputes the todo counts if anything changed since the last call.
+    pub fn refresh_todo_counts(&mut self) {
+        if self.todo_counts.is_some() {
             return;
+        }
+
+        let mut counts = HashMap::new();
+
+        for project in self.todos.inner.values() {
+            let mut project_count = (0, 0);
+
+            for sub_project in project.inner.values().filter(|v| !v.is_deleted) {
+                let mut count = (0, 0);
+
+                for subject in sub_project.inner.values() {
+                    let subject = subject.lock().unwrap();
+
+                    if subject.is_deleted {
+                        continue;
+                    }
+
+                    count.0 += usize::from(!subject.is_done);
+                    count.1 += 1;
+                }
+
+                project_count.0 += count.0;
+                project_count.1 += count.1;
+                counts.insert(sub_project.id, count);
+            }
+
+            counts.insert(project.id, project_count);
+        }
+
+        self.todo_counts = Some(counts);
+    }
+
+    /// Open and total todos of a todo project or sub project as of the last refresh.
+    pub fn todo_count(&self, key: &Uuid) -> (usize, usize) {
+        self.todo_counts
+            .as_ref()
+            .and_then(|v| v.get(key).copied())
+            .unwrap_or_default()
+    }
+
+    /// Totals of projects and sub projects, from the cache when it is fresh.
+    fn get_total(&self, key: &Uuid) -> Option<Duration> {
+        if let Some(totals) = &self.totals {
+            return totals.get(key).copied();
+        }
+
+        if let Some(project) = self.projects.inner.get(key) {
+            return Some(project.inner.keys().filter_map(|v| self.get_total(v)).sum());
+        }
+
+        let sub_project = self
+            .projects
+            .inner
+            .values()
+            .find_map(|v| v.inner.get(key))?;
+
+        Some(
+            sub_project
+                .inner
+                .values()
+                .fold(Duration::default(), |v, iv| v + iv.lock().unwrap().duration),
+        )
+    }
+
+    pub fn get_project_time(&self, key: &Uuid) -> Option<Duration> {
+        self.get_total(key)
+    }
+
+    /// Time tracked on a project in the current day or week.
+    pub fn get_project_period_time(&self, key: &Uuid, period: GoalPeriod) -> chrono::Duration {
+        let today = self.now().date_naive();
+
+        let (from, to) = match period {
+            GoalPeriod::Day => (today, today + chrono::Duration::days(1)),
+            GoalPeriod::Week => {
+                let from = start_of_week(today, self.settings.first_day_of_week);
+                (from, from + chrono::Duration::days(7))
+            }
         };
 
-        let project_id = project.id;
+        self.history
+            .get_records((start_of_day(from), start_of_day(to)))
+            .iter()
+            .filter(|v| v.project_id == *key)
+            .fold(chrono::Duration::zero(), |s, v| s + v.get_duration())
+    }
 
-        let Some(sub_project) = project.get_current_mut() else {
+    pub fn set_project_goal(&mut self, key: &Uuid, goal: Option<Goal>) {
+        let Some(project) = self.projects.inner.get_mut(key) else {
             return;
         };
 
-        let sub_project_id = sub_project.id;
+        project.goal = goal;
 
-        let Some(subject) = sub_project.get_current_mut() else {
+        self.dirty();
+    }
+
+    pub fn set_project_rate(&mut self, key: &Uuid, hourly_rate: Option<f64>, currency: &str) {
+        let Some(project) = self.projects.inner.get_mut(key) else {
             return;
         };
 
-        let subject_id = subject.lock().unwrap().id;
+        project.hourly_rate = hourly_rate;
+        project.currency = currency.trim().to_string();
 
-        if self.last_session_subject_id != subject_id {
-            self.current_session_duration = Duration::ZERO;
+        self.dirty();
+    }
+
+    /// Time per project within `range` priced at the project rates. Every session is
+    /// rounded on its own before it is added up.
+    pub fn billing(
+        &self,
+        range: (DateTime<Local>, DateTime<Local>),
+        rounding: BillingRounding,
+    ) -> Billing {
+        let mut durations: HashMap<Uuid, chrono::Duration> = HashMap::new();
+
+        for record in self.history.get_records(range) {
+            let duration = durations
+                .entry(record.project_id)
+                .or_insert_with(chrono::Duration::zero);
+
+            *duration = *duration + rounding.apply(record.get_duration());
         }
 
-        self.last_session_subject_id = subject_id;
+        let mut lines: Vec<BillingLine> = durations
+            .into_iter()
+            .map(|(project_id, duration)| {
+                let project = self.projects.inner.get(&project_id);
+                let rate = project.and_then(|v| v.hourly_rate);
 
-        self.working_mode = WorkingMode::InProgress(WorkingProgress::start(
-            subject.clone(),
-            self.history
-                .add_record(project_id, sub_project_id, subject_id),
-        ));
-    }
+                BillingLine {
+                    name: project
+                        .map_or_else(|| tr!("<deleted project>").to_string(), |v| v.name.clone()),
+                    duration,
+                    rate,
+                    currency: project.map(|v| v.currency.clone()).unwrap_or_default(),
+                    amount: rate.map(|v| v * duration.num_seconds() as f64 / 3600.),
+                }
+            })
+            .collect();
 
-    pub fn stop_subject(&mut self, force: bool) {
-        self.working_mode = WorkingMode::Idle;
+        lines.sort_by_key(|v| v.name.to_lowercase());
 
-        if force {
-            self.current_session_duration = Duration::ZERO;
-        } else if let Some(subject) = self.get_current_subject() {
-            if self.last_session_subject_id != subject.lock().unwrap().id {
-                self.current_session_duration = Duration::ZERO;
+        let mut totals: Vec<(String, f64)> = vec![];
+
+        for line in &lines {
+            let Some(amount) = line.amount else {
+                continue;
+            };
+
+            match totals.iter_mut().find(|v| v.0 == line.currency) {
+                Some(total) => total.1 += amount,
+                None => totals.push((line.currency.clone(), amount)),
             }
         }
+
+        Billing { lines, totals }
     }
-}
 
-impl Default for Backend {
-    fn default() -> Self {
-        Self {
-            projects: PContainer::new("root"),
-            working_mode: Default::default(),
-            current_session_duration: Duration::default(),
-            last_session_subject_id: Uuid::new_v4(),
-            last_save: SystemTime::now(),
-            history: History::new(),
-            todos: PContainer::new("root"),
-            dirty: false,
+    /// `None` when the project has no sub project `key`.
+    pub fn get_sub_project_time(&self, project_id: &Uuid, key: &Uuid) -> Option<Duration> {
+        let project = self.projects.inner.get(project_id)?;
+
+        project
+            .inner
+            .contains_key(key)
+            .then(|| self.get_total(key))
+            .flatten()
+    }
+
+    pub fn get_today_times(&self) -> TodayTimes {
+        let today = self.now().date_naive();
+        let mut times = TodayTimes::default();
+
+        for record in self.history.get_records((
+            start_of_day(today),
+            start_of_day(today + chrono::Duration::days(1)),
+        )) {
+            let duration = record.get_duration().to_std().unwrap_or_default();
+
+            *times.projects.entry(record.project_id).or_default() += duration;
+            *times.sub_projects.entry(record.sub_project_id).or_default() += duration;
+            *times.subjects.entry(record.subject_id).or_default() += duration;
         }
+
+        times
     }
-}
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct Subject {
-    pub(crate) id: Uuid,
-    pub(crate) name: String,
-    pub(crate) created_at: SystemTime,
-    pub(crate) duration: Duration,
-    pub(crate) is_deleted: bool,
-}
+    /// Time tracked on each project within the local day, longest first.
+    pub fn project_day_totals(&self, day: NaiveDate) -> Vec<(String, chrono::Duration)> {
+        self.project_totals((
+            start_of_day(day),
+            start_of_day(day + chrono::Duration::days(1)),
+        ))
+    }
 
-impl Subject {
-    fn create(name: &str) -> Self {
-        Subject {
-            id: Uuid::new_v4(),
-            name: name.to_string(),
-            created_at: SystemTime::now(),
-            duration: Duration::default(),
-            is_deleted: false,
+    /// Time tracked on each project within the range, longest first.
+    pub fn project_totals(
+        &self,
+        range: (DateTime<Local>, DateTime<Local>),
+    ) -> Vec<(String, chrono::Duration)> {
+        let mut totals: HashMap<Uuid, (String, chrono::Duration)> = HashMap::new();
+
+        for record in self.history.get_records(range) {
+            let total = totals.entry(record.project_id).or_insert_with(|| {
+                (
+                    self.get_record_path(&record).project,
+                    chrono::Duration::zero(),
+                )
+            });
+
+            total.1 = total.1 + record.get_duration();
         }
+
+        let mut totals = totals.into_values().collect::<Vec<_>>();
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        totals
     }
-}
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct TodoSubject {
-    pub(crate) id: Uuid,
-    pub(crate) name: String,
-    pub(crate) created_at: SystemTime,
-    pub(crate) is_deleted: bool,
-    pub(crate) is_done: bool,
-}
+    pub fn get_record_path(&self, record: &HistoryRecord) -> RecordPath {
+        record_path(&self.projects, record)
+    }
 
-impl TodoSubject {
-    fn create(name: &str) -> Self {
-        TodoSubject {
-            id: Uuid::new_v4(),
-            name: name.to_string(),
-            created_at: SystemTime::now(),
-            is_deleted: false,
-            is_done: false,
+    /// Path of the subject `subject_id` in whatever sub project holds it.
+    fn subject_entry_path(&self, subject_id: Uuid) -> Option<EntryPath> {
+        self.projects.inner.values().find_map(|p| {
+            p.inner
+                .values()
+                .find(|s| s.inner.contains_key(&subject_id))
+                .map(|s| EntryPath::Subject(p.id, s.id, subject_id))
+        })
+    }
+
+    /// Names of the project, sub project and subject of the subject `subject_id`, wherever
+    /// it is, deleted entries included.
+    pub fn get_subject_path(&self, subject_id: &Uuid) -> Option<(String, String, String)> {
+        let EntryPath::Subject(p, s, x) = self.subject_entry_path(*subject_id)? else {
+            return None;
+        };
+
+        let project = &self.projects.inner[&p];
+        let sub_project = &project.inner[&s];
+        let subject = sub_project.inner[&x].lock().unwrap();
+
+        Some((
+            project.name.clone(),
+            sub_project.name.clone(),
+            subject.name.clone(),
+        ))
+    }
+
+    /// Last `n` distinct subjects with tracked time, newest first, with their names and full
+    /// paths. Subjects deleted since, or inside a deleted or archived project, are passed
+    /// over.
+    pub fn recent_subjects(&self, n: usize) -> Vec<(EntryPath, String, String)> {
+        let path =
+            |v: &HistoryRecord| EntryPath::Subject(v.project_id, v.sub_project_id, v.subject_id);
+
+        self.history
+            .recent_subjects(n, |v| self.live_subject_names(path(v)).is_some())
+            .into_iter()
+            .filter_map(|v| {
+                let (name, parent) = self.live_subject_names(path(&v))?;
+
+                Some((path(&v), name.clone(), format!("{parent} / {name}")))
+            })
+            .collect()
+    }
+
+    /// Pinned subjects by name, with the names of their project and sub project. Those that
+    /// can no longer be tracked, being deleted themselves or inside something deleted or
+    /// archived, are left out.
+    pub fn pinned_subjects(&self) -> Vec<(EntryPath, String, String)> {
+        let mut pinned = Vec::new();
+
+        for project in self.projects.inner.values() {
+            for sub_project in project.inner.values() {
+                for (id, subject) in &sub_project.inner {
+                    if !subject.lock().unwrap().is_pinned {
+                        continue;
+                    }
+
+                    let path = EntryPath::Subject(project.id, sub_project.id, *id);
+
+                    if let Some((name, parent)) = self.live_subject_names(path) {
+                        pinned.push((path, name, parent));
+                    }
+                }
+            }
         }
+
+        pinned.sort_by_cached_key(|(_, name, _)| name.to_lowercase());
+
+        pinned
     }
 
-    pub(crate) fn toggle(&mut self) {
-        self.is_done = !self.is_done;
+    pub fn is_subject_pinned(&self, path: EntryPath) -> bool {
+        let EntryPath::Subject(p, s, x) = path else {
+            return false;
+        };
+
+        self.projects
+            .inner
+            .get(&p)
+            .and_then(|v| v.inner.get(&s))
+            .and_then(|v| v.inner.get(&x))
+            .is_some_and(|v| v.lock().unwrap().is_pinned)
+    }
+
+    pub fn set_subject_pinned(&mut self, path: EntryPath, pinned: bool) {
+        let EntryPath::Subject(p, s, x) = path else {
+            return;
+        };
+
+        let Some(subject) = self
+            .projects
+            .inner
+            .get(&p)
+            .and_then(|v| v.inner.get(&s))
+            .and_then(|v| v.inner.get(&x))
+        else {
+            return;
+        };
+
+        subject.lock().unwrap().is_pinned = pinned;
+
+        self.dirty();
+    }
+
+    /// Name of a trackable subject and the `project / sub project` it sits in, `None` when it
+    /// or anything above it is gone, deleted or archived.
+    fn live_subject_names(&self, path: EntryPath) -> Option<(String, String)> {
+        let EntryPath::Subject(p, s, x) = path else {
+            return None;
+        };
+
+        let project = self
+            .projects
+            .inner
+            .get(&p)
+            .filter(|v| !v.is_deleted && !v.is_archived)?;
+        let sub_project = project.inner.get(&s).filter(|v| !v.is_deleted)?;
+        let subject = sub_project.inner.get(&x)?.lock().unwrap();
+
+        (!subject.is_deleted).then(|| {
+            (
+                subject.name.clone(),
+                format!("{} / {}", project.name, sub_project.name),
+            )
+        })
+    }
+
+    /// `project/sub project/subject` of the current subject, callers pick the text for none.
+    pub fn get_current_work_name(&self) -> Option<String> {
+        let project = self.projects.get_current()?;
+        let sub_project = project.get_current()?;
+        let subject = sub_project.get_current()?;
+
+        let name = format!(
+            "{}/{}/{}",
+            project.name,
+            sub_project.name,
+            subject.lock().unwrap().name,
+        );
+
+        Some(name)
+    }
+
+    pub fn add_todo_project(&mut self, name: &str) {
+        let mut project = PContainer::new(name);
+        project.color = self.new_project_color(Tree::Todo);
+
+        self.todos.inner.insert(project.id, project);
+
+        self.dirty();
+    }
+
+    pub fn add_todo_sub_project(&mut self, name: &str) {
+        let Some(project) = self.todos.get_current_mut() else {
+            return;
+        };
+
+        let sub_project = PContainer::new(name);
+
+        project.inner.insert(sub_project.id, sub_project);
+
+        self.dirty();
+    }
+
+    pub fn add_todo_subject(&mut self, name: &str, priority: Priority) {
+        let Some(project) = self.todos.get_current_mut() else {
+            return;
+        };
+
+        let Some(sub_project) = project.get_current_mut() else {
+            return;
+        };
+
+        let subject = TodoSubject::create(name, priority);
+
+        sub_project
+            .inner
+            .insert(subject.id, Arc::new(Mutex::new(subject)));
+
+        self.dirty();
+    }
+
+    pub fn toggle_todo_subject(
+        &mut self,
+        project_id: Uuid,
+        sub_project_id: Uuid,
+        subject_id: Uuid,
+    ) {
+        let Some(subject) = self
+            .todos
+            .inner
+            .get(&project_id)
+            .and_then(|v| v.inner.get(&sub_project_id))
+            .and_then(|v| v.inner.get(&subject_id))
+        else {
+            return;
+        };
+
+        subject.lock().unwrap().toggle();
+
+        self.dirty();
+    }
+
+    /// Checks a todo off or reopens it, nothing happens when it already is in that state.
+    pub fn set_todo_done(
+        &mut self,
+        project_id: Uuid,
+        sub_project_id: Uuid,
+        subject_id: Uuid,
+        done: bool,
+    ) {
+        let Some(subject) = self
+            .todos
+            .inner
+            .get(&project_id)
+            .and_then(|v| v.inner.get(&sub_project_id))
+            .and_then(|v| v.inner.get(&subject_id))
+        else {
+            return;
+        };
+
+        let mut subject = subject.lock().unwrap();
+
+        if subject.is_done == done {
+            return;
+        }
+
+        subject.toggle();
+        drop(subject);
+
+        self.dirty();
+    }
+
+    pub fn set_todo_due(
+        &mut self,
+        project_id: Uuid,
+        sub_project_id: Uuid,
+        subject_id: Uuid,
+        due: Option<DateTime<Local>>,
+    ) {
+        let Some(subject) = self
+            .todos
+            .inner
+            .get(&project_id)
+            .and_then(|v| v.inner.get(&sub_project_id))
+            .and_then(|v| v.inner.get(&subject_id))
+        else {
+            return;
+        };
+
+        subject.lock().unwrap().due = due;
+
+        self.dirty();
+    }
+
+    pub fn set_todo_priority(
+        &mut self,
+        project_id: Uuid,
+        sub_project_id: Uuid,
+        subject_id: Uuid,
+        priority: Priority,
+    ) {
+        let Some(subject) = self
+            .todos
+            .inner
+            .get(&project_id)
+            .and_then(|v| v.inner.get(&sub_project_id))
+            .and_then(|v| v.inner.get(&subject_id))
+        else {
+            return;
+        };
+
+        subject.lock().unwrap().priority = priority;
+
+        self.dirty();
+    }
+
+    pub fn set_todo_notes(
+        &mut self,
+        project_id: Uuid,
+        sub_project_id: Uuid,
+        subject_id: Uuid,
+        notes: &str,
+    ) {
+        let Some(subject) = self
+            .todos
+            .inner
+            .get(&project_id)
+            .and_then(|v| v.inner.get(&sub_project_id))
+            .and_then(|v| v.inner.get(&subject_id))
+        else {
+            return;
+        };
+
+        let mut subject = subject.lock().unwrap();
+
+        if subject.notes == notes {
+            return;
+        }
+
+        subject.notes = notes.to_string();
+        drop(subject);
+
+        self.dirty();
+    }
+
+    /// Open todos past their due time, deleted entries left out.
+    pub fn overdue_todos(&self, now: DateTime<Local>) -> usize {
+        self.todos
+            .inner
+            .values()
+            .filter(|v| !v.is_deleted)
+            .flat_map(|v| v.inner.values())
+            .filter(|v| !v.is_deleted)
+            .flat_map(|v| v.inner.values())
+            .filter(|v| {
+                let subject = v.lock().unwrap();
+                !subject.is_deleted && subject.is_overdue(now)
+            })
+            .count()
+    }
+
+    pub fn is_todo_expanded(&self, id: &Uuid) -> bool {
+        self.settings.todo_expanded.contains(id)
+    }
+
+    pub fn set_todo_expanded(&mut self, id: Uuid, expanded: bool) {
+        let changed = if expanded {
+            self.settings.todo_expanded.insert(id)
+        } else {
+            self.settings.todo_expanded.remove(&id)
+        };
+
+        if changed {
+            self.dirty();
+        }
+    }
+
+    pub fn add_project(&mut self, name: &str) {
+        let mut project = PContainer::new(name);
+        project.color = self.new_project_color(Tree::Time);
+
+        self.projects.inner.insert(project.id, project);
+
+        self.dirty();
+    }
+
+    pub fn add_sub_project(&mut self, name: &str) {
+        let Some(project) = self.projects.get_current_mut() else {
+            return;
+        };
+
+        let sub_project = PContainer::new(name);
+
+        project.inner.insert(sub_project.id, sub_project);
+
+        self.dirty();
+    }
+
+    pub fn add_subject(&mut self, name: &str) {
+        let Some(project) = self.projects.get_current_mut() else {
+            return;
+        };
+
+        let Some(sub_project) = project.get_current_mut() else {
+            return;
+        };
+
+        let subject = Subject::create(name);
+
+        sub_project
+            .inner
+            .insert(subject.id, Arc::new(Mutex::new(subject)));
+
+        self.dirty();
+    }
+
+    /// Adds `project/sub project/subject` or a shorter prefix of it. Levels that already exist
+    /// are reused, ignoring case, the others are created, and the whole chain becomes the
+    /// current selection. `None` for an empty or too deep path, or one into an archived
+    /// project.
+    pub fn add_path(&mut self, path: &str, tree: Tree) -> Option<EntryPath> {
+        let names = path_names(path);
+
+        if names.is_empty() || names.len() > 3 {
+            return None;
+        }
+
+        let color = self.new_project_color(tree);
+
+        let added = match tree {
+            Tree::Time => chain_add_path(&mut self.projects, &names, color, Subject::create)?,
+            Tree::Todo => chain_add_path(&mut self.todos, &names, color, |v| {
+                TodoSubject::create(v, Priority::default())
+            })?,
+        };
+
+        self.select_entry(tree, added);
+        self.dirty();
+
+        Some(added)
+    }
+
+    /// Live subject at a `project/sub project/subject` path, names compared like
+    /// [`add_path`](Self::add_path) does.
+    pub fn find_subject(&self, path: &str) -> Option<EntryPath> {
+        let [p, s, x] = path_names(path)[..] else {
+            return None;
+        };
+
+        let (Some(project), Some(sub_project), Some(subject)) = self.lookup_names([p, s, x]) else {
+            return None;
+        };
+
+        let archived = self
+            .projects
+            .inner
+            .get(&project)
+            .is_some_and(|v| v.is_archived);
+
+        (!archived).then_some(EntryPath::Subject(project, sub_project, subject))
+    }
+
+    /// Ids of the live project, sub project and subject with these names, as far down as
+    /// they exist.
+    fn lookup_names(&self, names: [&str; 3]) -> (Option<Uuid>, Option<Uuid>, Option<Uuid>) {
+        let same = |name: &str, other: &str| name.trim().eq_ignore_ascii_case(other.trim());
+
+        let project = self
+            .projects
+            .inner
+            .values()
+            .find(|v| !v.is_deleted && same(&v.name, names[0]));
+
+        let sub_project = project.and_then(|project| {
+            project
+                .inner
+                .values()
+                .find(|v| !v.is_deleted && same(&v.name, names[1]))
+        });
+
+        let subject = sub_project.and_then(|sub_project| {
+            sub_project.inner.values().find_map(|v| {
+                let v = v.lock().unwrap();
+                (!v.is_deleted && same(&v.name, names[2])).then_some(v.id)
+            })
+        });
+
+        (project.map(|v| v.id), sub_project.map(|v| v.id), subject)
+    }
+
+    /// Whether the subject already has a record spanning exactly `start` to `end`.
+    fn has_record(&self, subject_id: Uuid, start: DateTime<Local>, end: DateTime<Local>) -> bool {
+        self.history
+            .get_records((start, end))
+            .iter()
+            .any(|v| v.subject_id == subject_id && v.start_date == start && v.end_date == end)
+    }
+
+    /// Reads the history the entries fall into, to tell which are already in it.
+    fn load_entries_history(&mut self, entries: &[TogglEntry]) {
+        let from = entries.iter().map(|v| v.start.date_naive()).min();
+        let to = entries.iter().map(|v| v.end.date_naive()).max();
+
+        if let (Some(from), Some(to)) = (from, to) {
+            self.history.load_range(from, to);
+        }
+    }
+
+    /// What [`import_toggl`](Self::import_toggl) would do with `entries`.
+    pub fn toggl_preview(&mut self, entries: &[TogglEntry]) -> ImportPreview {
+        self.load_entries_history(entries);
+
+        let mut preview = ImportPreview::default();
+        let mut seen = HashSet::new();
+        let mut created = (HashSet::new(), HashSet::new(), HashSet::new());
+
+        for entry in entries {
+            let names = entry.names.clone().map(|v| v.trim().to_lowercase());
+            let (project, sub_project, subject) =
+                self.lookup_names(std::array::from_fn(|i| entry.names[i].as_str()));
+
+            if project.is_some_and(|v| self.projects.inner.get(&v).is_some_and(|v| v.is_archived)) {
+                preview.archived += 1;
+                continue;
+            }
+
+            let duplicate = subject.is_some_and(|v| self.has_record(v, entry.start, entry.end));
+
+            if duplicate || !seen.insert((names.clone(), entry.start, entry.end)) {
+                preview.duplicates += 1;
+                continue;
+            }
+
+            preview.entries += 1;
+
+            if project.is_none() {
+                created.0.insert(names[0].clone());
+            }
+
+            if sub_project.is_none() {
+                created.1.insert(names[..2].to_vec());
+            }
+
+            if subject.is_none() {
+                created.2.insert(names);
+            }
+        }
+
+        preview.projects = created.0.len();
+        preview.sub_projects = created.1.len();
+        preview.subjects = created.2.len();
+
+        preview
+    }
+
+    /// Adds the entries as records, creating the entries they name. Entries already in the
+    /// history and those of archived projects are skipped, returns how many were added.
+    pub fn import_toggl(&mut self, entries: &[TogglEntry]) -> usize {
+        self.load_entries_history(entries);
+
+        let mut imported = 0;
+
+        for entry in entries {
+            let names = entry.names.each_ref().map(|v| v.as_str());
+            let color = self.new_project_color(Tree::Time);
+
+            let Some(EntryPath::Subject(p, s, x)) =
+                chain_add_path(&mut self.projects, &names, color, Subject::create)
+            else {
+                continue;
+            };
+
+            if self.has_record(x, entry.start, entry.end) {
+                continue;
+            }
+
+            if self
+                .add_manual_record(p, s, x, entry.start, entry.end)
+                .is_some()
+            {
+                imported += 1;
+            }
+        }
+
+        self.dirty();
+
+        imported
+    }
+
+    pub fn entry_name(&self, tree: Tree, path: EntryPath) -> Option<String> {
+        match tree {
+            Tree::Time => chain_entry_name(&self.projects, path),
+            Tree::Todo => chain_entry_name(&self.todos, path),
+        }
+    }
+
+    /// Link of a subject or todo, `None` when it has none.
+    pub fn entry_link(&self, tree: Tree, path: EntryPath) -> Option<String> {
+        match tree {
+            Tree::Time => chain_link_mut(&self.projects, path, |v| v.clone()),
+            Tree::Todo => chain_link_mut(&self.todos, path, |v| v.clone()),
+        }
+        .flatten()
+    }
+
+    /// Stores the link as typed, only trimmed, an empty one removes it.
+    pub fn set_entry_link(&mut self, tree: Tree, path: EntryPath, link: &str) {
+        let link = Some(link.trim().to_string()).filter(|v| !v.is_empty());
+
+        let set = |v: &mut Option<String>| {
+            let changed = *v != link;
+            *v = link.clone();
+            changed
+        };
+
+        let changed = match tree {
+            Tree::Time => chain_link_mut(&self.projects, path, set),
+            Tree::Todo => chain_link_mut(&self.todos, path, set),
+        };
+
+        if changed == Some(true) {
+            self.dirty();
+        }
+    }
+
+    pub fn note_template(&self, path: EntryPath) -> Option<String> {
+        self.subject(path)?.lock().unwrap().note_template.clone()
+    }
+
+    /// Stores the template as typed, trailing spaces included, a blank one removes it.
+    pub fn set_note_template(&mut self, path: EntryPath, template: &str) {
+        let Some(subject) = self.subject(path) else {
+            return;
+        };
+
+        let template = Some(template.to_string()).filter(|v| !v.trim().is_empty());
+        let mut subject = subject.lock().unwrap();
+
+        if subject.note_template != template {
+            subject.note_template = template;
+            drop(subject);
+            self.dirty();
+        }
+    }
+
+    /// Leaves a note on a stored session, an empty one removes it.
+    pub fn set_session_note(&mut self, id: Uuid, note: &str) {
+        if self.history.set_note(id, note) {
+            self.dirty();
+        }
+    }
+
+    /// Note of the latest session of the subject at `path` that has one, among the loaded
+    /// days.
+    pub fn last_session_note(&self, path: EntryPath) -> Option<&str> {
+        let EntryPath::Subject(.., x) = path else {
+            return None;
+        };
+
+        self.history.last_note_for_subject(x)
+    }
+
+    /// Id and name of the live entries under `parent`, or of the projects when it is `None`.
+    pub fn entry_children(&self, tree: Tree, parent: Option<EntryPath>) -> Vec<(Uuid, String)> {
+        match tree {
+            Tree::Time => chain_children(&self.projects, parent),
+            Tree::Todo => chain_children(&self.todos, parent),
+        }
+    }
+
+    /// Whether an entry can go without asking: no tracked time and no live children in the
+    /// time chain, no open todos below a todo project or sub project.
+    pub fn entry_is_empty(&self, tree: Tree, path: EntryPath) -> bool {
+        match (tree, path) {
+            (Tree::Todo, EntryPath::Subject(..)) => true,
+            (Tree::Todo, _) => self.open_todos(path) == 0,
+            (Tree::Time, _) => {
+                self.entry_total(path).is_zero() && self.entry_children(tree, Some(path)).is_empty()
+            }
+        }
+    }
+
+    /// Live todo subjects at or below an entry of the todo chain that are not done yet.
+    pub fn open_todos(&self, path: EntryPath) -> usize {
+        let (EntryPath::Project(project_id)
+        | EntryPath::SubProject(project_id, _)
+        | EntryPath::Subject(project_id, _, _)) = path;
+
+        let Some(project) = self.todos.inner.get(&project_id).filter(|v| !v.is_deleted) else {
+            return 0;
+        };
+
+        project
+            .inner
+            .values()
+            .filter(|v| !v.is_deleted)
+            .flat_map(|v| v.inner.values().map(move |subject| (v.id, subject)))
+            .filter(|(sub_project_id, subject)| {
+                let subject = subject.lock().unwrap();
+
+                !subject.is_deleted
+                    && !subject.is_done
+                    && path.contains(&EntryPath::Subject(project_id, *sub_project_id, subject.id))
+            })
+            .count()
+    }
+
+    /// Tracked time of an entry of the time chain.
+    pub fn entry_total(&self, path: EntryPath) -> Duration {
+        match path {
+            EntryPath::Project(p) => self.get_project_time(&p).unwrap_or_default(),
+            EntryPath::SubProject(p, s) => self.get_sub_project_time(&p, &s).unwrap_or_default(),
+            EntryPath::Subject(p, s, x) => self
+                .projects
+                .inner
+                .get(&p)
+                .and_then(|v| v.inner.get(&s))
+                .and_then(|v| v.inner.get(&x))
+                .map_or(Duration::ZERO, |v| v.lock().unwrap().duration),
+        }
+    }
+
+    pub fn rename_entry(&mut self, tree: Tree, path: EntryPath, name: &str) {
+        let renamed = match tree {
+            Tree::Time => {
+                chain_entry_mut(&mut self.projects, path, |v, _, _| *v = name.to_string())
+            }
+            Tree::Todo => chain_entry_mut(&mut self.todos, path, |v, _, _| *v = name.to_string()),
+        };
+
+        if renamed.is_some() {
+            self.dirty();
+        }
+    }
+
+    /// Hides an entry, its history stays untouched.
+    pub fn delete_entry(&mut self, tree: Tree, path: EntryPath) {
+        if tree == Tree::Time {
+            self.stop_tracking_inside(path);
+        }
+
+        let deleted = match tree {
+            Tree::Time => chain_delete(&mut self.projects, path),
+            Tree::Todo => chain_delete(&mut self.todos, path),
+        };
+
+        if deleted.is_some() {
+            self.dirty();
+        }
+    }
+
+    /// Deleted entries of a chain, most recently deleted first.
+    pub fn trash(&self, tree: Tree) -> Vec<TrashEntry> {
+        let mut found = match tree {
+            Tree::Time => chain_trash(&self.projects),
+            Tree::Todo => chain_trash(&self.todos),
+        };
+
+        found.sort_by_key(|v| std::cmp::Reverse(v.deleted_at));
+
+        found
+    }
+
+    /// Takes an entry and its deleted parents out of the trash, false when a live entry
+    /// already has one of their names.
+    pub fn restore_entry(&mut self, tree: Tree, path: EntryPath) -> bool {
+        let restored = match tree {
+            Tree::Time => chain_restore(&mut self.projects, path),
+            Tree::Todo => chain_restore(&mut self.todos, path),
+        };
+
+        if restored.is_some() {
+            self.dirty();
+        }
+
+        restored.is_some()
+    }
+
+    /// Every inconsistency of the data, in the order [`repair`](Self::repair) should fix them
+    /// since fixing records changes durations.
+    pub fn check_integrity(&mut self) -> Vec<Issue> {
+        self.history.load_all();
+
+        let mut issues = Vec::new();
+
+        let mut records = self.history.records().collect::<Vec<_>>();
+        records.sort();
+
+        for record in &records {
+            let subject = self
+                .projects
+                .inner
+                .get(&record.project_id)
+                .and_then(|v| v.inner.get(&record.sub_project_id))
+                .and_then(|v| v.inner.get(&record.subject_id));
+
+            if subject.is_none() {
+                issues.push(Issue::MissingEntry { record: record.id });
+            }
+        }
+
+        for record in &records {
+            if record.end_date < record.start_date {
+                issues.push(Issue::NegativeRecord { record: record.id });
+            }
+        }
+
+        issues.extend(
+            dangling_selections(&self.projects)
+                .into_iter()
+                .map(|container| Issue::DanglingSelection {
+                    tree: Tree::Time,
+                    container,
+                }),
+        );
+        issues.extend(
+            dangling_selections(&self.todos)
+                .into_iter()
+                .map(|container| Issue::DanglingSelection {
+                    tree: Tree::Todo,
+                    container,
+                }),
+        );
+
+        let history = self.history_durations();
+
+        for project in self.projects.inner.values() {
+            for sub_project in project.inner.values() {
+                for (id, subject) in &sub_project.inner {
+                    let stored = subject.lock().unwrap().duration;
+                    let history = history.get(id).copied().unwrap_or_default();
+
+                    if stored.abs_diff(history) > DURATION_TOLERANCE {
+                        issues.push(Issue::DurationMismatch {
+                            path: EntryPath::Subject(project.id, sub_project.id, *id),
+                            stored,
+                            history,
+                        });
+                    }
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Tracked time of every subject with records, summed from the history.
+    fn history_durations(&self) -> HashMap<Uuid, Duration> {
+        let mut durations = HashMap::<Uuid, Duration>::new();
+
+        for record in self.history.records() {
+            let duration = record.get_duration().to_std().unwrap_or_default();
+            *durations.entry(record.subject_id).or_default() += duration;
+        }
+
+        durations
+    }
+
+    /// Fixes one issue. Records of missing entries move to a recovered subject, negative
+    /// records shrink to nothing, dangling selections are cleared and durations are summed
+    /// from the history again.
+    pub fn repair(&mut self, issue: Issue) {
+        match issue {
+            Issue::MissingEntry { record } => {
+                let color = self.new_project_color(Tree::Time);
+
+                let Some(path) =
+                    chain_add_path(&mut self.projects, &RECOVERED_PATH, color, Subject::create)
+                else {
+                    return;
+                };
+
+                let EntryPath::Subject(p, s, x) = path else {
+                    return;
+                };
+
+                self.history.retarget(
+                    |v| v.id == record,
+                    |v| {
+                        v.project_id = p;
+                        v.sub_project_id = s;
+                        v.subject_id = x;
+                    },
+                );
+            }
+            Issue::NegativeRecord { record } => {
+                let Some(start) = self.history.get(&record).map(|v| v.start_date) else {
+                    return;
+                };
+
+                self.history.set_times(record, start, start);
+            }
+            Issue::DanglingSelection { tree, container } => match tree {
+                Tree::Time => clear_selection(&mut self.projects, container),
+                Tree::Todo => clear_selection(&mut self.todos, container),
+            },
+            Issue::DurationMismatch { path, .. } => {
+                let EntryPath::Subject(p, s, x) = path else {
+                    return;
+                };
+
+                let history = self.history_durations();
+
+                let Some(subject) = self
+                    .projects
+                    .inner
+                    .get(&p)
+                    .and_then(|v| v.inner.get(&s))
+                    .and_then(|v| v.inner.get(&x))
+                else {
+                    return;
+                };
+
+                subject.lock().unwrap().duration = history.get(&x).copied().unwrap_or_default();
+            }
+        }
+
+        self.totals = None;
+        self.dirty();
+    }
+
+    /// Sets the duration of every subject to the sum of its records, subjects without any
+    /// get zero. Returns how many subjects changed.
+    pub fn rebuild_durations(&mut self) -> usize {
+        self.history.load_all();
+
+        let history = self.history_durations();
+        let mut changed = 0;
+
+        for project in self.projects.inner.values() {
+            for sub_project in project.inner.values() {
+                for (id, subject) in &sub_project.inner {
+                    let duration = history.get(id).copied().unwrap_or_default();
+                    let mut subject = subject.lock().unwrap();
+
+                    if subject.duration != duration {
+                        subject.duration = duration;
+                        changed += 1;
+                    }
+                }
+            }
+        }
+
+        if changed > 0 {
+            self.totals = None;
+            self.dirty();
+        }
+
+        changed
+    }
+
+    pub fn repair_all(&mut self) {
+        for issue in self.check_integrity() {
+            self.repair(issue);
+        }
+
+        // Moved and clamped records change the durations they add up to.
+        for issue in self.check_integrity() {
+            self.repair(issue);
+        }
+    }
+
+    /// Removes deleted entries for good. Time entries stay as long as history records point
+    /// at them, until those are deleted or reassigned.
+    pub fn empty_trash(&mut self) -> usize {
+        self.history.load_all();
+
+        let referenced = self.history.referenced_ids();
+
+        // Todos have no history.
+        let removed = chain_purge(&mut self.projects, &referenced)
+            + chain_purge(&mut self.todos, &HashSet::new());
+
+        if removed > 0 {
+            self.dirty();
+        }
+
+        removed
+    }
+
+    /// Subject of the running session, wherever the selection went since.
+    pub fn tracked_path(&self) -> Option<EntryPath> {
+        let WorkingMode::InProgress(progress) = &self.working_mode else {
+            return None;
+        };
+
+        let record = self.history.get(&progress.session_id)?;
+
+        Some(EntryPath::Subject(
+            record.project_id,
+            record.sub_project_id,
+            record.subject_id,
+        ))
+    }
+
+    /// Stops and finalizes the running session when its subject is `path` or inside it,
+    /// before something makes it untrackable.
+    fn stop_tracking_inside(&mut self, path: EntryPath) {
+        if self.tracked_path().is_some_and(|v| path.contains(&v)) {
+            self.stop_subject(true);
+        }
+    }
+
+    /// Selected entry of the time chain, as deep as the selection goes.
+    pub fn current_path(&self) -> Option<EntryPath> {
+        let project = self.get_current_project()?.id;
+
+        let Some(sub_project) = self.get_current_sub_project().map(|v| v.id) else {
+            return Some(EntryPath::Project(project));
+        };
+
+        let Some(subject) = self.get_current_subject() else {
+            return Some(EntryPath::SubProject(project, sub_project));
+        };
+
+        let subject = subject.lock().unwrap().id;
+
+        Some(EntryPath::Subject(project, sub_project, subject))
+    }
+
+    /// Live entries of a chain whose name contains `query`, ignoring case, with the names
+    /// leading to them. Projects come first, then sub projects, then subjects.
+    pub fn search(&self, tree: Tree, query: &str) -> Vec<(EntryPath, String)> {
+        let mut found = match tree {
+            Tree::Time => chain_search(&self.projects, query),
+            Tree::Todo => chain_search(&self.todos, query),
+        };
+
+        found.sort_by_cached_key(|(path, label)| {
+            let depth = match path {
+                EntryPath::Project(_) => 0,
+                EntryPath::SubProject(..) => 1,
+                EntryPath::Subject(..) => 2,
+            };
+
+            (depth, label.to_lowercase())
+        });
+
+        found
+    }
+
+    /// Makes an entry and everything above it the current selection of its chain. Todo
+    /// subjects cannot be selected, their sub project is instead.
+    pub fn select_entry(&mut self, tree: Tree, path: EntryPath) {
+        match tree {
+            Tree::Time => self.select_path(path),
+            Tree::Todo => {
+                let (EntryPath::Project(p)
+                | EntryPath::SubProject(p, _)
+                | EntryPath::Subject(p, _, _)) = path;
+
+                self.set_current_todo_project(Some(p));
+
+                if let EntryPath::SubProject(_, s) | EntryPath::Subject(_, s, _) = path {
+                    self.set_current_todo_sub_project(Some(s));
+                }
+            }
+        }
+    }
+
+    /// Selecting another subject stops the running session, so it never runs on a subject
+    /// other than the selected one.
+    fn select_path(&mut self, path: EntryPath) {
+        if let (EntryPath::Subject(..), Some(tracked)) = (path, self.tracked_path()) {
+            if tracked != path {
+                self.stop_subject(true);
+            }
+        }
+
+        match path {
+            EntryPath::Project(p) => self.set_current_project(Some(p)),
+            EntryPath::SubProject(p, s) => {
+                self.set_current_project(Some(p));
+                self.set_current_sub_project(Some(s));
+            }
+            EntryPath::Subject(p, s, x) => {
+                self.set_current_project(Some(p));
+                self.set_current_sub_project(Some(s));
+                self.set_current_subject(Some(x));
+            }
+        }
+    }
+
+    pub fn move_subject(
+        &mut self,
+        subject_id: Uuid,
+        target_project_id: Uuid,
+        target_sub_project_id: Uuid,
+    ) -> bool {
+        let Some(source) = self.subject_entry_path(subject_id) else {
+            return false;
+        };
+
+        let target = EntryPath::SubProject(target_project_id, target_sub_project_id);
+
+        self.move_entry(Tree::Time, source, target)
+    }
+
+    pub fn move_sub_project(&mut self, sub_project_id: Uuid, target_project_id: Uuid) -> bool {
+        let source = self
+            .projects
+            .inner
+            .values()
+            .find(|p| p.inner.contains_key(&sub_project_id))
+            .map(|p| EntryPath::SubProject(p.id, sub_project_id));
+
+        let Some(source) = source else {
+            return false;
+        };
+
+        self.move_entry(Tree::Time, source, EntryPath::Project(target_project_id))
+    }
+
+    /// Moves a sub project to another project or a subject to another sub project. Fails when
+    /// the target already has an entry with the same name. A selection inside the moved entry,
+    /// the running session included, moves along with it.
+    pub fn move_entry(&mut self, tree: Tree, path: EntryPath, parent: EntryPath) -> bool {
+        let Some(name) = self.entry_name(tree, path) else {
+            return false;
+        };
+
+        if self
+            .entry_children(tree, Some(parent))
+            .iter()
+            .any(|(_, v)| v.eq_ignore_ascii_case(&name))
+        {
+            return false;
+        }
+
+        let current = self.current_path().filter(|_| tree == Tree::Time);
+
+        let moved = match tree {
+            Tree::Time => chain_move(&mut self.projects, path, parent),
+            Tree::Todo => chain_move(&mut self.todos, path, parent),
+        };
+
+        if moved.is_none() {
+            return false;
+        }
+
+        // Records first, the running session is found by its record when reselecting.
+        if tree == Tree::Time {
+            match (path, parent) {
+                (EntryPath::SubProject(_, s), EntryPath::Project(p)) => self
+                    .history
+                    .retarget(|v| v.sub_project_id == s, |v| v.project_id = p),
+                (EntryPath::Subject(_, _, x), EntryPath::SubProject(p, s)) => {
+                    self.history.retarget(
+                        |v| v.subject_id == x,
+                        |v| {
+                            v.project_id = p;
+                            v.sub_project_id = s;
+                        },
+                    )
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(current) = current.filter(|v| path.contains(v)) {
+            self.select_path(current.rebase(parent));
+        }
+
+        self.dirty();
+
+        true
+    }
+
+    pub fn set_project_color(&mut self, tree: Tree, key: &Uuid, color: (u8, u8, u8)) {
+        let project = match tree {
+            Tree::Time => self.projects.inner.get_mut(key).map(|v| &mut v.color),
+            Tree::Todo => self.todos.inner.get_mut(key).map(|v| &mut v.color),
+        };
+
+        let Some(project_color) = project else {
+            return;
+        };
+
+        *project_color = color;
+
+        self.dirty();
+    }
+
+    /// Color of the palette setting for a project added to a chain.
+    fn new_project_color(&self, tree: Tree) -> (u8, u8, u8) {
+        let used = match tree {
+            Tree::Time => chain_colors(&self.projects),
+            Tree::Todo => chain_colors(&self.todos),
+        };
+
+        self.settings.palette.next_color(&used)
+    }
+
+    /// Gives every project of both chains a new color of the palette setting, in name order
+    /// so the palette colors are spread evenly. Colors of sub projects and subjects stay.
+    pub fn reassign_colors(&mut self) {
+        let palette = self.settings.palette;
+
+        fn reassign<T>(chain: &mut PContainer<PContainer<T, IdType>, IdType>, palette: Palette) {
+            let mut projects = chain.inner.values_mut().collect::<Vec<_>>();
+            projects.sort_by_key(|v| (v.is_deleted, v.name.to_lowercase()));
+
+            let mut used = Vec::new();
+
+            for project in projects {
+                project.color = palette.next_color(&used);
+                used.push(project.color);
+            }
+        }
+
+        reassign(&mut self.projects, palette);
+        reassign(&mut self.todos, palette);
+
+        self.dirty();
+    }
+
+    /// Color the timeline shows an entry of the time chain in.
+    pub fn entry_color(&self, path: EntryPath) -> (u8, u8, u8) {
+        let (EntryPath::Project(p) | EntryPath::SubProject(p, _) | EntryPath::Subject(p, _, _)) =
+            path;
+
+        let project = self.projects.inner.get(&p);
+
+        let sub_project = match path {
+            EntryPath::SubProject(_, s) | EntryPath::Subject(_, s, _) => {
+                project.and_then(|v| v.inner.get(&s))
+            }
+            EntryPath::Project(_) => None,
+        };
+
+        let subject = match path {
+            EntryPath::Subject(_, _, x) => sub_project.and_then(|v| v.inner.get(&x)),
+            _ => None,
+        };
+
+        most_specific_color(project, sub_project, subject)
+    }
+
+    /// Color set on a time sub project or subject itself, `None` when it uses its parent's.
+    pub fn own_color(&self, path: EntryPath) -> Option<(u8, u8, u8)> {
+        match path {
+            EntryPath::Project(_) => None,
+            EntryPath::SubProject(p, s) => {
+                self.projects.inner.get(&p)?.inner.get(&s)?.color_override
+            }
+            EntryPath::Subject(..) => self.subject(path)?.lock().unwrap().color,
+        }
+    }
+
+    /// Sets the color of a time sub project or subject, `None` going back to its parent's.
+    pub fn set_own_color(&mut self, path: EntryPath, color: Option<(u8, u8, u8)>) {
+        match path {
+            EntryPath::Project(_) => return,
+            EntryPath::SubProject(p, s) => {
+                let Some(sub_project) = self
+                    .projects
+                    .inner
+                    .get_mut(&p)
+                    .and_then(|v| v.inner.get_mut(&s))
+                else {
+                    return;
+                };
+
+                sub_project.color_override = color;
+            }
+            EntryPath::Subject(..) => {
+                let Some(subject) = self.subject(path) else {
+                    return;
+                };
+
+                subject.lock().unwrap().color = color;
+            }
+        }
+
+        self.dirty();
+    }
+
+    pub fn start_subject(&mut self) {
+        let Some(project) = self.projects.get_current_mut() else {
+            return;
+        };
+
+        let project_id = project.id;
+
+        let Some(sub_project) = project.get_current_mut() else {
+            return;
+        };
+
+        let sub_project_id = sub_project.id;
+
+        let Some(subject) = sub_project.get_current_mut() else {
+            return;
+        };
+
+        let subject_id = subject.lock().unwrap().id;
+        let subject = subject.clone();
+
+        self.check_clock();
+
+        if self.last_session_subject_id != subject_id {
+            self.current_session_duration = Duration::ZERO;
+        }
+
+        self.last_session_subject_id = subject_id;
+
+        let session_id =
+            self.history
+                .add_record(project_id, sub_project_id, subject_id, self.now());
+
+        self.working_mode =
+            WorkingMode::InProgress(WorkingProgress::start(subject, session_id, &*self.clock));
+
+        if self.settings.pomodoro {
+            self.pomodoro = PomodoroState::Work(self.clock.instant() + self.pomodoro_work());
+        }
+    }
+
+    fn pomodoro_work(&self) -> Duration {
+        Duration::from_secs(self.settings.pomodoro_work_minutes as u64 * 60)
+    }
+
+    fn pomodoro_break(&self) -> Duration {
+        Duration::from_secs(self.settings.pomodoro_break_minutes as u64 * 60)
+    }
+
+    pub fn set_pomodoro(&mut self, enabled: bool) {
+        self.settings.pomodoro = enabled;
+
+        self.pomodoro = match (enabled, &self.working_mode) {
+            (true, WorkingMode::InProgress(_)) => {
+                PomodoroState::Work(self.clock.instant() + self.pomodoro_work())
+            }
+            _ => PomodoroState::Off,
+        };
+
+        self.dirty();
+    }
+
+    /// Moves the pomodoro cycle on when the current phase is over, returning true if it did.
+    pub fn update_pomodoro(&mut self) -> bool {
+        let now = self.clock.instant();
+
+        match self.pomodoro {
+            PomodoroState::Work(ends_at) if now >= ends_at => {
+                self.stop_subject(false);
+
+                if self.settings.pomodoro_track_breaks {
+                    self.start_break();
+                }
+
+                self.pomodoro = PomodoroState::Break(now + self.pomodoro_break());
+
+                true
+            }
+            PomodoroState::Break(ends_at) if now >= ends_at => {
+                self.stop_subject(false);
+                self.start_subject();
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Tracks a break under the "Break" subject of the current sub project, keeping the
+    /// selected subject for the next work phase.
+    fn start_break(&mut self) {
+        let Some(project) = self.projects.get_current_mut() else {
+            return;
+        };
+
+        let project_id = project.id;
+
+        let Some(sub_project) = project.get_current_mut() else {
+            return;
+        };
+
+        let sub_project_id = sub_project.id;
+
+        let existing = sub_project
+            .inner
+            .values()
+            .find(|v| {
+                let v = v.lock().unwrap();
+                !v.is_deleted && v.name == BREAK_SUBJECT
+            })
+            .cloned();
+
+        let subject = existing.unwrap_or_else(|| {
+            let subject = Arc::new(Mutex::new(Subject::create(BREAK_SUBJECT)));
+            let id = subject.lock().unwrap().id;
+
+            sub_project.inner.insert(id, subject.clone());
+
+            subject
+        });
+
+        let subject_id = subject.lock().unwrap().id;
+
+        let session_id =
+            self.history
+                .add_record(project_id, sub_project_id, subject_id, self.now());
+
+        self.working_mode =
+            WorkingMode::InProgress(WorkingProgress::start(subject, session_id, &*self.clock));
+
+        self.dirty();
+    }
+
+    pub fn add_manual_record(
+        &mut self,
+        project_id: Uuid,
+        sub_project_id: Uuid,
+        subject_id: Uuid,
+        start: DateTime<Local>,
+        end: DateTime<Local>,
+    ) -> Option<Uuid> {
+        let Ok(duration) = end.signed_duration_since(start).to_std() else {
+            return None;
+        };
+
+        if duration.is_zero() {
+            return None;
+        }
+
+        let subject = self
+            .projects
+            .inner
+            .get(&project_id)?
+            .inner
+            .get(&sub_project_id)?
+            .inner
+            .get(&subject_id)?;
+
+        subject.lock().unwrap().duration += duration;
+
+        let id = self
+            .history
+            .add_manual_record(project_id, sub_project_id, subject_id, start, end);
+
+        self.dirty();
+
+        Some(id)
+    }
+
+    pub fn active_session_id(&self) -> Option<Uuid> {
+        match &self.working_mode {
+            WorkingMode::Idle => None,
+            WorkingMode::InProgress(progress) => Some(progress.session_id),
+        }
+    }
+
+    /// Changes the span of a stored record and moves the difference into its subject's duration.
+    pub fn set_record_times(
+        &mut self,
+        id: Uuid,
+        start: DateTime<Local>,
+        end: DateTime<Local>,
+    ) -> bool {
+        if end <= start || self.active_session_id() == Some(id) {
+            return false;
+        }
+
+        let Some(record) = self.history.get(&id).copied() else {
+            return false;
+        };
+
+        self.history.set_times(id, start, end);
+
+        if let Some(subject) = self.record_subject(&record) {
+            let old = record.get_duration().to_std().unwrap_or_default();
+            let new = end
+                .signed_duration_since(start)
+                .to_std()
+                .unwrap_or_default();
+
+            let mut subject = subject.lock().unwrap();
+            subject.duration = subject.duration.saturating_sub(old) + new;
+        }
+
+        self.dirty();
+
+        true
+    }
+
+    /// Splits a stored record at `at`, strictly inside it, returning the id of the second
+    /// half. The running session cannot be split.
+    pub fn split_record(&mut self, id: Uuid, at: DateTime<Local>) -> Option<Uuid> {
+        if self.active_session_id() == Some(id) {
+            return None;
+        }
+
+        let rest = self.history.split(id, at)?;
+
+        self.dirty();
+
+        Some(rest)
+    }
+
+    /// Points a stored record at another subject and moves its time along. The running
+    /// session cannot be reassigned.
+    pub fn reassign_record(
+        &mut self,
+        id: Uuid,
+        project_id: Uuid,
+        sub_project_id: Uuid,
+        subject_id: Uuid,
+    ) -> bool {
+        if self.active_session_id() == Some(id) {
+            return false;
+        }
+
+        let Some(record) = self.history.get(&id).copied() else {
+            return false;
+        };
+
+        let moved = HistoryRecord {
+            project_id,
+            sub_project_id,
+            subject_id,
+            ..record
+        };
+
+        let Some(target) = self.record_subject(&moved).cloned() else {
+            return false;
+        };
+
+        let duration = record.get_duration().to_std().unwrap_or_default();
+
+        if let Some(subject) = self.record_subject(&record) {
+            let mut subject = subject.lock().unwrap();
+            subject.duration = subject.duration.saturating_sub(duration);
+        }
+
+        target.lock().unwrap().duration += duration;
+
+        self.history.insert(moved);
+
+        self.dirty();
+
+        true
+    }
+
+    /// Removes a stored record and takes its time off its subject. The running session
+    /// cannot be deleted.
+    pub fn delete_record(&mut self, id: Uuid) -> bool {
+        if self.active_session_id() == Some(id) {
+            return false;
+        }
+
+        let Some(record) = self.history.remove(&id) else {
+            return false;
+        };
+
+        if let Some(subject) = self.record_subject(&record) {
+            let mut subject = subject.lock().unwrap();
+            subject.duration = subject
+                .duration
+                .saturating_sub(record.get_duration().to_std().unwrap_or_default());
+        }
+
+        self.dirty();
+
+        true
+    }
+
+    /// Merges records of one subject within `range` separated by less than `max_gap` and
+    /// moves the filled gaps into the subject durations. Returns how many records went away.
+    pub fn coalesce_records(
+        &mut self,
+        range: (DateTime<Local>, DateTime<Local>),
+        max_gap: chrono::Duration,
+    ) -> usize {
+        let merged = self.history.coalesce(range, max_gap);
+
+        if merged.is_empty() {
+            return 0;
+        }
+
+        for run in &merged {
+            let Some(subject) = self.record_subject(&run.record) else {
+                continue;
+            };
+
+            let mut subject = subject.lock().unwrap();
+
+            match run.gained.to_std() {
+                Ok(gained) => subject.duration += gained,
+                Err(_) => {
+                    let lost = (-run.gained).to_std().unwrap_or_default();
+                    subject.duration = subject.duration.saturating_sub(lost);
+                }
+            }
+        }
+
+        self.dirty();
+
+        merged.iter().map(|v| v.absorbed).sum()
+    }
+
+    fn record_subject(&self, record: &HistoryRecord) -> Option<&Arc<Mutex<Subject>>> {
+        self.projects
+            .inner
+            .get(&record.project_id)?
+            .inner
+            .get(&record.sub_project_id)?
+            .inner
+            .get(&record.subject_id)
+    }
+
+    /// Pauses the running session as if PAUSE was pressed at `at`.
+    pub fn pause_at(&mut self, at: DateTime<Local>) {
+        let WorkingMode::InProgress(progress) = &self.working_mode else {
+            return;
+        };
+
+        let subject = progress.subject.clone();
+
+        // Up to now first, so everything after `at` is cut off below.
+        self.accumulate_time();
+
+        let WorkingMode::InProgress(progress) = &self.working_mode else {
+            return;
+        };
+
+        let session_id = progress.session_id;
+
+        // Trimmed first, so the minimum length and the stop hook see what is kept.
+        if let Some(cut) = self.history.trim_end(session_id, at) {
+            let cut = cut.to_std().unwrap_or_default();
+
+            let mut subject = subject.lock().unwrap();
+            subject.duration = subject.duration.saturating_sub(cut);
+
+            self.current_session_duration = self.current_session_duration.saturating_sub(cut);
+        }
+
+        self.finish_session(false);
+
+        self.dirty();
+    }
+
+    fn subject(&self, path: EntryPath) -> Option<Arc<Mutex<Subject>>> {
+        let EntryPath::Subject(p, s, x) = path else {
+            return None;
+        };
+
+        self.projects
+            .inner
+            .get(&p)?
+            .inner
+            .get(&s)?
+            .inner
+            .get(&x)
+            .cloned()
+    }
+
+    /// Opens a record for the subject at `path` that runs until [`stop_detached`] without
+    /// the app running, ending the detached session in progress first.
+    ///
+    /// [`stop_detached`]: Self::stop_detached
+    pub fn start_detached(&mut self, path: EntryPath) {
+        let EntryPath::Subject(p, s, x) = path else {
+            return;
+        };
+
+        self.stop_detached();
+        self.select_entry(Tree::Time, path);
+
+        if self.last_session_subject_id != x {
+            self.current_session_duration = Duration::ZERO;
+        }
+
+        self.last_session_subject_id = x;
+        self.detached_session = Some(self.history.add_record(p, s, x, self.now()));
+        self.dirty();
+    }
+
+    /// Ends the detached session now, returning its record, or `None` when there was none
+    /// or it was shorter than the configured minimum and dropped.
+    pub fn stop_detached(&mut self) -> Option<HistoryRecord> {
+        let id = self.detached_session.take()?;
+        let record = *self.history.get(&id)?;
+        let path = EntryPath::Subject(record.project_id, record.sub_project_id, record.subject_id);
+
+        self.dirty();
+
+        let now = self.now();
+        let duration = now.signed_duration_since(record.start_date);
+
+        if duration < chrono::Duration::seconds(self.settings.min_session_seconds as i64) {
+            self.history.remove(&id);
+            return None;
+        }
+
+        self.history.update(id, now);
+        let end_date = self.history.get(&id)?.end_date;
+        self.history.split_days(id);
+
+        let duration = duration.to_std().unwrap_or_default();
+
+        if let Some(subject) = self.subject(path) {
+            subject.lock().unwrap().duration += duration;
+        }
+
+        self.current_session_duration += duration;
+
+        Some(HistoryRecord { end_date, ..record })
+    }
+
+    /// Continues a session started from the command line as the running session.
+    pub fn resume_detached(&mut self) {
+        let Some(id) = self.detached_session.take() else {
+            return;
+        };
+
+        let Some(record) = self.history.get(&id).copied() else {
+            return;
+        };
+
+        let path = EntryPath::Subject(record.project_id, record.sub_project_id, record.subject_id);
+
+        self.dirty();
+
+        // Without its subject the record is kept as it was when started.
+        let Some(subject) = self.subject(path) else {
+            return;
+        };
+
+        self.select_entry(Tree::Time, path);
+
+        let now = self.now();
+
+        let elapsed = now
+            .signed_duration_since(record.start_date)
+            .to_std()
+            .unwrap_or_default();
+
+        self.history.update(id, now);
+        let id = self.history.split_days(id);
+
+        subject.lock().unwrap().duration += elapsed;
+        self.current_session_duration += elapsed;
+        self.last_session_subject_id = record.subject_id;
+
+        let mut progress = WorkingProgress::start(subject, id, &*self.clock);
+        progress.started_at = record.start_date;
+
+        self.working_mode = WorkingMode::InProgress(progress);
+    }
+
+    /// Returns true when the session was shorter than the configured minimum and dropped.
+    pub fn stop_subject(&mut self, force: bool) -> bool {
+        // The last tick may be a second behind, or more while the window is hidden.
+        self.accumulate_time();
+
+        self.finish_session(force)
+    }
+
+    /// Stops the running session as it was last accumulated.
+    fn finish_session(&mut self, force: bool) -> bool {
+        // Sessions that never ran the start hook are not announced as stopped either.
+        let announced = match &self.working_mode {
+            WorkingMode::InProgress(v) if v.announced => self
+                .history
+                .get(&v.session_id)
+                .map(|record| (*record, v.started_at)),
+            _ => None,
+        };
+
+        let tracked = match &self.working_mode {
+            WorkingMode::InProgress(v) => Some(v.subject.lock().unwrap().id),
+            WorkingMode::Idle => None,
+        };
+
+        let discarded = self.discard_short_session();
+
+        if let Some((record, started_at)) = announced.filter(|_| !discarded) {
+            run_hook(
+                &self.settings.on_stop_command,
+                HookEvent::Stop {
+                    duration: record.end_date.signed_duration_since(started_at),
+                },
+                &record_path(&self.projects, &record),
+            );
+        }
+
+        self.working_mode = WorkingMode::Idle;
+        self.pomodoro = PomodoroState::Off;
+
+        // The selection may have moved on while the session ran, the subject it ran on counts.
+        if force || tracked.is_some_and(|v| v != self.last_session_subject_id) {
+            self.current_session_duration = Duration::ZERO;
+        }
+
+        discarded
+    }
+
+    /// Removes the running session from the history and its time from the subject when it
+    /// is below `min_session_seconds`.
+    fn discard_short_session(&mut self) -> bool {
+        let WorkingMode::InProgress(progress) = &self.working_mode else {
+            return false;
+        };
+
+        let Some(record) = self.history.get(&progress.session_id) else {
+            return false;
+        };
+
+        let min = chrono::Duration::seconds(self.settings.min_session_seconds as i64);
+
+        if record.get_duration() >= min {
+            return false;
+        }
+
+        let subject = progress.subject.clone();
+
+        let Some(record) = self.history.remove(&progress.session_id) else {
+            return false;
+        };
+
+        let duration = record.get_duration().to_std().unwrap_or_default();
+
+        let mut subject = subject.lock().unwrap();
+        subject.duration = subject.duration.saturating_sub(duration);
+
+        self.current_session_duration = self.current_session_duration.saturating_sub(duration);
+
+        self.dirty();
+
+        true
+    }
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Self {
+            projects: PContainer::new("root"),
+            working_mode: Default::default(),
+            pomodoro: PomodoroState::Off,
+            current_session_duration: Duration::default(),
+            last_session_subject_id: Uuid::new_v4(),
+            last_save: SystemTime::now(),
+            history: History::new(),
+            todos: PContainer::new("root"),
+            dirty: false,
+            storage: Some(Box::new(RonStorage::new(&data_dir()))),
+            saves_held: false,
+            totals: None,
+            todo_counts: None,
+            interrupted_at: None,
+            save_error: None,
+            clock_behind: None,
+            scratchpad: String::new(),
+            scratchpad_day: None,
+            journal: BTreeMap::new(),
+            end_of_day_shown: None,
+            detached_session: None,
+            settings: Settings::default(),
+            window_state: WindowState::default(),
+            clock: system_clock(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Backend {
+    /// A backend that never saves, for tests.
+    pub(crate) fn unsaved() -> Self {
+        Self {
+            storage: None,
+            ..Self::default()
+        }
+    }
+
+    /// A backend that never saves, reading the time from `clock`.
+    pub(crate) fn unsaved_at(clock: &crate::clock::TestClock) -> Self {
+        Self {
+            clock: Box::new(clock.clone()),
+            ..Self::unsaved()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Subject {
+    pub(crate) id: Uuid,
+    pub(crate) name: String,
+    pub(crate) created_at: SystemTime,
+    pub(crate) duration: Duration,
+    pub(crate) is_deleted: bool,
+    #[serde(default)]
+    pub(crate) deleted_at: Option<SystemTime>,
+    /// Listed above the subjects column whatever sub project is selected.
+    #[serde(default)]
+    pub(crate) is_pinned: bool,
+    /// Issue or ticket the subject is about, opened from its row.
+    #[serde(default)]
+    pub(crate) link: Option<String>,
+    /// Timeline color instead of the one of its sub project or project.
+    #[serde(default)]
+    pub(crate) color: Option<(u8, u8, u8)>,
+    /// Filled into the note asked for when a session of the subject is paused.
+    #[serde(default)]
+    pub(crate) note_template: Option<String>,
+}
+
+impl Subject {
+    fn create(name: &str) -> Self {
+        Subject {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            created_at: SystemTime::now(),
+            duration: Duration::default(),
+            is_deleted: false,
+            deleted_at: None,
+            is_pinned: false,
+            link: None,
+            color: None,
+            note_template: None,
+        }
+    }
+}
+
+/// Written out so tests can count the clones, the Time view is drawn without any.
+impl Clone for Subject {
+    fn clone(&self) -> Self {
+        #[cfg(test)]
+        SUBJECT_CLONES.with(|v| v.set(v.get() + 1));
+
+        Subject {
+            id: self.id,
+            name: self.name.clone(),
+            created_at: self.created_at,
+            duration: self.duration,
+            is_deleted: self.is_deleted,
+            deleted_at: self.deleted_at,
+            is_pinned: self.is_pinned,
+            link: self.link.clone(),
+            color: self.color,
+            note_template: self.note_template.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Subjects cloned on this thread.
+    pub(crate) static SUBJECT_CLONES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Ordered so that sorting ascending puts high priority todos first.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Low];
+
+    /// Next state of the row toggle.
+    pub fn next(self) -> Self {
+        match self {
+            Priority::Normal => Priority::High,
+            Priority::High => Priority::Low,
+            Priority::Low => Priority::Normal,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TodoSubject {
+    pub(crate) id: Uuid,
+    pub(crate) name: String,
+    pub(crate) created_at: SystemTime,
+    pub(crate) is_deleted: bool,
+    #[serde(default)]
+    pub(crate) deleted_at: Option<SystemTime>,
+    pub(crate) is_done: bool,
+    #[serde(default)]
+    pub(crate) due: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub(crate) priority: Priority,
+    /// When the todo was last checked off, `None` while open.
+    #[serde(default)]
+    pub(crate) completed_at: Option<SystemTime>,
+    #[serde(default)]
+    pub(crate) notes: String,
+    /// Issue or ticket the todo is about, opened from its row.
+    #[serde(default)]
+    pub(crate) link: Option<String>,
+}
+
+impl TodoSubject {
+    fn create(name: &str, priority: Priority) -> Self {
+        TodoSubject {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            created_at: SystemTime::now(),
+            is_deleted: false,
+            deleted_at: None,
+            is_done: false,
+            due: None,
+            priority,
+            completed_at: None,
+            notes: String::new(),
+            link: None,
+        }
+    }
+
+    pub(crate) fn toggle(&mut self) {
+        self.is_done = !self.is_done;
+        self.completed_at = self.is_done.then(SystemTime::now);
+    }
+
+    /// Open and past its due time.
+    pub(crate) fn is_overdue(&self, now: DateTime<Local>) -> bool {
+        !self.is_done && self.due.is_some_and(|v| v < now)
+    }
+
+    /// Open and due later on the day of `now`.
+    pub(crate) fn is_due_today(&self, now: DateTime<Local>) -> bool {
+        !self.is_done
+            && self
+                .due
+                .is_some_and(|v| v >= now && v.date_naive() == now.date_naive())
+    }
+}
+
+/// Name, deleted flag and deletion time of an entry.
+type EntryParts<'a> = (&'a mut String, &'a mut bool, &'a mut Option<SystemTime>);
+
+/// Tracked and todo subjects, the leaves of a chain.
+pub trait Leaf {
+    fn id(&self) -> Uuid;
+    fn name(&self) -> &str;
+    fn is_deleted(&self) -> bool;
+    fn deleted_at(&self) -> Option<SystemTime>;
+    fn parts_mut(&mut self) -> EntryParts<'_>;
+    fn link_mut(&mut self) -> &mut Option<String>;
+}
+
+impl Leaf for Subject {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+
+    fn deleted_at(&self) -> Option<SystemTime> {
+        self.deleted_at
+    }
+
+    fn parts_mut(&mut self) -> EntryParts<'_> {
+        (&mut self.name, &mut self.is_deleted, &mut self.deleted_at)
+    }
+
+    fn link_mut(&mut self) -> &mut Option<String> {
+        &mut self.link
+    }
+}
+
+impl Leaf for TodoSubject {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+
+    fn deleted_at(&self) -> Option<SystemTime> {
+        self.deleted_at
+    }
+
+    fn parts_mut(&mut self) -> EntryParts<'_> {
+        (&mut self.name, &mut self.is_deleted, &mut self.deleted_at)
+    }
+
+    fn link_mut(&mut self) -> &mut Option<String> {
+        &mut self.link
+    }
+}
+
+type Chain<S> = PContainer<PContainer<PContainer<Arc<Mutex<S>>, Uuid>, Uuid>, Uuid>;
+
+fn chain_entry_name<S: Leaf>(chain: &Chain<S>, path: EntryPath) -> Option<String> {
+    match path {
+        EntryPath::Project(p) => Some(chain.inner.get(&p)?.name.clone()),
+        EntryPath::SubProject(p, s) => Some(chain.inner.get(&p)?.inner.get(&s)?.name.clone()),
+        EntryPath::Subject(p, s, x) => {
+            let subject = chain.inner.get(&p)?.inner.get(&s)?.inner.get(&x)?;
+            let name = subject.lock().unwrap().name().to_string();
+
+            Some(name)
+        }
+    }
+}
+
+/// Runs `f` on the link of the subject at `path`, `None` for other entries or when it is gone.
+fn chain_link_mut<S: Leaf, R>(
+    chain: &Chain<S>,
+    path: EntryPath,
+    f: impl FnOnce(&mut Option<String>) -> R,
+) -> Option<R> {
+    let EntryPath::Subject(p, s, x) = path else {
+        return None;
+    };
+
+    let subject = chain.inner.get(&p)?.inner.get(&s)?.inner.get(&x)?;
+    let result = f(subject.lock().unwrap().link_mut());
+
+    Some(result)
+}
+
+/// Levels of a `project/sub project/subject` path, blank ones left out.
+pub fn path_names(path: &str) -> Vec<&str> {
+    path.split('/')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// Adds what is missing of the path `names`, a project added gets `project_color`.
+fn chain_add_path<S: Leaf + Serialize + DeserializeOwned>(
+    chain: &mut Chain<S>,
+    names: &[&str],
+    project_color: (u8, u8, u8),
+    create: impl FnOnce(&str) -> S,
+) -> Option<EntryPath> {
+    fn find_or_insert<T: Serialize + DeserializeOwned + Clone>(
+        container: &mut PContainer<PContainer<T, Uuid>, Uuid>,
+        name: &str,
+        color: Option<(u8, u8, u8)>,
+    ) -> Uuid {
+        let found = container
+            .inner
+            .values()
+            .find(|v| !v.is_deleted && v.name.trim().eq_ignore_ascii_case(name));
+
+        if let Some(found) = found {
+            return found.id;
+        }
+
+        let mut added = PContainer::new(name);
+        added.color = color.unwrap_or(added.color);
+        let id = added.id;
+        container.inner.insert(id, added);
+
+        id
+    }
+
+    let [project_name, rest @ ..] = names else {
+        return None;
+    };
+
+    let project_id = find_or_insert(chain, project_name, Some(project_color));
+    let project = chain.inner.get_mut(&project_id)?;
+
+    if project.is_archived {
+        return None;
+    }
+
+    let [sub_project_name, rest @ ..] = rest else {
+        return Some(EntryPath::Project(project_id));
+    };
+
+    let sub_project_id = find_or_insert(project, sub_project_name, None);
+    let sub_project = project.inner.get_mut(&sub_project_id)?;
+
+    let [subject_name] = rest else {
+        return Some(EntryPath::SubProject(project_id, sub_project_id));
+    };
+
+    let found = sub_project.inner.iter().find(|(_, v)| {
+        let v = v.lock().unwrap();
+        !v.is_deleted() && v.name().trim().eq_ignore_ascii_case(subject_name)
+    });
+
+    let subject_id = match found {
+        Some((id, _)) => *id,
+        None => {
+            let subject = create(subject_name);
+            let id = subject.id();
+            sub_project.inner.insert(id, Arc::new(Mutex::new(subject)));
+            id
+        }
+    };
+
+    Some(EntryPath::Subject(project_id, sub_project_id, subject_id))
+}
+
+/// Archived projects are skipped with everything in them, nothing there can be selected.
+fn chain_search<S: Leaf>(chain: &Chain<S>, query: &str) -> Vec<(EntryPath, String)> {
+    let query = query.to_lowercase();
+    let matches = |name: &str| name.to_lowercase().contains(&query);
+
+    let mut found = Vec::new();
+
+    for project in chain.inner.values() {
+        if project.is_deleted || project.is_archived {
+            continue;
+        }
+
+        if matches(&project.name) {
+            found.push((EntryPath::Project(project.id), project.name.clone()));
+        }
+
+        for sub_project in project.inner.values().filter(|v| !v.is_deleted) {
+            let label = format!("{} / {}", project.name, sub_project.name);
+
+            if matches(&sub_project.name) {
+                let path = EntryPath::SubProject(project.id, sub_project.id);
+                found.push((path, label.clone()));
+            }
+
+            for (id, subject) in &sub_project.inner {
+                let subject = subject.lock().unwrap();
+
+                if subject.is_deleted() || !matches(subject.name()) {
+                    continue;
+                }
+
+                let path = EntryPath::Subject(project.id, sub_project.id, *id);
+                found.push((path, format!("{label} / {}", subject.name())));
+            }
+        }
+    }
+
+    found
+}
+
+fn chain_children<S: Leaf>(chain: &Chain<S>, parent: Option<EntryPath>) -> Vec<(Uuid, String)> {
+    fn live<T>(c: &PContainer<T, Uuid>) -> Option<(Uuid, String)> {
+        (!c.is_deleted).then(|| (c.id, c.name.clone()))
+    }
+
+    match parent {
+        None => chain.inner.values().filter_map(live).collect(),
+        Some(EntryPath::Project(p)) => chain
+            .inner
+            .get(&p)
+            .map(|v| v.inner.values().filter_map(live).collect())
+            .unwrap_or_default(),
+        Some(EntryPath::SubProject(p, s)) => chain
+            .inner
+            .get(&p)
+            .and_then(|v| v.inner.get(&s))
+            .map(|v| {
+                v.inner
+                    .iter()
+                    .filter_map(|(id, v)| {
+                        let v = v.lock().unwrap();
+                        (!v.is_deleted()).then(|| (*id, v.name().to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        Some(EntryPath::Subject(..)) => vec![],
+    }
+}
+
+fn chain_entry_mut<S: Leaf, R>(
+    chain: &mut Chain<S>,
+    path: EntryPath,
+    f: impl FnOnce(&mut String, &mut bool, &mut Option<SystemTime>) -> R,
+) -> Option<R> {
+    match path {
+        EntryPath::Project(p) => {
+            let v = chain.inner.get_mut(&p)?;
+            Some(f(&mut v.name, &mut v.is_deleted, &mut v.deleted_at))
+        }
+        EntryPath::SubProject(p, s) => {
+            let v = chain.inner.get_mut(&p)?.inner.get_mut(&s)?;
+            Some(f(&mut v.name, &mut v.is_deleted, &mut v.deleted_at))
+        }
+        EntryPath::Subject(p, s, x) => {
+            let mut v = chain
+                .inner
+                .get(&p)?
+                .inner
+                .get(&s)?
+                .inner
+                .get(&x)?
+                .lock()
+                .unwrap();
+            let (name, is_deleted, deleted_at) = v.parts_mut();
+            Some(f(name, is_deleted, deleted_at))
+        }
+    }
+}
+
+fn chain_delete<S: Leaf>(chain: &mut Chain<S>, path: EntryPath) -> Option<()> {
+    chain_entry_mut(chain, path, |_, is_deleted, deleted_at| {
+        *is_deleted = true;
+        *deleted_at = Some(SystemTime::now());
+    })?;
+
+    // A hidden entry must not stay selected.
+    match path {
+        EntryPath::Project(p) => unselect(chain, p),
+        EntryPath::SubProject(p, s) => unselect(chain.inner.get_mut(&p)?, s),
+        EntryPath::Subject(p, s, x) => unselect(chain.inner.get_mut(&p)?.inner.get_mut(&s)?, x),
+    }
+
+    Some(())
+}
+
+/// Deleted entries with the names leading to them, including those inside deleted parents.
+fn chain_trash<S: Leaf>(chain: &Chain<S>) -> Vec<TrashEntry> {
+    let mut found = Vec::new();
+
+    for project in chain.inner.values() {
+        if project.is_deleted {
+            found.push(TrashEntry {
+                path: EntryPath::Project(project.id),
+                label: project.name.clone(),
+                deleted_at: project.deleted_at,
+            });
+        }
+
+        for sub_project in project.inner.values() {
+            let label = format!("{} / {}", project.name, sub_project.name);
+
+            if sub_project.is_deleted {
+                found.push(TrashEntry {
+                    path: EntryPath::SubProject(project.id, sub_project.id),
+                    label: label.clone(),
+                    deleted_at: sub_project.deleted_at,
+                });
+            }
+
+            for (id, subject) in &sub_project.inner {
+                let subject = subject.lock().unwrap();
+
+                if subject.is_deleted() {
+                    found.push(TrashEntry {
+                        path: EntryPath::Subject(project.id, sub_project.id, *id),
+                        label: format!("{label} / {}", subject.name()),
+                        deleted_at: subject.deleted_at(),
+                    });
+                }
+            }
+        }
+    }
+
+    found
+}
+
+/// Brings back an entry along with any deleted parent above it. Nothing changes when one of
+/// them would share its name with a live sibling.
+fn chain_restore<S: Leaf>(chain: &mut Chain<S>, path: EntryPath) -> Option<()> {
+    let mut levels = Vec::new();
+    let mut next = Some(path);
+
+    while let Some(v) = next {
+        levels.push(v);
+        next = v.parent();
+    }
+
+    for level in &levels {
+        let (name, is_deleted) = chain_entry_mut(chain, *level, |name, is_deleted, _| {
+            (name.clone(), *is_deleted)
+        })?;
+
+        let taken = chain_children(chain, level.parent())
+            .iter()
+            .any(|(id, v)| *id != level.id() && v.trim().eq_ignore_ascii_case(name.trim()));
+
+        if is_deleted && taken {
+            return None;
+        }
+    }
+
+    for level in levels {
+        chain_entry_mut(chain, level, |_, is_deleted, deleted_at| {
+            *is_deleted = false;
+            *deleted_at = None;
+        })?;
+    }
+
+    Some(())
+}
+
+/// Removes deleted entries for good, with everything inside them, unless their id is in
+/// `keep`. Returns how many were removed.
+fn chain_purge<S: Leaf>(chain: &mut Chain<S>, keep: &HashSet<Uuid>) -> usize {
+    let mut removed = 0;
+
+    chain.inner.retain(|id, project| {
+        if project.is_deleted && !keep.contains(id) {
+            removed += 1;
+            return false;
+        }
+
+        project.inner.retain(|id, sub_project| {
+            if sub_project.is_deleted && !keep.contains(id) {
+                removed += 1;
+                return false;
+            }
+
+            sub_project.inner.retain(|id, subject| {
+                let gone = subject.lock().unwrap().is_deleted() && !keep.contains(id);
+                removed += usize::from(gone);
+
+                !gone
+            });
+
+            true
+        });
+
+        true
+    });
+
+    removed
+}
+
+/// Containers of a chain whose selection is missing or deleted, `None` standing for the chain.
+fn dangling_selections<S: Leaf>(chain: &Chain<S>) -> Vec<Option<EntryPath>> {
+    fn dangling<T>(container: &PContainer<T, Uuid>, is_deleted: impl Fn(&T) -> bool) -> bool {
+        container
+            .current_inner_id
+            .is_some_and(|id| container.inner.get(&id).is_none_or(is_deleted))
+    }
+
+    let mut found = Vec::new();
+
+    if dangling(chain, |v| v.is_deleted) {
+        found.push(None);
+    }
+
+    for project in chain.inner.values() {
+        if dangling(project, |v| v.is_deleted) {
+            found.push(Some(EntryPath::Project(project.id)));
+        }
+
+        for sub_project in project.inner.values() {
+            if dangling(sub_project, |v| v.lock().unwrap().is_deleted()) {
+                found.push(Some(EntryPath::SubProject(project.id, sub_project.id)));
+            }
+        }
+    }
+
+    found
+}
+
+fn clear_selection<S: Leaf>(chain: &mut Chain<S>, container: Option<EntryPath>) {
+    let current = match container {
+        None => &mut chain.current_inner_id,
+        Some(EntryPath::Project(p)) => match chain.inner.get_mut(&p) {
+            Some(v) => &mut v.current_inner_id,
+            None => return,
+        },
+        Some(EntryPath::SubProject(p, s)) => {
+            match chain.inner.get_mut(&p).and_then(|v| v.inner.get_mut(&s)) {
+                Some(v) => &mut v.current_inner_id,
+                None => return,
+            }
+        }
+        Some(EntryPath::Subject(..)) => return,
+    };
+
+    *current = None;
+}
+
+fn unselect<T>(container: &mut PContainer<T, Uuid>, id: Uuid) {
+    if container.current_inner_id == Some(id) {
+        container.current_inner_id = None;
+    }
+}
+
+fn chain_move<S: Leaf>(chain: &mut Chain<S>, path: EntryPath, parent: EntryPath) -> Option<()> {
+    match (path, parent) {
+        (EntryPath::SubProject(p, s), EntryPath::Project(target)) if p != target => {
+            chain.inner.get(&target)?;
+
+            let project = chain.inner.get_mut(&p)?;
+            let sub_project = project.inner.remove(&s)?;
+            unselect(project, s);
+
+            chain.inner.get_mut(&target)?.inner.insert(s, sub_project);
+        }
+        (EntryPath::Subject(p, s, x), EntryPath::SubProject(target_p, target_s))
+            if (p, s) != (target_p, target_s) =>
+        {
+            chain.inner.get(&target_p)?.inner.get(&target_s)?;
+
+            let sub_project = chain.inner.get_mut(&p)?.inner.get_mut(&s)?;
+            let subject = sub_project.inner.remove(&x)?;
+            unselect(sub_project, x);
+
+            chain
+                .inner
+                .get_mut(&target_p)?
+                .inner
+                .get_mut(&target_s)?
+                .inner
+                .insert(x, subject);
+        }
+        _ => return None,
+    }
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TestClock;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, min, s).unwrap()
+    }
+
+    /// A backend that never saves, on a clock standing at `now`, with a subject selected.
+    fn backend_at(now: DateTime<Local>) -> (Backend, TestClock, EntryPath) {
+        let clock = TestClock::at(now);
+
+        let mut backend = Backend::unsaved_at(&clock);
+
+        let path = backend
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+
+        (backend, clock, path)
+    }
+
+    /// Lets `seconds` pass with a frame every second.
+    fn run(backend: &mut Backend, clock: &TestClock, seconds: u64) {
+        for _ in 0..seconds {
+            clock.advance(Duration::from_secs(1));
+            backend.update_time();
+        }
+    }
+
+    fn records(backend: &Backend) -> Vec<HistoryRecord> {
+        let mut records = backend.history.records().copied().collect::<Vec<_>>();
+        records.sort();
+        records
+    }
+
+    fn subject_duration(backend: &Backend, path: EntryPath) -> Duration {
+        backend.subject(path).unwrap().lock().unwrap().duration
+    }
+
+    #[test]
+    fn accumulates_monotonic_time() {
+        let (mut backend, clock, path) = backend_at(at(2024, 3, 5, 10, 0, 0));
+
+        backend.start_subject();
+        run(&mut backend, &clock, 90);
+
+        assert_eq!(subject_duration(&backend, path), Duration::from_secs(90));
+        assert_eq!(backend.current_session_duration, Duration::from_secs(90));
+
+        let [record] = records(&backend)[..] else {
+            panic!("expected one record");
+        };
+
+        assert_eq!(record.end_date, at(2024, 3, 5, 10, 1, 30));
+    }
+
+    #[test]
+    fn wall_clock_set_back_keeps_counting() {
+        let (mut backend, clock, path) = backend_at(at(2024, 3, 5, 10, 0, 0));
+
+        backend.start_subject();
+        run(&mut backend, &clock, 40);
+
+        clock.set_wall(at(2024, 3, 5, 9, 0, 0));
+        run(&mut backend, &clock, 20);
+
+        assert!(matches!(backend.working_mode, WorkingMode::InProgress(_)));
+        assert_eq!(subject_duration(&backend, path), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn hidden_window_keeps_counting() {
+        let (mut backend, clock, path) = backend_at(at(2024, 3, 5, 10, 0, 0));
+
+        backend.start_subject();
+        run(&mut backend, &clock, 60);
+
+        clock.advance(Duration::from_secs(20 * 60));
+        run(&mut backend, &clock, 1);
+
+        assert!(matches!(backend.working_mode, WorkingMode::InProgress(_)));
+        assert_eq!(backend.interrupted_at, None);
+        assert_eq!(
+            subject_duration(&backend, path),
+            Duration::from_secs(21 * 60 + 1)
+        );
+
+        let [record] = records(&backend)[..] else {
+            panic!("expected one record");
+        };
+
+        assert_eq!(record.end_date, at(2024, 3, 5, 10, 21, 1));
+    }
+
+    #[test]
+    fn stop_counts_time_since_last_tick() {
+        let (mut backend, clock, path) = backend_at(at(2024, 3, 5, 10, 0, 0));
+
+        backend.start_subject();
+        run(&mut backend, &clock, 30);
+
+        clock.advance(Duration::from_millis(15 * 60 * 1000 + 250));
+        backend.stop_subject(false);
+
+        let [record] = records(&backend)[..] else {
+            panic!("expected one record");
+        };
+
+        let duration = subject_duration(&backend, path);
+
+        assert_eq!(duration, Duration::from_millis(15 * 60 * 1000 + 30_250));
+        assert_eq!(record.get_duration().to_std().unwrap(), duration);
+        assert_eq!(record.end_date, backend.now());
+    }
+
+    #[test]
+    fn stop_after_suspend_ends_at_last_tick() {
+        let (mut backend, clock, path) = backend_at(at(2024, 3, 5, 10, 0, 0));
+
+        backend.start_subject();
+        run(&mut backend, &clock, 45);
+
+        clock.sleep(Duration::from_secs(60 * 60));
+        backend.stop_subject(false);
+
+        let [record] = records(&backend)[..] else {
+            panic!("expected one record");
+        };
+
+        assert_eq!(record.end_date, at(2024, 3, 5, 10, 0, 45));
+        assert_eq!(subject_duration(&backend, path), Duration::from_secs(45));
+    }
+
+    #[test]
+    fn failed_write_is_reported_and_retried() {
+        let (mut backend, _clock, _) = backend_at(at(2024, 3, 5, 10, 0, 0));
+
+        let dir = std::env::temp_dir().join(format!("ruh-missing-{}", Uuid::new_v4()));
+        backend.storage = Some(Box::new(RonStorage::new(&dir)));
+
+        backend.dump();
+        assert!(!backend.dirty);
+
+        backend.flush();
+        backend.update_time();
+
+        assert!(backend.dirty);
+        assert!(backend.save_error.is_some());
+
+        std::fs::create_dir_all(&dir).unwrap();
+
+        backend.dump();
+        backend.flush();
+        backend.update_time();
+
+        assert!(!backend.dirty);
+        assert_eq!(backend.save_error, None);
+        assert!(dir.join(crate::storage::DATA_FILE).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn splits_session_at_midnight() {
+        let (mut backend, clock, path) = backend_at(at(2024, 3, 5, 23, 59, 30));
+
+        backend.start_subject();
+        run(&mut backend, &clock, 60);
+        backend.stop_subject(false);
+
+        let [before, after] = records(&backend)[..] else {
+            panic!("expected a record on each day");
+        };
+
+        assert_eq!(before.start_date, at(2024, 3, 5, 23, 59, 30));
+        assert_eq!(
+            before.end_date,
+            at(2024, 3, 6, 0, 0, 0) - chrono::Duration::milliseconds(1)
+        );
+        assert_eq!(after.start_date, at(2024, 3, 6, 0, 0, 0));
+        assert_eq!(after.end_date, at(2024, 3, 6, 0, 0, 30));
+        assert_eq!(subject_duration(&backend, path), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn suspend_ends_session_at_last_tick() {
+        let (mut backend, clock, path) = backend_at(at(2024, 3, 5, 10, 0, 0));
+
+        backend.start_subject();
+        run(&mut backend, &clock, 60);
+
+        clock.sleep(Duration::from_secs(8 * 60 * 60));
+        run(&mut backend, &clock, 1);
+
+        assert!(matches!(backend.working_mode, WorkingMode::Idle));
+        assert_eq!(backend.interrupted_at, Some(at(2024, 3, 5, 10, 1, 0)));
+        assert_eq!(subject_duration(&backend, path), Duration::from_secs(60));
+
+        let [record] = records(&backend)[..] else {
+            panic!("expected one record");
+        };
+
+        assert_eq!(record.end_date, at(2024, 3, 5, 10, 1, 0));
+    }
+
+    #[test]
+    fn current_work_name_is_the_bare_path() {
+        let (mut backend, ..) = backend_at(at(2024, 3, 5, 10, 0, 0));
+
+        assert_eq!(
+            backend.get_current_work_name().as_deref(),
+            Some("Client/Website/Navbar")
+        );
+
+        backend.projects.current_inner_id = None;
+
+        assert_eq!(backend.get_current_work_name(), None);
+    }
+
+    #[test]
+    fn orphan_records_resolve_to_placeholders() {
+        let (mut backend, _, path) = backend_at(at(2024, 3, 5, 10, 0, 0));
+        let EntryPath::Subject(p, s, _) = path else {
+            unreachable!();
+        };
+
+        backend.projects.inner.get_mut(&p).unwrap().color = (10, 20, 30);
+
+        backend.add_manual_record(
+            p,
+            s,
+            path.id(),
+            at(2024, 3, 5, 8, 0, 0),
+            at(2024, 3, 5, 9, 0, 0),
+        );
+
+        let orphan = backend.history.add_manual_record(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            at(2024, 3, 5, 9, 0, 0),
+            at(2024, 3, 5, 9, 30, 0),
+        );
+        let stray_subject = backend.history.add_manual_record(
+            p,
+            s,
+            Uuid::new_v4(),
+            at(2024, 3, 5, 9, 30, 0),
+            at(2024, 3, 5, 9, 45, 0),
+        );
+
+        let orphan = backend.get_record_path(backend.history.get(&orphan).unwrap());
+
+        assert_eq!(orphan.project, "<deleted project>");
+        assert_eq!(orphan.sub_project, "<deleted sub project>");
+        assert_eq!(orphan.subject, "<deleted subject>");
+        assert_eq!(orphan.color, MISSING_COLOR);
+
+        let stray_subject = backend.get_record_path(backend.history.get(&stray_subject).unwrap());
+
+        assert_eq!(stray_subject.project, "Client");
+        assert_eq!(stray_subject.sub_project, "Website");
+        assert_eq!(stray_subject.subject, "<deleted subject>");
+        assert_eq!(stray_subject.color, (10, 20, 30));
+
+        assert_eq!(
+            backend.project_day_totals(NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()),
+            [
+                ("Client".to_string(), chrono::Duration::minutes(75)),
+                (
+                    "<deleted project>".to_string(),
+                    chrono::Duration::minutes(30)
+                ),
+            ]
+        );
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, d).unwrap()
+    }
+
+    #[test]
+    fn notes_move_to_the_journal_on_a_new_day() {
+        let (mut backend, clock, _) = backend_at(at(2024, 3, 5, 17, 0, 0));
+        backend.settings.daily_notes = true;
+
+        backend.roll_scratchpad();
+        backend.scratchpad = "Call back\n".to_string();
+
+        clock.set_wall(at(2024, 3, 5, 23, 59, 0));
+        backend.roll_scratchpad();
+
+        assert_eq!(backend.scratchpad, "Call back\n");
+        assert!(backend.journal.is_empty());
+
+        clock.set_wall(at(2024, 3, 6, 0, 1, 0));
+        backend.dirty = false;
+        backend.roll_scratchpad();
+
+        assert_eq!(backend.scratchpad, "");
+        assert_eq!(backend.scratchpad_day, Some(day(6)));
+        assert_eq!(
+            backend.journal,
+            BTreeMap::from([(day(5), "Call back".to_string())])
+        );
+        assert!(backend.dirty);
+    }
+
+    #[test]
+    fn notes_append_to_the_journal_entry_of_their_day() {
+        let (mut backend, clock, _) = backend_at(at(2024, 3, 6, 9, 0, 0));
+        backend.settings.daily_notes = true;
+        backend.journal.insert(day(5), "Order paper".to_string());
+
+        // The clock was set back, notes of the later day are kept until a new day starts.
+        backend.scratchpad_day = Some(day(7));
+        backend.scratchpad = "Call back".to_string();
+        backend.roll_scratchpad();
+
+        assert_eq!(backend.scratchpad, "Call back");
+
+        backend.scratchpad_day = Some(day(5));
+        backend.roll_scratchpad();
+
+        assert_eq!(backend.journal[&day(5)], "Order paper\nCall back");
+
+        // Blank notes leave no entry behind.
+        backend.scratchpad = " \n".to_string();
+        clock.set_wall(at(2024, 3, 9, 9, 0, 0));
+        backend.roll_scratchpad();
+
+        assert_eq!(backend.journal.len(), 1);
+    }
+
+    #[test]
+    fn notes_stay_without_daily_notes() {
+        let (mut backend, clock, _) = backend_at(at(2024, 3, 5, 17, 0, 0));
+
+        backend.roll_scratchpad();
+        backend.scratchpad = "Call back".to_string();
+
+        clock.set_wall(at(2024, 3, 6, 9, 0, 0));
+        backend.roll_scratchpad();
+
+        assert_eq!(backend.scratchpad, "Call back");
+        assert_eq!(backend.scratchpad_day, Some(day(6)));
+        assert!(backend.journal.is_empty());
+    }
+
+    #[test]
+    fn todo_expansion_is_saved_with_the_settings() {
+        let mut backend = Backend::unsaved();
+        let [project, sub_project] = [Uuid::new_v4(), Uuid::new_v4()];
+
+        backend.set_todo_expanded(project, true);
+        backend.set_todo_expanded(sub_project, true);
+
+        assert!(backend.dirty);
+        assert!(backend.is_todo_expanded(&project));
+
+        backend.dirty = false;
+        backend.set_todo_expanded(project, true);
+
+        assert!(!backend.dirty, "unchanged state is not saved again");
+
+        backend.set_todo_expanded(sub_project, false);
+
+        assert!(backend.dirty);
+
+        let loaded = ron::from_str::<Backend>(&backend.to_ron().unwrap()).unwrap();
+
+        assert!(loaded.is_todo_expanded(&project));
+        assert!(!loaded.is_todo_expanded(&sub_project));
+    }
+
+    #[test]
+    fn todos_are_added_at_the_selected_level() {
+        let mut backend = Backend::unsaved();
+
+        let Some(EntryPath::Subject(home, garden, _)) =
+            backend.add_path("Home/Garden/Mow", Tree::Todo)
+        else {
+            panic!("expected a todo");
+        };
+        let Some(EntryPath::Subject(work, mail, _)) =
+            backend.add_path("Work/Mail/Reply", Tree::Todo)
+        else {
+            panic!("expected a todo");
+        };
+
+        // The add button of a sub project selects its chain before the dialog adds to it.
+        backend.set_current_todo_project(Some(home));
+        backend.set_current_todo_sub_project(Some(garden));
+        backend.add_todo_subject("Water", Priority::High);
+
+        // The add button of a project only selects the project.
+        backend.set_current_todo_project(Some(work));
+        backend.add_todo_sub_project("Calls");
+
+        let names = |project: Uuid, sub_project: Uuid| {
+            let mut names = backend.todos.inner[&project].inner[&sub_project]
+                .inner
+                .values()
+                .map(|v| v.lock().unwrap().name.clone())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(home, garden), ["Mow", "Water"]);
+        assert_eq!(names(work, mail), ["Reply"]);
+
+        let mut sub_projects = backend.todos.inner[&work]
+            .inner
+            .values()
+            .map(|v| v.name.clone())
+            .collect::<Vec<_>>();
+        sub_projects.sort();
+
+        assert_eq!(sub_projects, ["Calls", "Mail"]);
+        assert_eq!(backend.todos.inner[&home].inner.len(), 1);
+
+        backend.add_todo_project("Errands");
+
+        assert_eq!(backend.todos.inner.len(), 3);
+        assert!(backend.projects.inner.is_empty());
+    }
+
+    #[test]
+    fn record_edits_move_the_subject_time() {
+        let (mut backend, _, path) = backend_at(at(2024, 3, 5, 18, 0, 0));
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+
+        let id = backend
+            .add_manual_record(p, s, x, at(2024, 3, 5, 9, 0, 0), at(2024, 3, 5, 17, 55, 0))
+            .unwrap();
+        backend
+            .add_manual_record(p, s, x, at(2024, 3, 4, 9, 0, 0), at(2024, 3, 4, 10, 0, 0))
+            .unwrap();
+        backend.dirty = false;
+
+        assert!(backend.set_record_times(id, at(2024, 3, 5, 9, 0, 0), at(2024, 3, 5, 17, 30, 0)));
+        assert!(backend.dirty);
+        assert_eq!(
+            subject_duration(&backend, path),
+            Duration::from_secs((8 * 60 + 30 + 60) * 60)
+        );
+
+        // Moved to the day before, where range queries have to find it.
+        assert!(backend.set_record_times(id, at(2024, 3, 4, 12, 0, 0), at(2024, 3, 4, 13, 0, 0)));
+        assert_eq!(
+            subject_duration(&backend, path),
+            Duration::from_secs(2 * 60 * 60)
+        );
+        assert_eq!(
+            backend
+                .history
+                .get_records((at(2024, 3, 4, 0, 0, 0), at(2024, 3, 5, 0, 0, 0)))
+                .len(),
+            2
+        );
+        assert!(backend
+            .history
+            .get_records((at(2024, 3, 5, 0, 0, 0), at(2024, 3, 6, 0, 0, 0)))
+            .is_empty());
+    }
+
+    #[test]
+    fn rejected_record_edits_change_nothing() {
+        let (mut backend, clock, path) = backend_at(at(2024, 3, 5, 18, 0, 0));
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+
+        let id = backend
+            .add_manual_record(p, s, x, at(2024, 3, 5, 9, 0, 0), at(2024, 3, 5, 10, 0, 0))
+            .unwrap();
+        let before = records(&backend);
+
+        backend.start_subject();
+        run(&mut backend, &clock, 60);
+
+        let session = backend.active_session_id().unwrap();
+        let duration = subject_duration(&backend, path);
+        backend.dirty = false;
+
+        for (id, start, end) in [
+            // Reversed and empty spans.
+            (id, at(2024, 3, 5, 10, 0, 0), at(2024, 3, 5, 9, 0, 0)),
+            (id, at(2024, 3, 5, 9, 0, 0), at(2024, 3, 5, 9, 0, 0)),
+            // The running session.
+            (session, at(2024, 3, 5, 17, 0, 0), at(2024, 3, 5, 18, 0, 0)),
+            // A record that does not exist.
+            (
+                Uuid::new_v4(),
+                at(2024, 3, 5, 9, 0, 0),
+                at(2024, 3, 5, 10, 0, 0),
+            ),
+        ] {
+            assert!(!backend.set_record_times(id, start, end));
+        }
+
+        assert!(!backend.dirty);
+        assert_eq!(subject_duration(&backend, path), duration);
+
+        let after = records(&backend);
+
+        assert!(after.iter().any(|v| v.same_fields(&before[0])));
+        assert_eq!(after.len(), 2);
+    }
+
+    #[test]
+    fn clock_behind_the_newest_record_is_detected() {
+        let (mut backend, clock, path) = backend_at(at(2024, 3, 5, 18, 0, 0));
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+
+        backend.check_clock();
+
+        assert_eq!(backend.clock_behind, None, "nothing to compare against");
+
+        backend
+            .add_manual_record(p, s, x, at(2024, 3, 5, 17, 0, 0), at(2024, 3, 5, 18, 4, 0))
+            .unwrap();
+        backend.check_clock();
+
+        assert_eq!(backend.clock_behind, None, "within the tolerance");
+
+        // Restored from a snapshot a day old.
+        clock.set_wall(at(2024, 3, 4, 18, 4, 0));
+        backend.start_subject();
+
+        assert_eq!(backend.clock_behind, Some(chrono::Duration::days(1)));
+    }
+
+    #[test]
+    fn clock_set_back_never_makes_time_negative() {
+        let (mut backend, clock, path) = backend_at(at(2024, 3, 5, 18, 0, 0));
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+
+        backend
+            .add_manual_record(p, s, x, at(2024, 3, 5, 9, 0, 0), at(2024, 3, 5, 10, 0, 0))
+            .unwrap();
+
+        backend.start_subject();
+        run(&mut backend, &clock, 60);
+
+        clock.set_wall(at(2024, 3, 4, 12, 0, 0));
+        run(&mut backend, &clock, 60);
+
+        let session = backend
+            .history
+            .get(&backend.active_session_id().unwrap())
+            .copied()
+            .unwrap();
+
+        // The record stops where it started instead of ending before it.
+        assert_eq!(session.end_date, session.start_date);
+        assert_eq!(session.get_duration(), chrono::Duration::zero());
+
+        // The worked time still counts, from the monotonic clock.
+        assert_eq!(
+            subject_duration(&backend, path),
+            Duration::from_secs(60 * 60 + 2 * 60)
+        );
+
+        let today = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+
+        assert_eq!(
+            backend.project_day_totals(today),
+            [("Client".to_string(), chrono::Duration::hours(1))]
+        );
+        assert!(backend
+            .history
+            .daily_totals(today - chrono::Duration::days(1), today)
+            .iter()
+            .all(|v| *v >= chrono::Duration::zero()));
+    }
+
+    #[test]
+    fn records_written_backwards_count_as_empty() {
+        let (mut backend, _, path) = backend_at(at(2024, 3, 5, 18, 0, 0));
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+
+        backend.history.add_manual_record(
+            p,
+            s,
+            x,
+            at(2024, 3, 5, 11, 0, 0),
+            at(2024, 3, 5, 10, 0, 0),
+        );
+        backend
+            .add_manual_record(p, s, x, at(2024, 3, 5, 12, 0, 0), at(2024, 3, 5, 12, 30, 0))
+            .unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+
+        assert_eq!(
+            backend.history.daily_totals(today, today),
+            [chrono::Duration::minutes(30)]
+        );
+        assert_eq!(
+            backend.project_day_totals(today),
+            [("Client".to_string(), chrono::Duration::minutes(30))]
+        );
+    }
+
+    #[test]
+    fn totals_are_cached_until_dirty() {
+        let (mut backend, _, path) = backend_at(at(2024, 3, 5, 18, 0, 0));
+        let EntryPath::Subject(p, s, _) = path else {
+            unreachable!();
+        };
+
+        let other = backend
+            .add_path("Client/Website/Footer", Tree::Time)
+            .unwrap();
+        backend.subject(path).unwrap().lock().unwrap().duration = Duration::from_secs(60);
+        backend.subject(other).unwrap().lock().unwrap().duration = Duration::from_secs(120);
+
+        backend.refresh_totals();
+
+        assert_eq!(backend.get_project_time(&p), Some(Duration::from_secs(180)));
+
+        // Read from the cache, which does not see changes made behind its back.
+        backend.subject(path).unwrap().lock().unwrap().duration = Duration::from_secs(600);
+        backend.refresh_totals();
+
+        assert_eq!(
+            backend.get_sub_project_time(&p, &s),
+            Some(Duration::from_secs(180))
+        );
+
+        backend.dirty();
+
+        assert_eq!(backend.get_project_time(&p), Some(Duration::from_secs(720)));
+
+        backend.refresh_totals();
+
+        assert_eq!(
+            backend.get_sub_project_time(&p, &s),
+            Some(Duration::from_secs(720))
+        );
+    }
+
+    #[test]
+    fn ticks_drop_the_cached_totals() {
+        let (mut backend, clock, path) = backend_at(at(2024, 3, 5, 18, 0, 0));
+        let EntryPath::Subject(p, ..) = path else {
+            unreachable!();
+        };
+
+        backend.start_subject();
+        run(&mut backend, &clock, 10);
+        backend.refresh_totals();
+
+        assert_eq!(backend.get_project_time(&p), Some(Duration::from_secs(10)));
+
+        run(&mut backend, &clock, 5);
+        backend.refresh_totals();
+
+        assert_eq!(backend.get_project_time(&p), Some(Duration::from_secs(15)));
+    }
+
+    /// A backend with sessions of 7, 52 and 60 minutes on Client at 80 EUR an hour, 20
+    /// minutes on Shop at 30 EUR and 10 minutes on Home without a rate, all on March 5.
+    fn billing_backend() -> Backend {
+        let (mut backend, ..) = backend_at(at(2024, 3, 5, 18, 0, 0));
+
+        for (path, sessions, rate) in [
+            (
+                "Client/Website/Navbar",
+                &[(9, 0, 7), (10, 0, 52), (13, 0, 60)][..],
+                Some(80.),
+            ),
+            ("Shop/Front/Cart", &[(15, 0, 20)], Some(30.)),
+            ("Home/Garden/Mow", &[(17, 0, 10)], None),
+        ] {
+            let path = backend.add_path(path, Tree::Time).unwrap();
+            let EntryPath::Subject(p, s, x) = path else {
+                unreachable!();
+            };
+
+            backend.set_project_rate(&p, rate, " EUR ");
+
+            for (h, min, length) in sessions {
+                let start = at(2024, 3, 5, *h, *min, 0);
+
+                backend
+                    .add_manual_record(p, s, x, start, start + chrono::Duration::minutes(*length))
+                    .unwrap();
+            }
+        }
+
+        backend
+    }
+
+    fn day_range(d: u32) -> (DateTime<Local>, DateTime<Local>) {
+        (at(2024, 3, d, 0, 0, 0), at(2024, 3, d + 1, 0, 0, 0))
+    }
+
+    #[test]
+    fn billing_prices_exact_time() {
+        let billing = billing_backend().billing(day_range(5), BillingRounding::None);
+
+        let lines = billing
+            .lines
+            .iter()
+            .map(|v| {
+                (
+                    v.name.as_str(),
+                    v.duration.num_minutes(),
+                    v.amount,
+                    v.currency.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            [
+                ("Client", 119, Some(80. * 119. / 60.), "EUR"),
+                ("Home", 10, None, "EUR"),
+                ("Shop", 20, Some(10.), "EUR"),
+            ]
+        );
+        assert_eq!(
+            billing.totals,
+            [("EUR".to_string(), 80. * 119. / 60. + 10.)]
+        );
+    }
+
+    #[test]
+    fn billing_rounds_every_session_up() {
+        let backend = billing_backend();
+
+        let minutes = |rounding| {
+            backend
+                .billing(day_range(5), rounding)
+                .lines
+                .iter()
+                .map(|v| v.duration.num_minutes())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            minutes(BillingRounding::UpTo5Minutes),
+            [10 + 55 + 60, 10, 20]
+        );
+        assert_eq!(
+            minutes(BillingRounding::UpTo15Minutes),
+            [15 + 60 + 60, 15, 30]
+        );
+
+        let billing = backend.billing(day_range(5), BillingRounding::UpTo15Minutes);
+
+        assert_eq!(billing.lines[0].amount, Some(180.));
+        assert_eq!(billing.totals, [("EUR".to_string(), 180. + 15.)]);
+    }
+
+    #[test]
+    fn billing_totals_every_currency_apart() {
+        let mut backend = billing_backend();
+        let shop = backend.lookup_names(["Shop", "", ""]).0.unwrap();
+
+        backend.set_project_rate(&shop, Some(30.), "USD");
+
+        let billing = backend.billing(day_range(5), BillingRounding::None);
+
+        assert_eq!(
+            billing.totals,
+            [
+                ("EUR".to_string(), 80. * 119. / 60.),
+                ("USD".to_string(), 10.)
+            ]
+        );
+    }
+
+    #[test]
+    fn billing_only_counts_the_range() {
+        let backend = billing_backend();
+
+        assert!(backend
+            .billing(day_range(6), BillingRounding::None)
+            .lines
+            .is_empty());
+
+        let billing = backend.billing(
+            (at(2024, 3, 5, 10, 30, 0), at(2024, 3, 5, 13, 30, 0)),
+            BillingRounding::UpTo15Minutes,
+        );
+
+        assert_eq!(billing.lines.len(), 1);
+        assert_eq!(billing.lines[0].duration.num_minutes(), 30 + 30);
+    }
+
+    #[test]
+    fn add_path_ignores_surrounding_slashes_and_spaces() {
+        let (mut backend, _, navbar) = backend_at(at(2024, 3, 5, 9, 0, 0));
+
+        assert_eq!(
+            backend.add_path("/ Client / Website / Navbar /", Tree::Time),
+            Some(navbar)
+        );
+        assert_eq!(
+            backend.add_path("//Client//Website/Navbar", Tree::Time),
+            Some(navbar)
+        );
+
+        for path in ["", "/", " / / ", "Client/Website/Navbar/Extra"] {
+            assert_eq!(backend.add_path(path, Tree::Time), None, "{path:?}");
+        }
+
+        assert_eq!(backend.projects.inner.len(), 1);
+    }
+
+    #[test]
+    fn add_path_reuses_names_at_every_level() {
+        let (mut backend, _, navbar) = backend_at(at(2024, 3, 5, 9, 0, 0));
+        let EntryPath::Subject(client, website, _) = navbar else {
+            unreachable!();
+        };
+
+        assert_eq!(
+            backend.add_path("CLIENT/website/navBar", Tree::Time),
+            Some(navbar)
+        );
+
+        let Some(EntryPath::Subject(p, s, footer)) =
+            backend.add_path("client/Website/Footer", Tree::Time)
+        else {
+            panic!("no subject added");
+        };
+
+        assert_eq!((p, s), (client, website));
+        assert_ne!(footer, navbar.id());
+        assert_eq!(backend.projects.inner.len(), 1);
+        assert_eq!(backend.projects.inner[&client].inner.len(), 1);
+        assert_eq!(
+            backend.projects.inner[&client].inner[&website].inner.len(),
+            2
+        );
+        assert_eq!(
+            backend.get_current_subject().unwrap().lock().unwrap().id,
+            footer
+        );
+    }
+
+    #[test]
+    fn add_path_stops_at_one_or_two_segments() {
+        let (mut backend, _, navbar) = backend_at(at(2024, 3, 5, 9, 0, 0));
+        let EntryPath::Subject(client, ..) = navbar else {
+            unreachable!();
+        };
+
+        let Some(EntryPath::Project(shop)) = backend.add_path("Shop", Tree::Time) else {
+            panic!("no project added");
+        };
+
+        assert_eq!(backend.get_current_project().unwrap().id, shop);
+        assert!(backend.get_current_sub_project().is_none());
+
+        let Some(EntryPath::SubProject(p, design)) = backend.add_path("Client/Design", Tree::Time)
+        else {
+            panic!("no sub project added");
+        };
+
+        assert_eq!(p, client);
+        assert_eq!(backend.get_current_sub_project().unwrap().id, design);
+        assert!(backend.projects.inner[&client].inner[&design]
+            .inner
+            .is_empty());
+        assert_eq!(backend.projects.inner.len(), 2);
+    }
+
+    #[test]
+    fn add_path_fills_the_todo_tree_apart() {
+        let (mut backend, ..) = backend_at(at(2024, 3, 5, 9, 0, 0));
+
+        let Some(EntryPath::Subject(p, s, _)) =
+            backend.add_path("/Client/Website/Navbar/", Tree::Todo)
+        else {
+            panic!("no todo added");
+        };
+
+        assert_ne!(Some(p), backend.get_current_project().map(|v| v.id));
+        assert_eq!(backend.get_current_todo_project().unwrap().id, p);
+        assert_eq!(backend.get_current_todo_sub_project().unwrap().id, s);
+        assert_eq!(backend.todos.inner.len(), 1);
+        assert_eq!(backend.projects.inner.len(), 1);
+    }
+
+    #[test]
+    fn rebuild_durations_sums_midnight_split_records() {
+        let (mut backend, clock, navbar) = backend_at(at(2024, 3, 5, 23, 59, 30));
+
+        backend.start_subject();
+        run(&mut backend, &clock, 60);
+        backend.stop_subject(false);
+
+        let footer = backend
+            .add_path("Client/Website/Footer", Tree::Time)
+            .unwrap();
+
+        for (path, drifted) in [(navbar, 3600), (footer, 300)] {
+            backend.subject(path).unwrap().lock().unwrap().duration = Duration::from_secs(drifted);
+        }
+
+        let recorded = records(&backend)
+            .iter()
+            .map(|v| v.get_duration().to_std().unwrap())
+            .sum::<Duration>();
+
+        assert_eq!(records(&backend).len(), 2);
+        assert_eq!(backend.rebuild_durations(), 2);
+        assert_eq!(subject_duration(&backend, navbar), recorded);
+        assert_eq!(subject_duration(&backend, footer), Duration::ZERO);
+
+        for _ in 0..2 {
+            assert_eq!(backend.rebuild_durations(), 0);
+            assert_eq!(subject_duration(&backend, navbar), recorded);
+            assert_eq!(subject_duration(&backend, footer), Duration::ZERO);
+        }
+    }
+
+    #[test]
+    fn sub_project_time_is_looked_up_under_its_own_project() {
+        let (mut backend, ..) = backend_at(at(2024, 3, 5, 18, 0, 0));
+
+        let mut website = Vec::new();
+
+        for (path, minutes) in [("Client/Website/Navbar", 30), ("Shop/Website/Cart", 45)] {
+            let EntryPath::Subject(p, s, x) = backend.add_path(path, Tree::Time).unwrap() else {
+                unreachable!();
+            };
+            let start = at(2024, 3, 5, 9, 0, 0);
+
+            backend
+                .add_manual_record(p, s, x, start, start + chrono::Duration::minutes(minutes))
+                .unwrap();
+            website.push((p, s));
+        }
+
+        let [(client, client_website), (shop, shop_website)] = website[..] else {
+            unreachable!();
+        };
+
+        backend.select_entry(Tree::Time, EntryPath::Project(client));
+
+        assert_eq!(
+            backend.get_sub_project_time(&client, &client_website),
+            Some(Duration::from_secs(30 * 60))
+        );
+        // Outside the current project too.
+        assert_eq!(
+            backend.get_sub_project_time(&shop, &shop_website),
+            Some(Duration::from_secs(45 * 60))
+        );
+        // Never the time of a sub project under another project.
+        assert_eq!(backend.get_sub_project_time(&client, &shop_website), None);
+        assert_eq!(backend.get_sub_project_time(&shop, &client_website), None);
+        assert_eq!(
+            backend.get_sub_project_time(&Uuid::new_v4(), &shop_website),
+            None
+        );
+    }
+
+    #[test]
+    fn subject_path_names_every_level() {
+        let (mut backend, _, navbar) = backend_at(at(2024, 3, 5, 9, 0, 0));
+        let cart = backend.add_path("Shop/Website/Cart", Tree::Time).unwrap();
+
+        backend.select_entry(Tree::Time, navbar);
+
+        let names = |path: EntryPath| backend.get_subject_path(&path.id());
+
+        assert_eq!(
+            names(navbar),
+            Some(("Client".into(), "Website".into(), "Navbar".into()))
+        );
+        assert_eq!(
+            names(cart),
+            Some(("Shop".into(), "Website".into(), "Cart".into()))
+        );
+        assert_eq!(backend.get_subject_path(&Uuid::new_v4()), None);
+
+        let EntryPath::Subject(p, s, _) = cart else {
+            unreachable!();
+        };
+
+        // Projects and sub projects are not subjects.
+        assert_eq!(backend.get_subject_path(&p), None);
+        assert_eq!(backend.get_subject_path(&s), None);
+    }
+
+    #[test]
+    fn sessions_continue_only_after_a_short_gap() {
+        let now = at(2024, 3, 5, 10, 0, 0);
+        let before = |seconds| Some(now - chrono::Duration::seconds(seconds));
+
+        assert!(continues_session(before(0), now));
+        assert!(continues_session(before(60), now));
+        assert!(continues_session(before(5 * 60 - 1), now));
+
+        assert!(!continues_session(None, now));
+        assert!(!continues_session(before(5 * 60), now));
+        assert!(!continues_session(before(24 * 60 * 60), now));
+        // A record ending later than now tells of a clock gone back, not of a session.
+        assert!(!continues_session(before(-60), now));
+    }
+
+    /// Saves ten minutes of session on Navbar with the last record ending `ago`, then loads
+    /// the data again, from the directory returned along.
+    fn restarted(ago: chrono::Duration) -> (Backend, EntryPath, tempfile::TempDir) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let open =
+            || Backend::load_from(Box::new(crate::storage::RonStorage::new(dir.path()))).unwrap();
+
+        let mut backend = open();
+        let path = backend
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+        let end = Local::now() - ago;
+
+        backend
+            .add_manual_record(p, s, x, end - chrono::Duration::minutes(10), end)
+            .unwrap();
+        backend.current_session_duration = Duration::from_secs(10 * 60);
+        backend.last_session_subject_id = x;
+        backend.flush();
+
+        (open(), path, dir)
+    }
+
+    #[test]
+    fn restart_right_after_a_session_resumes_it() {
+        let (mut backend, path, _dir) = restarted(chrono::Duration::minutes(1));
+
+        assert_eq!(
+            backend.current_session_duration,
+            Duration::from_secs(10 * 60)
+        );
+
+        backend.select_entry(Tree::Time, path);
+        backend.start_subject();
+
+        assert_eq!(
+            backend.current_session_duration,
+            Duration::from_secs(10 * 60)
+        );
+    }
+
+    #[test]
+    fn restart_long_after_a_session_starts_over() {
+        let (mut backend, path, _dir) = restarted(chrono::Duration::hours(10));
+
+        assert_eq!(backend.current_session_duration, Duration::ZERO);
+
+        // The same subject as before does not bring the old session back.
+        backend.select_entry(Tree::Time, path);
+        backend.start_subject();
+
+        assert_eq!(backend.current_session_duration, Duration::ZERO);
+    }
+
+    /// Two minutes of session on Navbar, still running at 10:02.
+    fn mid_session() -> (Backend, TestClock, EntryPath) {
+        let (mut backend, clock, navbar) = backend_at(at(2024, 3, 5, 10, 0, 0));
+
+        backend.start_subject();
+        run(&mut backend, &clock, 120);
+
+        (backend, clock, navbar)
+    }
+
+    fn ended_at_10_02(backend: &Backend) {
+        assert!(matches!(backend.working_mode, WorkingMode::Idle));
+
+        let [record] = records(backend)[..] else {
+            panic!("expected one record");
+        };
+
+        assert_eq!(record.end_date, at(2024, 3, 5, 10, 2, 0));
+    }
+
+    #[test]
+    fn deleting_the_tracked_subject_ends_the_session() {
+        let (mut backend, clock, navbar) = mid_session();
+
+        backend.delete_entry(Tree::Time, navbar);
+
+        ended_at_10_02(&backend);
+        assert_eq!(backend.current_session_duration, Duration::ZERO);
+
+        run(&mut backend, &clock, 60);
+
+        assert_eq!(subject_duration(&backend, navbar), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn deleting_a_parent_of_the_tracked_subject_ends_the_session() {
+        for parent in [|p, _| EntryPath::Project(p), EntryPath::SubProject] {
+            let (mut backend, clock, navbar) = mid_session();
+            let EntryPath::Subject(p, s, _) = navbar else {
+                unreachable!();
+            };
+
+            backend.delete_entry(Tree::Time, parent(p, s));
+
+            ended_at_10_02(&backend);
+
+            run(&mut backend, &clock, 60);
+
+            assert_eq!(subject_duration(&backend, navbar), Duration::from_secs(120));
+        }
+    }
+
+    #[test]
+    fn deleting_another_subject_keeps_the_session() {
+        let (mut backend, clock, navbar) = mid_session();
+        let footer = backend
+            .add_path("Client/Website/Footer", Tree::Time)
+            .unwrap();
+
+        // Adding selects the new subject, which stopped the session, so start again.
+        backend.select_entry(Tree::Time, navbar);
+        backend.start_subject();
+        backend.delete_entry(Tree::Time, footer);
+        run(&mut backend, &clock, 60);
+
+        assert_eq!(backend.tracked_path(), Some(navbar));
+        assert_eq!(subject_duration(&backend, navbar), Duration::from_secs(180));
+    }
+
+    #[test]
+    fn moving_the_tracked_subject_takes_the_session_along() {
+        let (mut backend, clock, navbar) = mid_session();
+        let design = backend.add_path("Shop/Design", Tree::Time).unwrap();
+        let EntryPath::SubProject(p, s) = design else {
+            unreachable!();
+        };
+
+        // Selecting a sub project leaves the session running, the subject it runs on again
+        // keeps it too.
+        assert_eq!(backend.tracked_path(), Some(navbar));
+        backend.select_entry(Tree::Time, navbar);
+        assert_eq!(backend.tracked_path(), Some(navbar));
+
+        assert!(backend.move_subject(navbar.id(), p, s));
+
+        let moved = EntryPath::Subject(p, s, navbar.id());
+
+        run(&mut backend, &clock, 60);
+
+        assert_eq!(backend.tracked_path(), Some(moved));
+        assert_eq!(backend.current_path(), Some(moved));
+
+        backend.stop_subject(false);
+
+        assert_eq!(subject_duration(&backend, moved), Duration::from_secs(180));
+        assert!(records(&backend)
+            .iter()
+            .all(|v| (v.project_id, v.sub_project_id) == (p, s)));
+        assert_eq!(backend.current_session_duration, Duration::from_secs(180));
+    }
+
+    #[test]
+    fn stopping_counts_the_tracked_subject_not_the_selection() {
+        let (mut backend, clock, navbar) = mid_session();
+        let footer = backend
+            .add_path("Client/Website/Footer", Tree::Time)
+            .unwrap();
+
+        backend.select_entry(Tree::Time, navbar);
+        backend.start_subject();
+        run(&mut backend, &clock, 60);
+
+        // Bypasses the selection guard, as the selection of a subject does.
+        backend.set_current_subject(Some(footer.id()));
+        run(&mut backend, &clock, 60);
+        backend.stop_subject(false);
+
+        assert_eq!(subject_duration(&backend, navbar), Duration::from_secs(240));
+        assert_eq!(subject_duration(&backend, footer), Duration::ZERO);
+        // Kept for the subject that ran, the selection moving on does not reset it.
+        assert_eq!(backend.current_session_duration, Duration::from_secs(120));
+    }
+
+    fn toggl_entries() -> Vec<TogglEntry> {
+        crate::import::parse_toggl(
+            "Project,Task,Description,Start date,Start time,End date,End time\n\
+            client,WEBSITE,Navbar,2024-03-05,09:00,2024-03-05,10:00\n\
+            Client,Website,Footer,2024-03-05,10:00,2024-03-05,10:30\n\
+            Shop,Front,Cart,2024-03-05,11:00,2024-03-05,11:45\n\
+            Shop,Front,Cart,2024-03-05,11:00,2024-03-05,11:45\n",
+        )
+        .unwrap()
+        .entries
+    }
+
+    #[test]
+    fn toggl_preview_counts_what_the_import_adds() {
+        let (mut backend, ..) = backend_at(at(2024, 3, 5, 18, 0, 0));
+
+        let preview = backend.toggl_preview(&toggl_entries());
+
+        assert_eq!(
+            (preview.entries, preview.duplicates, preview.archived),
+            (3, 1, 0)
+        );
+        assert_eq!(
+            (preview.projects, preview.sub_projects, preview.subjects),
+            (1, 1, 2)
+        );
+        assert!(records(&backend).is_empty());
+    }
+
+    #[test]
+    fn importing_the_same_file_twice_adds_nothing_the_second_time() {
+        let (mut backend, _, navbar) = backend_at(at(2024, 3, 5, 18, 0, 0));
+
+        assert_eq!(backend.import_toggl(&toggl_entries()), 3);
+        assert_eq!(records(&backend).len(), 3);
+        assert_eq!(backend.projects.inner.len(), 2);
+        // Matched to the subject already there despite the case.
+        assert_eq!(
+            subject_duration(&backend, navbar),
+            Duration::from_secs(60 * 60)
+        );
+
+        let preview = backend.toggl_preview(&toggl_entries());
+
+        assert_eq!((preview.entries, preview.duplicates), (0, 4));
+        assert_eq!(backend.import_toggl(&toggl_entries()), 0);
+        assert_eq!(records(&backend).len(), 3);
+        assert_eq!(backend.projects.inner.len(), 2);
+        assert_eq!(
+            subject_duration(&backend, navbar),
+            Duration::from_secs(60 * 60)
+        );
+    }
+
+    #[test]
+    fn toggl_entries_of_archived_projects_are_left_alone() {
+        let (mut backend, _, navbar) = backend_at(at(2024, 3, 5, 18, 0, 0));
+        let EntryPath::Subject(client, ..) = navbar else {
+            unreachable!();
+        };
+
+        backend.set_project_archived(&client, true);
+
+        assert_eq!(backend.toggl_preview(&toggl_entries()).archived, 2);
+        assert_eq!(backend.import_toggl(&toggl_entries()), 1);
+        assert_eq!(subject_duration(&backend, navbar), Duration::ZERO);
     }
 }
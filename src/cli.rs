@@ -0,0 +1,288 @@
+//! Commands working on the data file without opening a window, for scripts.
+//!
+//! `start <project/sub project/subject>` creates missing levels like the add dialogs and
+//! starts tracking, `stop` ends it, `status` tells what is tracked and `report` prints the
+//! time of a range. `rebuild-durations` sets the time of every subject to the sum of its
+//! records. `convert-storage <ron|sqlite>` moves the data into RON files or an SQLite
+//! database. They refuse to run while a window owns the data file, and work on the profile
+//! `--profile <name>` in front of them names, or on the one used last.
+
+use crate::backend::{path_names, Backend, Tree};
+use crate::history::HistoryRecord;
+use crate::instance;
+use crate::report::{csv_report, range_report, ReportEntry};
+use crate::storage::StorageKind;
+use crate::util::{format_chrono_duration, ExportZone, DATE_FORMAT};
+use chrono::{Local, NaiveDate};
+use std::fmt::Write;
+
+pub const COMMANDS: [&str; 6] = [
+    "start",
+    "stop",
+    "status",
+    "report",
+    "rebuild-durations",
+    "convert-storage",
+];
+
+const USAGE: &str = "usage: start <project/sub project/subject> | stop | status | \
+    report [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--format markdown|csv] [--zone local|utc] | \
+    rebuild-durations | \
+    convert-storage <ron|sqlite>";
+
+/// Runs the command in `args`, which starts with one of [`COMMANDS`].
+pub fn run(args: &[String]) -> Result<(), String> {
+    if let Some(owner) = instance::owner() {
+        return Err(format!(
+            "the tracker window (process {}) owns the data file, use it instead or close it \
+            (remove {} if that process is not the tracker)",
+            owner.pid,
+            instance::LOCK_PATH
+        ));
+    }
+
+    let mut backend = Backend::try_load()?;
+
+    match args {
+        [command, path] if command == "start" => start(&mut backend, path)?,
+        [command] if command == "stop" => stop(&mut backend),
+        [command] if command == "status" => print!("{}", status(&backend)),
+        [command, options @ ..] if command == "report" => {
+            print!("{}", report(&mut backend, options)?)
+        }
+        [command] if command == "rebuild-durations" => {
+            let changed = backend.rebuild_durations();
+            println!("{changed} subject durations changed");
+        }
+        [command, kind] if command == "convert-storage" => convert_storage(&mut backend, kind)?,
+        _ => return Err(USAGE.to_string()),
+    }
+
+    backend.flush();
+
+    Ok(())
+}
+
+fn start(backend: &mut Backend, path: &str) -> Result<(), String> {
+    if path_names(path).len() != 3 {
+        return Err(format!(
+            "\"{path}\" is not a project/sub project/subject path, like \"Client/Web/Fix navbar\""
+        ));
+    }
+
+    let Some(entry) = backend.add_path(path, Tree::Time) else {
+        return Err(format!("cannot track \"{path}\", its project is archived"));
+    };
+
+    backend.start_detached(entry);
+
+    let name = backend.get_current_work_name();
+    println!("tracking {}", name.as_deref().unwrap_or(path));
+
+    Ok(())
+}
+
+fn stop(backend: &mut Backend) {
+    let format = backend.settings.duration_format;
+
+    if backend.detached_session.is_none() {
+        println!("not tracking");
+        return;
+    }
+
+    match backend.stop_detached() {
+        Some(record) => println!(
+            "stopped {} after {}",
+            record_name(backend, &record),
+            format_chrono_duration(record.get_duration(), format)
+        ),
+        None => println!("stopped, the session was too short to keep"),
+    }
+}
+
+/// What is tracked and the time of today, as of the backend clock.
+fn status(backend: &Backend) -> String {
+    let format = backend.settings.duration_format;
+    let now = backend.now();
+
+    let record = backend
+        .detached_session
+        .and_then(|v| backend.history.get(&v));
+
+    let mut text = match record {
+        Some(record) => format!(
+            "tracking {} since {}, {}\n",
+            record_name(backend, record),
+            record.start_date.format("%H:%M"),
+            format_chrono_duration(now.signed_duration_since(record.start_date), format)
+        ),
+        None => "not tracking\n".to_string(),
+    };
+
+    let today = backend
+        .project_day_totals(now.date_naive())
+        .into_iter()
+        .fold(chrono::Duration::zero(), |s, v| s + v.1);
+
+    let _ = writeln!(text, "today {}", format_chrono_duration(today, format));
+
+    text
+}
+
+fn record_name(backend: &Backend, record: &HistoryRecord) -> String {
+    let path = backend.get_record_path(record);
+
+    format!("{}/{}/{}", path.project, path.sub_project, path.subject)
+}
+
+fn parse_day(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, DATE_FORMAT)
+        .map_err(|_| format!("days must look like 2024-01-31, got \"{value}\""))
+}
+
+fn convert_storage(backend: &mut Backend, kind: &str) -> Result<(), String> {
+    let Some(kind) = StorageKind::ALL.into_iter().find(|v| v.name() == kind) else {
+        return Err(format!("unknown storage \"{kind}\", use ron or sqlite"));
+    };
+
+    if backend.storage_kind() == Some(kind) {
+        println!("the data already is in {}", kind.name());
+        return Ok(());
+    }
+
+    backend.convert_storage(kind)?;
+    println!("converted the data to {}", kind.name());
+
+    Ok(())
+}
+
+/// The report of the range in `options`, days without one count from today on the backend clock.
+fn report(backend: &mut Backend, options: &[String]) -> Result<String, String> {
+    let today = backend.now().date_naive();
+    let (mut from, mut to, mut csv, mut zone) = (None, None, false, ExportZone::Local);
+
+    for pair in options.chunks(2) {
+        match pair {
+            [option, value] if option == "--from" => from = Some(parse_day(value)?),
+            [option, value] if option == "--to" => to = Some(parse_day(value)?),
+            [option, value] if option == "--format" => {
+                csv = match value.as_str() {
+                    "csv" => true,
+                    "markdown" | "md" => false,
+                    _ => return Err(format!("unknown format \"{value}\", use markdown or csv")),
+                }
+            }
+            [option, value] if option == "--zone" => {
+                zone = ExportZone::parse(value)
+                    .ok_or_else(|| format!("unknown zone \"{value}\", use local or utc"))?;
+            }
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    // Without --to the range runs up to today, --to alone reports that day.
+    let from = from.or(to).unwrap_or(today);
+    let to = to.unwrap_or(from.max(today));
+
+    if to < from {
+        return Err("--to is before --from".to_string());
+    }
+
+    // Days in UTC reach into the local day before or after.
+    let range = (
+        zone.day_start(&Local, from),
+        zone.day_start(&Local, to + chrono::Duration::days(1)),
+    );
+
+    backend
+        .history
+        .load_range(range.0.date_naive(), range.1.date_naive());
+
+    Ok(if csv {
+        csv_report(
+            range,
+            &backend.history,
+            &backend.projects,
+            zone,
+            backend.settings.report_rounding,
+        )
+    } else {
+        let projects = backend
+            .project_totals(range)
+            .into_iter()
+            .map(|(name, duration)| ReportEntry { name, duration })
+            .collect::<Vec<_>>();
+
+        range_report(
+            from,
+            to,
+            &projects,
+            None,
+            backend.settings.duration_format,
+            &zone.name((&range.0, &range.1)),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::EntryPath;
+    use crate::clock::TestClock;
+    use chrono::TimeZone;
+    use std::time::Duration;
+
+    fn backend_at(h: u32, min: u32) -> (Backend, TestClock, EntryPath) {
+        let clock = TestClock::at(Local.with_ymd_and_hms(2024, 3, 5, h, min, 0).unwrap());
+        let mut backend = Backend::unsaved_at(&clock);
+        let path = backend
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+
+        (backend, clock, path)
+    }
+
+    #[test]
+    fn status_reads_the_backend_clock() {
+        let (mut backend, clock, path) = backend_at(8, 0);
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+        let start = backend.now() - chrono::Duration::hours(1);
+
+        backend
+            .add_manual_record(p, s, x, start, start + chrono::Duration::minutes(30))
+            .unwrap();
+
+        assert_eq!(status(&backend), "not tracking\ntoday 0:30\n");
+
+        backend.start_detached(path);
+        clock.advance(Duration::from_secs(75 * 60));
+
+        assert_eq!(
+            status(&backend),
+            "tracking Client/Website/Navbar since 08:00, 1:15\ntoday 0:30\n"
+        );
+    }
+
+    #[test]
+    fn report_defaults_to_today_on_the_backend_clock() {
+        let (mut backend, _, _) = backend_at(12, 0);
+
+        let text = report(&mut backend, &[]).unwrap();
+        assert!(text.starts_with("## Time report 2024-03-05\n"), "{text}");
+
+        let options = ["--from", "2024-03-01"].map(String::from);
+        let text = report(&mut backend, &options).unwrap();
+        assert!(
+            text.starts_with("## Time report 2024-03-01 to 2024-03-05\n"),
+            "{text}"
+        );
+
+        let options = ["--from", "2024-03-05", "--to", "2024-03-04"].map(String::from);
+        assert_eq!(
+            report(&mut backend, &options).unwrap_err(),
+            "--to is before --from"
+        );
+    }
+}
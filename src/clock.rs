@@ -0,0 +1,85 @@
+//! Where the backend reads the time from, so the time accounting can run on a clock that
+//! tests move by hand.
+//!
+//! Durations come from the monotonic clock, which never goes back, and timestamps of
+//! records from the wall clock, which jumps on a suspend or when the time is set.
+
+use chrono::{DateTime, Local};
+use std::time::{Instant, SystemTime};
+
+pub trait Clock {
+    /// Monotonic time, for measuring durations.
+    fn instant(&self) -> Instant;
+
+    /// Wall clock time, for timestamps.
+    fn now(&self) -> SystemTime;
+
+    fn local_now(&self) -> DateTime<Local> {
+        DateTime::from(self.now())
+    }
+}
+
+/// The clocks of the system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+#[cfg(test)]
+pub use test_clock::TestClock;
+
+#[cfg(test)]
+mod test_clock {
+    use super::Clock;
+    use chrono::{DateTime, Local};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant, SystemTime};
+
+    /// A clock standing still until moved, clones share the time.
+    #[derive(Clone)]
+    pub struct TestClock {
+        time: Arc<Mutex<(Instant, SystemTime)>>,
+    }
+
+    impl TestClock {
+        pub fn at(now: DateTime<Local>) -> Self {
+            Self {
+                time: Arc::new(Mutex::new((Instant::now(), now.into()))),
+            }
+        }
+
+        /// Time passing while the machine is awake, both clocks move.
+        pub fn advance(&self, duration: Duration) {
+            let mut time = self.time.lock().unwrap();
+            time.0 += duration;
+            time.1 += duration;
+        }
+
+        /// Time passing while the machine sleeps, only the wall clock moves.
+        pub fn sleep(&self, duration: Duration) {
+            self.time.lock().unwrap().1 += duration;
+        }
+
+        /// The wall clock set to `now`, like a user or NTP does.
+        pub fn set_wall(&self, now: DateTime<Local>) {
+            self.time.lock().unwrap().1 = now.into();
+        }
+    }
+
+    impl Clock for TestClock {
+        fn instant(&self) -> Instant {
+            self.time.lock().unwrap().0
+        }
+
+        fn now(&self) -> SystemTime {
+            self.time.lock().unwrap().1
+        }
+    }
+}
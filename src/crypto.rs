@@ -0,0 +1,101 @@
+//! Encryption of the data file with a passphrase, for machines where others can read it.
+//!
+//! An encrypted file is [`MAGIC`], the salt its key was derived with by Argon2id, the nonce
+//! and the ChaCha20-Poly1305 ciphertext of the RON text. The key is derived once per
+//! passphrase and kept while the tracker runs, every save only draws a new nonce. Month files
+//! of the history and backups are encrypted the same way with the same key.
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+
+const MAGIC: &[u8] = b"ruh-encrypted-1\n";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+pub const WRONG_PASSPHRASE: &str = "wrong passphrase";
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub struct Cipher {
+    salt: [u8; SALT_LEN],
+    cipher: ChaCha20Poly1305,
+}
+
+impl Cipher {
+    /// A key for `passphrase` with a new salt, for a file encrypted from now on.
+    pub fn new(passphrase: &str) -> Result<Self, String> {
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+
+        Self::derive(passphrase, salt)
+    }
+
+    fn derive(passphrase: &str, salt: [u8; SALT_LEN]) -> Result<Self, String> {
+        let mut key = Key::default();
+
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| format!("failed to derive the key: {e}"))?;
+
+        Ok(Self {
+            salt,
+            cipher: ChaCha20Poly1305::new(&key),
+        })
+    }
+
+    /// Decrypts an encrypted file. The cipher returned along encrypts with the same key, so
+    /// saving needs no passphrase.
+    pub fn open(passphrase: &str, data: &[u8]) -> Result<(Self, Vec<u8>), String> {
+        let salt = data
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| "the file is not encrypted".to_string())?
+            .get(..SALT_LEN)
+            .ok_or_else(|| "the encrypted file is cut off".to_string())?;
+
+        let cipher = Self::derive(passphrase, salt.try_into().unwrap())?;
+        let plain = cipher.decrypt(data)?;
+
+        Ok((cipher, plain))
+    }
+
+    pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, data)
+            .map_err(|_| "failed to encrypt the data".to_string())?;
+
+        Ok([MAGIC, &self.salt, &nonce, &ciphertext].concat())
+    }
+
+    /// Decrypts a file encrypted with this key, like the month files written along with the
+    /// data file.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let data = data
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| "the file is not encrypted".to_string())?;
+
+        if data.len() < SALT_LEN + NONCE_LEN {
+            return Err("the encrypted file is cut off".to_string());
+        }
+
+        let (salt, data) = data.split_at(SALT_LEN);
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+
+        // Another salt means another key, encrypted with another passphrase.
+        if salt != self.salt {
+            return Err(WRONG_PASSPHRASE.to_string());
+        }
+
+        // The tag does not tell a wrong passphrase from a damaged file, the first is likelier.
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| WRONG_PASSPHRASE.to_string())
+    }
+}
@@ -1,26 +1,144 @@
-use crate::backend::{Backend, WorkingMode};
-use crate::custom_window_frame;
+use crate::backend::{
+    path_names, Backend, BillingRounding, EntryPath, Goal, GoalPeriod, ImportPreview, Issue,
+    PContainer, PomodoroState, Priority, ProjectChain, TodayTimes, TodoSubProject, TodoSubject,
+    Tree, WorkingMode, MISSING_COLOR,
+};
+use crate::crypto::WRONG_PASSPHRASE;
+use crate::history::{
+    cumulative_totals, group_adjacent_records, untracked_gaps, Breakdown, History, HistoryRecord,
+    RecordGroup,
+};
+use crate::hotkeys::{Hotkey, HotkeyAction};
+use crate::i18n::{
+    languages, month_name, month_short, set_language, tr, translate, weekday_name, weekday_short,
+    Language,
+};
+use crate::import::{parse_toggl, TogglImport};
+use crate::instance::InstanceLock;
+use crate::ipc::{error_reply, IpcCommand, IpcServer, SOCKET_PATH};
+use crate::merge::{apply_merge, merge, plan_merge, MergePlan};
+use crate::palette::{with_contrast, Palette};
+use crate::profiles::{active_name, data_dir, Profiles};
+use crate::report::{range_report, write_week_report, ReportEntry};
+use crate::settings::{ColumnTime, Theme, TodoLayout, UnlockResume, WindowRule, WindowState};
+use crate::storage::{is_encrypted, read_data_file, StorageKind};
+use crate::system_events::{notify, platform_listeners, SystemEvent};
+use crate::tray::{platform_tray, Tray, TrayCommand, TrayState};
 use crate::util::{
-    calendar_days_count, format_chrono_duration, format_duration, format_number,
-    get_days_from_month,
+    days_in_range, end_of_month, format_chrono_duration, format_date_time, format_due,
+    format_duration, format_number, fuzzy_score, get_days_from_month, parse_date_time, parse_due,
+    round_duration, start_of_day, start_of_month, start_of_week, to_local, truncate_middle,
+    truncate_middle_to_width, DurationFormat, ExportZone, RoundingMode, TimeFormat, DATE_FORMAT,
+    DATE_TIME_FORMAT,
 };
-use std::collections::HashMap;
+use crate::window_title::TitleWatcher;
+use crate::{custom_window_frame, is_resizable, window_size};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::ops::{Add, Sub};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
 
-use chrono::{DateTime, Datelike, Days, Local, LocalResult, Month, TimeZone, Timelike};
+use chrono::{DateTime, Datelike, Days, Local, Month, Months, NaiveDate, Timelike, Weekday};
 use eframe::egui;
 use eframe::egui::scroll_area::ScrollBarVisibility;
 use eframe::egui::{
-    Align, Color32, FontId, Key, Label, Layout, RichText, Rounding, ScrollArea, TextEdit, Ui, Vec2,
-    Visuals,
+    Align, Color32, CursorIcon, FontId, Key, Label, Layout, Rect, Response, RichText, Rounding,
+    ScrollArea, Stroke, TextEdit, TextStyle, Ui, Vec2, Visuals,
 };
 use eframe::epaint::RectShape;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use uuid::Uuid;
 
-const SAVE_PERIOD_SECONDS: u64 = 10_000;
+/// Width of the running total column to the right of the timeline.
+const CUMULATIVE_COLUMN_WIDTH: f32 = 80.0;
+
+/// Narrowest a minute gets in the statistic timeline before zooming.
+const TIMELINE_MINUTE_WIDTH: f32 = 1.0;
+
+/// Zoom range of the statistic timeline.
+const TIMELINE_MIN_ZOOM: f32 = 0.25;
+const TIMELINE_MAX_ZOOM: f32 = 4.0;
+
+/// Names longer than this are shortened in the fixed-width columns.
+const NAME_MAX_CHARS: usize = 24;
+
+/// Subjects offered by the recent strip and the minimal view.
+const RECENT_SUBJECTS: usize = 5;
+
+/// The tray tooltip is refreshed at most this often while nothing else changes.
+const TRAY_REFRESH_PERIOD: Duration = Duration::from_secs(60);
+
+const HIGH_PRIORITY_COLOR: Color32 = Color32::from_rgb(220, 70, 60);
+
+/// How long a toast stays on screen.
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+
+/// A lock this soon after the screen turned off counts as the reason the session stopped.
+const LOCK_AFTER_DISPLAY_OFF_SECONDS: i64 = 60;
+
+fn name_text(name: &str) -> RichText {
+    RichText::new(truncate_middle(name, NAME_MAX_CHARS))
+}
+
+/// Small icon opening `link` in the browser, as it was typed.
+fn link_button(ui: &mut Ui, link: &str) {
+    if ui
+        .add(egui::Button::new(RichText::new("🔗").small()).frame(false))
+        .on_hover_text(link)
+        .clicked()
+    {
+        open_link(ui.ctx(), link);
+    }
+}
+
+fn open_link(ctx: &egui::Context, link: &str) {
+    ctx.output_mut(|o| o.open_url = Some(egui::output::OpenUrl::new_tab(link)));
+}
+
+/// Shows the full name on hover when it did not fit into [`NAME_MAX_CHARS`].
+fn with_full_name(response: Response, name: &str) -> Response {
+    if truncate_middle(name, NAME_MAX_CHARS) != name {
+        response.on_hover_text(name)
+    } else {
+        response
+    }
+}
+
+/// Wakes the window every repaint period from a thread of its own. eframe does not honor
+/// `request_repaint_after` while the window is hidden or minimized, sessions keep ticking
+/// and saving through this then. The thread ends once this is dropped.
+struct RepaintWaker {
+    period_ms: Arc<AtomicU64>,
+}
+
+impl RepaintWaker {
+    fn spawn(ctx: egui::Context, period: Duration) -> Self {
+        let period_ms = Arc::new(AtomicU64::new(period.as_millis() as u64));
+        let shared = Arc::downgrade(&period_ms);
+
+        std::thread::spawn(move || {
+            while let Some(period) = shared.upgrade().map(|v| v.load(Ordering::Relaxed)) {
+                std::thread::sleep(Duration::from_millis(period));
+                ctx.request_repaint();
+            }
+        });
+
+        Self { period_ms }
+    }
+
+    fn set_period(&self, period: Duration) {
+        self.period_ms
+            .store(period.as_millis() as u64, Ordering::Relaxed);
+    }
+}
 
-#[derive(Copy, Clone, Debug, PartialEq, Default)]
+#[derive(
+    Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
 pub enum DisplayMode {
     #[default]
     Time,
@@ -29,7 +147,7 @@ pub enum DisplayMode {
     Todo,
 }
 
-#[derive(Default, PartialEq)]
+#[derive(Default, Copy, Clone, PartialEq)]
 enum CurrentDialog {
     #[default]
     None,
@@ -39,6 +157,23 @@ enum CurrentDialog {
     AddTodoProject,
     AddTodoSubProject,
     AddTodoSubject,
+    AddManualRecord,
+    InspectRecords,
+    TidyRecords,
+    ImportToggl,
+    MergeFile,
+    Trash,
+    CheckData,
+    Settings,
+    ResumeSession,
+    ResumeAfterLock(EntryPath, DateTime<Local>),
+    RenameEntry(Tree, EntryPath),
+    DeleteEntry(Tree, EntryPath),
+    MoveEntry(Tree, EntryPath),
+    EditLink(Tree, EntryPath),
+    EditNoteTemplate(EntryPath),
+    SessionNote(Uuid),
+    SyncConflict,
 }
 
 #[derive(Default)]
@@ -47,12 +182,41 @@ pub struct Frontend {
 
     current_display_mode: DisplayMode,
 
-    hotkeys_blocked: bool,
-
     dialog_options: DialogOptions,
     time_tracker_options: TimeTrackerOptions,
     minimal_time_tracker_options: MinimalTrackerOptions,
     statistic_options: StatisticOptions,
+    todo_options: TodoOptions,
+    scratchpad_options: ScratchpadOptions,
+    profile_options: ProfileOptions,
+    search_options: SearchOptions,
+    palette_options: PaletteOptions,
+    sync_options: SyncOptions,
+    end_of_day_options: EndOfDayOptions,
+    window_suggestion_options: WindowSuggestionOptions,
+    settings_options: SettingsOptions,
+
+    toast: Option<(String, Instant)>,
+
+    system_events: Option<Receiver<SystemEvent>>,
+    /// Session the screen turning off stopped and when.
+    display_off_session: Option<(EntryPath, DateTime<Local>)>,
+    /// Session the screen lock stopped and when, offered again on unlock.
+    locked_session: Option<(EntryPath, DateTime<Local>)>,
+
+    tray: Option<Box<dyn Tray>>,
+    tray_commands: Option<Receiver<TrayCommand>>,
+    /// Last state shown by the tray and when it was sent.
+    tray_state: Option<(TrayState, Instant)>,
+
+    /// Keeps other instances and the command line off the data file while the window runs.
+    instance_lock: Option<InstanceLock>,
+    /// Running while the control socket setting is on.
+    ipc_server: Option<IpcServer>,
+    repaint_waker: Option<RepaintWaker>,
+
+    /// The window size changed and is not saved yet.
+    window_resized: bool,
 }
 
 impl Frontend {
@@ -62,1197 +226,7248 @@ impl Frontend {
         }
 
         self.current_display_mode = mode;
+        self.todo_options.clear_selection();
+        self.search_options.query.clear();
     }
 
-    pub fn init(cc: &eframe::CreationContext<'_>) -> Self {
-        let context = cc.egui_ctx.clone();
-        std::thread::spawn(move || loop {
-            std::thread::sleep(Duration::from_millis(SAVE_PERIOD_SECONDS));
-            context.request_repaint();
-        });
+    fn init(ctx: &egui::Context, mut backend: Backend, mut instance_lock: InstanceLock) -> Self {
+        let (sender, receiver) = channel();
 
-        Self {
-            backend: Backend::load(),
-            ..Self::default()
+        for listener in platform_listeners() {
+            listener.listen(sender.clone(), ctx.clone());
         }
-    }
-}
 
-impl eframe::App for Frontend {
-    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        match self.current_display_mode {
-            DisplayMode::Statistic => {
-                custom_window_frame(ctx, frame, "_", self.current_display_mode, |ui: &mut Ui| {
-                    self.build_statistic(ui);
-                });
-            }
+        // Later instances raise the window through the tray commands, with or without a tray.
+        let (tray_sender, tray_receiver) = channel();
+        instance_lock.listen(tray_sender.clone(), ctx.clone());
+        let tray = platform_tray(tray_sender, ctx.clone());
 
-            DisplayMode::Time => {
-                custom_window_frame(ctx, frame, "_", self.current_display_mode, |ui: &mut Ui| {
-                    self.time_tracker_build(ui);
-                });
-            }
+        set_language(&backend.settings.language);
 
-            DisplayMode::Todo => {
-                custom_window_frame(ctx, frame, "_", self.current_display_mode, |ui: &mut Ui| {
-                    self.todo_build(ui);
-                });
-            }
+        backend.check_clock();
+        backend.resume_detached();
 
-            DisplayMode::Minimal => {
-                custom_window_frame(ctx, frame, "_", self.current_display_mode, |ui| {
-                    self.minimal_time_tracker_build(ui);
-                });
-            }
+        let repaint_waker = RepaintWaker::spawn(ctx.clone(), backend.repaint_period());
+
+        Self {
+            current_display_mode: backend.window_state.display_mode,
+            backend,
+            repaint_waker: Some(repaint_waker),
+            system_events: Some(receiver),
+            tray_commands: Some(tray_receiver),
+            tray,
+            instance_lock: Some(instance_lock),
+            profile_options: ProfileOptions {
+                profiles: Profiles::load(),
+                ..Default::default()
+            },
+            ..Self::default()
         }
+    }
 
-        self.backend.update_time();
+    /// Continues with `backend` in place of the data in use, as if the window opened with it.
+    fn replace_backend(&mut self, mut backend: Backend) {
+        set_language(&backend.settings.language);
+
+        backend.check_clock();
+        backend.resume_detached();
+
+        // Everything besides the window, its connections and the profiles belongs to the
+        // previous data.
+        *self = Self {
+            current_display_mode: backend.window_state.display_mode,
+            backend,
+            profile_options: ProfileOptions {
+                profiles: std::mem::take(&mut self.profile_options.profiles),
+                ..Default::default()
+            },
+            system_events: self.system_events.take(),
+            tray: self.tray.take(),
+            tray_commands: self.tray_commands.take(),
+            instance_lock: self.instance_lock.take(),
+            ipc_server: self.ipc_server.take(),
+            repaint_waker: self.repaint_waker.take(),
+            ..Self::default()
+        };
+    }
 
-        self.dialog_build(ctx);
+    /// Keeps the persisted window state in line with the window, saving only on change.
+    fn remember_window_state(&mut self, frame: &eframe::Frame) {
+        let position = frame.info().window_info.position.map(|v| (v.x, v.y));
+        let state = &mut self.backend.window_state;
 
-        if !self.hotkeys_blocked && self.dialog_options.current_dialog == CurrentDialog::None {
-            if ctx.input(|i| i.key_pressed(Key::Q)) {
-                self.set_display_mode(DisplayMode::Time);
-            } else if ctx.input(|i| i.key_pressed(Key::W)) {
-                self.set_display_mode(DisplayMode::Statistic);
-            } else if ctx.input(|i| i.key_pressed(Key::E)) {
-                self.set_display_mode(DisplayMode::Todo);
-            } else if ctx.input(|i| i.key_pressed(Key::D)) {
-                self.set_display_mode(DisplayMode::Minimal);
-            }
+        if state.display_mode == self.current_display_mode
+            && (position.is_none() || state.position == position)
+        {
+            return;
         }
-    }
 
-    fn clear_color(&self, _visuals: &Visuals) -> [f32; 4] {
-        egui::Rgba::TRANSPARENT.to_array() // Make sure we don't paint anything behind the rounded corners
+        state.display_mode = self.current_display_mode;
+        state.position = position.or(state.position);
+
+        self.backend.dirty();
     }
-}
 
-/**
-Menu block
- **/
+    fn handle_tray_commands(&mut self, frame: &mut eframe::Frame) {
+        let Some(receiver) = &self.tray_commands else {
+            return;
+        };
 
-#[derive(Default)]
-struct MenuOptions {}
+        let commands = receiver.try_iter().collect::<Vec<_>>();
 
-impl Frontend {
-    fn build_menu(&mut self, ui: &mut Ui) {
-        match self.current_display_mode {
-            DisplayMode::Todo | DisplayMode::Statistic | DisplayMode::Time => {
-                ui.horizontal_top(|ui| {
-                    ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
-                        ui.horizontal(|ui| {
-                            ui.add_space(5.);
-                            egui::ComboBox::from_label("")
-                                .selected_text(format!("{:?}", self.current_display_mode))
-                                .show_ui(ui, |ui| {
-                                    ui.selectable_value(
-                                        &mut self.current_display_mode,
-                                        DisplayMode::Time,
-                                        "Time",
-                                    );
-                                    ui.selectable_value(
-                                        &mut self.current_display_mode,
-                                        DisplayMode::Statistic,
-                                        "Statistic",
-                                    );
-                                    ui.selectable_value(
-                                        &mut self.current_display_mode,
-                                        DisplayMode::Todo,
-                                        "Todo",
-                                    );
-                                });
-                        });
-                    });
-                });
-            }
+        for command in commands {
+            match command {
+                TrayCommand::StartPause => self.toggle_tracking(),
+                TrayCommand::OpenWindow => {
+                    if self.current_display_mode == DisplayMode::Minimal {
+                        self.set_display_mode(self.minimal_time_tracker_options.prev_mode);
+                    }
 
-            DisplayMode::Minimal => {
-                if ui.button("⬆").clicked() {
-                    self.set_display_mode(self.minimal_time_tracker_options.prev_mode);
+                    frame.set_minimized(false);
+                    frame.focus();
+                }
+                TrayCommand::Minimal => {
+                    if self.current_display_mode != DisplayMode::Minimal {
+                        self.set_display_mode(DisplayMode::Minimal);
+                    }
+
+                    frame.set_minimized(false);
+                }
+                TrayCommand::Quit => {
+                    self.backend.flush();
+                    frame.close();
                 }
             }
         }
     }
-}
 
-/**
-    Dialog block
-**/
+    /// Starts or stops the control socket with its setting and answers what it received.
+    fn handle_ipc_requests(&mut self, ctx: &egui::Context) {
+        if self.backend.settings.control_socket != self.ipc_server.is_some() {
+            self.ipc_server = match self.ipc_server {
+                None => crate::ipc::listen(ctx.clone()),
+                Some(_) => None,
+            };
+
+            // Retried once the setting is toggled again, not on every frame.
+            if self.ipc_server.is_none() && self.backend.settings.control_socket {
+                self.backend.settings.control_socket = false;
+                self.show_toast(tr!("Could not open the control socket"));
+            }
+        }
 
-#[derive(Default)]
-struct DialogOptions {
-    current_dialog: CurrentDialog,
-    buffer: String,
-}
+        let Some(server) = &self.ipc_server else {
+            return;
+        };
 
-impl Frontend {
-    fn dialog_build(&mut self, ctx: &egui::Context) {
-        match self.dialog_options.current_dialog {
-            CurrentDialog::None => {}
+        let requests = server.requests.try_iter().collect::<Vec<_>>();
 
-            CurrentDialog::AddProject => {
-                egui::Window::new("Add Project")
-                    .collapsible(false)
-                    .resizable(false)
-                    .show(ctx, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.add(egui::TextEdit::singleline(&mut self.dialog_options.buffer));
+        for request in requests {
+            let reply = match request.command {
+                IpcCommand::Status => self.ipc_status(),
+                IpcCommand::Start { path } => match self.backend.find_subject(&path) {
+                    Some(path) => {
+                        self.start_entry(path);
+                        self.ipc_status()
+                    }
+                    None => error_reply(&format!("no subject at \"{path}\"")),
+                },
+                IpcCommand::Stop => {
+                    if matches!(self.backend.working_mode, WorkingMode::InProgress(_)) {
+                        self.time_tracker_stop_subject(false);
+                    }
 
-                            if ui.button("Cancel").clicked() {
-                                self.dialog_options.current_dialog = CurrentDialog::None;
-                                self.dialog_options.buffer = "".to_string();
-                            }
+                    self.ipc_status()
+                }
+            };
 
-                            if ui.button("Add").clicked() {
-                                self.dialog_options.current_dialog = CurrentDialog::None;
-                                self.backend.add_project(&self.dialog_options.buffer);
-                                self.dialog_options.buffer = "".to_string();
-                            }
-                        });
-                    });
-            }
+            let _ = request.reply.send(reply);
+        }
+    }
 
-            CurrentDialog::AddSubProject => {
-                egui::Window::new("Add Sub Project")
-                    .collapsible(false)
-                    .resizable(false)
-                    .show(ctx, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.add(egui::TextEdit::singleline(&mut self.dialog_options.buffer));
+    fn ipc_status(&self) -> serde_json::Value {
+        let mode = match (&self.backend.working_mode, &self.backend.pomodoro) {
+            (WorkingMode::Idle, _) => "idle",
+            (_, PomodoroState::Break(_)) => "break",
+            _ => "tracking",
+        };
 
-                            if ui.button("Cancel").clicked() {
-                                self.dialog_options.current_dialog = CurrentDialog::None;
-                                self.dialog_options.buffer = "".to_string();
-                            }
+        let subject = self.backend.get_current_work_name();
 
-                            if ui.button("Add").clicked() {
-                                self.dialog_options.current_dialog = CurrentDialog::None;
-                                self.backend.add_sub_project(&self.dialog_options.buffer);
-                                self.dialog_options.buffer = "".to_string();
-                            }
-                        });
-                    });
-            }
+        serde_json::json!({
+            "ok": true,
+            "mode": mode,
+            "subject": subject,
+            "session_seconds": self.backend.current_session_duration.as_secs(),
+        })
+    }
 
-            CurrentDialog::AddSubject => {
-                egui::Window::new("Add Project")
-                    .collapsible(false)
-                    .resizable(false)
-                    .show(ctx, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.add(egui::TextEdit::singleline(&mut self.dialog_options.buffer));
+    /// Sends the tracking state to the tray when it changed, and the session time once a
+    /// minute.
+    fn update_tray(&mut self) {
+        let Some(tray) = &self.tray else {
+            return;
+        };
 
-                            if ui.button("Cancel").clicked() {
-                                self.dialog_options.current_dialog = CurrentDialog::None;
-                                self.dialog_options.buffer = "".to_string();
-                            }
+        let state = TrayState {
+            running: matches!(self.backend.working_mode, WorkingMode::InProgress(_)),
+            work_name: self
+                .backend
+                .get_current_work_name()
+                .unwrap_or_else(|| tr!("no subject").to_string()),
+            session: format_duration(
+                self.backend.current_session_duration,
+                self.backend.settings.duration_format,
+            ),
+        };
 
-                            if ui.button("Add").clicked() {
-                                self.dialog_options.current_dialog = CurrentDialog::None;
-                                self.backend.add_subject(&self.dialog_options.buffer);
-                                self.dialog_options.buffer = "".to_string();
-                            }
-                        });
-                    });
+        if let Some((shown, sent_at)) = &self.tray_state {
+            let same = shown.running == state.running && shown.work_name == state.work_name;
+
+            if same && sent_at.elapsed() < TRAY_REFRESH_PERIOD {
+                return;
             }
+        }
 
-            CurrentDialog::AddTodoProject => {
-                egui::Window::new("Add Project")
-                    .collapsible(false)
-                    .resizable(false)
-                    .show(ctx, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.add(egui::TextEdit::singleline(&mut self.dialog_options.buffer));
+        tray.update(&state);
+        self.tray_state = Some((state, Instant::now()));
+    }
 
-                            if ui.button("Cancel").clicked() {
-                                self.dialog_options.current_dialog = CurrentDialog::None;
-                                self.dialog_options.buffer = "".to_string();
-                            }
+    fn clock_warning_build(&mut self, ctx: &egui::Context) {
+        let Some(behind) = self.backend.clock_behind else {
+            return;
+        };
 
-                            if ui.button("Add").clicked() {
-                                self.dialog_options.current_dialog = CurrentDialog::None;
-                                self.backend.add_todo_project(&self.dialog_options.buffer);
-                                self.dialog_options.buffer = "".to_string();
-                            }
-                        });
-                    });
-            }
+        egui::Area::new("clock_warning")
+            .anchor(egui::Align2::CENTER_TOP, Vec2::new(0., 40.))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_max_width(420.);
+
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        tr!(
+                            "The system clock is {behind} behind your latest record. New \
+                            sessions will sort before existing ones until the clock is fixed.",
+                            behind = format_chrono_duration(
+                                behind,
+                                self.backend.settings.duration_format
+                            )
+                        ),
+                    );
 
-            CurrentDialog::AddTodoSubProject => {
-                egui::Window::new("Add Sub Project")
-                    .collapsible(false)
-                    .resizable(false)
-                    .show(ctx, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.add(egui::TextEdit::singleline(&mut self.dialog_options.buffer));
+                    if ui.button(tr!("Dismiss")).clicked() {
+                        self.backend.clock_behind = None;
+                    }
+                });
+            });
+    }
 
-                            if ui.button("Cancel").clicked() {
-                                self.dialog_options.current_dialog = CurrentDialog::None;
-                                self.dialog_options.buffer = "".to_string();
-                            }
+    fn save_error_build(&mut self, ctx: &egui::Context) {
+        let Some(error) = &self.backend.save_error else {
+            return;
+        };
 
-                            if ui.button("Add").clicked() {
-                                self.dialog_options.current_dialog = CurrentDialog::None;
-                                self.backend
-                                    .add_todo_sub_project(&self.dialog_options.buffer);
-                                self.dialog_options.buffer = "".to_string();
-                            }
-                        });
-                    });
-            }
+        egui::Area::new("save_error")
+            .anchor(egui::Align2::CENTER_BOTTOM, Vec2::new(0., -40.))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_max_width(420.);
 
-            CurrentDialog::AddTodoSubject => {
-                egui::Window::new("Add Subject")
-                    .collapsible(false)
-                    .resizable(false)
-                    .show(ctx, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.add(egui::TextEdit::singleline(&mut self.dialog_options.buffer));
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        tr!("Saving failed, retrying: {error}", error = error),
+                    );
+                });
+            });
+    }
 
-                            if ui.button("Cancel").clicked() {
-                                self.dialog_options.current_dialog = CurrentDialog::None;
-                                self.dialog_options.buffer = "".to_string();
-                            }
+    /// Switches the visuals whenever the theme setting or, for [`Theme::System`], the
+    /// system theme changes.
+    fn apply_theme(&self, ctx: &egui::Context, frame: &eframe::Frame) {
+        let dark = match self.backend.settings.theme {
+            Theme::Light => false,
+            Theme::Dark => true,
+            Theme::System => frame.info().system_theme != Some(eframe::Theme::Light),
+        };
 
-                            if ui.button("Add").clicked() {
-                                self.dialog_options.current_dialog = CurrentDialog::None;
-                                self.backend.add_todo_subject(&self.dialog_options.buffer);
-                                self.dialog_options.buffer = "".to_string();
-                            }
-                        });
-                    });
-            }
+        if ctx.style().visuals.dark_mode != dark {
+            ctx.set_visuals(if dark {
+                egui::Visuals::dark()
+            } else {
+                egui::Visuals::light()
+            });
         }
     }
-}
 
-/**
-    Statistics block
-**/
+    fn show_toast(&mut self, text: &str) {
+        self.toast = Some((text.to_string(), Instant::now()));
+    }
 
-struct StatisticOptions {
-    scroll_offset_x: f32,
-    scroll_offset_y: f32,
-    label_from: SimpleDate,
-    label_to: SimpleDate,
-    from: DateTime<Local>,
-    to: DateTime<Local>,
-    current_project_id: Option<Uuid>,
-    current_sub_project_id: Option<Uuid>,
-    invalid_from: bool,
-    invalid_to: bool,
-}
+    fn toast_build(&mut self, ctx: &egui::Context) {
+        let Some((text, shown_at)) = &self.toast else {
+            return;
+        };
 
-struct SimpleDate {
-    year: String,
-    month: Month,
-    day: String,
-}
+        let Some(remaining) = TOAST_DURATION.checked_sub(shown_at.elapsed()) else {
+            self.toast = None;
+            return;
+        };
 
-impl TryInto<DateTime<Local>> for &SimpleDate {
-    type Error = ();
+        egui::Area::new("toast")
+            .anchor(egui::Align2::CENTER_BOTTOM, Vec2::new(0., -20.))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.weak(text.as_str());
+                });
+            });
 
-    fn try_into(self) -> Result<DateTime<Local>, Self::Error> {
-        let Ok(year) = self.year.parse::<i32>() else {
-            return Err(());
-        };
-        let Ok(day) = self.day.parse::<u32>() else {
-            return Err(());
-        };
+        ctx.request_repaint_after(remaining);
+    }
 
-        let LocalResult::Single(res) =
-            Local.with_ymd_and_hms(year, self.month.number_from_month(), day, 0, 0, 0)
-        else {
-            return Err(());
+    fn handle_system_events(&mut self) {
+        let Some(receiver) = &self.system_events else {
+            return;
         };
 
-        Ok(res)
-    }
-}
+        let events = receiver.try_iter().collect::<Vec<_>>();
 
-impl StatisticOptions {
-    fn update_from_labels(&mut self) {
-        let from: Result<DateTime<Local>, ()> = (&self.label_from).try_into();
-        let to: Result<DateTime<Local>, ()> = (&self.label_to).try_into();
+        for event in events {
+            match event {
+                SystemEvent::DisplayOff(at) => {
+                    if !self.backend.settings.pause_on_display_off {
+                        continue;
+                    }
 
-        self.invalid_from = from.is_err();
-        self.invalid_to = to.is_err();
+                    self.display_off_session = self
+                        .auto_pause(at, tr!("The screen turned off"))
+                        .map(|v| (v, at));
+                }
+                SystemEvent::ScreenLocked(at) => {
+                    if !self.backend.settings.pause_on_lock {
+                        continue;
+                    }
 
-        if let Ok(f) = from {
-            if let Ok(t) = to {
-                self.from = f;
-                self.to = t
-                    .checked_add_days(Days::new(1))
-                    .unwrap()
-                    .sub(chrono::Duration::milliseconds(100));
+                    let stopped = self.auto_pause(at, tr!("The screen was locked"));
+
+                    // Screen savers often lock right after turning the screen off, which
+                    // already stopped the session.
+                    let display_off = self.display_off_session.take().filter(|(_, off_at)| {
+                        (at - *off_at).num_seconds() < LOCK_AFTER_DISPLAY_OFF_SECONDS
+                    });
+
+                    self.locked_session = stopped.map(|v| (v, at)).or(display_off);
+                }
+                SystemEvent::ScreenUnlocked => self.offer_resume_after_lock(),
             }
         }
     }
-}
-
-impl Default for StatisticOptions {
-    fn default() -> Self {
-        let s1 = DateTime::<Local>::from(SystemTime::now());
-        let days = get_days_from_month(s1.year(), s1.month());
 
-        let from;
-        {
-            let mut day = s1.day();
-            let mut month = s1.month();
-            let mut year = s1.year();
+    /// Runs the action of a pressed hotkey. Any focused text field, dialog or not, owns the
+    /// keyboard, and stops owning it once it is no longer drawn.
+    fn handle_hotkeys(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() || self.dialog_options.current_dialog != CurrentDialog::None {
+            return;
+        }
 
-            if days == 1 {
-                if month == 1 {
-                    year -= 1;
-                    month = 12;
-                } else {
-                    month -= 1;
-                }
-                day = get_days_from_month(year, month);
+        match ctx.input(|i| self.backend.settings.hotkeys.pressed(i)) {
+            Some(HotkeyAction::Notes) => {
+                self.scratchpad_options.open = !self.scratchpad_options.open;
             }
+            Some(HotkeyAction::TimeView) => self.set_display_mode(DisplayMode::Time),
+            Some(HotkeyAction::StatisticView) => self.set_display_mode(DisplayMode::Statistic),
+            Some(HotkeyAction::TodoView) => self.set_display_mode(DisplayMode::Todo),
+            Some(HotkeyAction::MinimalView) => self.set_display_mode(DisplayMode::Minimal),
+            Some(HotkeyAction::StartPause) => self.toggle_tracking(),
+            Some(HotkeyAction::Palette) => self.open_palette(),
+            None => {}
+        }
+    }
+
+    /// Pauses at `at`, returns the subject that was running.
+    fn auto_pause(&mut self, at: DateTime<Local>, reason: &str) -> Option<EntryPath> {
+        let path = self.tracked_path();
 
-            from = Local.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap();
+        if path.is_some() && self.backend.settings.notify_auto_pause {
+            notify(tr!("Tracking paused"), reason);
         }
 
-        let to;
-        {
-            let mut day = s1.day();
-            let mut month = s1.month();
-            let mut year = s1.year();
+        self.backend.pause_at(at);
+        self.time_tracker_options.current_label = "".to_string();
 
-            if days == s1.day() {
-                if month == 12 {
-                    year += 1;
-                    month = 1;
-                    day = 1;
-                } else {
-                    month += 1;
-                    day = 1;
-                }
-            }
+        path
+    }
+
+    /// Restarts the session stopped by the lock, or asks first, unless something else got
+    /// tracked meanwhile.
+    fn offer_resume_after_lock(&mut self) {
+        let Some((path, at)) = self.locked_session.take() else {
+            return;
+        };
 
-            to = Local
-                .with_ymd_and_hms(year, month, day, 23, 59, 59)
-                .unwrap();
+        if !matches!(self.backend.working_mode, WorkingMode::Idle) {
+            return;
         }
 
-        StatisticOptions {
-            scroll_offset_x: 0.,
-            scroll_offset_y: 0.,
-            label_from: SimpleDate {
-                year: from.year().to_string(),
-                month: Month::try_from(from.month() as u8).unwrap(),
-                day: from.day().to_string(),
-            },
-            label_to: SimpleDate {
-                year: to.year().to_string(),
-                month: Month::try_from(to.month() as u8).unwrap(),
-                day: to.day().to_string(),
-            },
-            invalid_from: false,
-            invalid_to: false,
-            from,
-            to,
-            current_project_id: None,
-            current_sub_project_id: None,
+        match self.backend.settings.resume_on_unlock {
+            UnlockResume::Off => {}
+            UnlockResume::Automatic => self.start_entry(path),
+            UnlockResume::Ask => {
+                if self.dialog_options.current_dialog == CurrentDialog::None {
+                    self.dialog_options.current_dialog = CurrentDialog::ResumeAfterLock(path, at);
+                }
+            }
         }
     }
 }
 
-impl Frontend {
-    fn build_statistic(&mut self, ui: &mut Ui) {
-        self.build_menu(ui);
+impl eframe::App for Frontend {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.apply_theme(ctx, frame);
 
-        let style = ui.style().clone();
-        let mut new_style = (*style).clone();
-        new_style.spacing.item_spacing = Vec2::new(0., 0.);
+        let display_mode = self.current_display_mode;
 
-        ui.set_style(new_style);
+        let opacity = if display_mode == DisplayMode::Minimal {
+            self.backend.settings.minimal_opacity.clamp(0.2, 1.0)
+        } else {
+            1.0
+        };
 
-        ui.horizontal_top(|ui| {
-            ui.add_space(400.);
+        let mut size = window_size(display_mode, &self.backend.window_state);
+
+        // The minimal window is far too small for the palette, the end of day summary, a
+        // window suggestion or the recent subjects, it grows while those are open.
+        if self.palette_options.open {
+            size = size.max(PALETTE_WINDOW_SIZE);
+        } else if self.end_of_day_options.open {
+            size = size.max(END_OF_DAY_WINDOW_SIZE);
+        } else if display_mode == DisplayMode::Minimal && self.window_suggestion().is_some() {
+            size = size.max(SUGGESTION_WINDOW_SIZE);
+        } else if display_mode == DisplayMode::Minimal
+            && self.minimal_time_tracker_options.recent_open
+        {
+            size = size.max(RECENT_WINDOW_SIZE);
+        }
 
-            ui.set_max_height(30.);
-            {
-                let y = ui.add_sized(
-                    (50., 15.),
-                    TextEdit::singleline(&mut self.statistic_options.label_from.year),
-                );
+        let previous_size = size;
 
-                if y.gained_focus() {
-                    self.hotkeys_blocked = true;
-                }
+        let title = if self.profile_options.profiles.folders.is_empty() {
+            "Ruh Time Tracker".to_string()
+        } else {
+            format!("Ruh Time Tracker – {}", active_name())
+        };
 
-                if y.lost_focus() {
-                    self.hotkeys_blocked = false;
-                    self.statistic_options.update_from_labels();
+        let close_requested =
+            custom_window_frame(ctx, frame, &title, display_mode, opacity, &mut size, |ui| {
+                match display_mode {
+                    DisplayMode::Statistic => self.build_statistic(ui),
+                    DisplayMode::Time => self.time_tracker_build(ui),
+                    DisplayMode::Todo => self.todo_build(ui),
+                    DisplayMode::Minimal => self.minimal_time_tracker_build(ui),
                 }
+            });
 
-                ui.add_space(2.);
+        if close_requested {
+            self.backend.flush();
+            frame.close();
+        }
 
-                ui.push_id(9, |ui| {
-                    if egui::ComboBox::from_label("")
-                        .selected_text(self.statistic_options.label_from.month.name())
-                        .show_ui(ui, |ui| {
-                            for month in 1..=12 {
-                                let m = Month::try_from(month).unwrap();
-                                ui.selectable_value(
-                                    &mut self.statistic_options.label_from.month,
-                                    m,
-                                    m.name(),
-                                );
-                            }
-                        })
-                        .response
-                        .changed()
-                    {
-                        self.statistic_options.update_from_labels();
-                    };
-                });
+        if is_resizable(display_mode) && size != previous_size {
+            self.backend
+                .window_state
+                .sizes
+                .insert(display_mode, (size.x, size.y));
+            self.window_resized = true;
+        }
+
+        // Saved once the grip is let go rather than on every dragged frame.
+        if self.window_resized && !ctx.input(|i| i.pointer.any_down()) {
+            self.window_resized = false;
+            self.backend.dirty();
+        }
 
-                ui.add_space(2.);
+        self.handle_system_events();
 
-                let d = ui.add_sized(
-                    (30., 15.),
-                    TextEdit::singleline(&mut self.statistic_options.label_from.day),
-                );
+        self.handle_tray_commands(frame);
 
-                if d.gained_focus() {
-                    self.hotkeys_blocked = true;
-                }
+        self.handle_ipc_requests(ctx);
 
-                if d.lost_focus() {
-                    self.hotkeys_blocked = false;
-                    self.statistic_options.update_from_labels();
-                }
-            }
+        self.check_external_change();
 
-            ui.add_space(5.);
-            ui.add_sized((5., 15.), Label::new(":"));
-            ui.add_space(5.);
+        self.remember_window_state(frame);
 
-            {
-                let y = ui.add_sized(
-                    (50., 15.),
-                    TextEdit::singleline(&mut self.statistic_options.label_to.year),
-                );
+        self.backend.update_time();
 
-                if y.gained_focus() {
-                    self.hotkeys_blocked = true;
-                }
+        self.backend.roll_scratchpad();
+
+        if self.backend.update_pomodoro() {
+            frame.request_user_attention(egui::UserAttentionType::Informational);
 
-                if y.lost_focus() {
-                    self.hotkeys_blocked = false;
-                    self.statistic_options.update_from_labels();
+            if self.backend.settings.notify_pomodoro {
+                match self.backend.pomodoro {
+                    PomodoroState::Work(_) => {
+                        notify(tr!("Pomodoro"), tr!("Break is over, back to work"))
+                    }
+                    _ => notify(tr!("Pomodoro"), tr!("Time for a break")),
                 }
+            }
 
-                ui.add_space(2.);
+            self.time_tracker_options.current_label = match self.backend.pomodoro {
+                PomodoroState::Work(_) => self.backend.get_current_work_name().unwrap_or_default(),
+                _ => "".to_string(),
+            };
+        }
 
-                ui.push_id(10, |ui| {
-                    if egui::ComboBox::from_label("")
-                        .selected_text(self.statistic_options.label_to.month.name())
-                        .show_ui(ui, |ui| {
-                            for month in 1..=12 {
-                                let m = Month::try_from(month).unwrap();
-                                ui.selectable_value(
-                                    &mut self.statistic_options.label_to.month,
-                                    m,
-                                    m.name(),
-                                );
-                            }
-                        })
-                        .response
-                        .changed()
-                    {
-                        self.statistic_options.update_from_labels();
-                    };
-                });
+        self.check_goal_reached();
 
-                ui.add_space(2.);
+        self.check_stretch_reminder();
 
-                let d = ui.add_sized(
-                    (30., 15.),
-                    TextEdit::singleline(&mut self.statistic_options.label_to.day),
-                );
+        self.check_end_of_day();
 
-                if d.gained_focus() {
-                    self.hotkeys_blocked = true;
-                }
+        self.check_window_title(ctx);
 
-                if d.lost_focus() {
-                    self.hotkeys_blocked = false;
-                    self.statistic_options.update_from_labels();
-                }
-            }
-        });
+        self.update_tray();
 
-        ui.add_space(10.);
+        if self.backend.pomodoro != PomodoroState::Off {
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
 
-        let records = self
-            .backend
-            .history
-            .get_ordered_records((self.statistic_options.from, self.statistic_options.to));
+        ctx.request_repaint_after(self.backend.repaint_period());
 
-        ui.vertical(|ui| {
-            ui.push_id(3, |ui| {
-                ui.set_min_height(400.0);
-                ui.set_max_height(400.0);
-                ScrollArea::vertical().show(ui, |ui| {
-                    struct Summary {
-                        title: String,
-                        duration: chrono::Duration,
-                    }
+        if let Some(waker) = &self.repaint_waker {
+            waker.set_period(self.backend.repaint_period());
+        }
 
-                    let mut projects_summary: HashMap<Uuid, Summary> = HashMap::new();
-                    let mut sub_projects_summary: HashMap<Uuid, Summary> = HashMap::new();
-                    let mut subjects_summary: HashMap<Uuid, Summary> = HashMap::new();
+        if self.backend.interrupted_at.is_some()
+            && self.dialog_options.current_dialog == CurrentDialog::None
+        {
+            self.time_tracker_options.current_label = "".to_string();
+            self.dialog_options.current_dialog = CurrentDialog::ResumeSession;
 
-                    for record in self
-                        .backend
-                        .history
-                        .get_records((self.statistic_options.from, self.statistic_options.to))
-                    {
-                        if let Some(v) = projects_summary.get_mut(&record.project_id) {
-                            v.duration = v.duration.add(record.get_duration());
-                        } else {
-                            projects_summary.insert(
-                                record.project_id,
-                                Summary {
-                                    title: self
-                                        .backend
-                                        .projects
-                                        .inner
-                                        .get(&record.project_id)
-                                        .unwrap()
-                                        .name
-                                        .clone(),
-                                    duration: record.get_duration(),
-                                },
-                            );
-                        }
+            if self.backend.settings.notify_auto_pause {
+                notify(
+                    tr!("Tracking paused"),
+                    tr!("No activity for a while, the session was stopped"),
+                );
+            }
+        }
 
-                        if let Some(id) = self.statistic_options.current_project_id {
-                            if id == record.project_id {
-                                if let Some(v) =
-                                    sub_projects_summary.get_mut(&record.sub_project_id)
-                                {
-                                    v.duration = v.duration.add(record.get_duration());
-                                } else {
-                                    sub_projects_summary.insert(
-                                        record.sub_project_id,
-                                        Summary {
-                                            title: self
-                                                .backend
-                                                .projects
-                                                .inner
-                                                .get(&record.project_id)
-                                                .unwrap_or_else(|| {
-                                                    panic!("bad project id {}", record.subject_id)
-                                                })
-                                                .inner
-                                                .get(&record.sub_project_id)
-                                                .unwrap_or_else(|| {
-                                                    panic!(
-                                                        "bad sub-project id {}",
-                                                        record.subject_id
-                                                    )
-                                                })
-                                                .name
-                                                .clone(),
-                                            duration: record.get_duration(),
-                                        },
-                                    );
-                                }
-                            }
-                        }
+        self.dialog_build(ctx);
 
-                        if let Some(id) = self.statistic_options.current_sub_project_id {
-                            if id == record.sub_project_id {
-                                if let Some(v) = subjects_summary.get_mut(&record.subject_id) {
-                                    v.duration = v.duration.add(record.get_duration());
-                                } else {
-                                    subjects_summary.insert(
-                                        record.subject_id,
-                                        Summary {
-                                            title: self
-                                                .backend
-                                                .projects
-                                                .inner
-                                                .get(&record.project_id)
-                                                .unwrap_or_else(|| {
-                                                    panic!("bad project id {}", record.subject_id)
-                                                })
-                                                .inner
-                                                .get(&record.sub_project_id)
-                                                .unwrap_or_else(|| {
-                                                    panic!(
-                                                        "bad sub-project id {}",
-                                                        record.subject_id
-                                                    )
-                                                })
-                                                .inner
-                                                .get(&record.subject_id)
-                                                .unwrap_or_else(|| {
-                                                    panic!("bad subject id {}", record.subject_id)
-                                                })
-                                                .lock()
-                                                .unwrap()
-                                                .name
-                                                .clone(),
-                                            duration: record.get_duration(),
-                                        },
-                                    );
-                                }
-                            }
-                        }
-                    }
+        self.scratchpad_build(ctx);
 
-                    ui.horizontal(|ui| {
-                        ui.vertical(|ui| {
-                            let mut c: Vec<(&Uuid, &Summary)> = projects_summary.iter().collect();
-                            c.sort_by(|a, b| a.1.duration.cmp(&b.1.duration));
+        self.palette_build(ctx);
 
-                            for v in c {
-                                let mut text = RichText::new(&v.1.title);
+        self.profile_unlock_build(ctx);
 
-                                if self.statistic_options.current_project_id == Some(*v.0) {
-                                    text = text.strong();
-                                }
+        self.end_of_day_build(ctx);
 
-                                ui.horizontal(|ui| {
-                                    if ui.button(text).clicked() {
-                                        self.statistic_options.current_project_id = Some(*v.0);
-                                        self.statistic_options.current_sub_project_id = None;
-                                    }
+        self.window_suggestion_build(ctx);
 
-                                    ui.label(format!(
-                                        " - {}",
-                                        format_chrono_duration(v.1.duration)
-                                    ));
-                                });
-                            }
-                        });
+        self.clock_warning_build(ctx);
+        self.save_error_build(ctx);
 
-                        ui.add_space(215.);
+        self.toast_build(ctx);
 
-                        ui.vertical(|ui| {
-                            let mut c: Vec<(&Uuid, &Summary)> =
-                                sub_projects_summary.iter().collect();
-                            c.sort_by(|a, b| a.1.duration.cmp(&b.1.duration));
+        self.handle_hotkeys(ctx);
+    }
 
-                            for v in c {
-                                let mut text = RichText::new(&v.1.title);
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.backend.flush();
+        self.ipc_server = None;
+        self.instance_lock = None;
+    }
 
-                                if self.statistic_options.current_sub_project_id == Some(*v.0) {
-                                    text = text.strong();
-                                }
+    fn clear_color(&self, _visuals: &Visuals) -> [f32; 4] {
+        egui::Rgba::TRANSPARENT.to_array() // Make sure we don't paint anything behind the rounded corners
+    }
+}
 
-                                ui.horizontal(|ui| {
-                                    if ui.button(text).clicked() {
-                                        self.statistic_options.current_sub_project_id = Some(*v.0);
-                                    }
+/**
+Menu block
+ **/
+impl Frontend {
+    fn build_menu(&mut self, ui: &mut Ui) {
+        match self.current_display_mode {
+            DisplayMode::Todo | DisplayMode::Statistic | DisplayMode::Time => {
+                ui.horizontal_top(|ui| {
+                    ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.add_space(5.);
+                            let overdue = self.backend.overdue_todos(Local::now());
+                            let todo_label = match overdue {
+                                0 => tr!("Todo").to_string(),
+                                n => tr!("Todo ({n} overdue)", n = n),
+                            };
 
-                                    ui.label(format!(
-                                        " - {}",
-                                        format_chrono_duration(v.1.duration)
-                                    ));
+                            egui::ComboBox::from_label("")
+                                .selected_text(translate(&format!(
+                                    "{:?}",
+                                    self.current_display_mode
+                                )))
+                                .show_ui(ui, |ui| {
+                                    for (mode, label) in [
+                                        (DisplayMode::Time, tr!("Time").to_string()),
+                                        (DisplayMode::Statistic, tr!("Statistic").to_string()),
+                                        (DisplayMode::Todo, todo_label),
+                                    ] {
+                                        if ui
+                                            .selectable_label(
+                                                self.current_display_mode == mode,
+                                                label,
+                                            )
+                                            .clicked()
+                                        {
+                                            self.set_display_mode(mode);
+                                        }
+                                    }
                                 });
+
+                            if ui.button("⚙").on_hover_text(tr!("Settings")).clicked() {
+                                self.dialog_options.current_dialog = CurrentDialog::Settings;
                             }
-                        });
 
-                        ui.add_space(215.);
+                            if ui.button("✏").on_hover_text(self.notes_hint()).clicked() {
+                                self.scratchpad_options.open = !self.scratchpad_options.open;
+                            }
 
-                        ui.vertical(|ui| {
-                            let mut c: Vec<&Summary> = subjects_summary.values().collect();
-                            c.sort_by(|a, b| a.duration.cmp(&b.duration));
-
-                            for v in c {
-                                ui.label(format!(
-                                    "{} - {}",
-                                    v.title,
-                                    format_chrono_duration(v.duration)
-                                ));
-                                ui.add_space(4.);
+                            self.profile_menu(ui);
+
+                            if self.current_display_mode != DisplayMode::Statistic {
+                                self.search_field(ui);
                             }
                         });
                     });
                 });
-            });
-        });
+            }
 
-        ui.separator();
+            DisplayMode::Minimal => {
+                if ui.button("⬆").clicked() {
+                    self.set_display_mode(self.minimal_time_tracker_options.prev_mode);
+                }
+            }
+        }
+    }
+}
 
-        ui.push_id(7, |ui| {
-            let time_block = ScrollArea::horizontal()
-                .scroll_bar_visibility(ScrollBarVisibility::AlwaysHidden)
-                .scroll_offset(Vec2::new(self.statistic_options.scroll_offset_x, 0.));
+/**
+    Scratchpad block
+**/
+#[derive(Default)]
+struct ScratchpadOptions {
+    open: bool,
+}
 
-            time_block.show(ui, |ui| {
-                ui.horizontal(|ui| {
-                    ui.add_space(50.);
-
-                    for i in 0..=24 {
-                        let c = ui.label(
-                            RichText::new(if i < 10 {
-                                format!("0{i}")
-                            } else {
-                                format!("{i}")
-                            })
-                            .font(FontId::proportional(12.0)),
-                        );
-                        if i < 24 {
-                            ui.add_space(60.0 - c.rect.size().x)
-                        }
-                    }
-                });
+impl Frontend {
+    fn scratchpad_build(&mut self, ctx: &egui::Context) {
+        if !self.scratchpad_options.open {
+            return;
+        }
 
-                ui.horizontal(|ui| {
-                    ui.add_space(50.);
-                    let (rect, _response) =
-                        ui.allocate_exact_size(egui::vec2(2., 10.0), egui::Sense::click());
+        let mut open = true;
+
+        egui::Window::new(tr!("Notes"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_TOP, Vec2::new(-10., 40.))
+            .show(ctx, |ui| {
+                let r = ui.add(
+                    TextEdit::multiline(&mut self.backend.scratchpad)
+                        .desired_width(280.)
+                        .desired_rows(12),
+                );
 
-                    let mut ident = rect.size().x;
+                if r.changed() {
+                    self.backend.dirty();
+                }
 
-                    ui.painter().add(RectShape {
-                        rect,
-                        rounding: Rounding::same(1.0),
-                        fill: Color32::LIGHT_GRAY,
-                        stroke: Default::default(),
+                if !self.backend.journal.is_empty() {
+                    ui.collapsing(tr!("Earlier notes"), |ui| {
+                        egui::ScrollArea::vertical()
+                            .max_height(200.)
+                            .show(ui, |ui| {
+                                for (day, notes) in self.backend.journal.iter().rev() {
+                                    ui.strong(day.format(DATE_FORMAT).to_string());
+                                    ui.label(notes);
+                                }
+                            });
                     });
+                }
+            });
 
-                    for _ in 0..24 {
-                        ui.add_space(60.0 - ident);
+        if !open {
+            self.scratchpad_options.open = false;
+        }
+    }
+}
+
+/**
+    Profiles block
+**/
+#[derive(Default)]
+struct ProfileOptions {
+    profiles: Profiles,
+    new_name: String,
+    /// Encrypted profile waiting for its passphrase.
+    unlocking: Option<String>,
+    unlock_options: UnlockOptions,
+}
 
-                        let (rect, _response) =
-                            ui.allocate_exact_size(egui::vec2(2., 10.0), egui::Sense::click());
+impl Frontend {
+    fn profile_menu(&mut self, ui: &mut Ui) {
+        let active = active_name();
+        let mut switch_to = None;
 
-                        ident = rect.size().x;
+        // Only named once there is more than one profile to pick from.
+        let label = if self.profile_options.profiles.folders.is_empty() {
+            "👤".to_string()
+        } else {
+            format!("👤 {active}")
+        };
 
-                        ui.painter().add(RectShape {
-                            rect,
-                            rounding: Rounding::same(1.0),
-                            fill: Color32::LIGHT_GRAY,
-                            stroke: Default::default(),
-                        });
+        ui.menu_button(label, |ui| {
+            for name in self.profile_options.profiles.names() {
+                if ui.selectable_label(name == active, name).clicked() {
+                    switch_to = Some(name.to_string());
+                    ui.close_menu();
+                }
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut self.profile_options.new_name)
+                        .desired_width(120.)
+                        .hint_text(tr!("New profile")),
+                );
+
+                if ui.button(tr!("Add")).clicked() {
+                    let name = self.profile_options.new_name.trim().to_string();
+
+                    match self.profile_options.profiles.add(&name) {
+                        Ok(()) => {
+                            self.profile_options.new_name.clear();
+                            switch_to = Some(name);
+                            ui.close_menu();
+                        }
+                        Err(e) => self.show_toast(&e),
                     }
-                });
+                }
             });
-        });
+        })
+        .response
+        .on_hover_text(tr!("Profile"));
 
-        ui.horizontal(|ui| {
-            ui.set_min_height(320.);
-            ui.set_max_height(320.);
+        let Some(name) = switch_to else {
+            return;
+        };
 
-            ui.push_id(5, |ui| {
-                ui.set_min_width(50.);
-                ui.set_max_width(50.);
+        let encrypted = self
+            .profile_options
+            .profiles
+            .folder(&name)
+            .is_some_and(|v| is_encrypted(&v));
+
+        if encrypted && name != active {
+            self.profile_options.unlocking = Some(name);
+            self.profile_options.unlock_options = UnlockOptions::default();
+        } else if let Err(e) = self.switch_profile(&name, None) {
+            self.show_toast(&e);
+        }
+    }
 
-                let date_block = ScrollArea::vertical()
-                    .scroll_bar_visibility(ScrollBarVisibility::AlwaysHidden)
-                    .scroll_offset(Vec2::new(0., self.statistic_options.scroll_offset_y));
+    fn profile_unlock_build(&mut self, ctx: &egui::Context) {
+        let Some(name) = self.profile_options.unlocking.clone() else {
+            return;
+        };
 
-                date_block.show(ui, |ui| {
-                    ui.vertical(|ui| {
-                        let mut first_year_first_month = true;
+        let mut open = true;
+        let mut unlock = false;
 
-                        for year in
-                            self.statistic_options.from.year()..=self.statistic_options.to.year()
-                        {
-                            //TODO: fix month
-                            for month in self.statistic_options.from.month()
-                                ..=self.statistic_options.to.month()
-                            {
-                                let from = if first_year_first_month {
-                                    first_year_first_month = false;
+        egui::Window::new(tr!("Unlock {name}", name = name))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                unlock = passphrase_ui(ui, &mut self.profile_options.unlock_options);
+            });
 
-                                    self.statistic_options.from.day()
-                                } else {
-                                    1
-                                };
+        if !open {
+            self.profile_options.unlocking = None;
+        }
 
-                                let to = if year == self.statistic_options.to.year()
-                                    && month == self.statistic_options.to.month()
-                                {
-                                    self.statistic_options.to.day()
-                                } else {
-                                    get_days_from_month(year, month)
-                                };
+        if unlock {
+            let passphrase = std::mem::take(&mut self.profile_options.unlock_options.passphrase);
 
-                                for day in from..=to {
-                                    ui.horizontal(|ui| {
-                                        ui.set_min_height(25.);
-                                        ui.set_max_height(25.);
-
-                                        ui.label(
-                                            RichText::new(format!(
-                                                "{}/{}",
-                                                format_number(day),
-                                                format_number(month)
-                                            ))
-                                            .font(FontId::proportional(13.0)),
-                                        );
-                                    });
-                                }
-                            }
-                        }
-                    });
-                });
-            });
+            if let Err(e) = self.switch_profile(&name, Some(&passphrase)) {
+                self.profile_options.unlock_options.error = Some(unlock_error(e));
+            }
+        }
+    }
 
-            ui.push_id(6, |ui| {
-                let bars_block = ScrollArea::both().show(ui, |ui| {
-                    ui.set_min_size(Vec2::new(
-                        60.0 * 24.0,
-                        315.0f32.max(
-                            25. * calendar_days_count(
-                                self.statistic_options.from,
-                                self.statistic_options.to,
-                            ) as f32,
-                        ),
-                    ));
+    /// Saves the data of the profile in use and continues with that of `name`, opened with
+    /// `passphrase` when encrypted. A running session is stopped first, so its record ends at
+    /// the switch.
+    fn switch_profile(&mut self, name: &str, passphrase: Option<&str>) -> Result<(), String> {
+        let previous = active_name();
 
-                    ui.vertical(|ui| {
-                        let mut first_year_first_month = true;
+        if name == previous {
+            return Ok(());
+        }
+
+        if matches!(self.backend.working_mode, WorkingMode::InProgress(_)) {
+            self.backend.update_time();
+            self.time_tracker_stop_subject(false);
+        }
 
-                        let mut i = 0usize;
+        self.backend.flush();
 
-                        for year in
-                            self.statistic_options.from.year()..=self.statistic_options.to.year()
-                        {
-                            for month in self.statistic_options.from.month()
-                                ..=self.statistic_options.to.month()
-                            {
-                                let from = if first_year_first_month {
-                                    first_year_first_month = false;
+        let profiles = std::mem::take(&mut self.profile_options.profiles);
 
-                                    self.statistic_options.from.day()
-                                } else {
-                                    1
-                                };
+        let loaded = profiles
+            .activate(Some(name))
+            .and_then(|_| match passphrase {
+                Some(v) => Backend::unlock(v),
+                None => Backend::try_load(),
+            });
 
-                                let to = if year == self.statistic_options.to.year()
-                                    && month == self.statistic_options.to.month()
-                                {
-                                    self.statistic_options.to.day()
-                                } else {
-                                    get_days_from_month(year, month)
-                                };
+        if loaded.is_err() {
+            let _ = profiles.activate(Some(&previous));
+        }
 
-                                for _ in from..=to {
-                                    let mut previous_ending = None;
-                                    let mut space_added = false;
-                                    let mut length = 0_f32;
-
-                                    ui.horizontal(|ui| {
-                                        ui.set_min_height(25.);
-                                        ui.set_max_height(25.);
-
-                                        for record in records.get(i).unwrap() {
-                                            if !space_added {
-                                                let d = record.start_date.hour() as f32 * 60.0
-                                                    + record.start_date.minute() as f32;
-                                                ui.add_space(d);
-                                                length += d;
-
-                                                space_added = true;
-                                            }
-
-                                            let duration = record.get_duration();
-
-                                            if duration.num_minutes() <= 0 {
-                                                continue;
-                                            }
-
-                                            if let Some(prev) = previous_ending {
-                                                let dur = record
-                                                    .start_date
-                                                    .signed_duration_since(prev)
-                                                    .num_minutes();
-
-                                                if dur > 0 {
-                                                    ui.add_space(dur as f32);
-                                                    length += dur as f32;
-                                                }
-                                            }
-
-                                            let desired_size = egui::vec2(
-                                                record.get_duration().num_minutes() as f32,
-                                                15.0,
-                                            );
-
-                                            length += desired_size.x;
-
-                                            let (rect, response) = ui.allocate_exact_size(
-                                                desired_size,
-                                                egui::Sense::click(),
-                                            );
-
-                                            let project = self
-                                                .backend
-                                                .projects
-                                                .inner
-                                                .get(&record.project_id)
-                                                .unwrap();
-
-                                            let sub_project =
-                                                project.inner.get(&record.sub_project_id).unwrap();
-
-                                            let subject = sub_project
-                                                .inner
-                                                .get(&record.subject_id)
-                                                .unwrap()
-                                                .lock()
-                                                .unwrap();
-
-                                            response.on_hover_text(format!(
-                                                "{}/{}/{}",
-                                                project.name, sub_project.name, subject.name
-                                            ));
-
-                                            ui.painter().add(RectShape {
-                                                rect,
-                                                rounding: Rounding::same(4.0),
-                                                fill: Color32::from_rgb(
-                                                    project.color.0,
-                                                    project.color.1,
-                                                    project.color.2,
-                                                ),
-                                                stroke: Default::default(),
-                                            });
-
-                                            previous_ending = Some(record.end_date);
-                                        }
+        self.profile_options.profiles = profiles;
+        self.replace_backend(loaded?);
 
-                                        if length < 60.0 * 24.0 {
-                                            ui.add_space(60.0 * 24.0 - length);
-                                        }
-                                    });
+        self.profile_options.profiles.last = Some(name.to_string());
 
-                                    i += 1;
-                                }
-                            }
-                        }
-                    });
-                });
+        if let Err(e) = self.profile_options.profiles.save() {
+            eprintln!("{e}");
+        }
 
-                self.statistic_options.scroll_offset_x = bars_block.state.offset.x;
-                self.statistic_options.scroll_offset_y = bars_block.state.offset.y;
-            });
-        });
+        self.show_toast(&tr!("Switched to the profile {name}", name = name));
 
-        ui.set_style(style);
+        Ok(())
     }
 }
 
 /**
-    Maximized Time Tracker block
+    Unlock block
 **/
-
 #[derive(Default)]
-struct TimeTrackerOptions {
-    current_label: String,
+struct UnlockOptions {
+    passphrase: String,
+    error: Option<String>,
 }
 
-impl Frontend {
-    fn time_tracker_build(&mut self, ui: &mut Ui) {
-        ui.horizontal_top(|ui| {
-            ui.label(format!(
-                "Current work: {}",
-                self.time_tracker_options.current_label
-            ));
-
-            self.build_menu(ui);
-        });
-
-        ui.horizontal(|ui| {
-            ui.set_min_height(55.0);
-            ui.set_max_height(55.0);
-
-            if self.backend.get_current_subject().is_some() {
-                match self.backend.working_mode {
-                    WorkingMode::Idle => {
-                        if ui.button("START").clicked() {
-                            self.time_tracker_start_subject()
-                        }
-                    }
-                    WorkingMode::InProgress(_) => {
-                        if ui.button("PAUSE").clicked() {
-                            self.time_tracker_stop_subject(false);
-                        }
-                    }
-                }
-                ui.label(format_duration(self.backend.current_session_duration));
-            }
-        });
+/// Passphrase field with its error, true once the passphrase should be tried.
+fn passphrase_ui(ui: &mut Ui, options: &mut UnlockOptions) -> bool {
+    let r = ui.add(
+        TextEdit::singleline(&mut options.passphrase)
+            .password(true)
+            .desired_width(240.)
+            .hint_text(tr!("Passphrase")),
+    );
 
-        ui.separator();
+    let entered = r.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
 
-        ui.horizontal(|ui| {
-            ui.set_min_height(290.0);
-            ui.set_max_height(290.0);
+    if let Some(error) = &options.error {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
 
-            ui.push_id(1, |ui| {
-                ScrollArea::both().show(ui, |ui| {
-                    self.time_tracker_build_projects(ui);
-                });
-            });
+    let clicked = ui.button(tr!("Unlock")).clicked();
 
-            ui.separator();
+    (entered || clicked) && !options.passphrase.is_empty()
+}
 
-            ui.push_id(2, |ui| {
-                ScrollArea::both().show(ui, |ui| {
-                    self.time_tracker_build_sub_projects(ui);
-                });
-            });
+/// The data of the profile in use, `None` when it is encrypted and waits for its passphrase.
+pub fn load_unencrypted() -> Result<Option<Backend>, String> {
+    if is_encrypted(&data_dir()) {
+        return Ok(None);
+    }
 
-            ui.separator();
+    Backend::try_load().map(Some)
+}
 
-            ui.push_id(3, |ui| {
-                ScrollArea::both().show(ui, |ui| {
-                    self.time_tracker_build_subjects(ui);
-                });
-            });
-        });
+fn unlock_error(e: String) -> String {
+    match e.as_str() {
+        WRONG_PASSPHRASE => tr!("Wrong passphrase, try again").to_string(),
+        _ => e,
     }
+}
 
-    fn time_tracker_build_sub_projects(&mut self, ui: &mut Ui) {
-        ui.set_min_width(300.0);
-        ui.set_max_width(300.0);
+/// Runs the tracker, once the passphrase was entered when the data file is encrypted and
+/// once the data loaded.
+pub struct Launcher {
+    frontend: Option<Frontend>,
+    /// Handed to the tracker once it is built.
+    instance_lock: Option<InstanceLock>,
+    unlock_options: UnlockOptions,
+    /// Why the data could not be loaded. The tracker does not start with empty data, its
+    /// first save would overwrite the data that is there.
+    load_error: Option<String>,
+}
 
-        let Some(current_project) = self.backend.get_current_project() else {
-            return;
+impl Launcher {
+    /// `backend` is `Ok(None)` while the data file waits for its passphrase.
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        backend: Result<Option<Backend>, String>,
+        instance_lock: InstanceLock,
+    ) -> Self {
+        let mut launcher = Self {
+            frontend: None,
+            instance_lock: Some(instance_lock),
+            unlock_options: UnlockOptions::default(),
+            load_error: None,
         };
 
-        let current_id = if let Some(cur_project) = self.backend.get_current_sub_project() {
-            cur_project.id
-        } else {
-            Uuid::new_v4()
-        };
+        launcher.loaded(&cc.egui_ctx, backend);
+
+        launcher
+    }
 
-        let c = current_project.get_inner_sorted(|a, b| a.created_at.cmp(&b.created_at));
+    fn loaded(&mut self, ctx: &egui::Context, backend: Result<Option<Backend>, String>) {
+        self.load_error = None;
 
-        ui.vertical(|ui| {
-            for sub_project in c {
-                if sub_project.is_deleted {
-                    continue;
+        match backend {
+            Ok(Some(backend)) => {
+                if let Some(instance_lock) = self.instance_lock.take() {
+                    self.frontend = Some(Frontend::init(ctx, backend, instance_lock));
                 }
+            }
+            Ok(None) => {}
+            Err(e) => self.load_error = Some(e),
+        }
+    }
 
-                ui.horizontal(|ui| {
-                    let mut text = RichText::new(&sub_project.name);
+    fn load_error_build(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let mut size = window_size(DisplayMode::Time, &WindowState::default());
+        let mut retry = false;
+
+        let close_requested = custom_window_frame(
+            ctx,
+            frame,
+            "Ruh Time Tracker",
+            DisplayMode::Time,
+            1.0,
+            &mut size,
+            |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(120.);
+                    ui.label(tr!(
+                        "The data could not be loaded, fix or move the file and try again"
+                    ));
+                    ui.add_space(8.);
 
-                    if sub_project.id == current_id {
-                        text = text.strong();
+                    if let Some(error) = &self.load_error {
+                        ui.colored_label(ui.visuals().error_fg_color, error);
                     }
 
-                    if ui.button(text).clicked() {
-                        self.backend.set_current_sub_project(Some(sub_project.id));
-                    }
+                    ui.add_space(8.);
 
-                    ui.label(format_duration(
-                        self.backend.get_sub_project_time(&sub_project.id).unwrap(),
-                    ));
+                    retry = ui.button(tr!("Try again")).clicked();
                 });
+            },
+        );
 
-                ui.add_space(5.0);
-            }
+        if close_requested {
+            frame.close();
+        }
 
-            if ui.button("   +   ").clicked() {
-                self.dialog_options.current_dialog = CurrentDialog::AddSubProject;
-            }
-        });
+        if retry {
+            let backend = load_unencrypted();
+            self.loaded(ctx, backend);
+        }
     }
 
-    fn time_tracker_build_projects(&mut self, ui: &mut Ui) {
-        ui.set_min_width(300.0);
-        ui.set_max_width(300.0);
+    fn unlock_build(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let mut size = window_size(DisplayMode::Time, &WindowState::default());
+        let mut unlock = false;
+
+        let close_requested = custom_window_frame(
+            ctx,
+            frame,
+            "Ruh Time Tracker",
+            DisplayMode::Time,
+            1.0,
+            &mut size,
+            |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(120.);
+                    ui.label(tr!("The data file is encrypted, enter its passphrase"));
+                    ui.add_space(8.);
+
+                    unlock = passphrase_ui(ui, &mut self.unlock_options);
+                });
+            },
+        );
 
-        let current_id = if let Some(cur_project) = self.backend.get_current_project() {
-            cur_project.id
-        } else {
-            Uuid::new_v4()
-        };
+        if close_requested {
+            frame.close();
+        }
 
-        let c = self
-            .backend
-            .projects
-            .get_inner_sorted(|a, b| a.created_at.cmp(&b.created_at));
+        if !unlock {
+            return;
+        }
 
-        ui.vertical(|ui| {
-            for project in c {
-                if project.is_deleted {
-                    continue;
-                }
+        // A wrong passphrase leaves the file untouched, nothing saves before it opens.
+        match Backend::unlock(&std::mem::take(&mut self.unlock_options.passphrase)) {
+            Ok(backend) => self.loaded(ctx, Ok(Some(backend))),
+            Err(e) => self.unlock_options.error = Some(unlock_error(e)),
+        }
+    }
+}
 
-                ui.horizontal(|ui| {
-                    let mut text = RichText::new(&project.name);
+impl eframe::App for Launcher {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        match &mut self.frontend {
+            Some(frontend) => frontend.update(ctx, frame),
+            None if self.load_error.is_some() => self.load_error_build(ctx, frame),
+            None => self.unlock_build(ctx, frame),
+        }
+    }
 
-                    if project.id == current_id {
-                        text = text.strong();
-                    }
+    fn on_exit(&mut self, gl: Option<&eframe::glow::Context>) {
+        if let Some(frontend) = &mut self.frontend {
+            frontend.on_exit(gl);
+        }
+    }
 
-                    if ui.button(text).clicked() {
-                        self.backend.set_current_project(Some(project.id));
-                    }
+    fn clear_color(&self, _visuals: &Visuals) -> [f32; 4] {
+        egui::Rgba::TRANSPARENT.to_array()
+    }
+}
 
-                    ui.label(format_duration(
-                        self.backend.get_project_time(&project.id).unwrap(),
-                    ));
-                });
+/**
+    Sync block
+**/
+#[derive(Default)]
+struct SyncOptions {
+    checked_at: Option<Instant>,
+    /// Data written elsewhere while ours had unsaved changes, until the user settles it.
+    theirs: Option<Backend>,
+}
 
-                ui.add_space(5.0);
-            }
+/// How often the data file is checked for changes made elsewhere.
+const SYNC_CHECK_PERIOD: Duration = Duration::from_secs(2);
 
-            if ui.button("   +   ").clicked() {
-                self.dialog_options.current_dialog = CurrentDialog::AddProject;
-            }
-        });
-    }
+impl Frontend {
+    /// Reloads data a sync tool wrote over ours, or asks what to do when ours has unsaved
+    /// changes the reload would lose.
+    fn check_external_change(&mut self) {
+        let options = &mut self.sync_options;
+
+        if options.theirs.is_some()
+            || options
+                .checked_at
+                .is_some_and(|v| v.elapsed() < SYNC_CHECK_PERIOD)
+        {
+            return;
+        }
 
-    fn time_tracker_build_subjects(&mut self, ui: &mut Ui) {
-        ui.set_min_width(300.0);
-        ui.set_max_width(300.0);
+        options.checked_at = Some(Instant::now());
 
-        let Some(current_sub_project) = self.backend.get_current_sub_project() else {
+        if !self.backend.changed_outside() {
             return;
-        };
+        }
 
-        let current_id = if let Some(cur_subject) = self.backend.get_current_subject() {
-            cur_subject.lock().unwrap().id
-        } else {
-            Uuid::new_v4()
+        // Tried again with the next check, a sync tool may still be writing.
+        let theirs = match self.backend.reload() {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("failed to reload the data changed elsewhere: {e}");
+                return;
+            }
         };
 
-        let c = current_sub_project.get_inner_sorted(|a, b| {
-            a.lock()
-                .unwrap()
-                .created_at
-                .cmp(&b.lock().unwrap().created_at)
-        });
+        if self.backend.has_unsaved_changes() {
+            self.backend.saves_held = true;
+            self.sync_options.theirs = Some(theirs);
+            self.dialog_options.current_dialog = CurrentDialog::SyncConflict;
+        } else {
+            self.replace_backend(theirs);
+            self.show_toast(tr!("Reloaded the data changed elsewhere"));
+        }
+    }
 
-        ui.vertical(|ui| {
-            for subject in c {
-                let r_subject = subject.lock().unwrap();
+    fn sync_conflict_build(&mut self, ctx: &egui::Context) {
+        let mut resolution = None;
 
-                if r_subject.is_deleted {
-                    continue;
-                }
+        egui::Window::new(tr!("Data changed elsewhere"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(tr!(
+                    "The data file was changed by another program while there were unsaved \
+                    changes here."
+                ));
 
                 ui.horizontal(|ui| {
-                    let mut text = RichText::new(&r_subject.name);
-
-                    if r_subject.id == current_id {
-                        text = text.strong();
+                    if ui
+                        .button(tr!("Keep mine"))
+                        .on_hover_text(tr!("Overwrites the other changes"))
+                        .clicked()
+                    {
+                        resolution = Some(SyncResolution::KeepMine);
                     }
 
-                    if ui.button(text).clicked() {
-                        if current_id != r_subject.id {
-                            self.time_tracker_stop_subject(true);
-                        }
-                        self.backend.set_current_subject(Some(r_subject.id));
+                    if ui
+                        .button(tr!("Take theirs"))
+                        .on_hover_text(tr!("Drops the unsaved changes and stops tracking"))
+                        .clicked()
+                    {
+                        resolution = Some(SyncResolution::TakeTheirs);
                     }
 
-                    ui.label(format_duration(r_subject.duration));
+                    if ui
+                        .button(tr!("Merge histories"))
+                        .on_hover_text(tr!(
+                            "Keeps the records of both, entries take the side changed last"
+                        ))
+                        .clicked()
+                    {
+                        resolution = Some(SyncResolution::Merge);
+                    }
+                });
+            });
+
+        let Some(resolution) = resolution else {
+            return;
+        };
+
+        let Some(theirs) = self.sync_options.theirs.take() else {
+            return;
+        };
+
+        self.dialog_options.current_dialog = CurrentDialog::None;
+        self.backend.saves_held = false;
+
+        match resolution {
+            SyncResolution::KeepMine => self.backend.flush(),
+            SyncResolution::TakeTheirs => self.replace_backend(theirs),
+            SyncResolution::Merge => {
+                let summary = merge(&mut self.backend, theirs);
+                self.backend.flush();
+
+                self.show_toast(&tr!(
+                    "Merged {records} records and {entries} entries",
+                    records = summary.added_records,
+                    entries = summary.added_entries + summary.updated_entries
+                ));
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+enum SyncResolution {
+    KeepMine,
+    TakeTheirs,
+    Merge,
+}
+
+/**
+    Search block
+**/
+#[derive(Default)]
+struct SearchOptions {
+    query: String,
+}
+
+impl Frontend {
+    fn search_field(&mut self, ui: &mut Ui) {
+        let r = ui.add(
+            TextEdit::singleline(&mut self.search_options.query)
+                .desired_width(140.)
+                .hint_text(tr!("Search")),
+        );
+
+        if r.lost_focus() && ui.input(|i| i.key_pressed(Key::Escape)) {
+            self.search_options.query.clear();
+        }
+    }
+
+    /// Entries of the whole tree matching the search, in place of the columns. False while
+    /// nothing is searched for.
+    fn search_results_build(&mut self, ui: &mut Ui, tree: Tree) -> bool {
+        let query = self.search_options.query.trim();
+
+        if query.is_empty() {
+            return false;
+        }
+
+        let found = self.backend.search(tree, query);
+
+        ScrollArea::vertical()
+            .id_source("search_results")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                if found.is_empty() {
+                    ui.label(RichText::new(tr!("No matches")).weak());
+                }
+
+                for (path, label) in found {
+                    let text = truncate_middle(&label, NAME_MAX_CHARS * 3);
+
+                    if with_full_name(ui.button(text), &label).clicked() {
+                        self.search_options.query.clear();
+                        self.pick_entry(tree, path);
+                    }
+                }
+            });
+
+        true
+    }
+
+    /// Selects a tracked subject and starts tracking it unless that already runs.
+    fn start_entry(&mut self, path: EntryPath) {
+        self.pick_entry(Tree::Time, path);
+
+        if matches!(self.backend.working_mode, WorkingMode::Idle) {
+            self.time_tracker_start_subject();
+        }
+    }
+
+    /// Selects an entry with everything above it, opening its way in the todo tree.
+    fn pick_entry(&mut self, tree: Tree, path: EntryPath) {
+        if tree == Tree::Todo {
+            let (EntryPath::Project(p) | EntryPath::SubProject(p, _) | EntryPath::Subject(p, _, _)) =
+                path;
+
+            self.backend.set_todo_expanded(p, true);
+
+            if let EntryPath::SubProject(_, s) | EntryPath::Subject(_, s, _) = path {
+                self.backend.set_todo_expanded(s, true);
+            }
+
+            self.todo_options.clear_selection();
+            self.backend.select_entry(tree, path);
+
+            return;
+        }
+
+        // Picking another subject stops the running one, same as clicking it in its column.
+        if let EntryPath::Subject(_, _, x) = path {
+            let current = self.backend.get_current_subject();
+
+            if current.is_some_and(|v| v.lock().unwrap().id != x) {
+                self.time_tracker_stop_subject(true);
+            }
+        }
+
+        self.backend.select_entry(tree, path);
+    }
+}
+
+/**
+    Palette block
+**/
+const PALETTE_MAX_RESULTS: usize = 8;
+const PALETTE_WINDOW_SIZE: Vec2 = Vec2::new(480., 320.);
+
+#[derive(Default)]
+struct PaletteOptions {
+    open: bool,
+    query: String,
+    /// Row of the highlighted match.
+    selected: usize,
+    /// Subjects of both trees with their paths, gathered when the palette opens.
+    entries: Vec<(Tree, EntryPath, String)>,
+}
+
+impl Frontend {
+    fn open_palette(&mut self) {
+        let mut entries = Vec::new();
+
+        for tree in [Tree::Time, Tree::Todo] {
+            entries.extend(
+                self.backend
+                    .search(tree, "")
+                    .into_iter()
+                    .filter(|(path, _)| matches!(path, EntryPath::Subject(..)))
+                    .map(|(path, label)| (tree, path, label)),
+            );
+        }
+
+        self.palette_options = PaletteOptions {
+            open: true,
+            entries,
+            ..Default::default()
+        };
+    }
+
+    fn palette_build(&mut self, ctx: &egui::Context) {
+        let options = &mut self.palette_options;
+
+        if !options.open {
+            return;
+        }
+
+        let mut found = options
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (_, _, label))| Some((fuzzy_score(&options.query, label)?, i)))
+            .collect::<Vec<_>>();
+
+        found.sort_by_key(|(score, i)| (std::cmp::Reverse(*score), *i));
+        found.truncate(PALETTE_MAX_RESULTS);
+
+        let (up, down, enter, escape) = ctx.input(|i| {
+            (
+                i.key_pressed(Key::ArrowUp),
+                i.key_pressed(Key::ArrowDown),
+                i.key_pressed(Key::Enter),
+                i.key_pressed(Key::Escape),
+            )
+        });
+
+        if down {
+            options.selected += 1;
+        }
+
+        if up {
+            options.selected = options.selected.saturating_sub(1);
+        }
+
+        options.selected = options.selected.min(found.len().saturating_sub(1));
+
+        let mut picked = enter
+            .then(|| found.get(options.selected))
+            .flatten()
+            .map(|v| v.1);
+
+        egui::Window::new(tr!("Go to"))
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, Vec2::new(0., 20.))
+            .show(ctx, |ui| {
+                let r = ui.add(
+                    TextEdit::singleline(&mut options.query)
+                        .desired_width(400.)
+                        .hint_text(tr!("project / sub project / subject")),
+                );
+                r.request_focus();
+
+                if r.changed() {
+                    options.selected = 0;
+                }
+
+                if found.is_empty() {
+                    ui.label(RichText::new(tr!("No matches")).weak());
+                }
+
+                for (row, (_, i)) in found.iter().enumerate() {
+                    let (tree, _, label) = &options.entries[*i];
+
+                    let mut text = truncate_middle(label, NAME_MAX_CHARS * 2);
+
+                    if *tree == Tree::Todo {
+                        text.push_str("  (todo)");
+                    }
+
+                    if ui.selectable_label(row == options.selected, text).clicked() {
+                        picked = Some(*i);
+                    }
+                }
+            });
+
+        if escape {
+            options.open = false;
+        }
+
+        let Some((tree, path, _)) = picked.map(|i| options.entries[i].clone()) else {
+            return;
+        };
+
+        options.open = false;
+
+        match tree {
+            Tree::Time => self.start_entry(path),
+            Tree::Todo => {
+                self.pick_entry(tree, path);
+                self.set_display_mode(DisplayMode::Todo);
+            }
+        }
+    }
+}
+
+/**
+    End of day block
+**/
+#[derive(Default)]
+struct EndOfDayOptions {
+    open: bool,
+    day: NaiveDate,
+    /// Project names with their time on `day`, as of when the summary opened.
+    totals: Vec<(String, chrono::Duration)>,
+}
+
+const END_OF_DAY_WINDOW_SIZE: Vec2 = Vec2::new(360., 300.);
+
+impl EndOfDayOptions {
+    fn total(&self) -> chrono::Duration {
+        self.totals
+            .iter()
+            .fold(chrono::Duration::zero(), |s, (_, v)| s + *v)
+    }
+
+    fn text(&self, format: DurationFormat) -> String {
+        let mut text = tr!("Tracked on {day}", day = self.day.format(DATE_FORMAT)) + "\n";
+
+        for (name, duration) in &self.totals {
+            let duration = format_chrono_duration(*duration, format);
+            text.push_str(&format!("{name}: {duration}\n"));
+        }
+
+        let total = format_chrono_duration(self.total(), format);
+        text.push_str(&format!("{}: {total}\n", tr!("Total")));
+
+        text
+    }
+}
+
+impl Frontend {
+    /// Opens the summary once the configured time has passed on a day with tracked time,
+    /// also when the app only starts later. It is remembered per day across restarts.
+    fn check_end_of_day(&mut self) {
+        let settings = &self.backend.settings;
+
+        if !settings.end_of_day_summary || self.end_of_day_options.open {
+            return;
+        }
+
+        let now = Local::now();
+        let today = now.date_naive();
+
+        if self.backend.end_of_day_shown == Some(today)
+            || now.hour() * 60 + now.minute() < settings.end_of_day_minutes
+        {
+            return;
+        }
+
+        let totals = self.backend.project_day_totals(today);
+
+        if totals.is_empty() {
+            return;
+        }
+
+        self.end_of_day_options = EndOfDayOptions {
+            open: true,
+            day: today,
+            totals,
+        };
+
+        self.backend.end_of_day_shown = Some(today);
+        self.backend.dirty();
+    }
+
+    fn end_of_day_build(&mut self, ctx: &egui::Context) {
+        if !self.end_of_day_options.open {
+            return;
+        }
+
+        let mut open = true;
+        let mut show_statistic = false;
+
+        egui::Window::new(tr!("End of day"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, Vec2::new(0., 20.))
+            .show(ctx, |ui| {
+                let options = &self.end_of_day_options;
+                let format = self.backend.settings.duration_format;
+
+                egui::Grid::new("end_of_day_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        for (name, duration) in &options.totals {
+                            ui.label(truncate_middle(name, NAME_MAX_CHARS));
+                            ui.label(format_chrono_duration(*duration, format));
+                            ui.end_row();
+                        }
+
+                        ui.strong(tr!("Total"));
+                        ui.strong(format_chrono_duration(options.total(), format));
+                        ui.end_row();
+                    });
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("Copy")).clicked() {
+                        ui.output_mut(|o| o.copied_text = options.text(format));
+                    }
+
+                    if ui.button(tr!("Statistic")).clicked() {
+                        show_statistic = true;
+                    }
+                });
+            });
+
+        if show_statistic {
+            open = false;
+
+            self.set_display_mode(DisplayMode::Statistic);
+            self.statistic_options.apply_preset(
+                RangePreset::Today,
+                self.end_of_day_options.day,
+                self.backend.settings.first_day_of_week,
+            );
+        }
+
+        self.end_of_day_options.open = open;
+    }
+}
+
+/**
+    Window suggestion block
+**/
+#[derive(Default)]
+struct WindowSuggestionOptions {
+    watcher: Option<TitleWatcher>,
+    title: Option<String>,
+    /// Title whose suggestion was turned down, it is offered again once another window had
+    /// the focus.
+    declined: Option<String>,
+}
+
+const SUGGESTION_WINDOW_SIZE: Vec2 = Vec2::new(300., 120.);
+
+impl Frontend {
+    /// Starts or stops watching the focused window with the setting and takes in its title.
+    fn check_window_title(&mut self, ctx: &egui::Context) {
+        let settings = &self.backend.settings;
+        let options = &mut self.window_suggestion_options;
+        let enabled = settings.window_suggestions && !settings.window_rules.is_empty();
+
+        if enabled != options.watcher.is_some() {
+            options.watcher = enabled.then(|| TitleWatcher::spawn(ctx.clone()));
+            options.title = None;
+        }
+
+        let Some(watcher) = &options.watcher else {
+            return;
+        };
+
+        if let Some(title) = watcher.titles.try_iter().last() {
+            if title != options.declined {
+                options.declined = None;
+            }
+
+            options.title = title;
+        }
+    }
+
+    /// Path of the first rule matching the focused window, while nothing is tracked.
+    fn window_suggestion(&self) -> Option<&str> {
+        let options = &self.window_suggestion_options;
+        let title = options.title.as_ref()?;
+
+        if !matches!(self.backend.working_mode, WorkingMode::Idle)
+            || options.declined.as_ref() == Some(title)
+        {
+            return None;
+        }
+
+        self.backend
+            .settings
+            .window_rules
+            .iter()
+            .find(|v| {
+                !path_names(&v.path).is_empty() && crate::window_title::matches(&v.pattern, title)
+            })
+            .map(|v| v.path.as_str())
+    }
+
+    fn window_suggestion_build(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.window_suggestion().map(|v| v.to_string()) else {
+            return;
+        };
+
+        // A rule naming only a project or sub project selects it, the subject is left to pick.
+        let whole = path_names(&path).len() == 3;
+        let (mut start, mut decline) = (false, false);
+
+        egui::Area::new("window_suggestion")
+            .anchor(egui::Align2::RIGHT_BOTTOM, Vec2::new(-10., -10.))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_max_width(260.);
+
+                    let shown = truncate_middle(path.trim_matches('/'), NAME_MAX_CHARS * 2);
+
+                    ui.label(if whole {
+                        tr!("Start tracking {shown}?", shown = shown)
+                    } else {
+                        tr!("Start tracking {shown}/…?", shown = shown)
+                    });
+
+                    ui.horizontal(|ui| {
+                        start = ui
+                            .button(if whole { tr!("Start") } else { tr!("Select") })
+                            .clicked();
+                        decline = ui.button(tr!("Not now")).clicked();
+                    });
+                });
+            });
+
+        if decline {
+            let options = &mut self.window_suggestion_options;
+            options.declined = options.title.clone();
+        }
+
+        if !start {
+            return;
+        }
+
+        match self.backend.add_path(&path, Tree::Time) {
+            Some(path @ EntryPath::Subject(..)) => self.start_entry(path),
+            Some(_) => self.set_display_mode(DisplayMode::Time),
+            None => {
+                let options = &mut self.window_suggestion_options;
+                options.declined = options.title.clone();
+
+                self.show_toast(tr!("Its project is archived"));
+            }
+        }
+    }
+}
+
+/**
+    Settings block
+**/
+
+#[derive(Default)]
+struct SettingsOptions {
+    /// Action waiting for its new key.
+    rebinding: Option<HotkeyAction>,
+    hotkey_error: Option<String>,
+    /// Read once the dialog opens, listing them reads every catalog.
+    languages: Vec<Language>,
+    passphrase: String,
+    passphrase_repeat: String,
+}
+
+fn time_format_label(format: TimeFormat) -> &'static str {
+    match format {
+        TimeFormat::HoursMinutes => "1:45",
+        TimeFormat::HoursMinutesSeconds => "1:45:30",
+        TimeFormat::DecimalHours => "1.75h",
+    }
+}
+
+impl Frontend {
+    fn settings_dialog_build(&mut self, ui: &mut Ui) {
+        let settings = &mut self.backend.settings;
+        let options = &mut self.settings_options;
+        let mut changed = false;
+        let mut reassign_colors = false;
+
+        if let Some(action) = options.rebinding {
+            let pressed = ui.input(|i| {
+                i.events.iter().find_map(|e| match e {
+                    egui::Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some((*key, *modifiers)),
+                    _ => None,
+                })
+            });
+
+            match pressed {
+                Some((Key::Escape, _)) => options.rebinding = None,
+                Some((key, modifiers)) if Hotkey::is_bindable(key) => {
+                    options.rebinding = None;
+
+                    match settings.hotkeys.set(action, Hotkey::new(key, modifiers)) {
+                        Ok(()) => {
+                            options.hotkey_error = None;
+                            changed = true;
+                        }
+                        Err(other) => {
+                            options.hotkey_error =
+                                Some(tr!("Already used by \"{hotkey}\"", hotkey = other.label()));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        egui::Grid::new("settings_grid")
+            .num_columns(2)
+            .spacing([10.0, 6.0])
+            .show(ui, |ui| {
+                if options.languages.is_empty() {
+                    options.languages = languages();
+                }
+
+                let current = options
+                    .languages
+                    .iter()
+                    .find(|v| v.code == settings.language)
+                    .map_or(settings.language.as_str(), |v| v.name.as_str());
+
+                ui.label(tr!("Language"));
+                egui::ComboBox::from_id_source("settings_language")
+                    .selected_text(current)
+                    .show_ui(ui, |ui| {
+                        for language in &options.languages {
+                            if ui
+                                .selectable_label(
+                                    settings.language == language.code,
+                                    &language.name,
+                                )
+                                .clicked()
+                                && settings.language != language.code
+                            {
+                                settings.language = language.code.clone();
+                                set_language(&settings.language);
+                                changed = true;
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(tr!("Theme"));
+                egui::ComboBox::from_id_source("settings_theme")
+                    .selected_text(translate(&format!("{:?}", settings.theme)))
+                    .show_ui(ui, |ui| {
+                        for theme in [Theme::Light, Theme::Dark, Theme::System] {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut settings.theme,
+                                    theme,
+                                    translate(&format!("{theme:?}")),
+                                )
+                                .changed();
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(tr!("Project colors"));
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("settings_palette")
+                        .selected_text(settings.palette.label())
+                        .show_ui(ui, |ui| {
+                            for palette in Palette::ALL {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut settings.palette,
+                                        palette,
+                                        palette.label(),
+                                    )
+                                    .changed();
+                            }
+                        });
+
+                    reassign_colors = ui
+                        .button(tr!("Re-assign colors"))
+                        .on_hover_text(tr!(
+                            "Gives every project a new color of the palette, sub project and \
+                            subject colors stay"
+                        ))
+                        .clicked();
+                });
+                ui.end_row();
+
+                ui.label(tr!("First day of week"));
+                egui::ComboBox::from_id_source("settings_first_day_of_week")
+                    .selected_text(weekday_name(settings.first_day_of_week))
+                    .show_ui(ui, |ui| {
+                        for day in [
+                            Weekday::Mon,
+                            Weekday::Tue,
+                            Weekday::Wed,
+                            Weekday::Thu,
+                            Weekday::Fri,
+                            Weekday::Sat,
+                            Weekday::Sun,
+                        ] {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut settings.first_day_of_week,
+                                    day,
+                                    weekday_name(day),
+                                )
+                                .changed();
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(tr!("Pause when the screen turns off"));
+                changed |= ui
+                    .checkbox(&mut settings.pause_on_display_off, "")
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Pause when the screen locks"));
+                changed |= ui.checkbox(&mut settings.pause_on_lock, "").changed();
+                ui.end_row();
+
+                ui.label(tr!("Resume after unlocking"));
+                ui.add_enabled_ui(settings.pause_on_lock, |ui| {
+                    egui::ComboBox::from_id_source("settings_resume_on_unlock")
+                        .selected_text(translate(&format!("{:?}", settings.resume_on_unlock)))
+                        .show_ui(ui, |ui| {
+                            for resume in [
+                                UnlockResume::Off,
+                                UnlockResume::Ask,
+                                UnlockResume::Automatic,
+                            ] {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut settings.resume_on_unlock,
+                                        resume,
+                                        translate(&format!("{resume:?}")),
+                                    )
+                                    .changed();
+                            }
+                        });
+                });
+                ui.end_row();
+
+                ui.label(tr!("Monthly goal, hours"));
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut settings.monthly_goal_hours).clamp_range(0..=744),
+                    )
+                    .on_hover_text(tr!("0 disables the goal line"))
+                    .changed();
+                ui.end_row();
+
+                let time_of_day = |v: f64, _| format!("{:02}:{:02}", v as u32 / 60, v as u32 % 60);
+                let parse_time_of_day = |v: &str| {
+                    let (h, m) = v.trim().split_once(':')?;
+                    Some((h.parse::<u32>().ok()? * 60 + m.parse::<u32>().ok()?) as f64)
+                };
+
+                ui.label(tr!("Working hours"));
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut settings.work_day_start_minutes)
+                                .clamp_range(0..=24 * 60)
+                                .speed(5)
+                                .custom_formatter(time_of_day)
+                                .custom_parser(parse_time_of_day),
+                        )
+                        .changed();
+                    ui.label("-");
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut settings.work_day_end_minutes)
+                                .clamp_range(0..=24 * 60)
+                                .speed(5)
+                                .custom_formatter(time_of_day)
+                                .custom_parser(parse_time_of_day),
+                        )
+                        .on_hover_text(tr!(
+                            "Untracked time in the Statistic view is counted within these hours"
+                        ))
+                        .changed();
+                });
+                ui.end_row();
+
+                ui.label(tr!("Pomodoro work, minutes"));
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut settings.pomodoro_work_minutes)
+                            .clamp_range(1..=180),
+                    )
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Pomodoro break, minutes"));
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut settings.pomodoro_break_minutes)
+                            .clamp_range(1..=60),
+                    )
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Track pomodoro breaks"));
+                changed |= ui
+                    .checkbox(&mut settings.pomodoro_track_breaks, "")
+                    .on_hover_text(tr!("Breaks are tracked under a \"Break\" subject"))
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Time next to entries"));
+                egui::ComboBox::from_id_source("settings_column_time")
+                    .selected_text(translate(&format!("{:?}", settings.column_time)))
+                    .show_ui(ui, |ui| {
+                        for column_time in [ColumnTime::Today, ColumnTime::Total, ColumnTime::Both]
+                        {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut settings.column_time,
+                                    column_time,
+                                    translate(&format!("{column_time:?}")),
+                                )
+                                .changed();
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(tr!("Durations"));
+                ui.horizontal(|ui| {
+                    let time_format = &mut settings.duration_format.time;
+
+                    egui::ComboBox::from_id_source("settings_time_format")
+                        .selected_text(time_format_label(*time_format))
+                        .show_ui(ui, |ui| {
+                            for format in [
+                                TimeFormat::HoursMinutes,
+                                TimeFormat::HoursMinutesSeconds,
+                                TimeFormat::DecimalHours,
+                            ] {
+                                changed |= ui
+                                    .selectable_value(
+                                        time_format,
+                                        format,
+                                        time_format_label(format),
+                                    )
+                                    .changed();
+                            }
+                        });
+
+                    changed |= ui
+                        .checkbox(&mut settings.duration_format.days, tr!("Days"))
+                        .on_hover_text(tr!("Write a day or more as 2d 04:30"))
+                        .changed();
+                });
+                ui.end_row();
+
+                ui.label(tr!("Notify when a goal is reached"));
+                changed |= ui.checkbox(&mut settings.notify_goal_reached, "").changed();
+                ui.end_row();
+
+                ui.label(tr!("Notify when tracking pauses by itself"));
+                changed |= ui.checkbox(&mut settings.notify_auto_pause, "").changed();
+                ui.end_row();
+
+                ui.label(tr!("Notify when a pomodoro phase ends"));
+                changed |= ui.checkbox(&mut settings.notify_pomodoro, "").changed();
+                ui.end_row();
+
+                ui.label(tr!("Stretch reminder every, minutes"));
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut settings.stretch_reminder_minutes)
+                            .clamp_range(0..=480),
+                    )
+                    .on_hover_text(tr!("0 turns the reminder off"))
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("End of day summary"));
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .checkbox(&mut settings.end_of_day_summary, tr!("at"))
+                        .changed();
+
+                    ui.add_enabled_ui(settings.end_of_day_summary, |ui| {
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut settings.end_of_day_minutes)
+                                    .clamp_range(0..=24 * 60 - 1)
+                                    .speed(5)
+                                    .custom_formatter(time_of_day)
+                                    .custom_parser(parse_time_of_day),
+                            )
+                            .on_hover_text(tr!("Shown once a day when anything was tracked"))
+                            .changed();
+                    });
+                });
+                ui.end_row();
+
+                ui.label(tr!("Daily notes"));
+                changed |= ui
+                    .checkbox(&mut settings.daily_notes, "")
+                    .on_hover_text(tr!(
+                        "Notes left from an earlier day move to the earlier notes"
+                    ))
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Session notes"));
+                changed |= ui
+                    .checkbox(&mut settings.session_notes, "")
+                    .on_hover_text(tr!(
+                        "Pausing asks for a note on the session, filled with the note template \
+                        of its subject"
+                    ))
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Round reported time"));
+                ui.horizontal(|ui| {
+                    let rounding = &mut settings.report_rounding;
+
+                    egui::ComboBox::from_id_source("settings_report_rounding")
+                        .selected_text(RoundingMode::label(rounding.minutes))
+                        .show_ui(ui, |ui| {
+                            for minutes in RoundingMode::MINUTES {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut rounding.minutes,
+                                        minutes,
+                                        RoundingMode::label(minutes),
+                                    )
+                                    .changed();
+                            }
+                        });
+
+                    ui.add_enabled_ui(rounding.minutes > 0, |ui| {
+                        changed |= ui
+                            .checkbox(&mut rounding.up, tr!("Always up"))
+                            .on_hover_text(tr!(
+                                "Round every session up instead of to the nearest step"
+                            ))
+                            .changed();
+                    });
+                });
+                ui.end_row();
+
+                ui.label(tr!("Minimal window opacity"));
+                changed |= ui
+                    .add(egui::Slider::new(&mut settings.minimal_opacity, 0.2..=1.0))
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Discard sessions shorter than, s"));
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut settings.min_session_seconds)
+                            .clamp_range(0..=600),
+                    )
+                    .on_hover_text(tr!("0 keeps every session"))
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Save running sessions every, s"));
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut settings.save_period_seconds)
+                            .clamp_range(1..=600),
+                    )
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Redraw while tracking every, ms"));
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut settings.tracking_repaint_ms)
+                            .clamp_range(100..=10_000),
+                    )
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Redraw when idle every, s"));
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut settings.idle_repaint_seconds)
+                            .clamp_range(1..=3600),
+                    )
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Control socket"));
+                changed |= ui
+                    .checkbox(&mut settings.control_socket, "")
+                    .on_hover_text(tr!(
+                        "Accept status, start and stop requests as JSON lines on {path}",
+                        path = SOCKET_PATH
+                    ))
+                    .changed();
+                ui.end_row();
+
+                let hook_hint = "Shell command, gets RUH_EVENT, RUH_PROJECT, RUH_SUB_PROJECT, \
+                    RUH_SUBJECT and on stop RUH_DURATION_SECONDS. Sessions dropped for being too \
+                    short run neither";
+
+                ui.label(tr!("Run on session start"));
+                changed |= ui
+                    .text_edit_singleline(&mut settings.on_start_command)
+                    .on_hover_text(hook_hint)
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Run on session stop"));
+                changed |= ui
+                    .text_edit_singleline(&mut settings.on_stop_command)
+                    .on_hover_text(hook_hint)
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Show done todos inline"));
+                changed |= ui
+                    .checkbox(&mut settings.todo_done_inline, "")
+                    .on_hover_text(tr!("Otherwise they are listed in a collapsed Done section"))
+                    .changed();
+                ui.end_row();
+
+                ui.label(tr!("Todo layout"));
+                egui::ComboBox::from_id_source("settings_todo_layout")
+                    .selected_text(translate(&format!("{:?}", settings.todo_layout)))
+                    .show_ui(ui, |ui| {
+                        for layout in [TodoLayout::Tree, TodoLayout::Columns] {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut settings.todo_layout,
+                                    layout,
+                                    translate(&format!("{layout:?}")),
+                                )
+                                .changed();
+                        }
+                    });
+                ui.end_row();
+            });
+
+        ui.separator();
+
+        changed |= ui
+            .checkbox(
+                &mut settings.window_suggestions,
+                tr!("Suggest what to track from the focused window"),
+            )
+            .on_hover_text(
+                "Reads window titles on X11 and Windows. In a pattern * stands for any text and \
+                ? for one character, without them it only has to appear in the title",
+            )
+            .changed();
+
+        ui.add_enabled_ui(settings.window_suggestions, |ui| {
+            let mut removed = None;
+
+            egui::Grid::new("settings_window_rules_grid")
+                .num_columns(3)
+                .spacing([10.0, 6.0])
+                .show(ui, |ui| {
+                    for (i, rule) in settings.window_rules.iter_mut().enumerate() {
+                        changed |= ui
+                            .add(
+                                TextEdit::singleline(&mut rule.pattern)
+                                    .hint_text(tr!("*.rs - Visual Studio Code"))
+                                    .desired_width(160.),
+                            )
+                            .changed();
+
+                        changed |= ui
+                            .add(
+                                TextEdit::singleline(&mut rule.path)
+                                    .hint_text(tr!("Project/Sub project/Subject"))
+                                    .desired_width(160.),
+                            )
+                            .changed();
+
+                        if ui.small_button("✖").on_hover_text(tr!("Remove")).clicked() {
+                            removed = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            if let Some(i) = removed {
+                settings.window_rules.remove(i);
+                changed = true;
+            }
+
+            if ui.button(tr!("+ Rule")).clicked() {
+                settings.window_rules.push(WindowRule::default());
+                changed = true;
+            }
+        });
+
+        ui.separator();
+
+        egui::Grid::new("settings_hotkeys_grid")
+            .num_columns(2)
+            .spacing([10.0, 6.0])
+            .show(ui, |ui| {
+                for action in HotkeyAction::ALL {
+                    ui.label(action.label());
+
+                    let text = if options.rebinding == Some(action) {
+                        tr!("Press a key...").to_string()
+                    } else {
+                        settings.hotkeys.get(action).to_string()
+                    };
+
+                    let r = ui
+                        .button(text)
+                        .on_hover_text(tr!("Click, then press the new key. Escape cancels"));
+
+                    if r.clicked() {
+                        // A focused button would take the captured Space or Enter as a click.
+                        r.surrender_focus();
+                        options.rebinding = Some(action);
+                        options.hotkey_error = None;
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(error) = &options.hotkey_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        if ui.button(tr!("Reset hotkeys")).clicked() {
+            settings.hotkeys = Default::default();
+            options.rebinding = None;
+            options.hotkey_error = None;
+            changed = true;
+        }
+
+        if changed {
+            self.backend.dirty();
+        }
+
+        if reassign_colors {
+            self.backend.reassign_colors();
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label(tr!("Storage"));
+
+            let current = self.backend.storage_kind();
+
+            for kind in StorageKind::ALL {
+                if ui
+                    .selectable_label(current == Some(kind), kind.label())
+                    .on_hover_text(tr!(
+                        "Converts the data, the previous files are kept as backups"
+                    ))
+                    .clicked()
+                    && current != Some(kind)
+                {
+                    match self.backend.convert_storage(kind) {
+                        Ok(()) => {
+                            self.show_toast(&tr!("Data moved to the {kind}", kind = kind.label()))
+                        }
+                        Err(e) => self.show_toast(&e),
+                    }
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr!("Encryption"));
+
+            if self.backend.encrypted() {
+                if ui.button(tr!("Turn off")).clicked() {
+                    match self.backend.set_passphrase(None) {
+                        Ok(()) => self.show_toast(tr!("The data file is no longer encrypted")),
+                        Err(e) => self.show_toast(&e),
+                    }
+                }
+
+                return;
+            }
+
+            let options = &mut self.settings_options;
+
+            for (text, hint) in [
+                (&mut options.passphrase, tr!("Passphrase")),
+                (&mut options.passphrase_repeat, tr!("Repeat")),
+            ] {
+                ui.add(
+                    TextEdit::singleline(text)
+                        .password(true)
+                        .desired_width(120.)
+                        .hint_text(hint),
+                );
+            }
+
+            let encrypt = ui
+                .add_enabled(
+                    !options.passphrase.is_empty(),
+                    egui::Button::new(tr!("Encrypt")),
+                )
+                .on_hover_text(tr!(
+                    "Without the passphrase the data cannot be opened again, backups made \
+                    before stay unencrypted"
+                ))
+                .clicked();
+
+            if !encrypt {
+                return;
+            }
+
+            if options.passphrase != options.passphrase_repeat {
+                self.show_toast(tr!("The passphrases differ"));
+                return;
+            }
+
+            let passphrase = std::mem::take(&mut options.passphrase);
+            options.passphrase_repeat.clear();
+
+            match self.backend.set_passphrase(Some(&passphrase)) {
+                Ok(()) => self.show_toast(tr!("The data file is encrypted")),
+                Err(e) => self.show_toast(&e),
+            }
+        });
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("Close")).clicked() {
+                self.dialog_options.current_dialog = CurrentDialog::None;
+                self.settings_options = SettingsOptions::default();
+            }
+
+            if ui.button(tr!("Import from Toggl…")).clicked() {
+                self.dialog_options.toggl_import = TogglImportOptions::default();
+                self.dialog_options.current_dialog = CurrentDialog::ImportToggl;
+                self.settings_options = SettingsOptions::default();
+            }
+
+            if ui.button(tr!("Import & merge data file…")).clicked() {
+                self.dialog_options.merge_file = MergeFileOptions::default();
+                self.dialog_options.current_dialog = CurrentDialog::MergeFile;
+                self.settings_options = SettingsOptions::default();
+            }
+
+            if ui.button(tr!("Trash…")).clicked() {
+                self.dialog_options.emptying_trash = false;
+                self.dialog_options.current_dialog = CurrentDialog::Trash;
+                self.settings_options = SettingsOptions::default();
+            }
+
+            if ui.button(tr!("Check data…")).clicked() {
+                self.dialog_options.issues = self.backend.check_integrity();
+                self.dialog_options.current_dialog = CurrentDialog::CheckData;
+                self.settings_options = SettingsOptions::default();
+            }
+        });
+    }
+}
+
+/**
+    Dialog block
+**/
+
+#[derive(Default)]
+struct DialogOptions {
+    current_dialog: CurrentDialog,
+    buffer: String,
+    /// Whether the name field of the open dialog already got its initial focus.
+    focused: bool,
+    manual_record: ManualRecordOptions,
+    move_entry: MoveEntryOptions,
+    inspect_records: InspectRecordsOptions,
+    /// Largest gap, in seconds, the tidy up dialog merges records across.
+    tidy_gap_seconds: i64,
+    /// Priority picked in the Add Todo Subject dialog.
+    todo_priority: Priority,
+    toggl_import: TogglImportOptions,
+    merge_file: MergeFileOptions,
+    /// Whether Empty trash was pressed once.
+    emptying_trash: bool,
+    /// Found when the check data dialog opened and after every fix.
+    issues: Vec<Issue>,
+}
+
+/// File of the Toggl import dialog with what was read from it.
+#[derive(Default)]
+struct TogglImportOptions {
+    path: String,
+    import: Option<(TogglImport, ImportPreview)>,
+    error: Option<String>,
+}
+
+/// Data file of the merge dialog with what merging it would do.
+#[derive(Default)]
+struct MergeFileOptions {
+    path: String,
+    plan: Option<MergePlan>,
+    error: Option<String>,
+}
+
+/// Records of a clicked timeline bar, several when adjacent records were joined.
+#[derive(Default)]
+struct InspectRecordsOptions {
+    ids: Vec<Uuid>,
+    /// Record being edited with its start and end text.
+    editing: Option<(Uuid, String, String)>,
+    /// Record whose delete button was pressed once.
+    deleting: Option<Uuid>,
+    /// Record being split with the split time text.
+    splitting: Option<(Uuid, String)>,
+    reassigning: Option<ReassignRecord>,
+    error: Option<&'static str>,
+}
+
+/// Record of the inspector getting a new subject, with the picked one.
+struct ReassignRecord {
+    id: Uuid,
+    project_id: Option<Uuid>,
+    sub_project_id: Option<Uuid>,
+    subject_id: Option<Uuid>,
+}
+
+#[derive(Default)]
+struct MoveEntryOptions {
+    project_id: Option<Uuid>,
+    sub_project_id: Option<Uuid>,
+}
+
+/// Project, sub project and subject combo boxes as three grid rows. Picking a parent
+/// clears the choices below it.
+fn subject_picker_rows(
+    ui: &mut Ui,
+    id_source: &str,
+    projects: &ProjectChain,
+    project_id: &mut Option<Uuid>,
+    sub_project_id: &mut Option<Uuid>,
+    subject_id: &mut Option<Uuid>,
+) {
+    let project = project_id.and_then(|id| projects.inner.get(&id));
+    let sub_project = project
+        .zip(*sub_project_id)
+        .and_then(|(p, id)| p.inner.get(&id));
+    let subject = sub_project
+        .zip(*subject_id)
+        .and_then(|(p, id)| p.inner.get(&id));
+
+    ui.label(tr!("Project"));
+    egui::ComboBox::from_id_source((id_source, "project"))
+        .selected_text(project.map_or(String::new(), |v| truncate_middle(&v.name, NAME_MAX_CHARS)))
+        .show_ui(ui, |ui| {
+            for v in projects.get_inner_sorted(|a, b| a.created_at.cmp(&b.created_at)) {
+                if v.is_deleted {
+                    continue;
+                }
+
+                if ui
+                    .selectable_value(project_id, Some(v.id), &v.name)
+                    .clicked()
+                {
+                    *sub_project_id = None;
+                    *subject_id = None;
+                }
+            }
+        });
+    ui.end_row();
+
+    ui.label(tr!("Sub Project"));
+    egui::ComboBox::from_id_source((id_source, "sub_project"))
+        .selected_text(
+            sub_project.map_or(String::new(), |v| truncate_middle(&v.name, NAME_MAX_CHARS)),
+        )
+        .show_ui(ui, |ui| {
+            let Some(project) = project else {
+                return;
+            };
+
+            for v in project.get_inner_sorted(|a, b| a.created_at.cmp(&b.created_at)) {
+                if v.is_deleted {
+                    continue;
+                }
+
+                if ui
+                    .selectable_value(sub_project_id, Some(v.id), &v.name)
+                    .clicked()
+                {
+                    *subject_id = None;
+                }
+            }
+        });
+    ui.end_row();
+
+    ui.label(tr!("Subject"));
+    egui::ComboBox::from_id_source((id_source, "subject"))
+        .selected_text(subject.map_or("".to_string(), |v| v.lock().unwrap().name.clone()))
+        .show_ui(ui, |ui| {
+            let Some(sub_project) = sub_project else {
+                return;
+            };
+
+            for v in sub_project.get_inner_sorted(|a, b| {
+                a.lock()
+                    .unwrap()
+                    .created_at
+                    .cmp(&b.lock().unwrap().created_at)
+            }) {
+                let v = v.lock().unwrap();
+
+                if v.is_deleted {
+                    continue;
+                }
+
+                ui.selectable_value(subject_id, Some(v.id), &v.name);
+            }
+        });
+    ui.end_row();
+}
+
+#[derive(Default)]
+struct ManualRecordOptions {
+    project_id: Option<Uuid>,
+    sub_project_id: Option<Uuid>,
+    subject_id: Option<Uuid>,
+    start: String,
+    end: String,
+    error: Option<&'static str>,
+}
+
+impl Frontend {
+    fn open_manual_record_dialog(&mut self) {
+        let now = DateTime::<Local>::from(SystemTime::now());
+
+        self.dialog_options.manual_record = ManualRecordOptions {
+            project_id: self.backend.get_current_project().map(|v| v.id),
+            sub_project_id: self.backend.get_current_sub_project().map(|v| v.id),
+            subject_id: self
+                .backend
+                .get_current_subject()
+                .map(|v| v.lock().unwrap().id),
+            start: format_date_time(now.sub(chrono::Duration::hours(1))),
+            end: format_date_time(now),
+            error: None,
+        };
+
+        self.dialog_options.current_dialog = CurrentDialog::AddManualRecord;
+    }
+
+    fn manual_record_dialog_build(&mut self, ui: &mut Ui) {
+        let options = &mut self.dialog_options.manual_record;
+
+        egui::Grid::new("manual_record_grid")
+            .num_columns(2)
+            .spacing([10.0, 6.0])
+            .show(ui, |ui| {
+                subject_picker_rows(
+                    ui,
+                    "manual_record",
+                    &self.backend.projects,
+                    &mut options.project_id,
+                    &mut options.sub_project_id,
+                    &mut options.subject_id,
+                );
+
+                ui.label(tr!("Start"));
+                ui.add(TextEdit::singleline(&mut options.start).hint_text(DATE_TIME_FORMAT));
+                ui.end_row();
+
+                ui.label(tr!("End"));
+                ui.add(TextEdit::singleline(&mut options.end).hint_text(DATE_TIME_FORMAT));
+                ui.end_row();
+            });
+
+        if let Some(error) = options.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("Cancel")).clicked() {
+                self.dialog_options.current_dialog = CurrentDialog::None;
+            }
+
+            if ui.button(tr!("Add")).clicked() {
+                self.add_manual_record();
+            }
+        });
+    }
+
+    fn add_manual_record(&mut self) {
+        let options = &mut self.dialog_options.manual_record;
+
+        let (Some(project_id), Some(sub_project_id), Some(subject_id)) = (
+            options.project_id,
+            options.sub_project_id,
+            options.subject_id,
+        ) else {
+            options.error = Some(tr!("Select project, sub project and subject"));
+            return;
+        };
+
+        let (Some(start), Some(end)) = (
+            parse_date_time(&options.start),
+            parse_date_time(&options.end),
+        ) else {
+            options.error = Some(tr!("Dates must look like 2023-07-21 14:30"));
+            return;
+        };
+
+        if end <= start {
+            options.error = Some(tr!("End must be later than start"));
+            return;
+        }
+
+        if self
+            .backend
+            .add_manual_record(project_id, sub_project_id, subject_id, start, end)
+            .is_none()
+        {
+            options.error = Some(tr!("Selected subject no longer exists"));
+            return;
+        }
+
+        self.dialog_options.current_dialog = CurrentDialog::None;
+    }
+
+    fn open_inspect_records_dialog(&mut self, ids: Vec<Uuid>) {
+        self.dialog_options.inspect_records = InspectRecordsOptions {
+            ids,
+            ..Default::default()
+        };
+
+        self.dialog_options.current_dialog = CurrentDialog::InspectRecords;
+    }
+
+    fn inspect_records_dialog_build(&mut self, ui: &mut Ui) {
+        let active_session = self.backend.active_session_id();
+        let format = self.backend.settings.duration_format;
+        let ids = self.dialog_options.inspect_records.ids.clone();
+
+        for id in ids {
+            let Some(record) = self.backend.history.get(&id).copied() else {
+                continue;
+            };
+
+            let path = self.backend.get_record_path(&record);
+            let options = &mut self.dialog_options.inspect_records;
+
+            ui.separator();
+
+            let link = self.backend.entry_link(
+                Tree::Time,
+                EntryPath::Subject(record.project_id, record.sub_project_id, record.subject_id),
+            );
+
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{}/{}/{}",
+                    path.project, path.sub_project, path.subject
+                ));
+
+                if let Some(link) = &link {
+                    link_button(ui, link);
+                }
+            });
+
+            ui.label(format!(
+                "{} - {}, {}",
+                format_date_time(record.start_date),
+                format_date_time(record.end_date),
+                format_chrono_duration(record.get_duration(), format)
+            ));
+
+            if active_session == Some(id) {
+                ui.weak(tr!("In progress, pause it to edit"));
+                continue;
+            }
+
+            if let Some((_, start, end)) = options.editing.as_mut().filter(|v| v.0 == id) {
+                egui::Grid::new(("inspect_record_grid", id))
+                    .num_columns(2)
+                    .spacing([10.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label(tr!("Start"));
+                        ui.add(TextEdit::singleline(start).hint_text(DATE_TIME_FORMAT));
+                        ui.end_row();
+
+                        ui.label(tr!("End"));
+                        ui.add(TextEdit::singleline(end).hint_text(DATE_TIME_FORMAT));
+                        ui.end_row();
+                    });
+
+                if let Some(error) = options.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                let mut save = false;
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("Cancel")).clicked() {
+                        options.editing = None;
+                        options.error = None;
+                    }
+
+                    save = ui.button(tr!("Save")).clicked();
+                });
+
+                if save {
+                    self.save_inspected_record();
+                }
+
+                continue;
+            }
+
+            if let Some((_, at)) = options.splitting.as_mut().filter(|v| v.0 == id) {
+                ui.horizontal(|ui| {
+                    ui.label(tr!("Split at"));
+                    ui.add(TextEdit::singleline(at).hint_text(DATE_TIME_FORMAT));
+                });
+
+                if let Some(error) = options.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                let mut split = false;
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("Cancel")).clicked() {
+                        options.splitting = None;
+                        options.error = None;
+                    }
+
+                    split = ui.button(tr!("Split")).clicked();
+                });
+
+                if split {
+                    self.split_inspected_record();
+                }
+
+                continue;
+            }
+
+            if let Some(reassign) = options.reassigning.as_mut().filter(|v| v.id == id) {
+                egui::Grid::new(("reassign_record_grid", id))
+                    .num_columns(2)
+                    .spacing([10.0, 6.0])
+                    .show(ui, |ui| {
+                        subject_picker_rows(
+                            ui,
+                            "reassign_record",
+                            &self.backend.projects,
+                            &mut reassign.project_id,
+                            &mut reassign.sub_project_id,
+                            &mut reassign.subject_id,
+                        );
+                    });
+
+                if let Some(error) = options.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                let mut save = false;
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("Cancel")).clicked() {
+                        options.reassigning = None;
+                        options.error = None;
+                    }
+
+                    save = ui.button(tr!("Save")).clicked();
+                });
+
+                if save {
+                    self.reassign_inspected_record();
+                }
+
+                continue;
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button(tr!("Edit")).clicked() {
+                    options.editing = Some((
+                        id,
+                        format_date_time(record.start_date),
+                        format_date_time(record.end_date),
+                    ));
+                    options.deleting = None;
+                    options.error = None;
+                }
+
+                if ui
+                    .button(tr!("Split"))
+                    .on_hover_text(tr!("Cut the record in two at a chosen time"))
+                    .clicked()
+                {
+                    let middle = record.start_date + record.get_duration() / 2;
+
+                    options.splitting = Some((id, format_date_time(middle)));
+                    options.deleting = None;
+                    options.error = None;
+                }
+
+                if ui
+                    .button(tr!("Reassign"))
+                    .on_hover_text(tr!("Move the record to another subject"))
+                    .clicked()
+                {
+                    options.reassigning = Some(ReassignRecord {
+                        id,
+                        project_id: Some(record.project_id),
+                        sub_project_id: Some(record.sub_project_id),
+                        subject_id: Some(record.subject_id),
+                    });
+                    options.deleting = None;
+                    options.error = None;
+                }
+
+                if options.deleting == Some(id) {
+                    if ui.button(tr!("Confirm delete")).clicked() {
+                        options.deleting = None;
+                        self.backend.delete_record(id);
+                    }
+                } else if ui.button(tr!("Delete")).clicked() {
+                    options.deleting = Some(id);
+                }
+            });
+        }
+
+        ui.separator();
+
+        if ui.button(tr!("Close")).clicked() || ui.input(|i| i.key_pressed(Key::Escape)) {
+            self.dialog_options.current_dialog = CurrentDialog::None;
+        }
+    }
+
+    fn open_tidy_records_dialog(&mut self) {
+        self.dialog_options.tidy_gap_seconds = 30;
+        self.dialog_options.current_dialog = CurrentDialog::TidyRecords;
+    }
+
+    fn tidy_records_dialog_build(&mut self, ui: &mut Ui) {
+        let range = (self.statistic_options.from, self.statistic_options.to);
+
+        ui.label(tr!(
+            "Merges records of the same subject from {from} to {to} that follow each other closely.",
+            from = range.0.format("%Y-%m-%d"),
+            to = range.1.format("%Y-%m-%d")
+        ));
+
+        ui.horizontal(|ui| {
+            ui.label(tr!("Gaps shorter than"));
+            ui.add(
+                egui::DragValue::new(&mut self.dialog_options.tidy_gap_seconds)
+                    .clamp_range(1..=600)
+                    .suffix(tr!(" s")),
+            );
+        });
+
+        let max_gap = chrono::Duration::seconds(self.dialog_options.tidy_gap_seconds);
+        let count = self.backend.history.coalesce_preview(range, max_gap);
+
+        ui.label(match count {
+            0 => tr!("Nothing to merge").to_string(),
+            1 => tr!("1 record would be merged").to_string(),
+            n => tr!("{n} records would be merged", n = n),
+        });
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("Cancel")).clicked() || ui.input(|i| i.key_pressed(Key::Escape)) {
+                self.dialog_options.current_dialog = CurrentDialog::None;
+            }
+
+            if ui
+                .add_enabled(count > 0, egui::Button::new(tr!("Merge")))
+                .clicked()
+            {
+                let merged = self.backend.coalesce_records(range, max_gap);
+
+                self.dialog_options.current_dialog = CurrentDialog::None;
+                self.show_toast(&tr!("Merged {n} records", n = merged));
+            }
+        });
+    }
+
+    fn toggl_import_dialog_build(&mut self, ui: &mut Ui) {
+        let options = &mut self.dialog_options.toggl_import;
+
+        ui.label(tr!(
+            "Detailed report CSV exported from Toggl Track. Projects, tasks and descriptions"
+        ));
+        ui.label(tr!("become projects, sub projects and subjects."));
+
+        ui.horizontal(|ui| {
+            let r = ui.add(
+                egui::TextEdit::singleline(&mut options.path)
+                    .hint_text(tr!("Path of the CSV file"))
+                    .desired_width(300.),
+            );
+
+            let load = ui.button(tr!("Read")).clicked()
+                || (r.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)));
+
+            if load {
+                let read = std::fs::read_to_string(options.path.trim())
+                    .map_err(|e| tr!("Could not read the file: {error}", error = e))
+                    .and_then(|v| parse_toggl(&v));
+
+                match read {
+                    Ok(import) => {
+                        let preview = self.backend.toggl_preview(&import.entries);
+
+                        options.import = Some((import, preview));
+                        options.error = None;
+                    }
+                    Err(e) => {
+                        options.import = None;
+                        options.error = Some(e);
+                    }
+                }
+            }
+        });
+
+        if let Some(error) = &options.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        let mut count = 0;
+
+        if let Some((import, preview)) = &options.import {
+            count = preview.entries;
+
+            ui.add_space(5.);
+            ui.label(tr!("{n} entries to import", n = preview.entries));
+            ui.label(tr!(
+                "{projects} new projects, {sub_projects} new sub projects, {subjects} new subjects",
+                projects = preview.projects,
+                sub_projects = preview.sub_projects,
+                subjects = preview.subjects
+            ));
+
+            if preview.duplicates > 0 {
+                ui.label(tr!(
+                    "{n} entries skipped, they are already imported",
+                    n = preview.duplicates
+                ));
+            }
+
+            if preview.archived > 0 {
+                ui.label(tr!(
+                    "{n} entries skipped, their projects are archived",
+                    n = preview.archived
+                ));
+            }
+
+            if import.invalid_rows > 0 {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    tr!(
+                        "{n} rows without readable dates skipped",
+                        n = import.invalid_rows
+                    ),
+                );
+            }
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("Cancel")).clicked() || ui.input(|i| i.key_pressed(Key::Escape)) {
+                self.dialog_options.current_dialog = CurrentDialog::None;
+            }
+
+            if ui
+                .add_enabled(count > 0, egui::Button::new(tr!("Import")))
+                .clicked()
+            {
+                let imported = self
+                    .dialog_options
+                    .toggl_import
+                    .import
+                    .take()
+                    .map_or(0, |(v, _)| self.backend.import_toggl(&v.entries));
+
+                self.dialog_options.current_dialog = CurrentDialog::None;
+                self.show_toast(&tr!("Imported {n} entries", n = imported));
+            }
+        });
+    }
+
+    fn merge_file_dialog_build(&mut self, ui: &mut Ui) {
+        let options = &mut self.dialog_options.merge_file;
+
+        ui.label(tr!(
+            "Data file of another machine or profile. Its records and entries are added to"
+        ));
+        ui.label(tr!("these, entries with the same name become one."));
+
+        ui.horizontal(|ui| {
+            let r = ui.add(
+                egui::TextEdit::singleline(&mut options.path)
+                    .hint_text(tr!("Path of the data file"))
+                    .desired_width(300.),
+            );
+
+            let load = ui.button(tr!("Read")).clicked()
+                || (r.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)));
+
+            if load {
+                match read_data_file(Path::new(options.path.trim())) {
+                    Ok(theirs) => {
+                        self.backend.history.load_all();
+
+                        options.plan = Some(plan_merge(&self.backend, theirs));
+                        options.error = None;
+                    }
+                    Err(e) => {
+                        options.plan = None;
+                        options.error = Some(e);
+                    }
+                }
+            }
+        });
+
+        if let Some(error) = &options.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        let mut changes = 0;
+
+        if let Some(plan) = &options.plan {
+            let summary = &plan.summary;
+
+            changes = summary.added_records + summary.added_entries + summary.updated_entries;
+
+            ui.add_space(5.);
+            ui.label(tr!("{n} records to add", n = summary.added_records));
+            ui.label(tr!(
+                "{added} new entries, {updated} entries taking their newer changes",
+                added = summary.added_entries,
+                updated = summary.updated_entries
+            ));
+
+            if summary.matched_by_name > 0 {
+                ui.label(tr!(
+                    "{n} entries matched by name",
+                    n = summary.matched_by_name
+                ));
+            }
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("Cancel")).clicked() || ui.input(|i| i.key_pressed(Key::Escape)) {
+                self.dialog_options.current_dialog = CurrentDialog::None;
+                self.dialog_options.merge_file = MergeFileOptions::default();
+            }
+
+            if ui
+                .add_enabled(changes > 0, egui::Button::new(tr!("Merge")))
+                .clicked()
+            {
+                let Some(plan) = self.dialog_options.merge_file.plan.take() else {
+                    return;
+                };
+
+                let summary = apply_merge(&mut self.backend, plan);
+                self.backend.flush();
+
+                self.dialog_options.current_dialog = CurrentDialog::None;
+                self.dialog_options.merge_file = MergeFileOptions::default();
+                self.show_toast(&tr!(
+                    "Merged {records} records and {entries} entries",
+                    records = summary.added_records,
+                    entries = summary.added_entries + summary.updated_entries
+                ));
+            }
+        });
+    }
+
+    /// Deleted entries of both chains with a restore button each.
+    fn trash_dialog_build(&mut self, ui: &mut Ui) {
+        let time = self.backend.trash(Tree::Time);
+        let todo = self.backend.trash(Tree::Todo);
+
+        let mut restore = None;
+
+        egui::ScrollArea::vertical()
+            .max_height(400.)
+            .show(ui, |ui| {
+                if time.is_empty() && todo.is_empty() {
+                    ui.weak(tr!("The trash is empty"));
+                }
+
+                for (tree, entries) in [(Tree::Time, &time), (Tree::Todo, &todo)] {
+                    if entries.is_empty() {
+                        continue;
+                    }
+
+                    ui.strong(match tree {
+                        Tree::Time => tr!("Time"),
+                        Tree::Todo => tr!("Todo"),
+                    });
+
+                    egui::Grid::new(("trash", tree == Tree::Time))
+                        .num_columns(3)
+                        .spacing((20., 4.))
+                        .show(ui, |ui| {
+                            for entry in entries.iter() {
+                                with_full_name(
+                                    ui.label(name_text(&truncate_middle(
+                                        &entry.label,
+                                        NAME_MAX_CHARS * 3,
+                                    ))),
+                                    &entry.label,
+                                );
+
+                                match entry.deleted_at {
+                                    Some(at) => ui.weak(format_date_time(at.into())),
+                                    None => ui.weak("-"),
+                                };
+
+                                if ui.button(tr!("Restore")).clicked() {
+                                    restore = Some((tree, entry.path));
+                                }
+                                ui.end_row();
+                            }
+                        });
+
+                    ui.add_space(5.);
+                }
+            });
+
+        if let Some((tree, path)) = restore {
+            if !self.backend.restore_entry(tree, path) {
+                self.show_toast(tr!("A live entry already has this name"));
+            }
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("Close")).clicked() || ui.input(|i| i.key_pressed(Key::Escape)) {
+                self.dialog_options.current_dialog = CurrentDialog::None;
+            }
+
+            let empty = time.is_empty() && todo.is_empty();
+
+            if self.dialog_options.emptying_trash {
+                if ui.button(tr!("Confirm empty trash")).clicked() {
+                    self.dialog_options.emptying_trash = false;
+
+                    let removed = self.backend.empty_trash();
+                    self.show_toast(&tr!("Removed {n} entries for good", n = removed));
+                }
+            } else if ui
+                .add_enabled(!empty, egui::Button::new(tr!("Empty trash")))
+                .on_hover_text(tr!(
+                    "Removes deleted entries for good. Time entries stay while records of them \
+                    remain in the history"
+                ))
+                .clicked()
+            {
+                self.dialog_options.emptying_trash = true;
+            }
+        });
+    }
+
+    /// What an integrity issue is about and the label of the button fixing it.
+    fn issue_text(&self, issue: Issue) -> (String, &'static str) {
+        let format = self.backend.settings.duration_format;
+
+        let record_day = |id: Uuid| {
+            self.backend
+                .history
+                .get(&id)
+                .map(|v| format_date_time(v.start_date))
+                .unwrap_or_default()
+        };
+
+        match issue {
+            Issue::MissingEntry { record } => (
+                tr!(
+                    "The record of {at} belongs to an entry that does not exist",
+                    at = record_day(record)
+                ),
+                tr!("Move to Recovered"),
+            ),
+            Issue::NegativeRecord { record } => (
+                tr!(
+                    "The record of {at} ends before it starts",
+                    at = record_day(record)
+                ),
+                tr!("Clamp"),
+            ),
+            Issue::DanglingSelection { tree, container } => {
+                let name = match container {
+                    Some(path) => self.backend.entry_name(tree, path).unwrap_or_default(),
+                    None => match tree {
+                        Tree::Time => tr!("Projects").to_string(),
+                        Tree::Todo => tr!("Todo").to_string(),
+                    },
+                };
+
+                (
+                    tr!(
+                        "The selection in \"{name}\" is a missing or deleted entry",
+                        name = truncate_middle(&name, NAME_MAX_CHARS)
+                    ),
+                    tr!("Clear"),
+                )
+            }
+            Issue::DurationMismatch {
+                path,
+                stored,
+                history,
+            } => {
+                let name = self
+                    .backend
+                    .entry_name(Tree::Time, path)
+                    .unwrap_or_default();
+
+                (
+                    tr!(
+                        "\"{name}\" has {stored} tracked but its records add up to {history}",
+                        name = truncate_middle(&name, NAME_MAX_CHARS),
+                        stored = format_duration(stored, format),
+                        history = format_duration(history, format)
+                    ),
+                    tr!("Recompute"),
+                )
+            }
+        }
+    }
+
+    fn check_data_dialog_build(&mut self, ui: &mut Ui) {
+        let mut repair = None;
+
+        egui::ScrollArea::vertical()
+            .max_height(400.)
+            .show(ui, |ui| {
+                if self.dialog_options.issues.is_empty() {
+                    ui.label(tr!("No problems found"));
+                }
+
+                egui::Grid::new("check_data")
+                    .num_columns(2)
+                    .spacing((20., 4.))
+                    .show(ui, |ui| {
+                        for issue in &self.dialog_options.issues {
+                            let (text, fix) = self.issue_text(*issue);
+
+                            ui.label(text);
+
+                            if ui.button(fix).clicked() {
+                                repair = Some(*issue);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if let Some(issue) = repair {
+            self.backend.repair(issue);
+            self.dialog_options.issues = self.backend.check_integrity();
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("Close")).clicked() || ui.input(|i| i.key_pressed(Key::Escape)) {
+                self.dialog_options.current_dialog = CurrentDialog::None;
+                self.dialog_options.issues = Vec::new();
+            }
+
+            if ui
+                .add_enabled(
+                    !self.dialog_options.issues.is_empty(),
+                    egui::Button::new(tr!("Fix all")),
+                )
+                .clicked()
+            {
+                self.backend.repair_all();
+                self.dialog_options.issues = self.backend.check_integrity();
+            }
+
+            if ui
+                .button(tr!("Recompute all durations"))
+                .on_hover_text(tr!(
+                    "Sets the time of every subject to the sum of its records"
+                ))
+                .clicked()
+            {
+                let changed = self.backend.rebuild_durations();
+                self.dialog_options.issues = self.backend.check_integrity();
+                self.show_toast(&tr!("{n} subject durations changed", n = changed));
+            }
+        });
+    }
+
+    fn split_inspected_record(&mut self) {
+        let options = &mut self.dialog_options.inspect_records;
+
+        let Some((id, at)) = &options.splitting else {
+            return;
+        };
+
+        let id = *id;
+
+        let Some(at) = parse_date_time(at) else {
+            options.error = Some(tr!("The time must look like 2023-07-21 14:30"));
+            return;
+        };
+
+        let Some(rest) = self.backend.split_record(id, at) else {
+            options.error = Some(tr!("The time must lie strictly inside the record"));
+            return;
+        };
+
+        let position = options
+            .ids
+            .iter()
+            .position(|v| *v == id)
+            .map_or(0, |v| v + 1);
+        options.ids.insert(position, rest);
+
+        options.splitting = None;
+        options.error = None;
+    }
+
+    fn reassign_inspected_record(&mut self) {
+        let options = &mut self.dialog_options.inspect_records;
+
+        let Some(reassign) = &options.reassigning else {
+            return;
+        };
+
+        let (Some(project_id), Some(sub_project_id), Some(subject_id)) = (
+            reassign.project_id,
+            reassign.sub_project_id,
+            reassign.subject_id,
+        ) else {
+            options.error = Some(tr!("Select project, sub project and subject"));
+            return;
+        };
+
+        if !self
+            .backend
+            .reassign_record(reassign.id, project_id, sub_project_id, subject_id)
+        {
+            options.error = Some(tr!("The record can no longer be changed"));
+            return;
+        }
+
+        options.reassigning = None;
+        options.error = None;
+    }
+
+    fn save_inspected_record(&mut self) {
+        let options = &mut self.dialog_options.inspect_records;
+
+        let Some((id, start, end)) = &options.editing else {
+            return;
+        };
+
+        let (Some(start), Some(end)) = (parse_date_time(start), parse_date_time(end)) else {
+            options.error = Some(tr!("Dates must look like 2023-07-21 14:30"));
+            return;
+        };
+
+        if end <= start {
+            options.error = Some(tr!("End must be later than start"));
+            return;
+        }
+
+        if !self.backend.set_record_times(*id, start, end) {
+            options.error = Some(tr!("The record can no longer be changed"));
+            return;
+        }
+
+        options.editing = None;
+        options.error = None;
+    }
+
+    fn close_name_dialog(&mut self) {
+        self.dialog_options.current_dialog = CurrentDialog::None;
+        self.dialog_options.buffer = "".to_string();
+        self.dialog_options.focused = false;
+        self.dialog_options.todo_priority = Priority::default();
+    }
+
+    /// Tree the current add dialog adds to.
+    fn dialog_tree(&self) -> Option<Tree> {
+        match self.dialog_options.current_dialog {
+            CurrentDialog::AddProject
+            | CurrentDialog::AddSubProject
+            | CurrentDialog::AddSubject => Some(Tree::Time),
+            CurrentDialog::AddTodoProject
+            | CurrentDialog::AddTodoSubProject
+            | CurrentDialog::AddTodoSubject => Some(Tree::Todo),
+            _ => None,
+        }
+    }
+
+    /// Adds a whole `project/sub project/subject` path typed into an add dialog.
+    fn add_dialog_path(&mut self, tree: Tree, path: &str) {
+        // Picking another subject stops the running one, same as clicking it in its column.
+        if tree == Tree::Time
+            && path_names(path).len() == 3
+            && matches!(self.backend.working_mode, WorkingMode::InProgress(_))
+        {
+            self.time_tracker_stop_subject(true);
+        }
+
+        let Some(added) = self.backend.add_path(path, tree) else {
+            return;
+        };
+
+        let EntryPath::Subject(p, s, x) = added else {
+            return;
+        };
+
+        if self.dialog_options.current_dialog == CurrentDialog::AddTodoSubject {
+            self.backend
+                .set_todo_priority(p, s, x, self.dialog_options.todo_priority);
+        }
+    }
+
+    /// Names already used next to the entry the current dialog adds.
+    fn dialog_sibling_names(&self) -> Vec<String> {
+        fn names<T>(
+            container: Option<&PContainer<T, Uuid>>,
+            name: impl Fn(&T) -> Option<String>,
+        ) -> Vec<String> {
+            container
+                .map(|v| v.inner.values().filter_map(&name).collect())
+                .unwrap_or_default()
+        }
+
+        fn container_name<T>(v: &PContainer<T, Uuid>) -> Option<String> {
+            (!v.is_deleted).then(|| v.name.clone())
+        }
+
+        match self.dialog_options.current_dialog {
+            CurrentDialog::AddProject => names(Some(&self.backend.projects), container_name),
+            CurrentDialog::AddSubProject => {
+                names(self.backend.get_current_project(), container_name)
+            }
+            CurrentDialog::AddSubject => names(self.backend.get_current_sub_project(), |v| {
+                let v = v.lock().unwrap();
+                (!v.is_deleted).then(|| v.name.clone())
+            }),
+            CurrentDialog::AddTodoProject => names(Some(&self.backend.todos), container_name),
+            CurrentDialog::AddTodoSubProject => {
+                names(self.backend.get_current_todo_project(), container_name)
+            }
+            CurrentDialog::AddTodoSubject => {
+                names(self.backend.get_current_todo_sub_project(), |v| {
+                    let v = v.lock().unwrap();
+                    (!v.is_deleted).then(|| v.name.clone())
+                })
+            }
+            CurrentDialog::RenameEntry(tree, path) => self
+                .backend
+                .entry_children(tree, path.parent())
+                .into_iter()
+                .filter(|(id, _)| *id != path.id())
+                .map(|(_, name)| name)
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Name prompt shared by the add dialogs, returns the trimmed name once confirmed.
+    fn name_dialog_build(&mut self, ctx: &egui::Context) -> Option<String> {
+        let title = match self.dialog_options.current_dialog {
+            CurrentDialog::AddProject | CurrentDialog::AddTodoProject => tr!("Add Project"),
+            CurrentDialog::AddSubProject | CurrentDialog::AddTodoSubProject => {
+                tr!("Add Sub Project")
+            }
+            CurrentDialog::RenameEntry(..) => tr!("Rename"),
+            _ => tr!("Add Subject"),
+        };
+        let confirm = match self.dialog_options.current_dialog {
+            CurrentDialog::RenameEntry(..) => tr!("Rename"),
+            _ => tr!("Add"),
+        };
+
+        let siblings = self.dialog_sibling_names();
+        let mut result = None;
+
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let name = self.dialog_options.buffer.trim().to_string();
+                let is_path = self.dialog_tree().is_some() && name.contains('/');
+
+                let error = if is_path {
+                    match path_names(&name).len() {
+                        0 => Some(tr!("Name can't be empty")),
+                        1..=3 => None,
+                        _ => Some(tr!("A path has at most project / sub project / subject")),
+                    }
+                } else if name.is_empty() {
+                    Some(tr!("Name can't be empty"))
+                } else if siblings
+                    .iter()
+                    .any(|v| v.trim().eq_ignore_ascii_case(&name))
+                {
+                    Some(tr!("This name is already taken"))
+                } else {
+                    None
+                };
+
+                let mut confirmed = false;
+
+                if self.dialog_options.current_dialog == CurrentDialog::AddTodoSubject {
+                    ui.horizontal(|ui| {
+                        ui.label(tr!("Priority"));
+
+                        for priority in Priority::ALL {
+                            ui.selectable_value(
+                                &mut self.dialog_options.todo_priority,
+                                priority,
+                                translate(&format!("{priority:?}")),
+                            );
+                        }
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    let r = ui.add(egui::TextEdit::singleline(&mut self.dialog_options.buffer));
+
+                    if !self.dialog_options.focused {
+                        r.request_focus();
+                        self.dialog_options.focused = true;
+                    }
+
+                    if r.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                        confirmed = true;
+                    }
+
+                    if ui.button(tr!("Cancel")).clicked()
+                        || ui.input(|i| i.key_pressed(Key::Escape))
+                    {
+                        self.close_name_dialog();
+                    }
+
+                    if ui
+                        .add_enabled(error.is_none(), egui::Button::new(confirm))
+                        .clicked()
+                    {
+                        confirmed = true;
+                    }
+                });
+
+                if let Some(error) = error {
+                    if !self.dialog_options.buffer.is_empty() {
+                        ui.colored_label(ui.visuals().error_fg_color, error);
+                    }
+                } else if is_path {
+                    ui.label(
+                        RichText::new(tr!(
+                            "Adds project / sub project / subject, missing levels included"
+                        ))
+                        .weak(),
+                    );
+                }
+
+                if error.is_none() && confirmed {
+                    result = Some(name);
+                }
+            });
+
+        result
+    }
+
+    fn dialog_build(&mut self, ctx: &egui::Context) {
+        match self.dialog_options.current_dialog {
+            CurrentDialog::None => {}
+
+            CurrentDialog::SyncConflict => self.sync_conflict_build(ctx),
+
+            CurrentDialog::ResumeSession => {
+                egui::Window::new(tr!("Tracking interrupted"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        if let Some(at) = self.backend.interrupted_at {
+                            ui.label(tr!(
+                                "No activity since {at}, the session was stopped there.",
+                                at = at.format("%H:%M")
+                            ));
+                        }
+
+                        ui.horizontal(|ui| {
+                            if ui.button(tr!("Dismiss")).clicked() {
+                                self.dialog_options.current_dialog = CurrentDialog::None;
+                                self.backend.interrupted_at = None;
+                            }
+
+                            if ui.button(tr!("Resume")).clicked() {
+                                self.dialog_options.current_dialog = CurrentDialog::None;
+                                self.backend.interrupted_at = None;
+                                self.time_tracker_start_subject();
+                            }
+                        });
+                    });
+            }
+
+            CurrentDialog::ResumeAfterLock(path, at) => {
+                egui::Window::new(tr!("Screen locked"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        let name = self
+                            .backend
+                            .entry_name(Tree::Time, path)
+                            .unwrap_or_default();
+
+                        ui.label(tr!(
+                            "The screen was locked at {at}, tracking \"{name}\" was stopped there.",
+                            at = at.format("%H:%M"),
+                            name = truncate_middle(&name, NAME_MAX_CHARS)
+                        ));
+
+                        ui.horizontal(|ui| {
+                            if ui.button(tr!("Dismiss")).clicked() {
+                                self.dialog_options.current_dialog = CurrentDialog::None;
+                            }
+
+                            if ui.button(tr!("Resume")).clicked() {
+                                self.dialog_options.current_dialog = CurrentDialog::None;
+                                self.start_entry(path);
+                            }
+                        });
+                    });
+            }
+
+            CurrentDialog::Settings => {
+                egui::Window::new(tr!("Settings"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        self.settings_dialog_build(ui);
+                    });
+            }
+
+            CurrentDialog::AddManualRecord => {
+                egui::Window::new(tr!("Add Time"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        self.manual_record_dialog_build(ui);
+                    });
+            }
+
+            CurrentDialog::InspectRecords => {
+                egui::Window::new(tr!("Records"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        self.inspect_records_dialog_build(ui);
+                    });
+            }
+
+            CurrentDialog::TidyRecords => {
+                egui::Window::new(tr!("Tidy up records"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        self.tidy_records_dialog_build(ui);
+                    });
+            }
+
+            CurrentDialog::ImportToggl => {
+                egui::Window::new(tr!("Import from Toggl"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        self.toggl_import_dialog_build(ui);
+                    });
+            }
+
+            CurrentDialog::MergeFile => {
+                egui::Window::new(tr!("Import & merge data file"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        self.merge_file_dialog_build(ui);
+                    });
+            }
+
+            CurrentDialog::Trash => {
+                egui::Window::new(tr!("Trash"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        self.trash_dialog_build(ui);
+                    });
+            }
+
+            CurrentDialog::CheckData => {
+                egui::Window::new(tr!("Check data"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        self.check_data_dialog_build(ui);
+                    });
+            }
+
+            CurrentDialog::AddProject
+            | CurrentDialog::AddSubProject
+            | CurrentDialog::AddSubject
+            | CurrentDialog::AddTodoProject
+            | CurrentDialog::AddTodoSubProject
+            | CurrentDialog::AddTodoSubject
+            | CurrentDialog::RenameEntry(..) => {
+                let Some(name) = self.name_dialog_build(ctx) else {
+                    return;
+                };
+
+                if let Some(tree) = self.dialog_tree().filter(|_| name.contains('/')) {
+                    self.add_dialog_path(tree, &name);
+                    self.close_name_dialog();
+
+                    return;
+                }
+
+                match self.dialog_options.current_dialog {
+                    CurrentDialog::AddProject => self.backend.add_project(&name),
+                    CurrentDialog::AddSubProject => self.backend.add_sub_project(&name),
+                    CurrentDialog::AddSubject => self.backend.add_subject(&name),
+                    CurrentDialog::AddTodoProject => self.backend.add_todo_project(&name),
+                    CurrentDialog::AddTodoSubProject => self.backend.add_todo_sub_project(&name),
+                    CurrentDialog::AddTodoSubject => self
+                        .backend
+                        .add_todo_subject(&name, self.dialog_options.todo_priority),
+                    CurrentDialog::RenameEntry(tree, path) => {
+                        self.backend.rename_entry(tree, path, &name)
+                    }
+                    _ => {}
+                }
+
+                self.close_name_dialog();
+            }
+
+            CurrentDialog::EditLink(tree, path) => {
+                egui::Window::new(tr!("Link"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        self.link_dialog_build(ui, tree, path);
+                    });
+            }
+
+            CurrentDialog::EditNoteTemplate(path) => {
+                egui::Window::new(tr!("Note template"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        self.note_template_dialog_build(ui, path);
+                    });
+            }
+
+            CurrentDialog::SessionNote(id) => {
+                egui::Window::new(tr!("Session note"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        self.session_note_dialog_build(ui, id);
+                    });
+            }
+
+            CurrentDialog::MoveEntry(tree, path) => {
+                egui::Window::new(tr!("Move to"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        self.move_dialog_build(ui, tree, path);
+                    });
+            }
+
+            CurrentDialog::DeleteEntry(tree, path) => {
+                let name = self.backend.entry_name(tree, path).unwrap_or_default();
+                let name = truncate_middle(&name, NAME_MAX_CHARS);
+
+                let question = match tree {
+                    Tree::Time => {
+                        tr!(
+                            "\"{name}\" has tracked time or entries inside it. Delete it anyway?",
+                            name = name
+                        )
+                    }
+                    Tree::Todo => match self.backend.open_todos(path) {
+                        1 => tr!(
+                            "\"{name}\" contains 1 open todo. Delete it anyway?",
+                            name = name
+                        ),
+                        n => tr!(
+                            "\"{name}\" contains {n} open todos. Delete it anyway?",
+                            name = name,
+                            n = n
+                        ),
+                    },
+                };
+
+                egui::Window::new(tr!("Delete"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        ui.label(question);
+
+                        ui.horizontal(|ui| {
+                            if ui.button(tr!("Cancel")).clicked() {
+                                self.dialog_options.current_dialog = CurrentDialog::None;
+                            }
+
+                            if ui.button(tr!("Delete")).clicked() {
+                                // Tracking may have started inside it since the dialog opened.
+                                self.backend.delete_entry(tree, path);
+
+                                if self.tracked_path().is_none() {
+                                    self.time_tracker_options.current_label.clear();
+                                }
+
+                                self.dialog_options.current_dialog = CurrentDialog::None;
+                            }
+                        });
+                    });
+            }
+        }
+    }
+}
+
+/**
+    Entry menu block
+**/
+impl Frontend {
+    /// Subject of the running session.
+    fn tracked_path(&self) -> Option<EntryPath> {
+        self.backend.tracked_path()
+    }
+
+    /// Rename, delete, move and copy actions of a row in the Time or Todo columns.
+    fn entry_context_menu(&mut self, ui: &mut Ui, tree: Tree, path: EntryPath) {
+        let tracked = self.tracked_path().filter(|_| tree == Tree::Time);
+
+        if let (Tree::Time, EntryPath::Subject(..)) = (tree, path) {
+            let pinned = self.backend.is_subject_pinned(path);
+
+            if ui
+                .button(if pinned { tr!("Unpin") } else { tr!("Pin") })
+                .clicked()
+            {
+                self.backend.set_subject_pinned(path, !pinned);
+                ui.close_menu();
+            }
+        }
+
+        if let EntryPath::Subject(..) = path {
+            let link = self.backend.entry_link(tree, path);
+
+            if ui.button(tr!("Link…")).clicked() {
+                self.dialog_options.buffer = link.clone().unwrap_or_default();
+                self.dialog_options.focused = false;
+                self.dialog_options.current_dialog = CurrentDialog::EditLink(tree, path);
+                ui.close_menu();
+            }
+
+            if let Some(link) = link {
+                if ui.button(tr!("Open link")).on_hover_text(&link).clicked() {
+                    open_link(ui.ctx(), &link);
+                    ui.close_menu();
+                }
+            }
+        }
+
+        if let (Tree::Time, EntryPath::Subject(..)) = (tree, path) {
+            if ui.button(tr!("Note template…")).clicked() {
+                self.dialog_options.buffer = self.backend.note_template(path).unwrap_or_default();
+                self.dialog_options.focused = false;
+                self.dialog_options.current_dialog = CurrentDialog::EditNoteTemplate(path);
+                ui.close_menu();
+            }
+        }
+
+        if tracked == Some(path) {
+            ui.weak(tr!("Pause tracking to edit this subject"));
+
+            if ui.button(tr!("Move to…")).clicked() {
+                self.open_move_dialog(tree, path);
+                ui.close_menu();
+            }
+
+            return;
+        }
+
+        // Deleting a parent of the running session would orphan it.
+        let holds_tracked = tracked.is_some_and(|v| path.contains(&v));
+
+        if ui.button(tr!("Rename")).clicked() {
+            self.dialog_options.buffer = self.backend.entry_name(tree, path).unwrap_or_default();
+            self.dialog_options.focused = false;
+            self.dialog_options.current_dialog = CurrentDialog::RenameEntry(tree, path);
+            ui.close_menu();
+        }
+
+        if let EntryPath::Project(id) = path {
+            let color = match tree {
+                Tree::Time => self.backend.projects.inner.get(&id).map(|v| v.color),
+                Tree::Todo => self.backend.todos.inner.get(&id).map(|v| v.color),
+            };
+
+            if let Some((r, g, b)) = color {
+                let mut rgb = [r, g, b];
+
+                ui.horizontal(|ui| {
+                    ui.label(tr!("Color"));
+
+                    if ui.color_edit_button_srgb(&mut rgb).changed() {
+                        self.backend
+                            .set_project_color(tree, &id, (rgb[0], rgb[1], rgb[2]));
+                    }
+                });
+            }
+        }
+
+        if let (Tree::Time, EntryPath::SubProject(..) | EntryPath::Subject(..)) = (tree, path) {
+            let own = self.backend.own_color(path);
+            let (r, g, b) = self.backend.entry_color(path);
+            let mut rgb = [r, g, b];
+
+            ui.horizontal(|ui| {
+                ui.label(tr!("Color"));
+
+                if ui.color_edit_button_srgb(&mut rgb).changed() {
+                    self.backend
+                        .set_own_color(path, Some((rgb[0], rgb[1], rgb[2])));
+                }
+
+                if own.is_some()
+                    && ui
+                        .button(tr!("Reset"))
+                        .on_hover_text(tr!("Use the color of the parent"))
+                        .clicked()
+                {
+                    self.backend.set_own_color(path, None);
+                }
+            });
+        }
+
+        if let (Tree::Time, EntryPath::Project(id)) = (tree, path) {
+            let archived = self
+                .backend
+                .projects
+                .inner
+                .get(&id)
+                .is_some_and(|v| v.is_archived);
+
+            if ui
+                .add_enabled(
+                    !holds_tracked,
+                    egui::Button::new(if archived {
+                        tr!("Unarchive")
+                    } else {
+                        tr!("Archive")
+                    }),
+                )
+                .clicked()
+            {
+                self.backend.set_project_archived(&id, !archived);
+                ui.close_menu();
+            }
+
+            if let Some(project) = self.backend.projects.inner.get(&id) {
+                let mut billed = project.hourly_rate.is_some();
+                let mut rate = project.hourly_rate.unwrap_or_default();
+                let mut currency = project.currency.clone();
+                let mut changed = false;
+
+                ui.horizontal(|ui| {
+                    changed |= ui.checkbox(&mut billed, tr!("Hourly rate")).changed();
+
+                    ui.add_enabled_ui(billed, |ui| {
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut rate)
+                                    .clamp_range(0.0..=f64::MAX)
+                                    .max_decimals(2),
+                            )
+                            .changed();
+
+                        changed |= ui
+                            .add(TextEdit::singleline(&mut currency).desired_width(40.))
+                            .on_hover_text(tr!("Currency"))
+                            .changed();
+                    });
+                });
+
+                if changed {
+                    self.backend
+                        .set_project_rate(&id, billed.then_some(rate), &currency);
+                }
+            }
+        }
+
+        if path.parent().is_some() && ui.button(tr!("Move to…")).clicked() {
+            self.open_move_dialog(tree, path);
+            ui.close_menu();
+        }
+
+        if tree == Tree::Time && ui.button(tr!("Copy total as text")).clicked() {
+            let name = self.backend.entry_name(tree, path).unwrap_or_default();
+            let text = format!(
+                "{name}: {}",
+                format_duration(
+                    self.backend.entry_total(path),
+                    self.backend.settings.duration_format
+                )
+            );
+
+            ui.output_mut(|o| o.copied_text = text);
+            ui.close_menu();
+        }
+
+        ui.separator();
+
+        if ui
+            .add_enabled(!holds_tracked, egui::Button::new(tr!("Delete")))
+            .clicked()
+        {
+            if self.backend.entry_is_empty(tree, path) {
+                self.backend.delete_entry(tree, path);
+            } else {
+                self.dialog_options.current_dialog = CurrentDialog::DeleteEntry(tree, path);
+            }
+
+            ui.close_menu();
+        }
+    }
+
+    fn link_dialog_build(&mut self, ui: &mut Ui, tree: Tree, path: EntryPath) {
+        let options = &mut self.dialog_options;
+
+        let response = ui.add(
+            TextEdit::singleline(&mut options.buffer)
+                .hint_text("https://…")
+                .desired_width(320.),
+        );
+
+        if !options.focused {
+            response.request_focus();
+            options.focused = true;
+        }
+
+        ui.weak(tr!("Leave it empty to remove the link"));
+
+        let submit = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+        let mut save = false;
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("Cancel")).clicked() {
+                options.current_dialog = CurrentDialog::None;
+            }
+
+            save = ui.button(tr!("Save")).clicked();
+        });
+
+        if save || submit {
+            self.backend.set_entry_link(tree, path, &options.buffer);
+            options.current_dialog = CurrentDialog::None;
+        }
+    }
+
+    fn note_template_dialog_build(&mut self, ui: &mut Ui, path: EntryPath) {
+        let options = &mut self.dialog_options;
+
+        let response = ui.add(
+            TextEdit::multiline(&mut options.buffer)
+                .desired_rows(3)
+                .desired_width(320.),
+        );
+
+        if !options.focused {
+            response.request_focus();
+            options.focused = true;
+        }
+
+        ui.weak(tr!("Leave it empty to remove the template"));
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("Cancel")).clicked() {
+                options.current_dialog = CurrentDialog::None;
+            }
+
+            if ui.button(tr!("Save")).clicked() {
+                self.backend.set_note_template(path, &options.buffer);
+                options.current_dialog = CurrentDialog::None;
+            }
+        });
+    }
+
+    /// Asks for a note on the session just paused, `buffer` starts out as the note template.
+    fn session_note_dialog_build(&mut self, ui: &mut Ui, id: Uuid) {
+        let options = &mut self.dialog_options;
+
+        let response = ui.add(
+            TextEdit::multiline(&mut options.buffer)
+                .hint_text(tr!("Where did you leave off?"))
+                .desired_rows(3)
+                .desired_width(320.),
+        );
+
+        if !options.focused {
+            response.request_focus();
+            options.focused = true;
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("Skip")).clicked() {
+                options.current_dialog = CurrentDialog::None;
+            }
+
+            if ui.button(tr!("Save")).clicked() {
+                self.backend.set_session_note(id, &options.buffer);
+                options.current_dialog = CurrentDialog::None;
+            }
+        });
+    }
+
+    fn open_move_dialog(&mut self, tree: Tree, path: EntryPath) {
+        let (project_id, sub_project_id) = match path {
+            EntryPath::Project(_) => return,
+            EntryPath::SubProject(p, _) => (p, None),
+            EntryPath::Subject(p, s, _) => (p, Some(s)),
+        };
+
+        self.dialog_options.move_entry = MoveEntryOptions {
+            project_id: Some(project_id),
+            sub_project_id,
+        };
+        self.dialog_options.current_dialog = CurrentDialog::MoveEntry(tree, path);
+    }
+
+    fn move_dialog_build(&mut self, ui: &mut Ui, tree: Tree, path: EntryPath) {
+        let sorted = |mut v: Vec<(Uuid, String)>| {
+            v.sort_by_key(|(_, name)| name.to_lowercase());
+            v
+        };
+
+        let name = self.backend.entry_name(tree, path).unwrap_or_default();
+
+        let from = match (tree, path) {
+            (Tree::Time, EntryPath::Subject(_, _, x)) => self
+                .backend
+                .get_subject_path(&x)
+                .map(|(project, sub_project, _)| format!("{project} / {sub_project}")),
+            _ => None,
+        };
+
+        let options = &mut self.dialog_options.move_entry;
+
+        // Nothing can be selected inside an archived project, so nothing moves into one.
+        let mut projects = sorted(self.backend.entry_children(tree, None));
+        projects.retain(|(id, _)| {
+            tree == Tree::Todo
+                || !self
+                    .backend
+                    .projects
+                    .inner
+                    .get(id)
+                    .is_some_and(|v| v.is_archived)
+        });
+
+        let sub_projects = options
+            .project_id
+            .map(|p| {
+                sorted(
+                    self.backend
+                        .entry_children(tree, Some(EntryPath::Project(p))),
+                )
+            })
+            .unwrap_or_default();
+
+        let selected_text = |list: &[(Uuid, String)], id: Option<Uuid>| {
+            list.iter()
+                .find(|(v, _)| Some(*v) == id)
+                .map_or(String::new(), |(_, v)| truncate_middle(v, NAME_MAX_CHARS))
+        };
+
+        egui::Grid::new("move_entry_grid")
+            .num_columns(2)
+            .spacing([10.0, 6.0])
+            .show(ui, |ui| {
+                if let Some(from) = &from {
+                    ui.label(tr!("From"));
+                    ui.label(truncate_middle(from, NAME_MAX_CHARS * 2));
+                    ui.end_row();
+                }
+
+                ui.label(tr!("Project"));
+                egui::ComboBox::from_id_source("move_entry_project")
+                    .selected_text(selected_text(&projects, options.project_id))
+                    .show_ui(ui, |ui| {
+                        for (id, project_name) in &projects {
+                            if ui
+                                .selectable_value(
+                                    &mut options.project_id,
+                                    Some(*id),
+                                    truncate_middle(project_name, NAME_MAX_CHARS),
+                                )
+                                .changed()
+                            {
+                                options.sub_project_id = None;
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                if let EntryPath::Subject(..) = path {
+                    ui.label(tr!("Sub project"));
+                    egui::ComboBox::from_id_source("move_entry_sub_project")
+                        .selected_text(selected_text(&sub_projects, options.sub_project_id))
+                        .show_ui(ui, |ui| {
+                            for (id, sub_project_name) in &sub_projects {
+                                ui.selectable_value(
+                                    &mut options.sub_project_id,
+                                    Some(*id),
+                                    truncate_middle(sub_project_name, NAME_MAX_CHARS),
+                                );
+                            }
+                        });
+                    ui.end_row();
+                }
+            });
+
+        let target = match (path, options.project_id, options.sub_project_id) {
+            (EntryPath::SubProject(..), Some(p), _) => Some(EntryPath::Project(p)),
+            (EntryPath::Subject(..), Some(p), Some(s)) => Some(EntryPath::SubProject(p, s)),
+            _ => None,
+        };
+
+        let taken = target.is_some_and(|v| {
+            self.backend
+                .entry_children(tree, Some(v))
+                .iter()
+                .any(|(_, v)| v.eq_ignore_ascii_case(&name))
+        });
+
+        if taken {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                tr!("The target already has an entry with this name"),
+            );
+        }
+
+        let target = target.filter(|v| !taken && Some(*v) != path.parent());
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("Cancel")).clicked() {
+                self.dialog_options.current_dialog = CurrentDialog::None;
+            }
+
+            if ui
+                .add_enabled(target.is_some(), egui::Button::new(tr!("Move")))
+                .clicked()
+            {
+                match (tree, path, target) {
+                    (Tree::Time, EntryPath::SubProject(_, s), Some(EntryPath::Project(p))) => {
+                        self.backend.move_sub_project(s, p);
+                    }
+                    (
+                        Tree::Time,
+                        EntryPath::Subject(_, _, x),
+                        Some(EntryPath::SubProject(p, s)),
+                    ) => {
+                        self.backend.move_subject(x, p, s);
+                    }
+                    (_, _, Some(target)) => {
+                        self.backend.move_entry(tree, path, target);
+                    }
+                    _ => {}
+                }
+
+                self.dialog_options.current_dialog = CurrentDialog::None;
+            }
+        });
+    }
+}
+
+/**
+    Statistics block
+**/
+struct StatisticOptions {
+    scroll_offset_x: f32,
+    scroll_offset_y: f32,
+    label_from: SimpleDate,
+    label_to: SimpleDate,
+    from: DateTime<Local>,
+    to: DateTime<Local>,
+    current_project_id: Option<Uuid>,
+    current_sub_project_id: Option<Uuid>,
+    invalid_from: bool,
+    invalid_to: bool,
+    coalesce_gap_minutes: i64,
+    view: StatisticView,
+    bar_drag: Option<BarDrag>,
+    records_cache: Option<RecordsCache>,
+    /// Width of one minute in the day timeline, follows the window width and the zoom.
+    minute_width: f32,
+    /// Scale of the day timeline on top of its window width based size.
+    zoom: f32,
+    /// The zoom moved `scroll_offset_x`, the timeline has to follow it.
+    zoom_scrolled: bool,
+    show_gaps: bool,
+    summary_sort: SummarySort,
+    /// Breakdown bars split by project instead of one color.
+    breakdown_stacked: bool,
+    breakdown_cache: Option<BreakdownCache>,
+    heatmap_cache: Option<HeatmapCache>,
+    /// Secondary range the summaries and the timeline are compared against.
+    compare: Option<CompareRange>,
+    billing_rounding: BillingRounding,
+}
+
+struct CompareRange {
+    label_from: SimpleDate,
+    label_to: SimpleDate,
+    range: Option<(DateTime<Local>, DateTime<Local>)>,
+    records_cache: Option<RecordsCache>,
+}
+
+impl CompareRange {
+    fn new(from: NaiveDate, to: NaiveDate) -> Self {
+        let mut compare = CompareRange {
+            label_from: from.into(),
+            label_to: to.into(),
+            range: None,
+            records_cache: None,
+        };
+
+        compare.update_from_labels();
+
+        compare
+    }
+
+    fn update_from_labels(&mut self) {
+        self.range = range_from_labels(&mut self.label_from, &mut self.label_to);
+    }
+}
+
+/// The range of equal length right before `from..=to`.
+fn previous_period(from: NaiveDate, to: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let length = to.signed_duration_since(from) + chrono::Duration::days(1);
+
+    (from - length, from - chrono::Duration::days(1))
+}
+
+/// Range from the start of the first label's day to the end of the second's, swapping
+/// the labels when they are reversed. `None` while either label is not a valid date.
+fn range_from_labels(
+    from: &mut SimpleDate,
+    to: &mut SimpleDate,
+) -> Option<(DateTime<Local>, DateTime<Local>)> {
+    let mut f: DateTime<Local> = (&*from).try_into().ok()?;
+    let mut t: DateTime<Local> = (&*to).try_into().ok()?;
+
+    if f > t {
+        std::mem::swap(&mut f, &mut t);
+        std::mem::swap(from, to);
+    }
+
+    Some((
+        f,
+        start_of_day(t.date_naive() + chrono::Duration::days(1))
+            .sub(chrono::Duration::milliseconds(100)),
+    ))
+}
+
+/// Daily totals of the year up to `today`, valid while the day and history revision match.
+struct HeatmapCache {
+    today: NaiveDate,
+    revision: u64,
+    totals: Rc<Vec<chrono::Duration>>,
+}
+
+/// Breakdown of the shown range, valid while the range and history revision match.
+struct BreakdownCache {
+    range: (DateTime<Local>, DateTime<Local>),
+    revision: u64,
+    breakdown: Rc<Breakdown>,
+}
+
+/// Day buckets of the shown range, valid while the range and history revision match.
+struct RecordsCache {
+    range: (DateTime<Local>, DateTime<Local>),
+    revision: u64,
+    records: Rc<Vec<Vec<HistoryRecord>>>,
+}
+
+fn cached_records(
+    cache: &mut Option<RecordsCache>,
+    history: &History,
+    range: (DateTime<Local>, DateTime<Local>),
+) -> Rc<Vec<Vec<HistoryRecord>>> {
+    let revision = history.revision();
+
+    if let Some(cache) = cache {
+        if cache.range == range && cache.revision == revision {
+            return cache.records.clone();
+        }
+    }
+
+    let records = Rc::new(history.get_ordered_records(range));
+
+    *cache = Some(RecordsCache {
+        range,
+        revision,
+        records: records.clone(),
+    });
+
+    records
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+enum StatisticView {
+    #[default]
+    Day,
+    Week,
+    Month,
+    /// Time per weekday and per hour of the day.
+    Breakdown,
+    /// Daily totals of the last year, independent of the selected range.
+    Heatmap,
+    /// Time and earnings per project at the project rates.
+    Billing,
+}
+
+struct SimpleDate {
+    year: String,
+    month: Month,
+    day: String,
+}
+
+impl SimpleDate {
+    fn parsed_year(&self) -> Option<i32> {
+        self.year
+            .trim()
+            .parse::<i32>()
+            .ok()
+            .filter(|v| (1970..=9999).contains(v))
+    }
+
+    fn parsed_day(&self) -> Option<u32> {
+        let day = self.day.trim().parse::<u32>().ok()?;
+
+        let max = self.parsed_year().map_or(31, |year| {
+            get_days_from_month(year, self.month.number_from_month())
+        });
+
+        (1..=max).contains(&day).then_some(day)
+    }
+
+    /// Keeps the day inside the selected month, e.g. March 31 becomes April 30.
+    fn clamp_day(&mut self) {
+        let (Some(year), Ok(day)) = (self.parsed_year(), self.day.trim().parse::<u32>()) else {
+            return;
+        };
+
+        let max = get_days_from_month(year, self.month.number_from_month());
+
+        if day > max {
+            self.day = max.to_string();
+        }
+    }
+}
+
+/// Untracked time in the timeline, striped so it does not read as a record.
+fn paint_hatched(ui: &Ui, rect: Rect) {
+    if rect.width() < 1. {
+        return;
+    }
+
+    let color = ui.visuals().weak_text_color();
+    let painter = ui.painter().with_clip_rect(rect.intersect(ui.clip_rect()));
+
+    painter.rect_stroke(rect, Rounding::same(2.0), Stroke::new(1.0, color));
+
+    let mut x = rect.left() - rect.height();
+
+    while x < rect.right() {
+        painter.line_segment(
+            [
+                egui::pos2(x, rect.bottom()),
+                egui::pos2(x + rect.height(), rect.top()),
+            ],
+            Stroke::new(1.0, color),
+        );
+
+        x += 6.;
+    }
+}
+
+/// Year / month / day editor of the statistic range. Returns true when the range should be
+/// re-read from the labels.
+fn statistic_date_edit(ui: &mut Ui, id: usize, date: &mut SimpleDate) -> bool {
+    let mut update = false;
+    let error_stroke = Stroke::new(1.0, ui.visuals().error_fg_color);
+
+    let y = ui.add_sized((50., 15.), TextEdit::singleline(&mut date.year));
+
+    if date.parsed_year().is_none() {
+        ui.painter().rect_stroke(y.rect, 2.0, error_stroke);
+    }
+
+    if y.lost_focus() {
+        date.clamp_day();
+        update = true;
+    }
+
+    ui.add_space(2.);
+
+    ui.push_id(id, |ui| {
+        let mut changed = false;
+
+        egui::ComboBox::from_label("")
+            .selected_text(month_name(date.month))
+            .show_ui(ui, |ui| {
+                for month in 1..=12 {
+                    let m = Month::try_from(month).unwrap();
+                    changed |= ui
+                        .selectable_value(&mut date.month, m, month_name(m))
+                        .changed();
+                }
+            });
+
+        if changed {
+            date.clamp_day();
+            update = true;
+        }
+    });
+
+    ui.add_space(2.);
+
+    let d = ui.add_sized((30., 15.), TextEdit::singleline(&mut date.day));
+
+    if date.parsed_day().is_none() {
+        ui.painter().rect_stroke(d.rect, 2.0, error_stroke);
+    }
+
+    if d.lost_focus() {
+        update = true;
+    }
+
+    update
+}
+
+impl TryInto<DateTime<Local>> for &SimpleDate {
+    type Error = ();
+
+    fn try_into(self) -> Result<DateTime<Local>, Self::Error> {
+        let Some(year) = self.parsed_year() else {
+            return Err(());
+        };
+        let Some(day) = self.parsed_day() else {
+            return Err(());
+        };
+
+        let Some(date) = NaiveDate::from_ymd_opt(year, self.month.number_from_month(), day) else {
+            return Err(());
+        };
+
+        Ok(start_of_day(date))
+    }
+}
+
+#[derive(Copy, Clone)]
+enum RangePreset {
+    Today,
+    Yesterday,
+    ThisWeek,
+    LastSevenDays,
+    ThisMonth,
+    LastMonth,
+}
+
+impl RangePreset {
+    const ALL: [RangePreset; 6] = [
+        RangePreset::Today,
+        RangePreset::Yesterday,
+        RangePreset::ThisWeek,
+        RangePreset::LastSevenDays,
+        RangePreset::ThisMonth,
+        RangePreset::LastMonth,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            RangePreset::Today => tr!("Today"),
+            RangePreset::Yesterday => tr!("Yesterday"),
+            RangePreset::ThisWeek => tr!("This week"),
+            RangePreset::LastSevenDays => tr!("Last 7 days"),
+            RangePreset::ThisMonth => tr!("This month"),
+            RangePreset::LastMonth => tr!("Last month"),
+        }
+    }
+
+    fn range(self, today: NaiveDate, first_day_of_week: Weekday) -> (NaiveDate, NaiveDate) {
+        match self {
+            RangePreset::Today => (today, today),
+            RangePreset::Yesterday => {
+                let yesterday = today.pred_opt().unwrap();
+
+                (yesterday, yesterday)
+            }
+            RangePreset::ThisWeek => {
+                let start = start_of_week(today, first_day_of_week);
+
+                (start, start.checked_add_days(Days::new(6)).unwrap())
+            }
+            RangePreset::LastSevenDays => (today.checked_sub_days(Days::new(6)).unwrap(), today),
+            RangePreset::ThisMonth => (start_of_month(today), end_of_month(today)),
+            RangePreset::LastMonth => {
+                let last_month = start_of_month(today).pred_opt().unwrap();
+
+                (start_of_month(last_month), last_month)
+            }
+        }
+    }
+}
+
+impl From<NaiveDate> for SimpleDate {
+    fn from(value: NaiveDate) -> Self {
+        SimpleDate {
+            year: value.year().to_string(),
+            month: Month::try_from(value.month() as u8).unwrap(),
+            day: value.day().to_string(),
+        }
+    }
+}
+
+impl StatisticOptions {
+    fn set_range(&mut self, from: NaiveDate, to: NaiveDate) {
+        self.label_from = from.into();
+        self.label_to = to.into();
+
+        self.update_from_labels();
+    }
+
+    fn apply_preset(&mut self, preset: RangePreset, today: NaiveDate, first_day_of_week: Weekday) {
+        let (from, to) = preset.range(today, first_day_of_week);
+
+        self.set_range(from, to);
+
+        if self.compare.is_some() {
+            self.compare_previous_period();
+        }
+    }
+
+    /// Keeps the time `anchor` points right of the visible timeline start in place, or the
+    /// start itself without one.
+    fn set_zoom(&mut self, zoom: f32, anchor: Option<f32>) {
+        let zoom = zoom.clamp(TIMELINE_MIN_ZOOM, TIMELINE_MAX_ZOOM);
+        let anchor = anchor.unwrap_or(0.);
+        let ratio = zoom / self.zoom;
+
+        self.scroll_offset_x = ((self.scroll_offset_x + anchor) * ratio - anchor).max(0.);
+        self.zoom = zoom;
+        self.zoom_scrolled = true;
+    }
+
+    fn update_from_labels(&mut self) {
+        let from: Result<DateTime<Local>, ()> = (&self.label_from).try_into();
+        let to: Result<DateTime<Local>, ()> = (&self.label_to).try_into();
+
+        self.invalid_from = from.is_err();
+        self.invalid_to = to.is_err();
+
+        if let Some((from, to)) = range_from_labels(&mut self.label_from, &mut self.label_to) {
+            self.from = from;
+            self.to = to;
+        }
+    }
+
+    fn compare_range(&self) -> Option<(DateTime<Local>, DateTime<Local>)> {
+        self.compare.as_ref().and_then(|v| v.range)
+    }
+
+    /// Points the compared range at the period right before the shown one.
+    fn compare_previous_period(&mut self) {
+        let (from, to) = previous_period(self.from.date_naive(), self.to.date_naive());
+
+        self.compare = Some(CompareRange::new(from, to));
+    }
+}
+
+impl Default for StatisticOptions {
+    fn default() -> Self {
+        let s1 = DateTime::<Local>::from(SystemTime::now());
+        let days = get_days_from_month(s1.year(), s1.month());
+
+        let from;
+        {
+            let mut day = s1.day();
+            let mut month = s1.month();
+            let mut year = s1.year();
+
+            if days == 1 {
+                if month == 1 {
+                    year -= 1;
+                    month = 12;
+                } else {
+                    month -= 1;
+                }
+                day = get_days_from_month(year, month);
+            }
+
+            from = start_of_day(NaiveDate::from_ymd_opt(year, month, day).unwrap());
+        }
+
+        let to;
+        {
+            let mut day = s1.day();
+            let mut month = s1.month();
+            let mut year = s1.year();
+
+            if days == s1.day() {
+                if month == 12 {
+                    year += 1;
+                    month = 1;
+                    day = 1;
+                } else {
+                    month += 1;
+                    day = 1;
+                }
+            }
+
+            to = to_local(
+                NaiveDate::from_ymd_opt(year, month, day)
+                    .and_then(|v| v.and_hms_opt(23, 59, 59))
+                    .unwrap(),
+            );
+        }
+
+        StatisticOptions {
+            scroll_offset_x: 0.,
+            scroll_offset_y: 0.,
+            label_from: SimpleDate {
+                year: from.year().to_string(),
+                month: Month::try_from(from.month() as u8).unwrap(),
+                day: from.day().to_string(),
+            },
+            label_to: SimpleDate {
+                year: to.year().to_string(),
+                month: Month::try_from(to.month() as u8).unwrap(),
+                day: to.day().to_string(),
+            },
+            invalid_from: false,
+            invalid_to: false,
+            from,
+            to,
+            current_project_id: None,
+            current_sub_project_id: None,
+            coalesce_gap_minutes: 3,
+            view: StatisticView::default(),
+            bar_drag: None,
+            records_cache: None,
+            minute_width: TIMELINE_MINUTE_WIDTH,
+            zoom: 1.,
+            zoom_scrolled: false,
+            show_gaps: false,
+            summary_sort: SummarySort::default(),
+            breakdown_stacked: true,
+            breakdown_cache: None,
+            heatmap_cache: None,
+            compare: None,
+            billing_rounding: BillingRounding::default(),
+        }
+    }
+}
+
+/// Tracked time of one project, sub project or subject in the statistic summaries.
+struct Summary {
+    title: String,
+    duration: chrono::Duration,
+    /// Time within the compared range, zero when not comparing.
+    compare: chrono::Duration,
+}
+
+#[derive(Copy, Clone, PartialEq, Default)]
+enum SummarySort {
+    #[default]
+    Duration,
+    Name,
+}
+
+/// Entries of a summary column in display order, with the column total.
+fn sorted_summary(
+    summary: &HashMap<Uuid, Summary>,
+    sort: SummarySort,
+) -> (Vec<(Uuid, &Summary)>, Summary) {
+    let mut entries: Vec<(Uuid, &Summary)> = summary.iter().map(|(k, v)| (*k, v)).collect();
+
+    match sort {
+        SummarySort::Duration => entries.sort_by_key(|v| std::cmp::Reverse(v.1.duration)),
+        SummarySort::Name => entries.sort_by_key(|v| v.1.title.to_lowercase()),
+    }
+
+    let total = Summary {
+        title: "Total".to_string(),
+        duration: entries
+            .iter()
+            .fold(chrono::Duration::zero(), |s, v| s.add(v.1.duration)),
+        compare: entries
+            .iter()
+            .fold(chrono::Duration::zero(), |s, v| s.add(v.1.compare)),
+    };
+
+    (entries, total)
+}
+
+/// Column title toggling the sort order of all summary columns, followed by the total.
+fn summary_header(
+    ui: &mut Ui,
+    title: &str,
+    sort: &mut SummarySort,
+    total: &Summary,
+    compare: bool,
+    format: DurationFormat,
+) {
+    let (arrow, hint) = match sort {
+        SummarySort::Duration => ("⏷", tr!("Sorted by time, click to sort by name")),
+        SummarySort::Name => ("A-Z", tr!("Sorted by name, click to sort by time")),
+    };
+
+    if ui
+        .add(egui::Button::new(RichText::new(format!("{title} {arrow}")).strong()).frame(false))
+        .on_hover_text(hint)
+        .clicked()
+    {
+        *sort = match sort {
+            SummarySort::Duration => SummarySort::Name,
+            SummarySort::Name => SummarySort::Duration,
+        };
+    }
+
+    ui.label(
+        RichText::new(format!(
+            "{}{}",
+            tr!("Total"),
+            summary_text(total, total, compare, format)
+        ))
+        .strong(),
+    );
+    ui.add_space(4.);
+}
+
+/// Duration of a summary entry with its share of the column total, and its time in the
+/// compared range when comparing.
+fn summary_text(
+    summary: &Summary,
+    total: &Summary,
+    compare: bool,
+    format: DurationFormat,
+) -> String {
+    let percent = match total.duration.num_seconds() {
+        0 => 0,
+        total => (summary.duration.num_seconds() * 100 + total / 2) / total,
+    };
+
+    let mut text = format!(
+        " - {} ({percent}%)",
+        format_chrono_duration(summary.duration, format)
+    );
+
+    if compare {
+        text += &tr!(
+            " vs {compare}, {delta}",
+            compare = format_chrono_duration(summary.compare, format),
+            delta = delta_text(summary.duration.sub(summary.compare), format)
+        );
+    }
+
+    text
+}
+
+/// Signed difference like `+2:30` or `-0:45`.
+fn delta_text(delta: chrono::Duration, format: DurationFormat) -> String {
+    let (sign, delta) = if delta < chrono::Duration::zero() {
+        ("-", -delta)
+    } else {
+        ("+", delta)
+    };
+
+    format!("{sign}{}", format_chrono_duration(delta, format))
+}
+
+/// Tracked time per project within one week or month of the statistic range.
+struct PeriodBucket {
+    title: String,
+    days: i64,
+    per_project: Vec<(Uuid, chrono::Duration)>,
+}
+
+impl Frontend {
+    /// Reads the month files of the shown ranges, and of the last year for the heatmap.
+    fn load_statistic_history(&mut self) {
+        let options = &self.statistic_options;
+        let mut ranges = vec![(options.from.date_naive(), options.to.date_naive())];
+
+        if let Some((from, to)) = options.compare_range() {
+            ranges.push((from.date_naive(), to.date_naive()));
+        }
+
+        if options.view == StatisticView::Heatmap {
+            let today = Local::now().date_naive();
+            ranges.push((self.heatmap_first_day(today), today));
+        }
+
+        for (from, to) in ranges {
+            self.backend.history.load_range(from, to);
+        }
+    }
+
+    fn statistic_records(&mut self) -> Rc<Vec<Vec<HistoryRecord>>> {
+        let range = (self.statistic_options.from, self.statistic_options.to);
+
+        cached_records(
+            &mut self.statistic_options.records_cache,
+            &self.backend.history,
+            range,
+        )
+    }
+
+    /// Day buckets of the compared range, `None` when not comparing.
+    fn compare_records(&mut self) -> Option<Rc<Vec<Vec<HistoryRecord>>>> {
+        let compare = self.statistic_options.compare.as_mut()?;
+        let range = compare.range?;
+
+        Some(cached_records(
+            &mut compare.records_cache,
+            &self.backend.history,
+            range,
+        ))
+    }
+
+    fn build_statistic(&mut self, ui: &mut Ui) {
+        let format = self.backend.settings.duration_format;
+
+        let area = ui.max_rect();
+
+        // Everything below scales with the window, the defaults fit the initial 1200x800.
+        let indent = area.width() / 3.;
+        let column_width = (area.width() - 20.) / 3.;
+        let summary_height = (area.height() / 2.).max(120.);
+
+        self.load_statistic_history();
+        self.build_menu(ui);
+
+        let style = ui.style().clone();
+        let mut new_style = (*style).clone();
+        new_style.spacing.item_spacing = Vec2::new(0., 0.);
+
+        ui.set_style(new_style);
+
+        let mut copy_report = false;
+
+        ui.horizontal_top(|ui| {
+            ui.add_space(indent);
+
+            ui.set_max_height(30.);
+
+            if statistic_date_edit(ui, 9, &mut self.statistic_options.label_from) {
+                self.statistic_options.update_from_labels();
+            }
+
+            ui.add_space(5.);
+            ui.add_sized((5., 15.), Label::new(":"));
+            ui.add_space(5.);
+
+            if statistic_date_edit(ui, 10, &mut self.statistic_options.label_to) {
+                self.statistic_options.update_from_labels();
+            }
+
+            if self.statistic_options.invalid_from || self.statistic_options.invalid_to {
+                ui.add_space(10.);
+                ui.colored_label(ui.visuals().error_fg_color, tr!("invalid date"));
+            }
+
+            ui.add_space(20.);
+
+            ui.add(
+                egui::DragValue::new(&mut self.statistic_options.coalesce_gap_minutes)
+                    .clamp_range(0..=60)
+                    .suffix(tr!(" min")),
+            )
+            .on_hover_text(tr!(
+                "Join bars of the same subject separated by less than this gap"
+            ));
+
+            ui.add_space(20.);
+
+            ui.checkbox(&mut self.statistic_options.show_gaps, tr!("Untracked"))
+                .on_hover_text(tr!(
+                    "Mark untracked time within the working hours set in the settings"
+                ));
+
+            ui.add_space(20.);
+
+            let mut compare = self.statistic_options.compare.is_some();
+
+            if ui
+                .checkbox(&mut compare, tr!("Compare"))
+                .on_hover_text(tr!(
+                    "Compare with a second range, the previous period by default"
+                ))
+                .changed()
+            {
+                if compare {
+                    self.statistic_options.compare_previous_period();
+                } else {
+                    self.statistic_options.compare = None;
+                }
+            }
+
+            ui.add_space(20.);
+
+            if ui
+                .button(tr!("Tidy up records…"))
+                .on_hover_text(tr!(
+                    "Merge records of the same subject separated by tiny gaps"
+                ))
+                .clicked()
+            {
+                self.open_tidy_records_dialog();
+            }
+
+            ui.add_space(20.);
+
+            copy_report = ui
+                .button(tr!("Copy report"))
+                .on_hover_text(
+                    "Copy the project totals, and the sub projects of the selected project, \
+                    as a Markdown table",
+                )
+                .clicked();
+
+            ui.add_space(4.);
+
+            if ui
+                .button(tr!("Week report"))
+                .on_hover_text(
+                    "Write a Markdown report of the ISO week the range starts in next to the \
+                    data file",
+                )
+                .clicked()
+            {
+                let result = write_week_report(
+                    self.statistic_options.from.date_naive(),
+                    &mut self.backend.history,
+                    &self.backend.projects,
+                    format,
+                );
+
+                match result {
+                    Ok(path) => self.show_toast(&tr!("Wrote {path}", path = path.display())),
+                    Err(e) => self.show_toast(&e),
+                }
+            }
+        });
+
+        let mut previous_period = false;
+
+        if let Some(compare) = &mut self.statistic_options.compare {
+            ui.add_space(5.);
+
+            ui.horizontal_top(|ui| {
+                ui.add_space(indent);
+
+                ui.set_max_height(30.);
+
+                let mut changed = statistic_date_edit(ui, 12, &mut compare.label_from);
+
+                ui.add_space(5.);
+                ui.add_sized((5., 15.), Label::new(":"));
+                ui.add_space(5.);
+
+                changed |= statistic_date_edit(ui, 13, &mut compare.label_to);
+
+                if changed {
+                    compare.update_from_labels();
+                }
+
+                if compare.range.is_none() {
+                    ui.add_space(10.);
+                    ui.colored_label(ui.visuals().error_fg_color, tr!("invalid date"));
+                }
+
+                ui.add_space(20.);
+
+                previous_period = ui.button(tr!("Previous period")).clicked();
+            });
+        }
+
+        if previous_period {
+            self.statistic_options.compare_previous_period();
+        }
+
+        ui.add_space(5.);
+
+        ui.horizontal(|ui| {
+            ui.add_space(indent);
+
+            let today = DateTime::<Local>::from(SystemTime::now()).date_naive();
+
+            for preset in RangePreset::ALL {
+                if ui.button(preset.label()).clicked() {
+                    self.statistic_options.apply_preset(
+                        preset,
+                        today,
+                        self.backend.settings.first_day_of_week,
+                    );
+                }
+
+                ui.add_space(4.);
+            }
+
+            ui.add_space(20.);
+
+            for view in [
+                StatisticView::Day,
+                StatisticView::Week,
+                StatisticView::Month,
+                StatisticView::Breakdown,
+                StatisticView::Heatmap,
+                StatisticView::Billing,
+            ] {
+                ui.selectable_value(
+                    &mut self.statistic_options.view,
+                    view,
+                    translate(&format!("{view:?}")),
+                );
+                ui.add_space(4.);
+            }
+
+            if self.statistic_options.view == StatisticView::Day {
+                ui.add_space(20.);
+
+                let zoom = self.statistic_options.zoom;
+
+                if ui
+                    .add_enabled(zoom > TIMELINE_MIN_ZOOM, egui::Button::new("-"))
+                    .on_hover_text(tr!("Zoom out (Ctrl+scroll)"))
+                    .clicked()
+                {
+                    self.statistic_options.set_zoom(zoom / 2., None);
+                }
+
+                ui.add_sized(
+                    (50., 15.),
+                    Label::new(format!("{}%", (zoom * 100.).round())),
+                );
+
+                if ui
+                    .add_enabled(zoom < TIMELINE_MAX_ZOOM, egui::Button::new("+"))
+                    .on_hover_text(tr!("Zoom in (Ctrl+scroll)"))
+                    .clicked()
+                {
+                    self.statistic_options.set_zoom(zoom * 2., None);
+                }
+            }
+        });
+
+        ui.add_space(10.);
+
+        let records = self.statistic_records();
+        let compare_records = self.compare_records();
+
+        ui.vertical(|ui| {
+            ui.push_id(3, |ui| {
+                ui.set_min_height(summary_height);
+                ui.set_max_height(summary_height);
+                ScrollArea::vertical().show(ui, |ui| {
+                    let mut projects_summary: HashMap<Uuid, Summary> = HashMap::new();
+                    let mut sub_projects_summary: HashMap<Uuid, Summary> = HashMap::new();
+                    let mut subjects_summary: HashMap<Uuid, Summary> = HashMap::new();
+                    // Timeline color of each subject, for its swatch.
+                    let mut subject_colors = HashMap::new();
+
+                    let options = &self.statistic_options;
+                    let rounding = self.backend.settings.report_rounding;
+
+                    let ranges = [Some((options.from, options.to)), options.compare_range()];
+
+                    for (compare, range) in [false, true].into_iter().zip(ranges) {
+                        let Some(range) = range else {
+                            continue;
+                        };
+
+                        for record in self.backend.history.get_records(range) {
+                            let path = self.backend.get_record_path(&record);
+
+                            let add = |summary: &mut HashMap<Uuid, Summary>, id, title| {
+                                let v = summary.entry(id).or_insert_with(|| Summary {
+                                    title,
+                                    duration: chrono::Duration::zero(),
+                                    compare: chrono::Duration::zero(),
+                                });
+
+                                let duration = round_duration(record.get_duration(), rounding);
+
+                                if compare {
+                                    v.compare = v.compare.add(duration);
+                                } else {
+                                    v.duration = v.duration.add(duration);
+                                }
+                            };
+
+                            add(&mut projects_summary, record.project_id, path.project);
+
+                            if options.current_project_id == Some(record.project_id) {
+                                add(
+                                    &mut sub_projects_summary,
+                                    record.sub_project_id,
+                                    path.sub_project,
+                                );
+                            }
+
+                            if options.current_sub_project_id == Some(record.sub_project_id) {
+                                subject_colors.insert(record.subject_id, path.color);
+                                add(&mut subjects_summary, record.subject_id, path.subject);
+                            }
+                        }
+                    }
+
+                    if copy_report {
+                        let entries = |summary: &HashMap<Uuid, Summary>| {
+                            sorted_summary(summary, options.summary_sort)
+                                .0
+                                .into_iter()
+                                .filter(|(_, v)| v.duration > chrono::Duration::zero())
+                                .map(|(_, v)| ReportEntry {
+                                    name: v.title.clone(),
+                                    duration: v.duration,
+                                })
+                                .collect::<Vec<_>>()
+                        };
+
+                        let sub_projects = entries(&sub_projects_summary);
+                        let project = options
+                            .current_project_id
+                            .and_then(|v| projects_summary.get(&v))
+                            .map(|v| v.title.as_str());
+
+                        let text = range_report(
+                            options.from.date_naive(),
+                            options.to.date_naive(),
+                            &entries(&projects_summary),
+                            project.map(|v| (v, sub_projects.as_slice())),
+                            format,
+                            &ExportZone::Local.name((&options.from, &options.to)),
+                        );
+
+                        ui.output_mut(|o| o.copied_text = text);
+                    }
+
+                    let compare = options.compare.is_some();
+                    let sort = &mut self.statistic_options.summary_sort;
+
+                    ui.horizontal(|ui| {
+                        ui.vertical(|ui| {
+                            ui.set_width(column_width);
+
+                            let (c, total) = sorted_summary(&projects_summary, *sort);
+                            summary_header(ui, tr!("Projects"), sort, &total, compare, format);
+
+                            for (id, v) in c {
+                                let mut text = name_text(&v.title);
+
+                                if self.statistic_options.current_project_id == Some(id) {
+                                    text = text.strong();
+                                }
+
+                                ui.horizontal(|ui| {
+                                    if with_full_name(ui.button(text), &v.title).clicked() {
+                                        self.statistic_options.current_project_id = Some(id);
+                                        self.statistic_options.current_sub_project_id = None;
+                                    }
+
+                                    ui.label(summary_text(v, &total, compare, format));
+                                });
+                            }
+                        });
+
+                        ui.vertical(|ui| {
+                            ui.set_width(column_width);
+
+                            if sub_projects_summary.is_empty() {
+                                return;
+                            }
+
+                            let (c, total) = sorted_summary(&sub_projects_summary, *sort);
+                            summary_header(ui, tr!("Sub projects"), sort, &total, compare, format);
+
+                            for (id, v) in c {
+                                let mut text = name_text(&v.title);
+
+                                if self.statistic_options.current_sub_project_id == Some(id) {
+                                    text = text.strong();
+                                }
+
+                                ui.horizontal(|ui| {
+                                    if with_full_name(ui.button(text), &v.title).clicked() {
+                                        self.statistic_options.current_sub_project_id = Some(id);
+                                    }
+
+                                    ui.label(summary_text(v, &total, compare, format));
+                                });
+                            }
+                        });
+
+                        ui.vertical(|ui| {
+                            ui.set_width(column_width);
+
+                            if subjects_summary.is_empty() {
+                                return;
+                            }
+
+                            let (c, total) = sorted_summary(&subjects_summary, *sort);
+                            summary_header(ui, tr!("Subjects"), sort, &total, compare, format);
+
+                            for (id, v) in c {
+                                ui.horizontal(|ui| {
+                                    let color =
+                                        subject_colors.get(&id).copied().unwrap_or(MISSING_COLOR);
+                                    let color = visible_color(ui, color);
+                                    let (rect, _) = ui.allocate_exact_size(
+                                        Vec2::splat(10.),
+                                        egui::Sense::hover(),
+                                    );
+                                    ui.painter().rect_filled(rect, 2.0, color);
+
+                                    with_full_name(
+                                        ui.label(format!(
+                                            "{}{}",
+                                            truncate_middle(&v.title, NAME_MAX_CHARS),
+                                            summary_text(v, &total, compare, format)
+                                        )),
+                                        &v.title,
+                                    );
+                                });
+                                ui.add_space(4.);
+                            }
+                        });
+                    });
+                });
+            });
+        });
+
+        ui.separator();
+
+        if self.statistic_options.view == StatisticView::Heatmap {
+            self.build_statistic_heatmap(ui);
+
+            ui.set_style(style);
+
+            return;
+        }
+
+        if self.statistic_options.view == StatisticView::Billing {
+            self.build_statistic_billing(ui);
+
+            ui.set_style(style);
+
+            return;
+        }
+
+        if self.statistic_options.view == StatisticView::Breakdown {
+            self.build_statistic_breakdown(ui);
+
+            ui.set_style(style);
+
+            return;
+        }
+
+        if self.statistic_options.view != StatisticView::Day {
+            self.build_statistic_periods(ui);
+
+            ui.set_style(style);
+
+            return;
+        }
+
+        let show_gaps = self.statistic_options.show_gaps;
+
+        // Leaves room for the tracked and untracked time next to the dates.
+        let date_width = if show_gaps { 130. } else { 50. };
+
+        // Stretches a day over the whole timeline once the window is wider than a day.
+        let minute_width = ((ui.available_width() - date_width - 10. - CUMULATIVE_COLUMN_WIDTH)
+            / (24. * 60.))
+            .max(TIMELINE_MINUTE_WIDTH)
+            * self.statistic_options.zoom;
+        let hour_width = minute_width * 60.;
+        let day_width = hour_width * 24.;
+
+        self.statistic_options.minute_width = minute_width;
+
+        ui.push_id(7, |ui| {
+            let time_block = ScrollArea::horizontal()
+                .scroll_bar_visibility(ScrollBarVisibility::AlwaysHidden)
+                .scroll_offset(Vec2::new(self.statistic_options.scroll_offset_x, 0.));
+
+            time_block.show(ui, |ui| {
+                let (rect, _) = ui.allocate_exact_size(
+                    Vec2::new(date_width + day_width + 20., 28.),
+                    egui::Sense::hover(),
+                );
+
+                let text_color = ui.visuals().text_color();
+                let tick_color = ui.visuals().weak_text_color();
+
+                // Zoomed out, only every few hours is labeled so the labels do not overlap.
+                let label_every = (30. / hour_width).ceil().max(1.) as u32;
+
+                for i in 0..=24_u32 {
+                    let x = rect.left() + date_width + i as f32 * hour_width;
+
+                    if i % label_every == 0 {
+                        ui.painter().text(
+                            egui::pos2(x, rect.top()),
+                            egui::Align2::LEFT_TOP,
+                            format_number(i),
+                            FontId::proportional(12.0),
+                            text_color,
+                        );
+                    }
+
+                    ui.painter().add(RectShape {
+                        rect: Rect::from_min_size(
+                            egui::pos2(x, rect.bottom() - 10.),
+                            Vec2::new(2., 10.),
+                        ),
+                        rounding: Rounding::same(1.0),
+                        fill: tick_color,
+                        stroke: Default::default(),
+                    });
+                }
+            });
+        });
+
+        let days = days_in_range(self.statistic_options.from, self.statistic_options.to);
+
+        let gaps: Vec<_> = days
+            .iter()
+            .enumerate()
+            .map(|(i, day)| {
+                if !show_gaps {
+                    return vec![];
+                }
+
+                let day_records = records.get(i).map_or(&[][..], |v| v.as_slice());
+
+                untracked_gaps(day_records, self.work_window(*day))
+            })
+            .collect();
+
+        let timeline_height = (ui.available_height() - 5.).max(100.);
+
+        ui.horizontal(|ui| {
+            ui.set_min_height(timeline_height);
+            ui.set_max_height(timeline_height);
+
+            ui.push_id(5, |ui| {
+                ui.set_min_width(date_width);
+                ui.set_max_width(date_width);
+
+                let date_block = ScrollArea::vertical()
+                    .scroll_bar_visibility(ScrollBarVisibility::AlwaysHidden)
+                    .scroll_offset(Vec2::new(0., self.statistic_options.scroll_offset_y));
+
+                date_block.show(ui, |ui| {
+                    ui.vertical(|ui| {
+                        for (i, day) in days.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.set_min_height(25.);
+                                ui.set_max_height(25.);
+
+                                ui.label(
+                                    RichText::new(format!(
+                                        "{}/{}",
+                                        format_number(day.day()),
+                                        format_number(day.month())
+                                    ))
+                                    .font(FontId::proportional(13.0)),
+                                );
+
+                                if !show_gaps {
+                                    return;
+                                }
+
+                                let (start, end) = self.work_window(*day);
+                                let window = end
+                                    .signed_duration_since(start)
+                                    .max(chrono::Duration::zero());
+                                let untracked =
+                                    gaps[i].iter().fold(chrono::Duration::zero(), |s, v| {
+                                        s.add(v.1.signed_duration_since(v.0))
+                                    });
+
+                                ui.label(
+                                    RichText::new(format!(
+                                        " {} / {}",
+                                        format_chrono_duration(window.sub(untracked), format),
+                                        format_chrono_duration(untracked, format)
+                                    ))
+                                    .font(FontId::proportional(11.0))
+                                    .weak(),
+                                )
+                                .on_hover_text(tr!("Tracked / untracked within the working hours"));
+                            });
+                        }
+                    });
+                });
+            });
+
+            ui.push_id(6, |ui| {
+                let mut bars_area = ScrollArea::both()
+                    .drag_to_scroll(false)
+                    .max_width(ui.available_width() - CUMULATIVE_COLUMN_WIDTH - 10.);
+
+                if std::mem::take(&mut self.statistic_options.zoom_scrolled) {
+                    bars_area = bars_area.scroll_offset(Vec2::new(
+                        self.statistic_options.scroll_offset_x,
+                        self.statistic_options.scroll_offset_y,
+                    ));
+                }
+
+                let bars_block = bars_area.show(ui, |ui| {
+                    ui.set_min_size(Vec2::new(
+                        day_width,
+                        (timeline_height - 5.).max(25. * days.len() as f32),
+                    ));
+
+                    ui.vertical(|ui| {
+                        for (i, day) in days.iter().enumerate() {
+                            let mut previous_ending = None;
+                            let mut space_added = false;
+                            let mut length = 0_f32;
+
+                            let day_records = records.get(i).map_or(&[][..], |v| v.as_slice());
+
+                            // Bars of the row in order, for moving the focus with the arrows.
+                            let mut bar_ids = Vec::new();
+
+                            let row = ui.horizontal(|ui| {
+                                ui.set_min_height(25.);
+                                ui.set_max_height(25.);
+
+                                let row_left = ui.cursor().left();
+
+                                for group in group_adjacent_records(
+                                    day_records,
+                                    chrono::Duration::minutes(
+                                        self.statistic_options.coalesce_gap_minutes,
+                                    ),
+                                ) {
+                                    let record = *group.head();
+
+                                    if !space_added {
+                                        let d = (record.start_date.hour() as f32 * 60.0
+                                            + record.start_date.minute() as f32)
+                                            * minute_width;
+                                        ui.add_space(d);
+                                        length += d;
+
+                                        space_added = true;
+                                    }
+
+                                    let duration = group.get_duration();
+
+                                    let in_progress = group
+                                        .records
+                                        .iter()
+                                        .any(|v| Some(v.id) == self.backend.active_session_id());
+
+                                    // A session started a moment ago still gets a sliver to grow from.
+                                    if duration.num_minutes() <= 0 && !in_progress {
+                                        continue;
+                                    }
+
+                                    if let Some(prev) = previous_ending {
+                                        let dur = group
+                                            .start_date()
+                                            .signed_duration_since(prev)
+                                            .num_minutes();
+
+                                        if dur > 0 {
+                                            ui.add_space(dur as f32 * minute_width);
+                                            length += dur as f32 * minute_width;
+                                        }
+                                    }
+
+                                    let desired_size = egui::vec2(
+                                        (duration.num_minutes() as f32 * minute_width).max(2.),
+                                        15.0,
+                                    );
+
+                                    length += desired_size.x;
+
+                                    let (rect, response) =
+                                        ui.allocate_exact_size(desired_size, egui::Sense::click());
+
+                                    let path = self.backend.get_record_path(&record);
+
+                                    let mut hover_text = format!(
+                                        "{}/{}/{}",
+                                        path.project, path.sub_project, path.subject
+                                    );
+
+                                    for v in &group.records {
+                                        hover_text += &format!(
+                                            "\n{} - {}, {}",
+                                            v.start_date.format("%H:%M"),
+                                            v.end_date.format("%H:%M"),
+                                            format_chrono_duration(v.get_duration(), format)
+                                        );
+                                    }
+
+                                    if group.records.len() > 1 {
+                                        hover_text += &format!(
+                                            "\n{} {}",
+                                            tr!("Total"),
+                                            format_chrono_duration(duration, format)
+                                        );
+                                    }
+
+                                    if in_progress {
+                                        hover_text += &format!("\n{}", tr!("In progress"));
+                                    }
+
+                                    hover_text += "\nClick for details";
+
+                                    let response = response.on_hover_text(hover_text);
+
+                                    bar_ids.push(response.id);
+
+                                    if response.gained_focus() {
+                                        response.scroll_to_me(None);
+                                    }
+
+                                    if response.has_focus() {
+                                        ui.painter().rect_stroke(
+                                            rect.expand(2.),
+                                            Rounding::same(5.0),
+                                            Stroke::new(2.0, ui.visuals().strong_text_color()),
+                                        );
+                                    }
+
+                                    if response.clicked() {
+                                        self.open_inspect_records_dialog(
+                                            group.records.iter().map(|v| v.id).collect(),
+                                        );
+                                    }
+
+                                    let fill = visible_color(ui, path.color);
+
+                                    // The running session is outlined and lighter, so it reads as
+                                    // not final yet.
+                                    ui.painter().add(RectShape {
+                                        rect,
+                                        rounding: Rounding::same(4.0),
+                                        fill: if in_progress {
+                                            fill.gamma_multiply(0.5)
+                                        } else {
+                                            fill
+                                        },
+                                        stroke: if in_progress {
+                                            Stroke::new(1.5, fill)
+                                        } else {
+                                            Stroke::NONE
+                                        },
+                                    });
+
+                                    for v in group.records.iter().skip(1) {
+                                        let x = rect.left()
+                                            + v.start_date
+                                                .signed_duration_since(group.start_date())
+                                                .num_minutes()
+                                                as f32
+                                                * minute_width;
+
+                                        ui.painter().vline(
+                                            x,
+                                            rect.shrink(3.0).y_range(),
+                                            Stroke::new(1.0, fill.linear_multiply(0.4)),
+                                        );
+                                    }
+
+                                    self.statistic_bar_handles(
+                                        ui,
+                                        rect,
+                                        &group,
+                                        *day,
+                                        row_left,
+                                        day_records,
+                                    );
+
+                                    previous_ending = Some(group.end_date());
+                                }
+
+                                if length < day_width {
+                                    ui.add_space(day_width - length);
+                                }
+                            });
+
+                            for (start, end) in &gaps[i] {
+                                let x = |v: &DateTime<Local>| {
+                                    row.response.rect.left()
+                                        + v.signed_duration_since(start_of_day(*day)).num_minutes()
+                                            as f32
+                                            * minute_width
+                                };
+
+                                let rect = Rect::from_x_y_ranges(
+                                    x(start)..=x(end),
+                                    row.response.rect.center().y - 7.5
+                                        ..=row.response.rect.center().y + 7.5,
+                                );
+
+                                paint_hatched(ui, rect);
+                            }
+
+                            // The same day of the compared range, as a muted line under the bars.
+                            let compare_day = compare_records.as_ref().and_then(|v| v.get(i));
+
+                            for record in compare_day.into_iter().flatten() {
+                                let day_start = start_of_day(record.start_date.date_naive());
+                                let x = |v: DateTime<Local>| {
+                                    row.response.rect.left()
+                                        + v.signed_duration_since(day_start).num_minutes() as f32
+                                            * minute_width
+                                };
+
+                                let color = self.backend.get_record_path(record).color;
+                                let y = row.response.rect.center().y + 9.;
+
+                                ui.painter().rect_filled(
+                                    Rect::from_x_y_ranges(
+                                        x(record.start_date)
+                                            ..=x(record.end_date).max(x(record.start_date) + 1.),
+                                        y..=y + 2.,
+                                    ),
+                                    1.0,
+                                    visible_color(ui, color).gamma_multiply(0.5),
+                                );
+                            }
+
+                            let focused = bar_ids
+                                .iter()
+                                .position(|id| ui.memory(|m| m.has_focus(*id)));
+
+                            if let Some(focused) = focused {
+                                let target = ui.input(|i| {
+                                    if i.key_pressed(Key::ArrowLeft) {
+                                        focused.checked_sub(1)
+                                    } else if i.key_pressed(Key::ArrowRight) {
+                                        Some(focused + 1)
+                                    } else {
+                                        None
+                                    }
+                                });
+
+                                if let Some(id) = target.and_then(|v| bar_ids.get(v)) {
+                                    ui.memory_mut(|m| m.request_focus(*id));
+                                }
+                            }
+                        }
+                    });
+                });
+
+                self.statistic_options.scroll_offset_x = bars_block.state.offset.x;
+                self.statistic_options.scroll_offset_y = bars_block.state.offset.y;
+
+                let zoom_delta = ui.input(|i| i.zoom_delta());
+
+                if zoom_delta != 1. && ui.rect_contains_pointer(bars_block.inner_rect) {
+                    let anchor = ui
+                        .input(|i| i.pointer.hover_pos())
+                        .map(|v| v.x - bars_block.inner_rect.left());
+
+                    self.statistic_options
+                        .set_zoom(self.statistic_options.zoom * zoom_delta, anchor);
+                }
+            });
+
+            ui.add_space(10.);
+
+            ui.push_id(11, |ui| {
+                self.build_statistic_cumulative(ui, &records, days.len());
+            });
+        });
+
+        ui.set_style(style);
+    }
+
+    /// Working hours of `day` from the settings, ending now at the latest.
+    fn work_window(&self, day: NaiveDate) -> (DateTime<Local>, DateTime<Local>) {
+        let settings = &self.backend.settings;
+        let now = DateTime::<Local>::from(SystemTime::now());
+
+        let start = start_of_day(day).add(chrono::Duration::minutes(
+            settings.work_day_start_minutes as i64,
+        ));
+        let end = start_of_day(day)
+            .add(chrono::Duration::minutes(
+                settings.work_day_end_minutes as i64,
+            ))
+            .min(now);
+
+        (start, end.max(start))
+    }
+
+    /// Running total of tracked time from the range start, one row per day next to the
+    /// timeline, with the monthly goal drawn as a vertical line when one is set.
+    fn build_statistic_cumulative(
+        &mut self,
+        ui: &mut Ui,
+        records: &[Vec<HistoryRecord>],
+        days_count: usize,
+    ) {
+        let format = self.backend.settings.duration_format;
+
+        ui.set_min_width(CUMULATIVE_COLUMN_WIDTH);
+        ui.set_max_width(CUMULATIVE_COLUMN_WIDTH);
+
+        let totals = cumulative_totals(records);
+
+        let goal = match self.backend.settings.monthly_goal_hours {
+            0 => None,
+            hours => Some(chrono::Duration::hours(hours as i64)),
+        };
+
+        let scale = cumulative_scale(&totals, goal);
+
+        let block = ScrollArea::vertical()
+            .scroll_bar_visibility(ScrollBarVisibility::AlwaysHidden)
+            .scroll_offset(Vec2::new(0., self.statistic_options.scroll_offset_y));
+
+        block.show(ui, |ui| {
+            let top = ui.cursor().top();
+
+            ui.vertical(|ui| {
+                for total in totals.iter().take(days_count) {
+                    ui.horizontal(|ui| {
+                        ui.set_min_height(25.);
+                        ui.set_max_height(25.);
+
+                        let (rect, _) = ui.allocate_exact_size(
+                            Vec2::new(CUMULATIVE_COLUMN_WIDTH, 25.),
+                            egui::Sense::hover(),
+                        );
+
+                        let line = Rect::from_min_size(
+                            rect.left_bottom() - Vec2::new(0., 4.),
+                            Vec2::new(
+                                cumulative_position(*total, scale) * CUMULATIVE_COLUMN_WIDTH,
+                                2.,
+                            ),
+                        );
+
+                        ui.painter()
+                            .rect_filled(line, 1.0, ui.visuals().text_color());
+
+                        ui.painter().text(
+                            rect.left_top(),
+                            egui::Align2::LEFT_TOP,
+                            format_chrono_duration(*total, format),
+                            FontId::proportional(12.0),
+                            ui.visuals().text_color(),
+                        );
+                    });
+                }
+            });
+
+            if let Some(goal) = goal {
+                let x = (cumulative_position(goal, scale) * CUMULATIVE_COLUMN_WIDTH)
+                    .min(CUMULATIVE_COLUMN_WIDTH - 1.);
+
+                ui.painter().vline(
+                    ui.min_rect().left() + x,
+                    top..=top + 25. * days_count as f32,
+                    Stroke::new(1.0, ui.visuals().warn_fg_color),
+                );
+            }
+        });
+    }
+}
+
+/// Seconds the width of the cumulative column stands for, the range total or the goal,
+/// whichever is larger, so both fit.
+fn cumulative_scale(totals: &[chrono::Duration], goal: Option<chrono::Duration>) -> f32 {
+    totals
+        .last()
+        .copied()
+        .unwrap_or_else(chrono::Duration::zero)
+        .max(goal.unwrap_or_else(chrono::Duration::zero))
+        .num_seconds()
+        .max(1) as f32
+}
+
+/// Where `value` lies across the cumulative column, 0 at its left edge and 1 at its right.
+fn cumulative_position(value: chrono::Duration, scale: f32) -> f32 {
+    (value.num_seconds() as f32 / scale).clamp(0., 1.)
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum BarEdge {
+    Start,
+    End,
+}
+
+/// A bar edge being dragged in the timeline, committed to the backend on release.
+struct BarDrag {
+    record_id: Uuid,
+    edge: BarEdge,
+    time: DateTime<Local>,
+}
+
+impl Frontend {
+    /// Resize handles on both ends of a timeline bar. Only edges that are real record boundaries
+    /// can be dragged, not the midnight cuts of records spanning several days.
+    fn statistic_bar_handles(
+        &mut self,
+        ui: &mut Ui,
+        rect: Rect,
+        group: &RecordGroup,
+        day: NaiveDate,
+        row_left: f32,
+        day_records: &[HistoryRecord],
+    ) {
+        const HANDLE_WIDTH: f32 = 4.;
+        const SNAP_MINUTES: i64 = 3;
+
+        let active_session = self.backend.active_session_id();
+
+        let first = group.head();
+        let last = group.records.iter().max_by_key(|v| v.end_date).unwrap();
+
+        for (edge, shown, x) in [
+            (BarEdge::Start, first, rect.left()),
+            (BarEdge::End, last, rect.right()),
+        ] {
+            if active_session == Some(shown.id) {
+                continue;
+            }
+
+            let Some(stored) = self.backend.history.get(&shown.id).copied() else {
+                continue;
+            };
+
+            let is_real_edge = match edge {
+                BarEdge::Start => stored.start_date == shown.start_date,
+                BarEdge::End => stored.end_date == shown.end_date,
+            };
+
+            if !is_real_edge || rect.width() < HANDLE_WIDTH * 3. {
+                continue;
+            }
+
+            let handle = Rect::from_x_y_ranges(
+                match edge {
+                    BarEdge::Start => x..=x + HANDLE_WIDTH,
+                    BarEdge::End => x - HANDLE_WIDTH..=x,
+                },
+                rect.y_range(),
+            );
+
+            let response = ui.interact(
+                handle,
+                ui.id().with((stored.id, edge == BarEdge::Start)),
+                egui::Sense::drag(),
+            );
+
+            if response.hovered() || response.dragged() {
+                ui.ctx().set_cursor_icon(CursorIcon::ResizeHorizontal);
+            }
+
+            if response.drag_started() {
+                self.statistic_options.bar_drag = Some(BarDrag {
+                    record_id: stored.id,
+                    edge,
+                    time: match edge {
+                        BarEdge::Start => stored.start_date,
+                        BarEdge::End => stored.end_date,
+                    },
+                });
+            }
+
+            let Some(drag) = &mut self.statistic_options.bar_drag else {
+                continue;
+            };
+
+            if drag.record_id != stored.id || drag.edge != edge {
+                continue;
+            }
+
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                self.statistic_options.bar_drag = None;
+
+                continue;
+            }
+
+            if response.dragged() {
+                if let Some(pointer) = response.interact_pointer_pos() {
+                    let minute = ((pointer.x - row_left) / self.statistic_options.minute_width)
+                        .round()
+                        .clamp(0., 24. * 60.) as i64;
+
+                    let mut time = start_of_day(day).add(chrono::Duration::minutes(minute));
+
+                    for other in day_records.iter().filter(|v| v.id != stored.id) {
+                        for v in [other.start_date, other.end_date] {
+                            if v.signed_duration_since(time).num_minutes().abs() < SNAP_MINUTES {
+                                time = v;
+                            }
+                        }
+                    }
+
+                    drag.time = time;
+
+                    ui.painter().vline(
+                        row_left
+                            + time.signed_duration_since(start_of_day(day)).num_minutes() as f32
+                                * self.statistic_options.minute_width,
+                        rect.expand(4.).y_range(),
+                        Stroke::new(2.0, ui.visuals().strong_text_color()),
+                    );
+
+                    egui::show_tooltip_at_pointer(ui.ctx(), response.id.with("time"), |ui| {
+                        ui.label(time.format("%H:%M").to_string());
+                    });
+                }
+            }
+
+            if response.drag_released() {
+                let (start, end) = match edge {
+                    BarEdge::Start => (drag.time, stored.end_date),
+                    BarEdge::End => (stored.start_date, drag.time),
+                };
+
+                self.statistic_options.bar_drag = None;
+
+                if start < end {
+                    self.backend.set_record_times(stored.id, start, end);
+                }
+            }
+        }
+    }
+
+    fn statistic_breakdown(&mut self) -> Rc<Breakdown> {
+        let range = (self.statistic_options.from, self.statistic_options.to);
+        let revision = self.backend.history.revision();
+
+        if let Some(cache) = &self.statistic_options.breakdown_cache {
+            if cache.range == range && cache.revision == revision {
+                return cache.breakdown.clone();
+            }
+        }
+
+        let breakdown = Rc::new(self.backend.history.breakdown(range));
+
+        self.statistic_options.breakdown_cache = Some(BreakdownCache {
+            range,
+            revision,
+            breakdown: breakdown.clone(),
+        });
+
+        breakdown
+    }
+
+    fn build_statistic_breakdown(&mut self, ui: &mut Ui) {
+        let breakdown = self.statistic_breakdown();
+
+        ui.add_space(5.);
+        ui.checkbox(
+            &mut self.statistic_options.breakdown_stacked,
+            tr!("Split by project"),
+        );
+        ui.add_space(10.);
+
+        let height = ((ui.available_height() - 80.) / 2.).max(60.);
+
+        // Rotated so the week starts on the configured day.
+        let first = self.backend.settings.first_day_of_week;
+        let weekdays: Vec<_> = (0..7)
+            .map(|i| first.num_days_from_monday() as usize + i)
+            .collect();
+
+        ui.label(tr!("Per weekday"));
+        self.breakdown_chart(
+            ui,
+            weekdays
+                .iter()
+                .map(|i| {
+                    let day = Weekday::try_from((i % 7) as u8).unwrap();
+                    (day.to_string(), &breakdown.weekdays[i % 7])
+                })
+                .collect(),
+            height,
+        );
+
+        ui.add_space(10.);
+
+        ui.label(tr!("Per hour of the day"));
+        self.breakdown_chart(
+            ui,
+            breakdown
+                .hours
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (format_number(i as u32), v))
+                .collect(),
+            height,
+        );
+    }
+
+    /// Vertical bars of tracked time drawn like the timeline, one per labeled bucket.
+    fn breakdown_chart(
+        &self,
+        ui: &mut Ui,
+        buckets: Vec<(String, &HashMap<Uuid, chrono::Duration>)>,
+        height: f32,
+    ) {
+        let format = self.backend.settings.duration_format;
+
+        const LABEL_HEIGHT: f32 = 16.;
+
+        let (rect, _) = ui.allocate_exact_size(
+            Vec2::new(ui.available_width() - 10., height + LABEL_HEIGHT),
+            egui::Sense::hover(),
+        );
+
+        let total = |v: &HashMap<Uuid, chrono::Duration>| {
+            v.values().fold(chrono::Duration::zero(), |s, v| s.add(*v))
+        };
+
+        let max = buckets
+            .iter()
+            .map(|v| total(v.1))
+            .max()
+            .unwrap_or_else(chrono::Duration::zero)
+            .num_seconds()
+            .max(1) as f32;
+
+        let slot_width = rect.width() / buckets.len().max(1) as f32;
+        let uniform = ui.visuals().selection.bg_fill;
+
+        for (i, (label, bucket)) in buckets.iter().enumerate() {
+            let slot = Rect::from_min_size(
+                rect.left_top() + Vec2::new(slot_width * i as f32, 0.),
+                Vec2::new(slot_width, height),
+            );
+
+            let mut projects: Vec<_> = bucket.iter().collect();
+            projects.sort_by_key(|v| *v.0);
+
+            let mut bottom = slot.bottom();
+            let mut hover_text = format!(
+                "{label} - {}",
+                format_chrono_duration(total(bucket), format)
+            );
+
+            for (project_id, duration) in projects {
+                let (name, color) = match self.backend.projects.inner.get(project_id) {
+                    Some(project) => (project.name.clone(), project.color),
+                    None => (tr!("<deleted project>").to_string(), MISSING_COLOR),
+                };
+
+                hover_text += &format!("\n{name} - {}", format_chrono_duration(*duration, format));
+
+                let bar_height = duration.num_seconds() as f32 / max * height;
+
+                let bar = Rect::from_x_y_ranges(
+                    slot.center().x - slot_width * 0.35..=slot.center().x + slot_width * 0.35,
+                    bottom - bar_height..=bottom,
+                );
+
+                ui.painter().add(RectShape {
+                    rect: bar,
+                    rounding: Rounding::none(),
+                    fill: if self.statistic_options.breakdown_stacked {
+                        visible_color(ui, color)
+                    } else {
+                        uniform
+                    },
+                    stroke: Default::default(),
+                });
+
+                bottom -= bar_height;
+            }
+
+            ui.painter().text(
+                egui::pos2(slot.center().x, slot.bottom() + 2.),
+                egui::Align2::CENTER_TOP,
+                label,
+                FontId::proportional(12.0),
+                ui.visuals().text_color(),
+            );
+
+            ui.interact(
+                slot,
+                ui.id().with(("breakdown", label)),
+                egui::Sense::hover(),
+            )
+            .on_hover_text(hover_text);
+        }
+    }
+
+    fn build_statistic_billing(&mut self, ui: &mut Ui) {
+        let format = self.backend.settings.duration_format;
+
+        let options = &mut self.statistic_options;
+
+        ui.add_space(5.);
+
+        ui.horizontal(|ui| {
+            ui.label(tr!("Round each session"));
+            ui.add_space(10.);
+
+            for rounding in BillingRounding::ALL {
+                ui.selectable_value(&mut options.billing_rounding, rounding, rounding.label());
+                ui.add_space(4.);
+            }
+        });
+
+        ui.add_space(10.);
+
+        let billing = self
+            .backend
+            .billing((options.from, options.to), options.billing_rounding);
+
+        if billing.lines.is_empty() {
+            ui.label(tr!("Nothing tracked in this range"));
+            return;
+        }
+
+        ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("billing")
+                .num_columns(4)
+                .spacing((30., 6.))
+                .striped(true)
+                .show(ui, |ui| {
+                    for title in [tr!("Project"), tr!("Time"), tr!("Rate"), tr!("Amount")] {
+                        ui.label(RichText::new(title).strong());
+                    }
+                    ui.end_row();
+
+                    for line in &billing.lines {
+                        with_full_name(ui.label(name_text(&line.name)), &line.name);
+                        ui.label(format_chrono_duration(line.duration, format));
+
+                        match (line.rate, line.amount) {
+                            (Some(rate), Some(amount)) => {
+                                ui.label(format!("{rate:.2} {}/h", line.currency));
+                                ui.label(format!("{amount:.2} {}", line.currency));
+                            }
+                            _ => {
+                                ui.weak(tr!("no rate"));
+                                ui.weak("-");
+                            }
+                        }
+                        ui.end_row();
+                    }
+
+                    for (currency, total) in &billing.totals {
+                        ui.label(RichText::new(tr!("Total")).strong());
+                        ui.label("");
+                        ui.label("");
+                        ui.label(RichText::new(format!("{total:.2} {currency}")).strong());
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    /// First day of the heatmap, the start of the week a year before `today`.
+    fn heatmap_first_day(&self, today: NaiveDate) -> NaiveDate {
+        start_of_week(
+            today - chrono::Duration::days(364),
+            self.backend.settings.first_day_of_week,
+        )
+    }
+
+    fn statistic_heatmap(&mut self, today: NaiveDate) -> Rc<Vec<chrono::Duration>> {
+        let revision = self.backend.history.revision();
+
+        if let Some(cache) = &self.statistic_options.heatmap_cache {
+            if cache.today == today && cache.revision == revision {
+                return cache.totals.clone();
+            }
+        }
+
+        let totals = Rc::new(
+            self.backend
+                .history
+                .daily_totals(self.heatmap_first_day(today), today),
+        );
+
+        self.statistic_options.heatmap_cache = Some(HeatmapCache {
+            today,
+            revision,
+            totals: totals.clone(),
+        });
+
+        totals
+    }
+
+    /// One square per day of the last year in weekly columns, darker for more tracked time.
+    /// Clicking a day shows it in the Day view.
+    fn build_statistic_heatmap(&mut self, ui: &mut Ui) {
+        let format = self.backend.settings.duration_format;
+
+        const GAP: f32 = 3.;
+        const LEVELS: i64 = 4;
+
+        let today = DateTime::<Local>::from(SystemTime::now()).date_naive();
+        let first_day = self.heatmap_first_day(today);
+        let totals = self.statistic_heatmap(today);
+
+        let max = totals
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or_else(chrono::Duration::zero)
+            .num_minutes()
+            .max(1);
+
+        let weeks = totals.len().div_ceil(7);
+
+        // Squares shrink to keep the whole year visible in a narrow window.
+        let step = ((ui.available_width() - 50.) / weeks as f32).clamp(6., 15.);
+        let cell_size = step - GAP;
+
+        ui.add_space(10.);
+
+        let (rect, _) = ui.allocate_exact_size(
+            Vec2::new(40. + weeks as f32 * step, 7. * step + 20.),
+            egui::Sense::hover(),
+        );
+
+        let text_color = ui.visuals().text_color();
+        let empty = ui.visuals().faint_bg_color;
+        let filled = ui.visuals().selection.bg_fill;
+
+        for (i, day) in first_day.iter_days().take(7).enumerate() {
+            if i % 2 == 0 {
+                ui.painter().text(
+                    egui::pos2(rect.left(), rect.top() + 20. + i as f32 * step),
+                    egui::Align2::LEFT_TOP,
+                    day.weekday().to_string(),
+                    FontId::proportional(11.0),
+                    text_color,
+                );
+            }
+        }
+
+        for (i, (day, total)) in first_day.iter_days().zip(totals.iter()).enumerate() {
+            let (week, weekday) = (i / 7, i % 7);
+
+            let cell = Rect::from_min_size(
+                rect.left_top() + Vec2::new(40. + week as f32 * step, 20. + weekday as f32 * step),
+                Vec2::splat(cell_size),
+            );
+
+            if day.day() == 1 {
+                ui.painter().text(
+                    egui::pos2(cell.left(), rect.top()),
+                    egui::Align2::LEFT_TOP,
+                    month_short(day.month()),
+                    FontId::proportional(11.0),
+                    text_color,
+                );
+            }
+
+            let minutes = total.num_minutes();
+            let level = (minutes * LEVELS + max - 1) / max;
+
+            ui.painter().add(RectShape {
+                rect: cell,
+                rounding: Rounding::same(2.0),
+                fill: if level == 0 {
+                    empty
+                } else {
+                    filled.gamma_multiply(level as f32 / LEVELS as f32)
+                },
+                stroke: Default::default(),
+            });
+
+            let response = ui
+                .interact(cell, ui.id().with(("heatmap", day)), egui::Sense::click())
+                .on_hover_text(format!(
+                    "{} {} - {}",
+                    weekday_short(day.weekday()),
+                    day.format("%d/%m/%Y"),
+                    format_chrono_duration(*total, format)
+                ));
+
+            if response.clicked() {
+                self.statistic_options.set_range(day, day);
+                self.statistic_options.view = StatisticView::Day;
+            }
+        }
+    }
+
+    fn build_statistic_periods(&mut self, ui: &mut Ui) {
+        let format = self.backend.settings.duration_format;
+
+        // Leaves room for the period title and the totals next to the bars.
+        let bar_width = (ui.available_width() - 300.).max(200.);
+
+        let options = &self.statistic_options;
+
+        let mut buckets: Vec<PeriodBucket> = Vec::new();
+
+        let last = options.to.date_naive();
+        let mut period_start = match options.view {
+            StatisticView::Week => start_of_week(
+                options.from.date_naive(),
+                self.backend.settings.first_day_of_week,
+            ),
+            _ => start_of_month(options.from.date_naive()),
+        };
+
+        while period_start <= last {
+            let period_end = match options.view {
+                StatisticView::Week => period_start.checked_add_days(Days::new(7)),
+                _ => period_start.checked_add_months(Months::new(1)),
+            }
+            .unwrap();
+
+            let title = match options.view {
+                StatisticView::Week => tr!(
+                    "W{week} {date}",
+                    week = format_number(period_start.iso_week().week()),
+                    date = period_start.format("%d/%m")
+                ),
+                _ => format!(
+                    "{} {}",
+                    month_short(period_start.month()),
+                    period_start.year()
+                ),
+            };
+
+            let range = (
+                start_of_day(period_start).max(options.from),
+                start_of_day(period_end).min(options.to),
+            );
+
+            let mut per_project: HashMap<Uuid, chrono::Duration> = HashMap::new();
+
+            for record in self.backend.history.get_records(range) {
+                let v = per_project
+                    .entry(record.project_id)
+                    .or_insert_with(chrono::Duration::zero);
+                *v = v.add(record.get_duration());
+            }
+
+            let mut per_project: Vec<(Uuid, chrono::Duration)> = per_project.into_iter().collect();
+            per_project.sort_by_key(|v| v.0);
+
+            let days = range.1.signed_duration_since(range.0).num_days().max(1);
+
+            buckets.push(PeriodBucket {
+                title,
+                days,
+                per_project,
+            });
+
+            period_start = period_end;
+        }
+
+        let max = buckets
+            .iter()
+            .map(|v| {
+                v.per_project
+                    .iter()
+                    .fold(chrono::Duration::zero(), |s, v| s.add(v.1))
+            })
+            .max()
+            .unwrap_or_else(chrono::Duration::zero)
+            .num_seconds()
+            .max(1) as f32;
+
+        ui.push_id(8, |ui| {
+            ScrollArea::vertical()
+                .max_height(ui.available_height())
+                .show(ui, |ui| {
+                    for PeriodBucket {
+                        title,
+                        days,
+                        per_project,
+                    } in buckets
+                    {
+                        ui.horizontal(|ui| {
+                            ui.set_min_height(25.);
+
+                            ui.add_sized(
+                                (90., 20.),
+                                Label::new(RichText::new(title).font(FontId::proportional(13.0))),
+                            );
+
+                            let mut total = chrono::Duration::zero();
+                            let mut attained = Vec::new();
+
+                            for (project_id, duration) in per_project {
+                                total = total.add(duration);
+
+                                let width = duration.num_seconds() as f32 / max * bar_width;
+
+                                if width < 1. {
+                                    continue;
+                                }
+
+                                let (rect, response) = ui.allocate_exact_size(
+                                    Vec2::new(width, 15.),
+                                    egui::Sense::hover(),
+                                );
+
+                                let (name, color, goal) =
+                                    match self.backend.projects.inner.get(&project_id) {
+                                        Some(project) => {
+                                            (project.name.clone(), project.color, project.goal)
+                                        }
+                                        None => (
+                                            tr!("<deleted project>").to_string(),
+                                            MISSING_COLOR,
+                                            None,
+                                        ),
+                                    };
+
+                                let attainment = goal
+                                    .map(|v| v.for_days(days).num_seconds())
+                                    .filter(|v| *v > 0)
+                                    .map(|v| {
+                                        let percent = duration.num_seconds() * 100 / v;
+                                        attained.push((name.clone(), percent));
+
+                                        tr!(", {percent}% of goal", percent = percent)
+                                    })
+                                    .unwrap_or_default();
+
+                                response.on_hover_text(format!(
+                                    "{} - {}{}",
+                                    name,
+                                    format_chrono_duration(duration, format),
+                                    attainment
+                                ));
+
+                                ui.painter().add(RectShape {
+                                    rect,
+                                    rounding: Rounding::same(2.0),
+                                    fill: visible_color(ui, color),
+                                    stroke: Default::default(),
+                                });
+                            }
+
+                            ui.add_space(6.);
+
+                            ui.label(
+                                RichText::new(format_chrono_duration(total, format))
+                                    .font(FontId::proportional(13.0)),
+                            );
+
+                            for (name, percent) in attained {
+                                ui.label(
+                                    RichText::new(format!(
+                                        "{} {percent}%",
+                                        truncate_middle(&name, 12)
+                                    ))
+                                    .font(FontId::proportional(11.0))
+                                    .weak(),
+                                );
+                            }
+                        });
+                    }
+                });
+        });
+    }
+}
+
+/**
+    Maximized Time Tracker block
+**/
+
+#[derive(Default)]
+struct TimeTrackerOptions {
+    current_label: String,
+    /// Projects whose goal was reached, by the start of the goal period.
+    goals_reached: HashSet<(Uuid, NaiveDate)>,
+    /// Refreshed at the start of every Time view frame.
+    today: TodayTimes,
+    show_archived: bool,
+    /// Start of the uninterrupted tracking stretch and the reminders sent during it.
+    stretch: Option<(Instant, u64)>,
+    summary: Option<DaySummary>,
+}
+
+/// Totals of the Time view header, valid while the day, week start and history revision
+/// match. A ticking session bumps the revision too.
+#[derive(Copy, Clone)]
+struct DaySummary {
+    today: NaiveDate,
+    first_day_of_week: Weekday,
+    revision: u64,
+    day: chrono::Duration,
+    week: chrono::Duration,
+    sessions: usize,
+}
+
+impl Frontend {
+    fn time_tracker_build(&mut self, ui: &mut Ui) {
+        let format = self.backend.settings.duration_format;
+
+        self.backend.refresh_totals();
+        self.time_tracker_options.today = self.backend.get_today_times();
+
+        ui.horizontal_top(|ui| {
+            let label = &self.time_tracker_options.current_label;
+            let shown = truncate_middle_to_width(
+                ui,
+                label,
+                TextStyle::Body.resolve(ui.style()),
+                ui.available_width() - 650.,
+            );
+
+            with_full_name(ui.label(tr!("Current work: {shown}", shown = shown)), label);
+
+            self.day_summary_build(ui);
+
+            self.build_menu(ui);
+        });
+
+        ui.horizontal(|ui| {
+            ui.set_min_height(55.0);
+            ui.set_max_height(55.0);
+
+            if self.backend.get_current_subject().is_some() {
+                match self.backend.working_mode {
+                    WorkingMode::Idle => {
+                        ui.vertical(|ui| {
+                            if ui
+                                .button(tr!("START"))
+                                .on_hover_text(self.start_pause_hint())
+                                .clicked()
+                            {
+                                self.time_tracker_start_subject()
+                            }
+
+                            if let Some(note) = self
+                                .backend
+                                .current_path()
+                                .and_then(|v| self.backend.last_session_note(v))
+                            {
+                                with_full_name(
+                                    ui.label(
+                                        RichText::new(truncate_middle(note, NAME_MAX_CHARS))
+                                            .small()
+                                            .weak(),
+                                    ),
+                                    note,
+                                );
+                            }
+                        });
+                    }
+                    WorkingMode::InProgress(_) => {
+                        if ui
+                            .button(tr!("PAUSE"))
+                            .on_hover_text(self.start_pause_hint())
+                            .clicked()
+                        {
+                            self.time_tracker_pause_subject();
+                        }
+                    }
+                }
+
+                ui.label(match self.backend.pomodoro {
+                    PomodoroState::Off => {
+                        format_duration(self.backend.current_session_duration, format)
+                    }
+                    PomodoroState::Work(_) => tr!("{time} left", time = self.session_time_text()),
+                    PomodoroState::Break(_) => {
+                        tr!("Break, {time} left", time = self.session_time_text())
+                    }
+                });
+            }
+
+            if ui.button(tr!("+ time")).clicked() {
+                self.open_manual_record_dialog();
+            }
+
+            let mut pomodoro = self.backend.settings.pomodoro;
+            if ui
+                .toggle_value(&mut pomodoro, tr!("Pomodoro"))
+                .on_hover_text(tr!(
+                    "{work} min of work followed by a {rest} min break",
+                    work = self.backend.settings.pomodoro_work_minutes,
+                    rest = self.backend.settings.pomodoro_break_minutes
+                ))
+                .changed()
+            {
+                self.backend.set_pomodoro(pomodoro);
+            }
+        });
+
+        ui.separator();
+
+        let top = ui.cursor().top();
+        self.recent_strip_build(ui);
+        let height = 290.0 - (ui.cursor().top() - top);
+
+        ui.horizontal(|ui| {
+            ui.set_min_height(height);
+            ui.set_max_height(height);
+
+            if self.search_results_build(ui, Tree::Time) {
+                return;
+            }
+
+            ui.push_id(1, |ui| {
+                ScrollArea::both().show(ui, |ui| {
+                    self.time_tracker_build_projects(ui);
+                });
+            });
+
+            ui.separator();
+
+            ui.push_id(2, |ui| {
+                ScrollArea::both().show(ui, |ui| {
+                    self.time_tracker_build_sub_projects(ui);
+                });
+            });
+
+            ui.separator();
+
+            ui.push_id(3, |ui| {
+                ScrollArea::both().show(ui, |ui| {
+                    self.time_tracker_build_subjects(ui);
+                });
+            });
+        });
+    }
+
+    fn day_summary(&mut self) -> DaySummary {
+        let today = Local::now().date_naive();
+        let first_day_of_week = self.backend.settings.first_day_of_week;
+        let revision = self.backend.history.revision();
+
+        if let Some(summary) = self.time_tracker_options.summary.filter(|v| {
+            v.today == today && v.first_day_of_week == first_day_of_week && v.revision == revision
+        }) {
+            return summary;
+        }
+
+        let total = |records: &[HistoryRecord]| {
+            records
+                .iter()
+                .fold(chrono::Duration::zero(), |s, v| s + v.get_duration())
+        };
+
+        let day = self.backend.history.get_records((
+            start_of_day(today),
+            start_of_day(today + chrono::Duration::days(1)),
+        ));
+
+        let week_start = start_of_week(today, first_day_of_week);
+        let week = self.backend.history.get_records((
+            start_of_day(week_start),
+            start_of_day(week_start + chrono::Duration::days(7)),
+        ));
+
+        let summary = DaySummary {
+            today,
+            first_day_of_week,
+            revision,
+            day: total(&day),
+            week: total(&week),
+            sessions: day.len(),
+        };
+
+        self.time_tracker_options.summary = Some(summary);
+
+        summary
+    }
+
+    /// Time tracked today and this week with the sessions of today, the week opens the
+    /// statistics of this week.
+    fn day_summary_build(&mut self, ui: &mut Ui) {
+        let format = self.backend.settings.duration_format;
+
+        let summary = self.day_summary();
+
+        let sessions = match summary.sessions {
+            1 => tr!("1 session").to_string(),
+            n => tr!("{n} sessions", n = n),
+        };
+
+        ui.separator();
+        ui.label(tr!(
+            "Today {time}, {sessions}",
+            time = format_chrono_duration(summary.day, format),
+            sessions = sessions
+        ));
+
+        if ui
+            .add(
+                egui::Button::new(tr!(
+                    "Week {time}",
+                    time = format_chrono_duration(summary.week, format)
+                ))
+                .frame(false),
+            )
+            .on_hover_text(tr!("Show this week in the statistics"))
+            .clicked()
+        {
+            self.set_display_mode(DisplayMode::Statistic);
+            self.statistic_options.apply_preset(
+                RangePreset::ThisWeek,
+                summary.today,
+                summary.first_day_of_week,
+            );
+        }
+    }
+
+    /// Last subjects tracked, a click picks one and starts tracking it.
+    fn recent_strip_build(&mut self, ui: &mut Ui) {
+        let recent = self.backend.recent_subjects(RECENT_SUBJECTS);
+
+        if recent.is_empty() {
+            return;
+        }
+
+        let current_id = self
+            .backend
+            .get_current_subject()
+            .map(|v| v.lock().unwrap().id);
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(tr!("Recent")).small().weak());
+
+            for (path, name, full_name) in recent {
+                let mut text = RichText::new(truncate_middle(&name, 16)).small();
+
+                if Some(path.id()) == current_id {
+                    text = text.strong();
+                }
+
+                if ui.button(text).on_hover_text(full_name).clicked() {
+                    self.start_entry(path);
+                }
+            }
+        });
+
+        ui.separator();
+    }
+
+    fn time_tracker_build_sub_projects(&mut self, ui: &mut Ui) {
+        ui.set_min_width(300.0);
+        ui.set_max_width(300.0);
+
+        let Some(current_project) = self.backend.get_current_project() else {
+            return;
+        };
+
+        let current_id = if let Some(cur_project) = self.backend.get_current_sub_project() {
+            cur_project.id
+        } else {
+            Uuid::new_v4()
+        };
+
+        let project_id = current_project.id;
+
+        let c = current_project
+            .get_inner_sorted(|a, b| a.created_at.cmp(&b.created_at))
+            .into_iter()
+            .map(PContainer::row)
+            .collect::<Vec<_>>();
+
+        ui.vertical(|ui| {
+            for sub_project in c {
+                if sub_project.is_deleted {
+                    continue;
+                }
+
+                ui.horizontal(|ui| {
+                    let mut text = name_text(&sub_project.name);
+
+                    if sub_project.id == current_id {
+                        text = text.strong();
+                    }
+
+                    let response = with_full_name(ui.button(text), &sub_project.name);
+
+                    if response.clicked() {
+                        self.backend.set_current_sub_project(Some(sub_project.id));
+                    }
+
+                    response.context_menu(|ui| {
+                        let path = EntryPath::SubProject(project_id, sub_project.id);
+                        self.entry_context_menu(ui, Tree::Time, path);
+                    });
+
+                    ui.label(
+                        self.column_time_text(
+                            self.time_tracker_options
+                                .today
+                                .sub_projects
+                                .get(&sub_project.id),
+                            self.backend
+                                .get_sub_project_time(&project_id, &sub_project.id)
+                                .unwrap_or_default(),
+                        ),
+                    );
+                });
+
+                ui.add_space(5.0);
+            }
+
+            if ui.button("   +   ").clicked() {
+                self.dialog_options.current_dialog = CurrentDialog::AddSubProject;
+            }
+        });
+    }
+
+    fn time_tracker_build_projects(&mut self, ui: &mut Ui) {
+        let format = self.backend.settings.duration_format;
+
+        ui.set_min_width(300.0);
+        ui.set_max_width(300.0);
+
+        let current_id = if let Some(cur_project) = self.backend.get_current_project() {
+            cur_project.id
+        } else {
+            Uuid::new_v4()
+        };
+
+        let c = self
+            .backend
+            .projects
+            .get_inner_sorted(|a, b| a.created_at.cmp(&b.created_at))
+            .into_iter()
+            .map(PContainer::row)
+            .collect::<Vec<_>>();
+
+        ui.vertical(|ui| {
+            for project in c {
+                if project.is_deleted
+                    || (project.is_archived && !self.time_tracker_options.show_archived)
+                {
+                    continue;
+                }
+
+                ui.horizontal(|ui| {
+                    let mut text = name_text(&project.name);
+
+                    if project.id == current_id {
+                        text = text.strong();
+                    }
+
+                    if project.is_archived {
+                        text = text.weak().italics();
+                    }
+
+                    let response = with_full_name(ui.button(text), &project.name);
+
+                    if response.clicked() && !project.is_archived {
+                        self.backend.set_current_project(Some(project.id));
+                    }
+
+                    response.context_menu(|ui| {
+                        if !project.is_archived {
+                            self.project_goal_editor(ui, project.id, project.goal);
+                            ui.separator();
+                        }
+
+                        self.entry_context_menu(ui, Tree::Time, EntryPath::Project(project.id));
+                    });
+
+                    ui.label(
+                        self.column_time_text(
+                            self.time_tracker_options.today.projects.get(&project.id),
+                            self.backend
+                                .get_project_time(&project.id)
+                                .unwrap_or_default(),
+                        ),
+                    );
+                });
+
+                if let Some(goal) = project.goal {
+                    let done = self
+                        .backend
+                        .get_project_period_time(&project.id, goal.period);
+
+                    let progress = done.num_seconds() as f32 / (goal.minutes.max(1) as f32 * 60.);
+
+                    let (rect, response) =
+                        ui.allocate_exact_size(Vec2::new(280., 3.), egui::Sense::hover());
+
+                    ui.painter()
+                        .rect_filled(rect, 1.0, ui.visuals().extreme_bg_color);
+                    ui.painter().rect_filled(
+                        Rect::from_min_size(
+                            rect.min,
+                            Vec2::new(rect.width() * progress.clamp(0., 1.), rect.height()),
+                        ),
+                        1.0,
+                        with_contrast(
+                            Color32::from_rgb(project.color.0, project.color.1, project.color.2),
+                            ui.visuals().extreme_bg_color,
+                        ),
+                    );
+
+                    let done = format_chrono_duration(done, format);
+                    let goal_time = format_chrono_duration(
+                        chrono::Duration::minutes(goal.minutes as i64),
+                        format,
+                    );
+
+                    response.on_hover_text(match goal.period {
+                        GoalPeriod::Day => {
+                            tr!("{done} of {goal} this day", done = done, goal = goal_time)
+                        }
+                        GoalPeriod::Week => {
+                            tr!("{done} of {goal} this week", done = done, goal = goal_time)
+                        }
+                    });
+                }
+
+                ui.add_space(5.0);
+            }
+
+            if ui.button("   +   ").clicked() {
+                self.dialog_options.current_dialog = CurrentDialog::AddProject;
+            }
+
+            ui.checkbox(
+                &mut self.time_tracker_options.show_archived,
+                tr!("Show archived"),
+            );
+        });
+    }
+
+    fn project_goal_editor(&mut self, ui: &mut Ui, project_id: Uuid, goal: Option<Goal>) {
+        let mut enabled = goal.is_some();
+        let mut goal = goal.unwrap_or(Goal {
+            period: GoalPeriod::Day,
+            minutes: 4 * 60,
+        });
+        let mut hours = goal.minutes as f32 / 60.;
+        let mut changed = false;
+
+        changed |= ui.checkbox(&mut enabled, tr!("Goal")).changed();
+
+        ui.add_enabled_ui(enabled, |ui| {
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut hours)
+                            .speed(0.25)
+                            .clamp_range(0.25..=168.)
+                            .suffix(tr!(" h")),
+                    )
+                    .changed();
+
+                for period in [GoalPeriod::Day, GoalPeriod::Week] {
+                    changed |= ui
+                        .selectable_value(
+                            &mut goal.period,
+                            period,
+                            translate(&format!("per {period:?}")),
+                        )
+                        .changed();
+                }
+            });
+        });
+
+        if changed {
+            goal.minutes = (hours * 60.).round() as u32;
+
+            self.backend
+                .set_project_goal(&project_id, enabled.then_some(goal));
+        }
+    }
+
+    /// Notifies once per period when the tracked project reaches its goal.
+    fn check_goal_reached(&mut self) {
+        let format = self.backend.settings.duration_format;
+
+        let WorkingMode::InProgress(_) = self.backend.working_mode else {
+            return;
+        };
+
+        let Some(project) = self.backend.get_current_project() else {
+            return;
+        };
+
+        let Some(goal) = project.goal else {
+            return;
+        };
+
+        let today = Local::now().date_naive();
+        let period_start = match goal.period {
+            GoalPeriod::Day => today,
+            GoalPeriod::Week => start_of_week(today, self.backend.settings.first_day_of_week),
+        };
+
+        if self
+            .time_tracker_options
+            .goals_reached
+            .contains(&(project.id, period_start))
+        {
+            return;
+        }
+
+        let done = self
+            .backend
+            .get_project_period_time(&project.id, goal.period);
+
+        if done < chrono::Duration::minutes(goal.minutes as i64) {
+            return;
+        }
+
+        if !self.backend.settings.notify_goal_reached {
+            return;
+        }
+
+        notify(
+            tr!("Goal reached"),
+            &tr!(
+                "{name}: {time} tracked",
+                name = project.name,
+                time = format_chrono_duration(done, format)
+            ),
+        );
+
+        self.time_tracker_options
+            .goals_reached
+            .insert((project.id, period_start));
+    }
+
+    /// Reminds to take a break every few minutes of uninterrupted tracking.
+    fn check_stretch_reminder(&mut self) {
+        let format = self.backend.settings.duration_format;
+
+        let WorkingMode::InProgress(_) = self.backend.working_mode else {
+            self.time_tracker_options.stretch = None;
+            return;
+        };
+
+        let (since, reminded) = self
+            .time_tracker_options
+            .stretch
+            .get_or_insert_with(|| (Instant::now(), 0));
+
+        let minutes = self.backend.settings.stretch_reminder_minutes as u64;
+
+        if minutes == 0 {
+            return;
+        }
+
+        let due = since.elapsed().as_secs() / (minutes * 60);
+
+        if due > *reminded {
+            *reminded = due;
+
+            notify(
+                tr!("Time to stretch"),
+                &tr!(
+                    "Tracking for {time}",
+                    time = format_duration(since.elapsed(), format)
+                ),
+            );
+        }
+    }
+
+    fn time_tracker_build_subjects(&mut self, ui: &mut Ui) {
+        ui.set_min_width(300.0);
+        ui.set_max_width(300.0);
+
+        self.pinned_subjects_build(ui);
+
+        let Some(current_sub_project) = self.backend.get_current_sub_project() else {
+            return;
+        };
+
+        let Some(project_id) = self.backend.get_current_project().map(|v| v.id) else {
+            return;
+        };
+        let sub_project_id = current_sub_project.id;
+
+        let current_id = if let Some(cur_subject) = self.backend.get_current_subject() {
+            cur_subject.lock().unwrap().id
+        } else {
+            Uuid::new_v4()
+        };
+
+        let c = current_sub_project
+            .get_inner_sorted(|a, b| {
+                a.lock()
+                    .unwrap()
+                    .created_at
+                    .cmp(&b.lock().unwrap().created_at)
+            })
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        ui.vertical(|ui| {
+            for subject in c {
+                // Copied out, the context menu locks the subject itself.
+                let (id, name, duration, link) = {
+                    let subject = subject.lock().unwrap();
+
+                    if subject.is_deleted {
+                        continue;
+                    }
+
+                    (
+                        subject.id,
+                        subject.name.clone(),
+                        subject.duration,
+                        subject.link.clone(),
+                    )
+                };
+
+                ui.horizontal(|ui| {
+                    let mut text = name_text(&name);
+
+                    if id == current_id {
+                        text = text.strong();
+                    }
+
+                    let path = EntryPath::Subject(project_id, sub_project_id, id);
+                    let mut response = with_full_name(ui.button(text), &name);
+
+                    if let Some(note) = self.backend.last_session_note(path) {
+                        response = response.on_hover_text(tr!("Last note: {note}", note = note));
+                    }
+
+                    if response.clicked() {
+                        if current_id != id {
+                            self.time_tracker_stop_subject(true);
+                        }
+                        self.backend.set_current_subject(Some(id));
+                    }
+
+                    response.context_menu(|ui| {
+                        self.entry_context_menu(ui, Tree::Time, path);
+                    });
+
+                    if let Some(link) = &link {
+                        link_button(ui, link);
+                    }
+
+                    ui.label(self.column_time_text(
+                        self.time_tracker_options.today.subjects.get(&id),
+                        duration,
+                    ));
                 });
 
                 ui.add_space(5.0);
@@ -1264,15 +7479,147 @@ impl Frontend {
         });
     }
 
+    /// Pinned subjects above the subjects column, each with a button starting it right away.
+    fn pinned_subjects_build(&mut self, ui: &mut Ui) {
+        let pinned = self.backend.pinned_subjects();
+
+        if pinned.is_empty() {
+            return;
+        }
+
+        let current_id = self
+            .backend
+            .get_current_subject()
+            .map(|v| v.lock().unwrap().id);
+
+        ui.label(RichText::new(tr!("Pinned")).small().weak());
+
+        for (path, name, parent) in pinned {
+            ui.horizontal(|ui| {
+                if ui.small_button("▶").on_hover_text(tr!("Start")).clicked() {
+                    self.start_entry(path);
+                }
+
+                let mut text = name_text(&name);
+
+                if Some(path.id()) == current_id {
+                    text = text.strong();
+                }
+
+                let response = with_full_name(ui.button(text), &name);
+
+                if response.clicked() {
+                    self.pick_entry(Tree::Time, path);
+                }
+
+                response.context_menu(|ui| {
+                    self.entry_context_menu(ui, Tree::Time, path);
+                });
+
+                if let Some(link) = self.backend.entry_link(Tree::Time, path) {
+                    link_button(ui, &link);
+                }
+
+                ui.label(
+                    RichText::new(truncate_middle(&parent, NAME_MAX_CHARS))
+                        .small()
+                        .weak(),
+                );
+            });
+        }
+
+        ui.separator();
+    }
+
+    fn column_time_text(&self, today: Option<&Duration>, total: Duration) -> String {
+        let format = self.backend.settings.duration_format;
+
+        let today = today.copied().unwrap_or_default();
+
+        match self.backend.settings.column_time {
+            ColumnTime::Today => format_duration(today, format),
+            ColumnTime::Total => format_duration(total, format),
+            ColumnTime::Both => {
+                format!(
+                    "{} / {}",
+                    format_duration(today, format),
+                    format_duration(total, format)
+                )
+            }
+        }
+    }
+
+    /// Elapsed session time, or the time left in the phase while a pomodoro runs.
+    fn session_time_text(&self) -> String {
+        let format = self.backend.settings.duration_format;
+
+        match self.backend.pomodoro_remaining() {
+            Some(remaining) => format_duration(remaining, format),
+            None => format_duration(self.backend.current_session_duration, format),
+        }
+    }
+
+    fn start_pause_hint(&self) -> String {
+        let hotkey = self.backend.settings.hotkeys.get(HotkeyAction::StartPause);
+
+        tr!("Hotkey: {hotkey}", hotkey = hotkey)
+    }
+
+    fn notes_hint(&self) -> String {
+        let hotkey = self.backend.settings.hotkeys.get(HotkeyAction::Notes);
+
+        tr!("Notes ({hotkey})", hotkey = hotkey)
+    }
+
+    fn toggle_tracking(&mut self) {
+        if self.backend.get_current_subject().is_none() {
+            self.show_toast(tr!("Select a subject to start tracking"));
+            return;
+        }
+
+        match self.backend.working_mode {
+            WorkingMode::Idle => self.time_tracker_start_subject(),
+            WorkingMode::InProgress(_) => self.time_tracker_pause_subject(),
+        }
+    }
+
     fn time_tracker_start_subject(&mut self) {
         self.backend.start_subject();
-        self.time_tracker_options.current_label = self.backend.get_current_work_name();
+        self.time_tracker_options.current_label =
+            self.backend.get_current_work_name().unwrap_or_default();
     }
 
     fn time_tracker_stop_subject(&mut self, force: bool) {
-        self.backend.stop_subject(force);
+        if self.backend.stop_subject(force) {
+            self.show_toast(tr!("Session discarded (too short)"));
+        }
+
         self.time_tracker_options.current_label = "".to_string();
     }
+
+    /// Stops the session the way the pause button does, then asks for a note on it when
+    /// session notes are on and it was long enough to be kept.
+    fn time_tracker_pause_subject(&mut self) {
+        let session = self.backend.active_session_id();
+        let path = self.backend.tracked_path();
+
+        self.time_tracker_stop_subject(false);
+
+        let (Some(id), Some(path)) = (session, path) else {
+            return;
+        };
+
+        if !self.backend.settings.session_notes
+            || self.backend.history.get(&id).is_none()
+            || self.dialog_options.current_dialog != CurrentDialog::None
+        {
+            return;
+        }
+
+        self.dialog_options.buffer = self.backend.note_template(path).unwrap_or_default();
+        self.dialog_options.focused = false;
+        self.dialog_options.current_dialog = CurrentDialog::SessionNote(id);
+    }
 }
 
 /**
@@ -1282,8 +7629,12 @@ impl Frontend {
 #[derive(Default)]
 struct MinimalTrackerOptions {
     prev_mode: DisplayMode,
+    /// The recent subjects are listed below the session.
+    recent_open: bool,
 }
 
+const RECENT_WINDOW_SIZE: Vec2 = Vec2::new(240., 220.);
+
 impl Frontend {
     fn minimal_time_tracker_build(&mut self, ui: &mut Ui) {
         let current_subject = self.backend.get_current_subject();
@@ -1292,41 +7643,597 @@ impl Frontend {
                 if current_subject.is_some() {
                     match self.backend.working_mode {
                         WorkingMode::Idle => {
-                            if ui.button("START").clicked() {
+                            if ui
+                                .button(tr!("START"))
+                                .on_hover_text(self.start_pause_hint())
+                                .clicked()
+                            {
                                 self.time_tracker_start_subject()
                             }
                         }
                         WorkingMode::InProgress(_) => {
-                            if ui.button("PAUSE").clicked() {
-                                self.time_tracker_stop_subject(false);
+                            if ui
+                                .button(tr!("PAUSE"))
+                                .on_hover_text(self.start_pause_hint())
+                                .clicked()
+                            {
+                                self.time_tracker_pause_subject();
                             }
                         }
                     }
                 }
 
+                let options = &mut self.minimal_time_tracker_options;
+
+                if ui
+                    .small_button("⏷")
+                    .on_hover_text(tr!("Recent subjects"))
+                    .clicked()
+                {
+                    options.recent_open = !options.recent_open;
+                }
+
                 self.build_menu(ui);
             });
 
-            if current_subject.is_some() {
-                ui.label(format_duration(self.backend.current_session_duration));
-            }
-        });
+            let (name, full_name) = match &current_subject {
+                Some(subject) => {
+                    let name = subject.lock().unwrap().name.clone();
+                    let full_name = self
+                        .backend
+                        .get_current_work_name()
+                        .unwrap_or_else(|| name.clone());
+
+                    (name, full_name)
+                }
+                None => (
+                    tr!("no subject").to_string(),
+                    tr!("No subject selected").to_string(),
+                ),
+            };
+
+            let font = FontId::proportional(14.);
+            let shown = truncate_middle_to_width(ui, &name, font.clone(), ui.available_width());
+
+            if ui
+                .add(Label::new(RichText::new(shown).font(font)).sense(egui::Sense::click()))
+                .on_hover_text(full_name)
+                .clicked()
+            {
+                self.set_display_mode(self.minimal_time_tracker_options.prev_mode);
+            }
+
+            if current_subject.is_some() {
+                ui.label(self.session_time_text());
+            }
+
+            if !self.minimal_time_tracker_options.recent_open {
+                return;
+            }
+
+            ui.separator();
+
+            let recent = self.backend.recent_subjects(RECENT_SUBJECTS);
+
+            if recent.is_empty() {
+                ui.label(RichText::new(tr!("Nothing tracked yet")).small().weak());
+            }
+
+            for (path, name, full_name) in recent {
+                if ui
+                    .button(truncate_middle(&name, NAME_MAX_CHARS))
+                    .on_hover_text(full_name)
+                    .clicked()
+                {
+                    self.minimal_time_tracker_options.recent_open = false;
+                    self.start_entry(path);
+                }
+            }
+        });
+    }
+}
+
+/**
+    TO DO block
+**/
+#[derive(Default)]
+struct TodoOptions {
+    /// Todo whose due date is being edited, with the date text.
+    editing_due: Option<(Uuid, String)>,
+    /// Done todos are left out of the subject lists.
+    only_open: bool,
+    /// Todos with their notes editor expanded, with the text being edited.
+    notes: HashMap<Uuid, String>,
+    /// Todos picked with ctrl or shift click for the bulk actions.
+    selected: Vec<EntryPath>,
+    /// Last todo picked, shift click selects the range up to it.
+    anchor: Option<EntryPath>,
+}
+
+impl TodoOptions {
+    fn clear_selection(&mut self) {
+        self.selected.clear();
+        self.anchor = None;
+    }
+}
+
+fn todo_count_text((open, total): (usize, usize)) -> String {
+    format!("({open}/{total})")
+}
+
+/// Name of a todo project or sub project, dimmed once nothing in it is open.
+fn todo_container_text(name: &str, (open, _): (usize, usize)) -> RichText {
+    let text = name_text(name);
+
+    if open == 0 {
+        text.weak()
+    } else {
+        text
+    }
+}
+
+/// Highest priority first, then earliest due with undated todos after dated ones, then
+/// by creation.
+fn todo_order(a: &Arc<Mutex<TodoSubject>>, b: &Arc<Mutex<TodoSubject>>) -> std::cmp::Ordering {
+    let (a, b) = (a.lock().unwrap(), b.lock().unwrap());
+
+    let due = match (a.due, b.due) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    };
+
+    a.priority
+        .cmp(&b.priority)
+        .then(due)
+        .then_with(|| a.created_at.cmp(&b.created_at))
+}
+
+/// A chain color made to stand out of the panel background.
+fn visible_color(ui: &Ui, (r, g, b): (u8, u8, u8)) -> Color32 {
+    with_contrast(Color32::from_rgb(r, g, b), ui.visuals().panel_fill)
+}
+
+/// Dot cycling the priority of a todo on click, filled and colored for high priority.
+fn priority_toggle(ui: &mut Ui, priority: Priority) -> Response {
+    let (rect, response) = ui.allocate_exact_size(Vec2::splat(14.), egui::Sense::click());
+
+    let weak = ui.visuals().weak_text_color();
+    let painter = ui.painter();
+
+    match priority {
+        Priority::High => painter.circle_filled(rect.center(), 4.5, HIGH_PRIORITY_COLOR),
+        Priority::Normal => painter.circle_stroke(rect.center(), 3.5, Stroke::new(1., weak)),
+        Priority::Low => painter.circle_filled(rect.center(), 2., weak),
+    }
+
+    response.on_hover_text(tr!(
+        "{priority} priority, click to change",
+        priority = translate(&format!("{priority:?}"))
+    ))
+}
+
+impl Frontend {
+    /// Open and total count next to a todo column entry, toggling the open only filter.
+    fn todo_count_button(&mut self, ui: &mut Ui, count: (usize, usize)) {
+        let only_open = &mut self.todo_options.only_open;
+
+        let mut text = RichText::new(todo_count_text(count)).small().weak();
+
+        if *only_open {
+            text = text.underline();
+        }
+
+        if ui
+            .add(egui::Button::new(text).frame(false))
+            .on_hover_text(if *only_open {
+                tr!("Open / total todos, click to show done ones again")
+            } else {
+                tr!("Open / total todos, click to show only open ones")
+            })
+            .clicked()
+        {
+            *only_open = !*only_open;
+        }
+    }
+
+    /// Rows of the given todo subjects in order, done ones moved into a collapsed section
+    /// sorted by completion unless the settings keep them inline.
+    fn todo_subject_rows(
+        &mut self,
+        ui: &mut Ui,
+        project_id: Uuid,
+        sub_project_id: Uuid,
+        subjects: Vec<Arc<Mutex<TodoSubject>>>,
+        spacing: f32,
+    ) {
+        let inline = self.backend.settings.todo_done_inline;
+        let only_open = self.todo_options.only_open;
+
+        let (mut done, open): (Vec<_>, Vec<_>) = subjects
+            .into_iter()
+            .filter(|v| !v.lock().unwrap().is_deleted)
+            .partition(|v| (only_open || !inline) && v.lock().unwrap().is_done);
+
+        let mut rows = |ui: &mut Ui, subjects: &[Arc<Mutex<TodoSubject>>]| {
+            let paths = subjects
+                .iter()
+                .map(|v| EntryPath::Subject(project_id, sub_project_id, v.lock().unwrap().id))
+                .collect::<Vec<_>>();
+
+            for (subject, path) in subjects.iter().zip(paths.iter().copied()) {
+                let picked = ui
+                    .horizontal(|ui| self.todo_subject_row(ui, path, subject))
+                    .inner;
+
+                if picked {
+                    let range = ui.input(|i| i.modifiers.shift);
+                    self.select_todo(path, &paths, range);
+                }
+
+                self.todo_notes_editor(ui, path);
+
+                ui.add_space(spacing);
+            }
+        };
+
+        rows(ui, &open);
+
+        if done.is_empty() || only_open {
+            return;
+        }
+
+        done.sort_by_key(|v| std::cmp::Reverse(v.lock().unwrap().completed_at));
+
+        egui::CollapsingHeader::new(tr!("Done ({n})", n = done.len()))
+            .id_source(("todo_done", sub_project_id))
+            .default_open(false)
+            .show(ui, |ui| {
+                rows(ui, &done);
+            });
+    }
+
+    /// Adds a todo to the selection or drops it again. A range reaches back to the last
+    /// picked todo when that one is among `paths`.
+    fn select_todo(&mut self, path: EntryPath, paths: &[EntryPath], range: bool) {
+        let options = &mut self.todo_options;
+
+        let start = options
+            .anchor
+            .filter(|_| range)
+            .and_then(|anchor| paths.iter().position(|v| *v == anchor));
+
+        let (Some(start), Some(end)) = (start, paths.iter().position(|v| *v == path)) else {
+            if let Some(i) = options.selected.iter().position(|v| *v == path) {
+                options.selected.remove(i);
+            } else {
+                options.selected.push(path);
+            }
+
+            options.anchor = Some(path);
+            return;
+        };
+
+        for path in &paths[start.min(end)..=start.max(end)] {
+            if !options.selected.contains(path) {
+                options.selected.push(*path);
+            }
+        }
+    }
+
+    /// Action bar for the selected todos.
+    fn todo_selection_build(&mut self, ui: &mut Ui) {
+        let count = self.todo_options.selected.len();
+
+        ui.horizontal(|ui| {
+            ui.label(tr!("{n} selected", n = count));
+
+            let mut done = None;
+
+            if ui.button(tr!("Done")).clicked() {
+                done = Some(true);
+            }
+
+            if ui.button(tr!("Undone")).clicked() {
+                done = Some(false);
+            }
+
+            if let Some(done) = done {
+                for path in &self.todo_options.selected {
+                    let EntryPath::Subject(p, s, x) = *path else {
+                        continue;
+                    };
+
+                    self.backend.set_todo_done(p, s, x, done);
+                }
+            }
+
+            ui.menu_button(tr!("Move to…"), |ui| {
+                let sorted = |mut v: Vec<(Uuid, String)>| {
+                    v.sort_by_key(|(_, name)| name.to_lowercase());
+                    v
+                };
+
+                for (project_id, project_name) in
+                    sorted(self.backend.entry_children(Tree::Todo, None))
+                {
+                    let project = EntryPath::Project(project_id);
+
+                    ui.menu_button(truncate_middle(&project_name, NAME_MAX_CHARS), |ui| {
+                        for (sub_project_id, sub_project_name) in
+                            sorted(self.backend.entry_children(Tree::Todo, Some(project)))
+                        {
+                            if ui
+                                .button(truncate_middle(&sub_project_name, NAME_MAX_CHARS))
+                                .clicked()
+                            {
+                                let target = EntryPath::SubProject(project_id, sub_project_id);
+                                self.move_selected_todos(target);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                }
+            });
+
+            if ui.button(tr!("Delete")).clicked() {
+                for path in std::mem::take(&mut self.todo_options.selected) {
+                    self.backend.delete_entry(Tree::Todo, path);
+                }
+
+                self.todo_options.clear_selection();
+            }
+
+            if ui
+                .small_button("✖")
+                .on_hover_text(tr!("Clear the selection"))
+                .clicked()
+            {
+                self.todo_options.clear_selection();
+            }
+        });
+    }
+
+    fn move_selected_todos(&mut self, target: EntryPath) {
+        let mut failed = 0;
+
+        for path in std::mem::take(&mut self.todo_options.selected) {
+            if path.parent() == Some(target) {
+                continue;
+            }
+
+            if !self.backend.move_entry(Tree::Todo, path, target) {
+                failed += 1;
+            }
+        }
+
+        self.todo_options.clear_selection();
+
+        if failed > 0 {
+            self.show_toast(&tr!(
+                "{n} todos not moved, the target has todos with the same name",
+                n = failed
+            ));
+        }
+    }
+
+    /// Checkbox of a todo subject followed by its due date, colored when overdue or due today.
+    /// True when the todo was ctrl or shift clicked to change the selection instead.
+    fn todo_subject_row(
+        &mut self,
+        ui: &mut Ui,
+        path: EntryPath,
+        subject: &Arc<Mutex<TodoSubject>>,
+    ) -> bool {
+        let EntryPath::Subject(project_id, sub_project_id, id) = path else {
+            return false;
+        };
+
+        let now = Local::now();
+
+        let (name, mut is_done, due, priority, color, notes, link) = {
+            let subject = subject.lock().unwrap();
+
+            let color = if subject.is_overdue(now) {
+                Some(ui.visuals().error_fg_color)
+            } else if subject.is_due_today(now) {
+                Some(ui.visuals().warn_fg_color)
+            } else {
+                None
+            };
+
+            (
+                subject.name.clone(),
+                subject.is_done,
+                subject.due,
+                subject.priority,
+                color,
+                subject.notes.clone(),
+                subject.link.clone(),
+            )
+        };
+
+        let expanded = self.todo_options.notes.contains_key(&id);
+
+        if ui
+            .add(egui::Button::new(if expanded { "⏷" } else { "⏵" }).frame(false))
+            .on_hover_text(tr!("Notes"))
+            .clicked()
+        {
+            if expanded {
+                self.close_todo_notes(path);
+            } else {
+                self.todo_options.notes.insert(id, notes.clone());
+            }
+        }
+
+        if priority_toggle(ui, priority).clicked() {
+            self.backend
+                .set_todo_priority(project_id, sub_project_id, id, priority.next());
+        }
+
+        let mut text = name_text(&name);
+
+        if let Some(color) = color {
+            text = text.color(color);
+        }
+
+        if self.todo_options.selected.contains(&path) {
+            text = text.background_color(ui.visuals().selection.bg_fill);
+        }
+
+        let response = with_full_name(ui.checkbox(&mut is_done, text), &name);
+
+        let modifiers = ui.input(|i| i.modifiers);
+        let picked = response.clicked() && (modifiers.command || modifiers.shift);
+
+        if response.clicked() && !picked {
+            self.backend
+                .toggle_todo_subject(project_id, sub_project_id, id);
+        }
+
+        response.context_menu(|ui| {
+            self.entry_context_menu(ui, Tree::Todo, path);
+        });
+
+        if !notes.trim().is_empty() {
+            ui.label(RichText::new("✏").small().weak())
+                .on_hover_text(notes);
+        }
+
+        if let Some(link) = &link {
+            link_button(ui, link);
+        }
+
+        let options = &mut self.todo_options;
+
+        if let Some((_, text)) = options.editing_due.as_mut().filter(|v| v.0 == id) {
+            let valid = text.trim().is_empty() || parse_due(text).is_some();
+
+            let r = ui.add(
+                TextEdit::singleline(text)
+                    .desired_width(110.)
+                    .hint_text(DATE_FORMAT)
+                    .text_color_opt((!valid).then_some(ui.visuals().error_fg_color)),
+            );
+
+            let submit = r.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+
+            let save = ui
+                .small_button("✔")
+                .on_hover_text(tr!("Empty clears the date"))
+                .clicked();
+            let cancel = ui.small_button("✖").clicked();
+
+            if (save || submit) && valid {
+                let due = parse_due(text);
+
+                options.editing_due = None;
+                self.backend
+                    .set_todo_due(project_id, sub_project_id, id, due);
+            } else if cancel {
+                options.editing_due = None;
+            }
+
+            return picked;
+        }
+
+        let label = match due {
+            Some(due) => RichText::new(tr!("due {date}", date = format_due(due))).small(),
+            None => RichText::new(tr!("+ due")).small().weak(),
+        };
+
+        if ui
+            .add(egui::Button::new(label).frame(false))
+            .on_hover_text(tr!("Set the due date"))
+            .clicked()
+        {
+            options.editing_due = Some((id, due.map(format_due).unwrap_or_default()));
+        }
+
+        picked
+    }
+
+    /// Notes of an expanded todo below its row, saved once the editor loses focus.
+    fn todo_notes_editor(&mut self, ui: &mut Ui, path: EntryPath) {
+        let EntryPath::Subject(project_id, sub_project_id, id) = path else {
+            return;
+        };
+
+        let Some(text) = self.todo_options.notes.get_mut(&id) else {
+            return;
+        };
+
+        let r = ui
+            .indent(("todo_notes", id), |ui| {
+                ui.add(
+                    TextEdit::multiline(text)
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(3)
+                        .hint_text(tr!("Notes")),
+                )
+            })
+            .inner;
+
+        if r.lost_focus() {
+            self.backend
+                .set_todo_notes(project_id, sub_project_id, id, text);
+        }
+    }
+
+    fn close_todo_notes(&mut self, path: EntryPath) {
+        let EntryPath::Subject(project_id, sub_project_id, id) = path else {
+            return;
+        };
+
+        let Some(text) = self.todo_options.notes.remove(&id) else {
+            return;
+        };
+
+        self.backend
+            .set_todo_notes(project_id, sub_project_id, id, &text);
     }
-}
-
-/**
-    TO DO block
-**/
 
-impl Frontend {
     fn todo_build(&mut self, ui: &mut Ui) {
+        self.backend.refresh_todo_counts();
+
         self.build_menu(ui);
 
         ui.separator();
 
+        let mut height = 353.0;
+
+        if !self.todo_options.selected.is_empty() {
+            let top = ui.cursor().top();
+            self.todo_selection_build(ui);
+            height -= ui.cursor().top() - top;
+        }
+
+        if !self.search_options.query.trim().is_empty() {
+            ui.allocate_ui(Vec2::new(ui.available_width(), height), |ui| {
+                self.search_results_build(ui, Tree::Todo);
+            });
+
+            return;
+        }
+
+        if self.backend.settings.todo_layout == TodoLayout::Tree {
+            ui.push_id(4, |ui| {
+                ScrollArea::vertical()
+                    .max_height(height)
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        self.todo_build_tree(ui);
+                    });
+            });
+
+            return;
+        }
+
         ui.horizontal(|ui| {
-            ui.set_min_height(353.0);
-            ui.set_max_height(353.0);
+            ui.set_min_height(height);
+            ui.set_max_height(height);
 
             ui.push_id(1, |ui| {
                 ScrollArea::both().show(ui, |ui| {
@@ -1352,6 +8259,114 @@ impl Frontend {
         });
     }
 
+    fn todo_build_tree(&mut self, ui: &mut Ui) {
+        let projects = self
+            .backend
+            .todos
+            .get_inner_sorted(|a, b| a.created_at.cmp(&b.created_at))
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for project in projects {
+            if project.is_deleted {
+                continue;
+            }
+
+            let expanded = self.backend.is_todo_expanded(&project.id);
+
+            let count = self.backend.todo_count(&project.id);
+            let title = todo_container_text(
+                &format!("{} {}", project.name, todo_count_text(count)),
+                count,
+            );
+
+            let r = egui::CollapsingHeader::new(title.strong())
+                .id_source(project.id)
+                .open(Some(expanded))
+                .show(ui, |ui| {
+                    for sub_project in
+                        project.get_inner_sorted(|a, b| a.created_at.cmp(&b.created_at))
+                    {
+                        if sub_project.is_deleted {
+                            continue;
+                        }
+
+                        self.todo_build_tree_sub_project(ui, project.id, sub_project);
+                    }
+
+                    if ui.button("   +   ").clicked() {
+                        self.backend.set_current_todo_project(Some(project.id));
+                        self.dialog_options.current_dialog = CurrentDialog::AddTodoSubProject;
+                    }
+                });
+
+            let header = with_full_name(r.header_response, &project.name).context_menu(|ui| {
+                self.entry_context_menu(ui, Tree::Todo, EntryPath::Project(project.id));
+            });
+
+            if header.double_clicked() {
+                self.backend.set_todo_expanded(project.id, false);
+
+                for id in project.inner.keys() {
+                    self.backend.set_todo_expanded(*id, false);
+                }
+            } else if header.clicked() {
+                self.backend.set_todo_expanded(project.id, !expanded);
+            }
+        }
+
+        if ui.button("   +   ").clicked() {
+            self.dialog_options.current_dialog = CurrentDialog::AddTodoProject;
+        }
+    }
+
+    fn todo_build_tree_sub_project(
+        &mut self,
+        ui: &mut Ui,
+        project_id: Uuid,
+        sub_project: &TodoSubProject,
+    ) {
+        let expanded = self.backend.is_todo_expanded(&sub_project.id);
+
+        let count = self.backend.todo_count(&sub_project.id);
+        let title = todo_container_text(
+            &format!("{} {}", sub_project.name, todo_count_text(count)),
+            count,
+        );
+
+        let r = egui::CollapsingHeader::new(title)
+            .id_source(sub_project.id)
+            .open(Some(expanded))
+            .show(ui, |ui| {
+                let subjects = sub_project
+                    .get_inner_sorted(todo_order)
+                    .into_iter()
+                    .cloned()
+                    .collect();
+
+                self.todo_subject_rows(ui, project_id, sub_project.id, subjects, 0.);
+
+                if ui.button("   +   ").clicked() {
+                    self.backend.set_current_todo_project(Some(project_id));
+                    self.backend
+                        .set_current_todo_sub_project(Some(sub_project.id));
+                    self.dialog_options.current_dialog = CurrentDialog::AddTodoSubject;
+                }
+            });
+
+        let header = with_full_name(r.header_response, &sub_project.name);
+
+        if header.clicked() {
+            self.backend.set_todo_expanded(sub_project.id, !expanded);
+        }
+
+        header.context_menu(|ui| {
+            let path = EntryPath::SubProject(project_id, sub_project.id);
+            self.entry_context_menu(ui, Tree::Todo, path);
+        });
+    }
+
     fn todo_build_projects(&mut self, ui: &mut Ui) {
         ui.set_min_width(300.0);
         ui.set_max_width(300.0);
@@ -1365,7 +8380,10 @@ impl Frontend {
         let c = self
             .backend
             .todos
-            .get_inner_sorted(|a, b| a.created_at.cmp(&b.created_at));
+            .get_inner_sorted(|a, b| a.created_at.cmp(&b.created_at))
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
 
         ui.vertical(|ui| {
             for project in c {
@@ -1374,15 +8392,25 @@ impl Frontend {
                 }
 
                 ui.horizontal(|ui| {
-                    let mut text = RichText::new(&project.name);
+                    let count = self.backend.todo_count(&project.id);
+                    let mut text = todo_container_text(&project.name, count);
 
                     if project.id == current_id {
                         text = text.strong();
                     }
 
-                    if ui.button(text).clicked() {
+                    let response = with_full_name(ui.button(text), &project.name);
+
+                    if response.clicked() {
                         self.backend.set_current_todo_project(Some(project.id));
+                        self.todo_options.clear_selection();
                     }
+
+                    response.context_menu(|ui| {
+                        self.entry_context_menu(ui, Tree::Todo, EntryPath::Project(project.id));
+                    });
+
+                    self.todo_count_button(ui, count);
                 });
 
                 ui.add_space(5.0);
@@ -1402,13 +8430,19 @@ impl Frontend {
             return;
         };
 
+        let project_id = current_project.id;
+
         let current_id = if let Some(cur_project) = self.backend.get_current_todo_sub_project() {
             cur_project.id
         } else {
             Uuid::new_v4()
         };
 
-        let c = current_project.get_inner_sorted(|a, b| a.created_at.cmp(&b.created_at));
+        let c = current_project
+            .get_inner_sorted(|a, b| a.created_at.cmp(&b.created_at))
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
 
         ui.vertical(|ui| {
             for sub_project in c {
@@ -1417,16 +8451,27 @@ impl Frontend {
                 }
 
                 ui.horizontal(|ui| {
-                    let mut text = RichText::new(&sub_project.name);
+                    let count = self.backend.todo_count(&sub_project.id);
+                    let mut text = todo_container_text(&sub_project.name, count);
 
                     if sub_project.id == current_id {
                         text = text.strong();
                     }
 
-                    if ui.button(text).clicked() {
+                    let response = with_full_name(ui.button(text), &sub_project.name);
+
+                    if response.clicked() {
                         self.backend
                             .set_current_todo_sub_project(Some(sub_project.id));
+                        self.todo_options.clear_selection();
                     }
+
+                    response.context_menu(|ui| {
+                        let path = EntryPath::SubProject(project_id, sub_project.id);
+                        self.entry_context_menu(ui, Tree::Todo, path);
+                    });
+
+                    self.todo_count_button(ui, count);
                 });
 
                 ui.add_space(5.0);
@@ -1446,40 +8491,613 @@ impl Frontend {
             return;
         };
 
-        let c = current_todo_sub_project.get_inner_sorted(|a, b| {
-            a.lock()
-                .unwrap()
-                .created_at
-                .cmp(&b.lock().unwrap().created_at)
-        });
+        let sub_project_id = current_todo_sub_project.id;
+        let Some(project_id) = self.backend.get_current_todo_project().map(|v| v.id) else {
+            return;
+        };
+
+        let c = current_todo_sub_project
+            .get_inner_sorted(todo_order)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
 
         ui.vertical(|ui| {
-            for subject in c {
-                let text;
-                let mut is_done;
-                {
-                    let r_subject = subject.lock().unwrap();
+            self.todo_subject_rows(ui, project_id, sub_project_id, c, 5.0);
 
-                    if r_subject.is_deleted {
-                        continue;
+            if ui.button("   +   ").clicked() {
+                self.dialog_options.current_dialog = CurrentDialog::AddTodoSubject;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::SUBJECT_CLONES;
+    use crate::clock::TestClock;
+    use crate::system_events::{MockEvents, SystemEvents};
+    use chrono::TimeZone;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn label(year: &str, month: u32, day: &str) -> SimpleDate {
+        SimpleDate {
+            year: year.to_string(),
+            month: Month::try_from(month as u8).unwrap(),
+            day: day.to_string(),
+        }
+    }
+
+    fn parse(year: &str, month: u32, day: &str) -> Option<NaiveDate> {
+        let date: Result<DateTime<Local>, ()> = (&label(year, month, day)).try_into();
+
+        date.ok().map(|v| v.date_naive())
+    }
+
+    #[test]
+    fn february_29_only_parses_in_leap_years() {
+        assert_eq!(parse("2024", 2, "29"), Some(date(2024, 2, 29)));
+        assert_eq!(parse("2000", 2, "29"), Some(date(2000, 2, 29)));
+        assert_eq!(parse("2023", 2, "29"), None);
+        assert_eq!(parse("2100", 2, "29"), None);
+    }
+
+    #[test]
+    fn out_of_range_days_do_not_parse() {
+        assert_eq!(parse("2024", 4, "31"), None);
+        assert_eq!(parse("2024", 2, "31"), None);
+        assert_eq!(parse("2024", 1, "32"), None);
+        assert_eq!(parse("2024", 1, "0"), None);
+        assert_eq!(parse("2024", 1, "-1"), None);
+        assert_eq!(parse("2024", 1, ""), None);
+        assert_eq!(parse("2024", 12, " 31 "), Some(date(2024, 12, 31)));
+    }
+
+    #[test]
+    fn out_of_range_years_do_not_parse() {
+        assert_eq!(parse("1969", 12, "31"), None);
+        assert_eq!(parse("10000", 1, "1"), None);
+        assert_eq!(parse("24", 1, "1"), None);
+        assert_eq!(parse("twenty", 1, "1"), None);
+        assert_eq!(parse("1970", 1, "2"), Some(date(1970, 1, 2)));
+    }
+
+    #[test]
+    fn day_is_clamped_to_the_month() {
+        let mut april = label("2024", 4, "31");
+        april.clamp_day();
+
+        assert_eq!(april.day, "30");
+
+        let mut february = label("2023", 2, "30");
+        february.clamp_day();
+
+        assert_eq!(february.day, "28");
+
+        let mut leap = label("2024", 2, "31");
+        leap.clamp_day();
+
+        assert_eq!(leap.day, "29");
+
+        // Nothing to clamp against without a valid year.
+        let mut unknown = label("", 2, "31");
+        unknown.clamp_day();
+
+        assert_eq!(unknown.day, "31");
+    }
+
+    #[test]
+    fn reversed_labels_are_swapped() {
+        let mut from = SimpleDate::from(date(2024, 3, 10));
+        let mut to = SimpleDate::from(date(2024, 3, 5));
+
+        let (start, end) = range_from_labels(&mut from, &mut to).unwrap();
+
+        assert_eq!(start, start_of_day(date(2024, 3, 5)));
+        assert_eq!(end.date_naive(), date(2024, 3, 10));
+        assert_eq!(from.day, "5");
+        assert_eq!(to.day, "10");
+
+        let mut invalid = label("2024", 2, "30");
+
+        assert!(range_from_labels(&mut from, &mut invalid).is_none());
+    }
+
+    fn at(h: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, 5, h, min, 0).unwrap()
+    }
+
+    /// A window tracking a subject since 10:00, its clock standing at 10:30.
+    fn tracking() -> (Frontend, TestClock, EntryPath) {
+        let clock = TestClock::at(at(10, 0));
+        let mut backend = Backend::unsaved_at(&clock);
+        let path = backend
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+
+        backend.start_subject();
+        clock.advance(std::time::Duration::from_secs(30 * 60));
+        backend.update_time();
+
+        let frontend = Frontend {
+            backend,
+            ..Frontend::default()
+        };
+
+        (frontend, clock, path)
+    }
+
+    fn deliver(frontend: &mut Frontend, events: Vec<SystemEvent>) {
+        let (sender, receiver) = channel();
+
+        Box::new(MockEvents(events)).listen(sender, egui::Context::default());
+
+        frontend.system_events = Some(receiver);
+        frontend.handle_system_events();
+    }
+
+    fn ends(frontend: &Frontend) -> Vec<DateTime<Local>> {
+        let mut ends = frontend
+            .backend
+            .history
+            .records()
+            .map(|v| v.end_date)
+            .collect::<Vec<_>>();
+        ends.sort();
+        ends
+    }
+
+    #[test]
+    fn display_off_pauses_at_its_time() {
+        let (mut frontend, _, path) = tracking();
+
+        deliver(&mut frontend, vec![SystemEvent::DisplayOff(at(10, 20))]);
+
+        assert!(frontend.tracked_path().is_none());
+        assert_eq!(ends(&frontend), [at(10, 20)]);
+        assert_eq!(frontend.display_off_session, Some((path, at(10, 20))));
+    }
+
+    #[test]
+    fn display_off_is_ignored_when_turned_off() {
+        let (mut frontend, _, path) = tracking();
+        frontend.backend.settings.pause_on_display_off = false;
+
+        deliver(&mut frontend, vec![SystemEvent::DisplayOff(at(10, 20))]);
+
+        assert_eq!(frontend.tracked_path(), Some(path));
+        assert!(frontend.display_off_session.is_none());
+    }
+
+    #[test]
+    fn lock_right_after_display_off_resumes_that_session() {
+        let (mut frontend, clock, path) = tracking();
+        frontend.backend.settings.resume_on_unlock = UnlockResume::Automatic;
+
+        deliver(
+            &mut frontend,
+            vec![
+                SystemEvent::DisplayOff(at(10, 20)),
+                SystemEvent::ScreenLocked(at(10, 20) + chrono::Duration::seconds(30)),
+            ],
+        );
+
+        assert!(frontend.display_off_session.is_none());
+        // Offered as stopped when the screen turned off.
+        assert_eq!(frontend.locked_session, Some((path, at(10, 20))));
+
+        clock.set_wall(at(11, 0));
+        deliver(&mut frontend, vec![SystemEvent::ScreenUnlocked]);
+
+        assert_eq!(frontend.tracked_path(), Some(path));
+        assert!(frontend.locked_session.is_none());
+    }
+
+    #[test]
+    fn lock_long_after_display_off_offers_nothing() {
+        let (mut frontend, _, _) = tracking();
+
+        deliver(
+            &mut frontend,
+            vec![
+                SystemEvent::DisplayOff(at(10, 20)),
+                SystemEvent::ScreenLocked(at(10, 25)),
+                SystemEvent::ScreenUnlocked,
+            ],
+        );
+
+        assert!(frontend.locked_session.is_none());
+        assert!(frontend.tracked_path().is_none());
+        assert!(frontend.dialog_options.current_dialog == CurrentDialog::None);
+    }
+
+    #[test]
+    fn unlock_asks_before_resuming() {
+        let (mut frontend, _, path) = tracking();
+
+        deliver(
+            &mut frontend,
+            vec![
+                SystemEvent::ScreenLocked(at(10, 25)),
+                SystemEvent::ScreenUnlocked,
+            ],
+        );
+
+        assert_eq!(ends(&frontend), [at(10, 25)]);
+        assert!(frontend.tracked_path().is_none());
+        assert!(
+            frontend.dialog_options.current_dialog
+                == CurrentDialog::ResumeAfterLock(path, at(10, 25))
+        );
+    }
+
+    #[test]
+    fn unlock_leaves_other_tracking_alone() {
+        let (mut frontend, _, path) = tracking();
+        frontend.backend.settings.resume_on_unlock = UnlockResume::Automatic;
+
+        deliver(&mut frontend, vec![SystemEvent::ScreenLocked(at(10, 25))]);
+
+        let other = frontend
+            .backend
+            .add_path("Client/Website/Footer", Tree::Time)
+            .unwrap();
+        frontend.start_entry(other);
+
+        deliver(&mut frontend, vec![SystemEvent::ScreenUnlocked]);
+
+        assert_eq!(frontend.tracked_path(), Some(other));
+        assert_ne!(other, path);
+    }
+
+    #[test]
+    fn lock_is_ignored_when_turned_off() {
+        let (mut frontend, _, path) = tracking();
+        frontend.backend.settings.pause_on_lock = false;
+
+        deliver(
+            &mut frontend,
+            vec![
+                SystemEvent::ScreenLocked(at(10, 25)),
+                SystemEvent::ScreenUnlocked,
+            ],
+        );
+
+        assert_eq!(frontend.tracked_path(), Some(path));
+        assert!(frontend.locked_session.is_none());
+    }
+
+    #[test]
+    fn goal_line_sits_at_its_share_of_the_column() {
+        let totals = [chrono::Duration::hours(10), chrono::Duration::hours(40)];
+
+        // The goal is ahead, the column spans up to it.
+        let scale = cumulative_scale(&totals, Some(chrono::Duration::hours(160)));
+
+        assert_eq!(cumulative_position(chrono::Duration::hours(160), scale), 1.);
+        assert_eq!(cumulative_position(totals[1], scale), 0.25);
+
+        // The goal is passed, the column spans up to the total.
+        let scale = cumulative_scale(&totals, Some(chrono::Duration::hours(20)));
+
+        assert_eq!(cumulative_position(chrono::Duration::hours(20), scale), 0.5);
+        assert_eq!(cumulative_position(totals[1], scale), 1.);
+
+        // Without a goal the last day fills the column.
+        let scale = cumulative_scale(&totals, None);
+
+        assert_eq!(cumulative_position(totals[0], scale), 0.25);
+    }
+
+    #[test]
+    fn empty_ranges_have_a_usable_scale() {
+        let scale = cumulative_scale(&[], None);
+
+        assert_eq!(scale, 1.);
+        assert_eq!(cumulative_position(chrono::Duration::zero(), scale), 0.);
+        assert_eq!(
+            cumulative_position(chrono::Duration::minutes(-5), scale),
+            0.
+        );
+    }
+
+    #[test]
+    fn time_view_renders_without_cloning_subjects() {
+        let (mut frontend, clock, _) = tracking();
+
+        for path in [
+            "Client/Website/Footer",
+            "Client/Shop/Cart",
+            "Home/Garden/Mow",
+        ] {
+            frontend.backend.add_path(path, Tree::Time).unwrap();
+        }
+
+        let ctx = egui::Context::default();
+        let clones = || SUBJECT_CLONES.with(|v| v.get());
+        let before = clones();
+
+        for _ in 0..3 {
+            clock.advance(std::time::Duration::from_secs(1));
+            frontend.backend.update_time();
+
+            let _ = ctx.run(egui::RawInput::default(), |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| frontend.time_tracker_build(ui));
+            });
+        }
+
+        assert_eq!(clones(), before);
+
+        // The counter does see clones.
+        let subject = frontend.backend.get_current_subject().unwrap();
+        let _ = subject.lock().unwrap().clone();
+
+        assert_eq!(clones(), before + 1);
+    }
+
+    fn press(key: Key) -> egui::RawInput {
+        egui::RawInput {
+            events: vec![egui::Event::Key {
+                key,
+                pressed: true,
+                repeat: false,
+                modifiers: egui::Modifiers::NONE,
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// Runs a frame with `input`, drawing a text field when `field` is set, focused when it
+    /// is `Some(true)`.
+    fn frame(
+        ctx: &egui::Context,
+        frontend: &mut Frontend,
+        input: egui::RawInput,
+        field: Option<bool>,
+    ) {
+        let mut text = String::new();
+
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                if let Some(focus) = field {
+                    let r = ui.text_edit_singleline(&mut text);
+
+                    if focus {
+                        r.request_focus();
                     }
-                    text = RichText::new(&r_subject.name);
-                    is_done = r_subject.is_done;
                 }
+            });
 
-                ui.horizontal(|ui| {
-                    if ui.checkbox(&mut is_done, text).clicked() {
-                        subject.lock().unwrap().toggle();
-                        self.backend.dirty();
-                    };
-                });
+            frontend.handle_hotkeys(ctx);
+        });
+    }
 
-                ui.add_space(5.0);
-            }
+    #[test]
+    fn focused_fields_block_hotkeys_until_they_let_go() {
+        let ctx = egui::Context::default();
+        let mut frontend = Frontend::default();
 
-            if ui.button("   +   ").clicked() {
-                self.dialog_options.current_dialog = CurrentDialog::AddTodoSubject;
-            }
-        });
+        frame(&ctx, &mut frontend, press(Key::W), None);
+
+        assert_eq!(frontend.current_display_mode, DisplayMode::Statistic);
+
+        // Typing into a field.
+        frame(&ctx, &mut frontend, Default::default(), Some(true));
+        frame(&ctx, &mut frontend, press(Key::Q), Some(false));
+
+        assert_eq!(frontend.current_display_mode, DisplayMode::Statistic);
+
+        // Escape lets go of the field.
+        frame(&ctx, &mut frontend, press(Key::Escape), Some(false));
+        frame(&ctx, &mut frontend, press(Key::Q), Some(false));
+
+        assert_eq!(frontend.current_display_mode, DisplayMode::Time);
+    }
+
+    #[test]
+    fn fields_gone_from_the_window_stop_blocking_hotkeys() {
+        let ctx = egui::Context::default();
+        let mut frontend = Frontend::default();
+
+        frame(&ctx, &mut frontend, Default::default(), Some(true));
+        frame(&ctx, &mut frontend, press(Key::E), Some(false));
+
+        assert_eq!(frontend.current_display_mode, DisplayMode::Time);
+
+        // The view with the field was left without the field losing focus first.
+        frame(&ctx, &mut frontend, Default::default(), None);
+        frame(&ctx, &mut frontend, press(Key::E), None);
+
+        assert_eq!(frontend.current_display_mode, DisplayMode::Todo);
+    }
+
+    #[test]
+    fn open_dialogs_block_hotkeys() {
+        let ctx = egui::Context::default();
+        let mut frontend = Frontend::default();
+        frontend.dialog_options.current_dialog = CurrentDialog::AddProject;
+
+        frame(&ctx, &mut frontend, press(Key::W), None);
+
+        assert_eq!(frontend.current_display_mode, DisplayMode::Time);
+
+        frontend.dialog_options.current_dialog = CurrentDialog::None;
+        frame(&ctx, &mut frontend, press(Key::W), None);
+
+        assert_eq!(frontend.current_display_mode, DisplayMode::Statistic);
+    }
+
+    fn open_in(dir: &std::path::Path) -> Backend {
+        Backend::load_from(Box::new(crate::storage::RonStorage::new(dir))).unwrap()
+    }
+
+    /// A frontend on saved data, with another copy of the same data that then saves
+    /// `Shop/Front/Cart` over it.
+    fn synced(dirty: bool) -> (Frontend, tempfile::TempDir) {
+        let dir = tempfile::TempDir::new().unwrap();
+
+        let mut backend = open_in(dir.path());
+        backend
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+        backend.flush();
+
+        let mut other = open_in(dir.path());
+        other.add_path("Shop/Front/Cart", Tree::Time).unwrap();
+        other.flush();
+
+        // A coarse file system clock could give the write the time of the one before.
+        std::fs::File::options()
+            .write(true)
+            .open(dir.path().join("data.ron"))
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+
+        if dirty {
+            backend.add_path("Home/Garden/Mow", Tree::Time).unwrap();
+        }
+
+        let frontend = Frontend {
+            backend,
+            ..Frontend::default()
+        };
+
+        (frontend, dir)
+    }
+
+    fn project_names(backend: &Backend) -> Vec<String> {
+        let mut names = backend
+            .projects
+            .inner
+            .values()
+            .map(|v| v.name.clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn data_changed_elsewhere_reloads_without_unsaved_changes() {
+        let (mut frontend, _dir) = synced(false);
+
+        frontend.check_external_change();
+
+        assert!(frontend.dialog_options.current_dialog == CurrentDialog::None);
+        assert!(frontend.sync_options.theirs.is_none());
+        assert!(frontend.toast.is_some());
+        assert_eq!(project_names(&frontend.backend), ["Client", "Shop"]);
+        assert!(!frontend.backend.changed_outside());
+    }
+
+    #[test]
+    fn data_changed_elsewhere_asks_with_unsaved_changes() {
+        let (mut frontend, _dir) = synced(true);
+
+        frontend.check_external_change();
+
+        assert!(frontend.dialog_options.current_dialog == CurrentDialog::SyncConflict);
+        assert!(frontend.backend.saves_held);
+        assert_eq!(project_names(&frontend.backend), ["Client", "Home"]);
+
+        let theirs = frontend.sync_options.theirs.as_ref().unwrap();
+
+        assert_eq!(project_names(theirs), ["Client", "Shop"]);
+
+        // Held saves keep theirs on disk until the conflict is resolved.
+        frontend.backend.dump();
+        frontend.backend.flush();
+
+        assert!(frontend.backend.changed_outside());
+    }
+
+    #[test]
+    fn switching_views_clears_the_selection_and_search() {
+        let (mut frontend, _, path) = tracking();
+
+        for mode in [
+            DisplayMode::Todo,
+            DisplayMode::Statistic,
+            DisplayMode::Minimal,
+            DisplayMode::Time,
+        ] {
+            frontend.todo_options.selected = vec![path];
+            frontend.todo_options.anchor = Some(path);
+            frontend.search_options.query = "nav".to_string();
+
+            frontend.set_display_mode(mode);
+
+            assert_eq!(frontend.current_display_mode, mode);
+            assert!(frontend.todo_options.selected.is_empty(), "{mode:?}");
+            assert_eq!(frontend.todo_options.anchor, None, "{mode:?}");
+            assert!(frontend.search_options.query.is_empty(), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn pausing_asks_for_a_note_filled_with_the_template() {
+        let (mut frontend, _, path) = tracking();
+        frontend.backend.settings.session_notes = true;
+        frontend.backend.set_note_template(path, "Next: ");
+
+        let session = frontend.backend.active_session_id().unwrap();
+
+        frontend.toggle_tracking();
+
+        assert!(frontend.dialog_options.current_dialog == CurrentDialog::SessionNote(session));
+        assert_eq!(frontend.dialog_options.buffer, "Next: ");
+
+        frontend.dialog_options.buffer.push_str("parser tests");
+        frontend
+            .backend
+            .set_session_note(session, &frontend.dialog_options.buffer);
+
+        assert_eq!(
+            frontend.backend.last_session_note(path),
+            Some("Next: parser tests")
+        );
+    }
+
+    #[test]
+    fn pausing_asks_for_nothing_without_session_notes() {
+        let (mut frontend, ..) = tracking();
+
+        frontend.toggle_tracking();
+
+        assert!(frontend.dialog_options.current_dialog == CurrentDialog::None);
+
+        // Nor for a session too short to be kept.
+        let (mut frontend, clock, _) = tracking();
+        frontend.backend.settings.session_notes = true;
+        frontend.toggle_tracking();
+        frontend.dialog_options.current_dialog = CurrentDialog::None;
+
+        frontend.toggle_tracking();
+        clock.advance(std::time::Duration::from_secs(5));
+        frontend.backend.update_time();
+        frontend.toggle_tracking();
+
+        assert!(frontend.dialog_options.current_dialog == CurrentDialog::None);
+    }
+
+    #[test]
+    fn notes_hint_follows_the_binding() {
+        let mut frontend = Frontend::default();
+
+        assert_eq!(frontend.notes_hint(), "Notes (N)");
+
+        let hotkey = Hotkey::new(egui::Key::E, egui::Modifiers::CTRL | egui::Modifiers::SHIFT);
+        frontend
+            .backend
+            .settings
+            .hotkeys
+            .set(HotkeyAction::Notes, hotkey)
+            .unwrap();
+
+        assert_eq!(frontend.notes_hint(), "Notes (Ctrl+Shift+E)");
     }
 }
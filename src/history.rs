@@ -1,115 +1,849 @@
-use chrono::{DateTime, Datelike, Duration, Local, TimeZone};
-use serde::{Deserialize, Serialize};
+use crate::storage::HistoryStore;
+use crate::util::{resolve_local, start_of_day};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone, Timelike};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
 use std::cmp::Ordering;
-use std::collections::HashMap;
-use std::time::SystemTime;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use uuid::Uuid;
 
-#[derive(Clone, Serialize, Deserialize)]
+/// Records of the loaded months. Past months live in a [`HistoryStore`] once
+/// [`open_archive`](History::open_archive) was called, queries only see the months read with
+/// [`load_range`](History::load_range) or [`load_all`](History::load_all).
+#[derive(Deserialize)]
+#[serde(from = "HistoryData")]
 pub struct History {
     records: HashMap<Uuid, HistoryRecord>,
+    /// Record ids by the local date they start on.
+    by_day: BTreeMap<NaiveDate, Vec<Uuid>>,
+    /// Duration of the longest record, how far back a range query has to look.
+    longest: Duration,
+    /// Bumped on every change, so derived data can tell when it is stale.
+    revision: u64,
+    archive: Archive,
+    /// Notes left on sessions, by record id. Kept whole in the main data, also for records
+    /// of months in the store.
+    notes: BTreeMap<Uuid, String>,
+}
+
+/// Months of records kept apart from the main data, written once the month is over and again
+/// only when one of its records changes.
+#[derive(Default)]
+struct Archive {
+    /// `None` keeps every record in the main data.
+    store: Option<Box<dyn HistoryStore>>,
+    /// Months in the store, by their first day.
+    stored: BTreeSet<NaiveDate>,
+    loaded: HashSet<NaiveDate>,
+    /// Stored months that could not be read. They are never overwritten, new records of
+    /// them stay in the main data.
+    unreadable: HashSet<NaiveDate>,
+    /// Months whose records changed since they were written.
+    changed: HashSet<NaiveDate>,
+    /// First month the main data keeps, older ones are left to the store.
+    main_from: Option<NaiveDate>,
+}
+
+/// First day of the month of `day`.
+pub fn month_of(day: NaiveDate) -> NaiveDate {
+    day.with_day(1).unwrap()
+}
+
+pub fn next_month(month: NaiveDate) -> NaiveDate {
+    month_of(month + Duration::days(31))
+}
+
+fn record_month(record: &HistoryRecord) -> NaiveDate {
+    month_of(record.start_date.date_naive())
+}
+
+/// Saves the records the main file keeps, oldest first so new records only ever add to the
+/// end of the file.
+impl Serialize for History {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let archive = &self.archive;
+
+        let mut records = self
+            .records
+            .values()
+            .filter(|v| {
+                let month = record_month(v);
+
+                archive.main_from.is_none_or(|v| month >= v)
+                    || !archive.stored.contains(&month)
+                    || archive.changed.contains(&month)
+            })
+            .collect::<Vec<_>>();
+        records.sort();
+
+        let mut state = serializer.serialize_struct("History", 2)?;
+        state.serialize_field("records", &RecordMap(records))?;
+
+        if self.notes.is_empty() {
+            state.skip_field("notes")?;
+        } else {
+            state.serialize_field("notes", &self.notes)?;
+        }
+
+        state.end()
+    }
+}
+
+struct RecordMap<'a>(Vec<&'a HistoryRecord>);
+
+impl Serialize for RecordMap<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|v| (v.id, v)))
+    }
+}
+
+#[derive(Deserialize)]
+struct HistoryData {
+    records: HashMap<Uuid, HistoryRecord>,
+    #[serde(default)]
+    notes: BTreeMap<Uuid, String>,
+}
+
+impl From<HistoryData> for History {
+    fn from(value: HistoryData) -> Self {
+        let mut history = History::new();
+
+        for record in value.records.into_values() {
+            history.insert(record);
+        }
+
+        history.notes = value.notes;
+
+        history
+    }
 }
 
 impl History {
     pub fn new() -> Self {
         History {
             records: HashMap::new(),
+            by_day: BTreeMap::new(),
+            longest: Duration::zero(),
+            revision: 0,
+            archive: Archive::default(),
+            notes: BTreeMap::new(),
+        }
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Keeps past months in `store` from now on. Months already there are read when a
+    /// query reaches them, records of them the main data still held are merged into the
+    /// store on the next write. Without a readable list of months the store is left alone.
+    pub fn open_archive(&mut self, mut store: Box<dyn HistoryStore>) {
+        let stored = match store.months() {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("failed to list the months of the history: {e}");
+                return;
+            }
+        };
+
+        let held = self
+            .by_day
+            .keys()
+            .map(|v| month_of(*v))
+            .collect::<BTreeSet<_>>();
+
+        self.archive = Archive {
+            changed: held.intersection(&stored).copied().collect(),
+            store: Some(store),
+            stored,
+            ..Archive::default()
+        };
+    }
+
+    /// Whether records start before the month of `day`, which the main data of an archive
+    /// does not keep.
+    pub fn starts_before_month(&self, day: NaiveDate) -> bool {
+        self.by_day.range(..month_of(day)).next().is_some()
+    }
+
+    fn load_month(&mut self, month: NaiveDate) {
+        let archive = &mut self.archive;
+
+        let Some(store) = &mut archive.store else {
+            return;
+        };
+
+        if !archive.stored.contains(&month)
+            || archive.loaded.contains(&month)
+            || archive.unreadable.contains(&month)
+        {
+            return;
+        }
+
+        let records = match store.read_month(month) {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!(
+                    "failed to read the history of {}: {e}",
+                    month.format("%Y-%m")
+                );
+                archive.unreadable.insert(month);
+                return;
+            }
+        };
+
+        archive.loaded.insert(month);
+
+        // Records also in the main data were saved there after the store, theirs win.
+        for record in records {
+            if let Entry::Vacant(entry) = self.records.entry(record.id) {
+                entry.insert(record);
+                self.index(&record);
+            }
         }
+
+        self.revision += 1;
     }
 
-    pub fn update(&mut self, id: Uuid) {
+    /// Reads the months of records that may overlap the local dates `from..=to`. Records
+    /// are looked for from the day before, one of the month before may run into the first
+    /// day.
+    pub fn load_range(&mut self, from: NaiveDate, to: NaiveDate) {
+        let from = month_of(from.pred_opt().unwrap_or(from));
+
+        if from > to {
+            return;
+        }
+
+        let months = self
+            .archive
+            .stored
+            .range(from..=to)
+            .copied()
+            .collect::<Vec<_>>();
+
+        for month in months {
+            self.load_month(month);
+        }
+    }
+
+    /// Reads every month, for whatever looks at the whole history.
+    pub fn load_all(&mut self) {
+        let months = self.archive.stored.iter().copied().collect::<Vec<_>>();
+
+        for month in months {
+            self.load_month(month);
+        }
+    }
+
+    /// Writes the months the store keeps that are new or changed, after which the main data
+    /// leaves them out. Unless the store keeps it, the month of `today` stays in the main
+    /// data, as do months that cannot be written.
+    pub fn write_months(&mut self, today: NaiveDate) {
+        let Some(store) = &self.archive.store else {
+            return;
+        };
+
+        let until = if store.keeps_current_month() {
+            NaiveDate::MAX
+        } else {
+            month_of(today)
+        };
+
+        let months = self
+            .by_day
+            .range(..until)
+            .map(|v| month_of(*v.0))
+            .filter(|v| !self.archive.stored.contains(v))
+            .chain(self.archive.changed.iter().copied().filter(|v| *v < until))
+            .filter(|v| !self.archive.unreadable.contains(v))
+            .collect::<BTreeSet<_>>();
+
+        for month in months {
+            self.load_month(month);
+
+            let mut records = self
+                .by_day
+                .range(month..next_month(month))
+                .flat_map(|(_, ids)| ids.iter())
+                .filter_map(|id| self.records.get(id))
+                .collect::<Vec<_>>();
+            records.sort();
+
+            let archive = &mut self.archive;
+
+            let Some(store) = &mut archive.store else {
+                return;
+            };
+
+            if let Err(e) = store.write_month(month, &records) {
+                eprintln!(
+                    "failed to save the history of {}: {e}",
+                    month.format("%Y-%m")
+                );
+                continue;
+            }
+
+            if records.is_empty() {
+                archive.stored.remove(&month);
+            } else {
+                archive.stored.insert(month);
+                archive.loaded.insert(month);
+            }
+
+            archive.changed.remove(&month);
+        }
+
+        self.archive.main_from = Some(until);
+    }
+
+    /// Marks the month of a record as needing to be written again.
+    fn touch(&mut self, record: &HistoryRecord) {
+        if self.archive.store.is_some() {
+            self.archive.changed.insert(record_month(record));
+        }
+    }
+
+    fn index(&mut self, record: &HistoryRecord) {
+        self.longest = self.longest.max(record.get_duration());
+
+        self.by_day
+            .entry(record.start_date.date_naive())
+            .or_default()
+            .push(record.id);
+    }
+
+    fn unindex(&mut self, record: &HistoryRecord) {
+        self.touch(record);
+
+        let day = record.start_date.date_naive();
+
+        if let Some(ids) = self.by_day.get_mut(&day) {
+            ids.retain(|v| *v != record.id);
+
+            if ids.is_empty() {
+                self.by_day.remove(&day);
+            }
+        }
+    }
+
+    /// Records starting within the local dates of `from..=to`.
+    fn records_by_start_day(
+        &self,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> impl Iterator<Item = &HistoryRecord> {
+        let days = if from <= to {
+            Some(self.by_day.range(from.date_naive()..=to.date_naive()))
+        } else {
+            None
+        };
+
+        days.into_iter()
+            .flatten()
+            .flat_map(|(_, ids)| ids.iter())
+            .filter_map(|id| self.records.get(id))
+    }
+
+    /// Ends a record at `now`, or where it starts when `now` is before that.
+    pub fn update(&mut self, id: Uuid, now: DateTime<Local>) {
         if let Some(session) = self.records.get_mut(&id) {
-            session.end_date = DateTime::from(SystemTime::now());
+            session.end_date = now.max(session.start_date);
+            self.longest = self.longest.max(session.get_duration());
+            self.revision += 1;
+
+            let session = *session;
+            self.touch(&session);
         }
     }
 
-    pub fn add_record(&mut self, project_id: Uuid, sub_project_id: Uuid, subject_id: Uuid) -> Uuid {
+    /// End of the newest loaded record.
+    pub fn latest_end(&self) -> Option<DateTime<Local>> {
+        self.records.values().map(|v| v.end_date).max()
+    }
+
+    pub fn get(&self, id: &Uuid) -> Option<&HistoryRecord> {
+        self.records.get(id)
+    }
+
+    /// Replaces the span of a record, returning the previous one.
+    pub fn set_times(
+        &mut self,
+        id: Uuid,
+        start_date: DateTime<Local>,
+        end_date: DateTime<Local>,
+    ) -> Option<(DateTime<Local>, DateTime<Local>)> {
+        let mut record = *self.records.get(&id)?;
+
+        let previous = (record.start_date, record.end_date);
+
+        self.unindex(&record);
+
+        record.start_date = start_date;
+        record.end_date = end_date;
+
+        self.insert(record);
+
+        Some(previous)
+    }
+
+    pub fn remove(&mut self, id: &Uuid) -> Option<HistoryRecord> {
+        let record = self.records.remove(id)?;
+
+        self.unindex(&record);
+        self.notes.remove(id);
+        self.revision += 1;
+
+        Some(record)
+    }
+
+    /// Moves the end of a record back to `end_date`, returning the cut off part.
+    pub fn trim_end(&mut self, id: Uuid, end_date: DateTime<Local>) -> Option<Duration> {
+        let record = self.records.get_mut(&id)?;
+
+        if end_date >= record.end_date || end_date < record.start_date {
+            return None;
+        }
+
+        let cut = record.end_date.signed_duration_since(end_date);
+        record.end_date = end_date;
+        self.revision += 1;
+
+        let record = *record;
+        self.touch(&record);
+
+        Some(cut)
+    }
+
+    pub fn add_record(
+        &mut self,
+        project_id: Uuid,
+        sub_project_id: Uuid,
+        subject_id: Uuid,
+        now: DateTime<Local>,
+    ) -> Uuid {
         let id = Uuid::new_v4();
 
-        self.records.insert(
+        self.insert(HistoryRecord {
             id,
-            HistoryRecord {
-                id,
-                start_date: DateTime::from(SystemTime::now()),
-                end_date: DateTime::from(SystemTime::now()),
-                project_id,
-                sub_project_id,
-                subject_id,
-            },
-        );
+            start_date: now,
+            end_date: now,
+            project_id,
+            sub_project_id,
+            subject_id,
+        });
+
+        id
+    }
+
+    /// Points the matching records at other projects or sub projects after an entry moved.
+    pub fn retarget(
+        &mut self,
+        filter: impl Fn(&HistoryRecord) -> bool,
+        update: impl Fn(&mut HistoryRecord),
+    ) {
+        self.load_all();
+
+        for record in self.records.values_mut().filter(|v| filter(v)) {
+            update(record);
+            self.archive.changed.insert(record_month(record));
+        }
+
+        self.revision += 1;
+    }
+
+    /// Loaded records, everything after [`load_all`](Self::load_all).
+    pub fn records(&self) -> impl Iterator<Item = &HistoryRecord> {
+        self.records.values()
+    }
+
+    /// Ids of every project, sub project and subject a record points at.
+    pub fn referenced_ids(&self) -> HashSet<Uuid> {
+        self.records
+            .values()
+            .flat_map(|v| [v.project_id, v.sub_project_id, v.subject_id])
+            .collect()
+    }
+
+    /// Adds or replaces a record, reading its month first so the month file keeps the
+    /// records already in it.
+    pub(crate) fn insert(&mut self, record: HistoryRecord) {
+        self.load_month(record_month(&record));
+
+        if let Some(previous) = self.records.insert(record.id, record) {
+            self.unindex(&previous);
+        }
+
+        self.index(&record);
+        self.touch(&record);
+        self.revision += 1;
+    }
+
+    pub fn add_manual_record(
+        &mut self,
+        project_id: Uuid,
+        sub_project_id: Uuid,
+        subject_id: Uuid,
+        start_date: DateTime<Local>,
+        end_date: DateTime<Local>,
+    ) -> Uuid {
+        let id = Uuid::new_v4();
+
+        self.insert(HistoryRecord {
+            id,
+            start_date,
+            end_date,
+            project_id,
+            sub_project_id,
+            subject_id,
+        });
 
         id
     }
 
+    /// Cuts a record in two at `at`, which has to lie strictly inside it. Returns the id of
+    /// the second part.
+    pub fn split(&mut self, id: Uuid, at: DateTime<Local>) -> Option<Uuid> {
+        let record = *self.records.get(&id)?;
+
+        if at <= record.start_date || at >= record.end_date {
+            return None;
+        }
+
+        let rest = HistoryRecord {
+            id: Uuid::new_v4(),
+            start_date: at,
+            ..record
+        };
+
+        self.insert(HistoryRecord {
+            end_date: at,
+            ..record
+        });
+        self.insert(rest);
+
+        Some(rest.id)
+    }
+
+    /// Cuts a record at every local midnight it spans, so each part lies within one day.
+    /// Returns the id of the last part.
+    pub fn split_days(&mut self, id: Uuid) -> Uuid {
+        let Some(mut record) = self.records.get(&id).copied() else {
+            return id;
+        };
+
+        for (_, midnight, _) in day_parts(record.start_date, record.end_date)
+            .into_iter()
+            .skip(1)
+        {
+            let rest = HistoryRecord {
+                id: Uuid::new_v4(),
+                start_date: midnight,
+                ..record
+            };
+
+            record.end_date = midnight - Duration::milliseconds(1);
+
+            self.insert(record);
+            self.insert(rest);
+
+            record = rest;
+        }
+
+        record.id
+    }
+
+    /// Records within the range bucketed by day, records spanning several days are split
+    /// into one part per day.
     pub fn get_ordered_records(
         &self,
         date_range: (DateTime<Local>, DateTime<Local>),
     ) -> Vec<Vec<HistoryRecord>> {
-        let number_of_days = date_range.1.signed_duration_since(date_range.0).num_days() + 1;
+        let first_day = date_range.0.date_naive();
+        let number_of_days = date_range
+            .1
+            .date_naive()
+            .signed_duration_since(first_day)
+            .num_days()
+            + 1;
 
         let mut res: Vec<Vec<HistoryRecord>> = (0..number_of_days).map(|_| Vec::new()).collect();
 
         let mut r: Vec<HistoryRecord> = self
-            .records
-            .values()
-            .filter(|v| v.start_date >= date_range.0 && v.start_date <= date_range.1)
+            .records_by_start_day(date_range.0 - self.longest, date_range.1)
+            .filter(|v| v.start_date <= date_range.1 && v.end_date >= date_range.0)
             .copied()
             .collect();
 
         r.sort();
 
         for record in r {
-            let ind = (record
-                .start_date
-                .signed_duration_since(date_range.0)
-                .num_days()) as usize;
-
-            if record.start_date.day() != record.end_date.day() {
-                let mut first_record = record;
-                first_record.end_date = Local
-                    .with_ymd_and_hms(
-                        record.start_date.year(),
-                        record.start_date.month(),
-                        record.start_date.day(),
-                        23,
-                        59,
-                        59,
-                    )
-                    .unwrap();
-
-                res.get_mut(ind).unwrap().push(first_record);
-
-                if let Some(vec) = res.get_mut(ind + 1) {
-                    let mut second_record = record;
-                    second_record.start_date = Local
-                        .with_ymd_and_hms(
-                            record.end_date.year(),
-                            record.end_date.month(),
-                            record.end_date.day(),
-                            00,
-                            00,
-                            00,
-                        )
-                        .unwrap();
-
-                    vec.push(second_record);
+            for (day, start_date, end_date) in day_parts(record.start_date, record.end_date) {
+                let part = HistoryRecord {
+                    start_date,
+                    end_date,
+                    ..record
+                };
+
+                let ind = day.signed_duration_since(first_day).num_days();
+
+                if ind < 0 {
+                    continue;
                 }
-            } else {
-                res.get_mut(ind).unwrap().push(record);
+
+                let Some(bucket) = res.get_mut(ind as usize) else {
+                    break;
+                };
+
+                bucket.push(part);
             }
         }
 
         res
     }
 
+    /// Tracked time of every day from `from` to `to`, records spanning midnight count on
+    /// both days.
+    pub fn daily_totals(&self, from: NaiveDate, to: NaiveDate) -> Vec<Duration> {
+        let range = (
+            start_of_day(from),
+            start_of_day(to + Duration::days(1)) - Duration::milliseconds(1),
+        );
+
+        self.get_ordered_records(range)
+            .iter()
+            .map(|day| {
+                day.iter()
+                    .fold(Duration::zero(), |s, v| s + v.get_duration())
+            })
+            .collect()
+    }
+
+    /// Time of the range per weekday and per hour of the day, split by project. Records are
+    /// cut at every hour edge, so each part lands in the hour and weekday it happened in.
+    pub fn breakdown(&self, date_range: (DateTime<Local>, DateTime<Local>)) -> Breakdown {
+        let mut breakdown = Breakdown::default();
+
+        for record in self.get_records(date_range) {
+            let mut start = record.start_date;
+
+            while start < record.end_date {
+                let hour_start = start
+                    .with_minute(0)
+                    .and_then(|v| v.with_second(0))
+                    .and_then(|v| v.with_nanosecond(0))
+                    .unwrap_or(start);
+                let end = (hour_start + Duration::hours(1)).min(record.end_date);
+
+                for bucket in [
+                    &mut breakdown.weekdays[start.weekday().num_days_from_monday() as usize],
+                    &mut breakdown.hours[start.hour() as usize],
+                ] {
+                    let v = bucket
+                        .entry(record.project_id)
+                        .or_insert_with(Duration::zero);
+                    *v = *v + end.signed_duration_since(start);
+                }
+
+                start = end;
+            }
+        }
+
+        breakdown
+    }
+
+    /// Runs of records starting within the range that [`coalesce`](Self::coalesce) would
+    /// merge. A run holds consecutive records of one subject on one day, each starting less
+    /// than `max_gap` after the run ends, and is at least two records long.
+    fn coalesce_runs(
+        &self,
+        date_range: (DateTime<Local>, DateTime<Local>),
+        max_gap: Duration,
+    ) -> Vec<Vec<HistoryRecord>> {
+        let mut records: Vec<HistoryRecord> = self
+            .records_by_start_day(date_range.0, date_range.1)
+            .filter(|v| v.start_date >= date_range.0 && v.start_date <= date_range.1)
+            .copied()
+            .collect();
+        records.sort();
+
+        let mut runs = Vec::new();
+        let mut run: Vec<HistoryRecord> = Vec::new();
+        let mut run_end = date_range.0;
+
+        for record in records {
+            let joins = run.first().is_some_and(|first| {
+                first.project_id == record.project_id
+                    && first.sub_project_id == record.sub_project_id
+                    && first.subject_id == record.subject_id
+                    && first.start_date.date_naive() == record.start_date.date_naive()
+                    && record.start_date.signed_duration_since(run_end) < max_gap
+            });
+
+            if !joins {
+                if run.len() > 1 {
+                    runs.push(std::mem::take(&mut run));
+                }
+
+                run.clear();
+                run_end = record.end_date;
+            }
+
+            run_end = run_end.max(record.end_date);
+            run.push(record);
+        }
+
+        if run.len() > 1 {
+            runs.push(run);
+        }
+
+        runs
+    }
+
+    /// How many records [`coalesce`](Self::coalesce) would merge into others.
+    pub fn coalesce_preview(
+        &self,
+        date_range: (DateTime<Local>, DateTime<Local>),
+        max_gap: Duration,
+    ) -> usize {
+        self.coalesce_runs(date_range, max_gap)
+            .iter()
+            .map(|v| v.len() - 1)
+            .sum()
+    }
+
+    /// Merges consecutive records of the same subject separated by less than `max_gap`.
+    /// Each run keeps the id of its last record, so a running session stays addressable,
+    /// and the notes of its records one below the other.
+    pub fn coalesce(
+        &mut self,
+        date_range: (DateTime<Local>, DateTime<Local>),
+        max_gap: Duration,
+    ) -> Vec<MergedRun> {
+        let mut merged = Vec::new();
+
+        for run in self.coalesce_runs(date_range, max_gap) {
+            let last = run[run.len() - 1];
+
+            let record = HistoryRecord {
+                start_date: run[0].start_date,
+                end_date: run
+                    .iter()
+                    .map(|v| v.end_date)
+                    .max()
+                    .unwrap_or(last.end_date),
+                ..last
+            };
+
+            let notes = run
+                .iter()
+                .filter_map(|v| self.note(&v.id))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            for v in &run[..run.len() - 1] {
+                self.remove(&v.id);
+            }
+
+            self.insert(record);
+            self.set_note(record.id, &notes);
+
+            merged.push(MergedRun {
+                record,
+                absorbed: run.len() - 1,
+                gained: run
+                    .iter()
+                    .fold(record.get_duration(), |s, v| s - v.get_duration()),
+            });
+        }
+
+        merged
+    }
+
+    /// Newest record of each of the last `n` distinct subjects, newest first. Subjects whose
+    /// record is turned down by `keep` are passed over.
+    pub fn recent_subjects(
+        &self,
+        n: usize,
+        keep: impl Fn(&HistoryRecord) -> bool,
+    ) -> Vec<HistoryRecord> {
+        let mut seen = HashSet::new();
+        let mut recent = Vec::new();
+
+        for ids in self.by_day.values().rev() {
+            let mut day = ids
+                .iter()
+                .filter_map(|id| self.records.get(id))
+                .collect::<Vec<_>>();
+
+            day.sort_by_key(|v| std::cmp::Reverse(v.start_date));
+
+            for record in day {
+                if recent.len() == n {
+                    return recent;
+                }
+
+                if seen.insert(record.subject_id) && keep(record) {
+                    recent.push(*record);
+                }
+            }
+        }
+
+        recent
+    }
+
+    pub fn note(&self, id: &Uuid) -> Option<&str> {
+        self.notes.get(id).map(String::as_str)
+    }
+
+    /// Leaves a note on a loaded record, an empty one removes it. False when there is no
+    /// such record.
+    pub fn set_note(&mut self, id: Uuid, note: &str) -> bool {
+        if !self.records.contains_key(&id) {
+            return false;
+        }
+
+        match note.trim() {
+            "" => self.notes.remove(&id),
+            note => self.notes.insert(id, note.to_string()),
+        };
+
+        self.revision += 1;
+
+        true
+    }
+
+    /// Note of the newest loaded session of the subject that has one. Walks the days back
+    /// from the newest, so only the days after that session are looked at.
+    pub fn last_note_for_subject(&self, subject_id: Uuid) -> Option<&str> {
+        for ids in self.by_day.values().rev() {
+            let newest = ids
+                .iter()
+                .filter_map(|id| self.records.get(id))
+                .filter(|v| v.subject_id == subject_id && self.notes.contains_key(&v.id))
+                .max_by_key(|v| v.start_date);
+
+            if let Some(record) = newest {
+                return self.note(&record.id);
+            }
+        }
+
+        None
+    }
+
+    /// Records overlapping the range, clipped to it.
     pub fn get_records(
         &self,
         date_range: (DateTime<Local>, DateTime<Local>),
     ) -> Vec<HistoryRecord> {
-        self.records
-            .values()
-            .filter(|v| v.start_date >= date_range.0 && v.start_date < date_range.1)
-            .copied()
+        self.records_by_start_day(date_range.0 - self.longest, date_range.1)
+            .filter(|v| v.start_date < date_range.1 && v.end_date > date_range.0)
+            .map(|v| HistoryRecord {
+                start_date: v.start_date.max(date_range.0),
+                end_date: v.end_date.min(date_range.1),
+                ..*v
+            })
             .collect()
     }
 }
@@ -126,26 +860,804 @@ pub struct HistoryRecord {
 
 impl Eq for HistoryRecord {}
 
+/// Records are the same record when their ids match, whatever their spans.
 impl PartialEq<Self> for HistoryRecord {
     fn eq(&self, other: &Self) -> bool {
-        self.start_date.eq(&other.start_date)
+        self.id.eq(&other.id)
     }
 }
 
 impl PartialOrd<Self> for HistoryRecord {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.start_date.partial_cmp(&other.start_date)
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for HistoryRecord {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.start_date.cmp(&other.start_date)
+        self.start_date
+            .cmp(&other.start_date)
+            .then_with(|| self.id.cmp(&other.id))
     }
 }
 
 impl HistoryRecord {
+    /// Whether every field matches, `==` only compares the ids.
+    pub fn same_fields(&self, other: &Self) -> bool {
+        (
+            self.id,
+            self.start_date,
+            self.end_date,
+            self.project_id,
+            self.sub_project_id,
+            self.subject_id,
+        ) == (
+            other.id,
+            other.start_date,
+            other.end_date,
+            other.project_id,
+            other.sub_project_id,
+            other.subject_id,
+        )
+    }
+
+    /// Never negative, even for records written while the clock was set back.
+    pub fn get_duration(&self) -> Duration {
+        self.end_date
+            .signed_duration_since(self.start_date)
+            .max(Duration::zero())
+    }
+}
+
+/// Parts of the span from `start` to `end` on each local day it touches, with the day. Every
+/// part but the last ends a millisecond before midnight, a span ending right at midnight has
+/// no part on the next day.
+fn day_parts<Tz: TimeZone>(
+    start: DateTime<Tz>,
+    end: DateTime<Tz>,
+) -> Vec<(NaiveDate, DateTime<Tz>, DateTime<Tz>)> {
+    let mut parts = Vec::new();
+    let mut day = start.date_naive();
+    let mut part_start = start;
+
+    while let Some(next_day) = day.succ_opt() {
+        let midnight = resolve_local(&end.timezone(), next_day.and_time(NaiveTime::MIN));
+        let part_end = end
+            .clone()
+            .min(midnight.clone() - Duration::milliseconds(1));
+
+        parts.push((day, part_start, part_end));
+
+        if end <= midnight {
+            return parts;
+        }
+
+        day = next_day;
+        part_start = midnight;
+    }
+
+    parts.push((day, part_start, end));
+
+    parts
+}
+
+/// Running total of tracked time at the end of each day of an ordered day list.
+pub fn cumulative_totals(days: &[Vec<HistoryRecord>]) -> Vec<Duration> {
+    let mut total = Duration::zero();
+
+    days.iter()
+        .map(|records| {
+            total = records.iter().fold(total, |s, v| s + v.get_duration());
+
+            total
+        })
+        .collect()
+}
+
+/// Tracked time per project, bucketed by weekday from Monday and by hour of the day.
+#[derive(Default)]
+pub struct Breakdown {
+    pub weekdays: [HashMap<Uuid, Duration>; 7],
+    pub hours: [HashMap<Uuid, Duration>; 24],
+}
+
+/// One record [`History::coalesce`] produced.
+pub struct MergedRun {
+    pub record: HistoryRecord,
+    /// Records merged into it besides the one whose id it kept.
+    pub absorbed: usize,
+    /// Time the merged record spans beyond its parts, negative when the parts overlapped.
+    pub gained: Duration,
+}
+
+/// Parts of `window` no record covers, in order. Overlapping records count once.
+pub fn untracked_gaps(
+    records: &[HistoryRecord],
+    window: (DateTime<Local>, DateTime<Local>),
+) -> Vec<(DateTime<Local>, DateTime<Local>)> {
+    let mut spans: Vec<_> = records.iter().map(|v| (v.start_date, v.end_date)).collect();
+    spans.sort();
+
+    let mut gaps = Vec::new();
+    let mut covered_until = window.0;
+
+    for (start, end) in spans {
+        if covered_until >= window.1 {
+            break;
+        }
+
+        if start > covered_until {
+            gaps.push((covered_until, start.min(window.1)));
+        }
+
+        covered_until = covered_until.max(end);
+    }
+
+    if covered_until < window.1 {
+        gaps.push((covered_until, window.1));
+    }
+
+    gaps
+}
+
+/// Consecutive records of one subject drawn as a single timeline bar.
+pub struct RecordGroup {
+    pub records: Vec<HistoryRecord>,
+}
+
+impl RecordGroup {
+    pub fn start_date(&self) -> DateTime<Local> {
+        self.records.first().unwrap().start_date
+    }
+
+    pub fn end_date(&self) -> DateTime<Local> {
+        self.records.iter().map(|v| v.end_date).max().unwrap()
+    }
+
     pub fn get_duration(&self) -> Duration {
-        self.end_date.signed_duration_since(self.start_date)
+        self.end_date().signed_duration_since(self.start_date())
+    }
+
+    pub fn head(&self) -> &HistoryRecord {
+        self.records.first().unwrap()
+    }
+}
+
+/// Groups an ordered day slice so that records of the same subject separated by less than
+/// `max_gap` end up together. Only used for rendering, stored records stay untouched.
+pub fn group_adjacent_records(records: &[HistoryRecord], max_gap: Duration) -> Vec<RecordGroup> {
+    let mut res: Vec<RecordGroup> = Vec::new();
+
+    for record in records {
+        if let Some(group) = res.last_mut() {
+            let head = group.head();
+
+            if head.project_id == record.project_id
+                && head.sub_project_id == record.sub_project_id
+                && head.subject_id == record.subject_id
+                && record.start_date.signed_duration_since(group.end_date()) < max_gap
+            {
+                group.records.push(*record);
+
+                continue;
+            }
+        }
+
+        res.push(RecordGroup {
+            records: vec![*record],
+        });
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const PROJECT: Uuid = Uuid::from_u128(1);
+    const SUB_PROJECT: Uuid = Uuid::from_u128(2);
+    const WRITING: Uuid = Uuid::from_u128(3);
+    const REVIEW: Uuid = Uuid::from_u128(4);
+
+    fn at(h: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2023, 3, 14, h, min, 0).unwrap()
+    }
+
+    fn record(subject_id: Uuid, start: (u32, u32), end: (u32, u32)) -> HistoryRecord {
+        HistoryRecord {
+            id: Uuid::new_v4(),
+            start_date: at(start.0, start.1),
+            end_date: at(end.0, end.1),
+            project_id: PROJECT,
+            sub_project_id: SUB_PROJECT,
+            subject_id,
+        }
+    }
+
+    impl HistoryRecord {
+        /// The same record with its start and end moved.
+        fn shifted(self, start: Duration, end: Duration) -> Self {
+            HistoryRecord {
+                start_date: self.start_date + start,
+                end_date: self.end_date + end,
+                ..self
+            }
+        }
+    }
+
+    fn group_sizes(records: &[HistoryRecord], max_gap: Duration) -> Vec<usize> {
+        group_adjacent_records(records, max_gap)
+            .iter()
+            .map(|v| v.records.len())
+            .collect()
+    }
+
+    #[test]
+    fn chained_records_form_one_group() {
+        let records = [
+            record(WRITING, (9, 0), (9, 30)),
+            record(WRITING, (9, 32), (10, 0)),
+            record(WRITING, (10, 4), (11, 0)),
+        ];
+
+        let groups = group_adjacent_records(&records, Duration::minutes(5));
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].records, records);
+        assert_eq!(groups[0].start_date(), at(9, 0));
+        assert_eq!(groups[0].end_date(), at(11, 0));
+        assert_eq!(groups[0].get_duration(), Duration::hours(2));
+    }
+
+    #[test]
+    fn gap_is_measured_from_the_group_end() {
+        // The second record ends after the third, which starts close to the group end but
+        // far from the end of the record before it.
+        let records = [
+            record(WRITING, (9, 0), (9, 10)),
+            record(WRITING, (9, 12), (10, 0)),
+            record(WRITING, (9, 30), (9, 40)),
+            record(WRITING, (10, 3), (10, 30)),
+        ];
+
+        assert_eq!(group_sizes(&records, Duration::minutes(5)), [4]);
+    }
+
+    #[test]
+    fn other_subjects_break_the_chain() {
+        let records = [
+            record(WRITING, (9, 0), (9, 30)),
+            record(REVIEW, (9, 31), (9, 40)),
+            record(WRITING, (9, 41), (10, 0)),
+            record(WRITING, (10, 1), (10, 30)),
+        ];
+
+        let groups = group_adjacent_records(&records, Duration::minutes(5));
+
+        assert_eq!(
+            groups
+                .iter()
+                .map(|v| v.head().subject_id)
+                .collect::<Vec<_>>(),
+            [WRITING, REVIEW, WRITING]
+        );
+        assert_eq!(group_sizes(&records, Duration::minutes(5)), [1, 1, 2]);
+    }
+
+    #[test]
+    fn other_sub_projects_break_the_chain() {
+        let mut moved = record(WRITING, (9, 31), (10, 0));
+        moved.sub_project_id = Uuid::from_u128(5);
+
+        let records = [record(WRITING, (9, 0), (9, 30)), moved];
+
+        assert_eq!(group_sizes(&records, Duration::minutes(5)), [1, 1]);
+    }
+
+    #[test]
+    fn gap_threshold_is_exclusive() {
+        let records = |gap: u32| {
+            [
+                record(WRITING, (9, 0), (9, 30)),
+                record(WRITING, (9, 30 + gap), (10, 0)),
+            ]
+        };
+
+        assert_eq!(group_sizes(&records(4), Duration::minutes(5)), [2]);
+        assert_eq!(group_sizes(&records(5), Duration::minutes(5)), [1, 1]);
+        assert_eq!(group_sizes(&records(6), Duration::minutes(5)), [1, 1]);
+    }
+
+    #[test]
+    fn zero_gap_groups_only_overlaps() {
+        let records = [
+            record(WRITING, (9, 0), (9, 30)),
+            record(WRITING, (9, 20), (9, 40)),
+            record(WRITING, (9, 40), (10, 0)),
+        ];
+
+        assert_eq!(group_sizes(&records, Duration::zero()), [2, 1]);
+        assert!(group_adjacent_records(&[], Duration::minutes(5)).is_empty());
+    }
+
+    #[test]
+    fn cumulative_totals_carry_over_empty_days() {
+        let days = vec![
+            vec![
+                record(WRITING, (9, 0), (10, 0)),
+                record(REVIEW, (10, 0), (10, 30)),
+            ],
+            vec![],
+            vec![record(WRITING, (13, 0), (13, 15))],
+        ];
+
+        assert_eq!(
+            cumulative_totals(&days),
+            [
+                Duration::minutes(90),
+                Duration::minutes(90),
+                Duration::minutes(105)
+            ]
+        );
+        assert!(cumulative_totals(&[]).is_empty());
+    }
+
+    #[test]
+    fn cumulative_totals_skip_backward_records() {
+        // Written while the clock was set back.
+        let days = vec![vec![
+            record(WRITING, (10, 0), (9, 0)),
+            record(WRITING, (11, 0), (11, 20)),
+        ]];
+
+        assert_eq!(cumulative_totals(&days), [Duration::minutes(20)]);
+    }
+
+    #[test]
+    fn cumulative_totals_follow_the_running_session() {
+        let mut history = History::new();
+        let id = history.add_record(PROJECT, SUB_PROJECT, WRITING, at(9, 0));
+        history.add_manual_record(
+            PROJECT,
+            SUB_PROJECT,
+            REVIEW,
+            at(7, 0) - Duration::days(1),
+            at(8, 0) - Duration::days(1),
+        );
+
+        let range = (
+            start_of_day(at(0, 0).date_naive() - Duration::days(1)),
+            at(23, 59),
+        );
+
+        history.update(id, at(9, 30));
+
+        assert_eq!(
+            cumulative_totals(&history.get_ordered_records(range)),
+            [Duration::hours(1), Duration::minutes(90)]
+        );
+
+        history.update(id, at(10, 15));
+
+        assert_eq!(
+            cumulative_totals(&history.get_ordered_records(range)),
+            [Duration::hours(1), Duration::minutes(135)]
+        );
+    }
+
+    #[test]
+    fn records_started_together_stay_distinct() {
+        let first = record(WRITING, (9, 0), (10, 0));
+        let second = HistoryRecord {
+            id: Uuid::new_v4(),
+            ..first
+        };
+
+        assert_ne!(first, second);
+        assert!(!first.same_fields(&second));
+        assert_eq!(
+            first,
+            HistoryRecord {
+                end_date: at(11, 0),
+                ..first
+            }
+        );
+
+        let set: BTreeSet<HistoryRecord> = [first, second].into();
+
+        assert_eq!(set.len(), 2);
+
+        let mut history = History::new();
+        history.insert(first);
+        history.insert(second);
+
+        assert_eq!(history.records().count(), 2);
+    }
+
+    #[test]
+    fn records_order_by_start_then_id() {
+        let mut records = (0..8)
+            .map(|v| HistoryRecord {
+                id: Uuid::from_u128(8 - v),
+                ..record(WRITING, (9, 0), (9, 30))
+            })
+            .collect::<Vec<_>>();
+        records.push(record(WRITING, (8, 0), (8, 30)));
+        records.sort();
+
+        assert_eq!(records[0].start_date, at(8, 0));
+        assert!(records[1..].windows(2).all(|v| v[0].id < v[1].id));
+
+        let mut reversed = records.clone();
+        reversed.reverse();
+        reversed.sort();
+
+        assert!(reversed.iter().zip(&records).all(|(a, b)| a.same_fields(b)));
+    }
+
+    fn history_of(records: &[HistoryRecord]) -> History {
+        let mut history = History::new();
+
+        for record in records {
+            history.insert(*record);
+        }
+
+        history
+    }
+
+    fn spans(records: Vec<HistoryRecord>) -> Vec<(DateTime<Local>, DateTime<Local>)> {
+        let mut spans = records
+            .iter()
+            .map(|v| (v.start_date, v.end_date))
+            .collect::<Vec<_>>();
+        spans.sort();
+        spans
+    }
+
+    #[test]
+    fn range_queries_clip_records_straddling_both_ends() {
+        let day = Duration::days(1);
+        let history = history_of(&[
+            // From the evening before into the range.
+            record(WRITING, (23, 0), (2, 0)).shifted(-day, Duration::zero()),
+            // Inside.
+            record(WRITING, (9, 0), (10, 0)),
+            // From the range into the next morning.
+            record(REVIEW, (23, 0), (1, 0)).shifted(Duration::zero(), day),
+        ]);
+
+        let records = history.get_records((at(0, 0), at(0, 0) + day));
+
+        assert_eq!(
+            spans(records.clone()),
+            [
+                (at(0, 0), at(2, 0)),
+                (at(9, 0), at(10, 0)),
+                (at(23, 0), at(0, 0) + day),
+            ]
+        );
+        assert_eq!(
+            records
+                .iter()
+                .fold(Duration::zero(), |s, v| s + v.get_duration()),
+            Duration::hours(4)
+        );
+    }
+
+    #[test]
+    fn range_queries_see_records_longer_than_the_range() {
+        let day = Duration::days(1);
+        let history = history_of(&[record(WRITING, (12, 0), (12, 0)).shifted(-day * 2, day)]);
+
+        assert_eq!(
+            spans(history.get_records((at(9, 0), at(10, 0)))),
+            [(at(9, 0), at(10, 0))]
+        );
+    }
+
+    #[test]
+    fn range_queries_skip_records_only_touching_the_range() {
+        let history = history_of(&[
+            record(WRITING, (8, 0), (9, 0)),
+            record(WRITING, (10, 0), (11, 0)),
+        ]);
+
+        assert!(history.get_records((at(9, 0), at(10, 0))).is_empty());
+        assert_eq!(
+            spans(history.get_records((at(8, 59), at(10, 1)))),
+            [(at(8, 59), at(9, 0)), (at(10, 0), at(10, 1))]
+        );
+    }
+
+    #[test]
+    fn three_day_records_split_into_one_part_per_day() {
+        let day = Duration::days(1);
+        let mut history = History::new();
+        let id =
+            history.add_manual_record(PROJECT, SUB_PROJECT, WRITING, at(22, 0), at(3, 0) + day * 2);
+
+        let days = history.get_ordered_records((at(0, 0), at(23, 59) + day * 3));
+
+        assert_eq!(
+            days.iter().map(|v| spans(v.clone())).collect::<Vec<_>>(),
+            [
+                vec![(at(22, 0), at(0, 0) + day - Duration::milliseconds(1))],
+                vec![(
+                    at(0, 0) + day,
+                    at(0, 0) + day * 2 - Duration::milliseconds(1)
+                )],
+                vec![(at(0, 0) + day * 2, at(3, 0) + day * 2)],
+                vec![],
+            ]
+        );
+
+        let last = history.split_days(id);
+
+        assert_eq!(history.records().count(), 3);
+        assert_eq!(history.get(&last).unwrap().start_date, at(0, 0) + day * 2);
+        assert_eq!(
+            spans(history.records().copied().collect()),
+            days.concat()
+                .iter()
+                .map(|v| (v.start_date, v.end_date))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn records_ending_at_midnight_stay_on_their_day() {
+        let mut history = History::new();
+        let id = history.add_manual_record(
+            PROJECT,
+            SUB_PROJECT,
+            WRITING,
+            at(22, 0),
+            at(0, 0) + Duration::days(1),
+        );
+
+        let days = history.get_ordered_records((at(0, 0), at(23, 59) + Duration::days(1)));
+
+        assert_eq!(days[0].len(), 1);
+        assert!(days[1].is_empty());
+        assert_eq!(history.split_days(id), id);
+        assert_eq!(history.records().count(), 1);
+    }
+
+    fn tz_at(
+        tz: chrono_tz::Tz,
+        (y, m, d): (i32, u32, u32),
+        (h, min): (u32, u32),
+    ) -> DateTime<chrono_tz::Tz> {
+        tz.with_ymd_and_hms(y, m, d, h, min, 0).single().unwrap()
+    }
+
+    fn part_lengths<Tz: TimeZone>(parts: &[(NaiveDate, DateTime<Tz>, DateTime<Tz>)]) -> Vec<i64> {
+        parts
+            .iter()
+            .map(|(_, start, end)| {
+                end.clone()
+                    .signed_duration_since(start.clone())
+                    .num_minutes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dst_nights_split_at_local_midnight() {
+        let berlin = chrono_tz::Europe::Berlin;
+
+        // Clocks go forward at 2:00, the night is an hour shorter.
+        let parts = day_parts(
+            tz_at(berlin, (2024, 3, 30), (22, 0)),
+            tz_at(berlin, (2024, 3, 31), (6, 0)),
+        );
+
+        assert_eq!(parts[1].1, tz_at(berlin, (2024, 3, 31), (0, 0)));
+        assert_eq!(part_lengths(&parts), [119, 5 * 60]);
+
+        // Clocks go back at 3:00, the night is an hour longer.
+        let parts = day_parts(
+            tz_at(berlin, (2024, 10, 26), (22, 0)),
+            tz_at(berlin, (2024, 10, 27), (6, 0)),
+        );
+
+        assert_eq!(part_lengths(&parts), [119, 7 * 60]);
+    }
+
+    #[test]
+    fn dst_skipping_midnight_starts_the_day_at_the_first_valid_time() {
+        let sao_paulo = chrono_tz::America::Sao_Paulo;
+
+        // Clocks went from midnight straight to 1:00.
+        let parts = day_parts(
+            tz_at(sao_paulo, (2018, 11, 3), (22, 0)),
+            tz_at(sao_paulo, (2018, 11, 4), (3, 0)),
+        );
+
+        assert_eq!(parts[0].0, NaiveDate::from_ymd_opt(2018, 11, 3).unwrap());
+        assert_eq!(parts[1].0, NaiveDate::from_ymd_opt(2018, 11, 4).unwrap());
+        assert_eq!(parts[1].1, tz_at(sao_paulo, (2018, 11, 4), (1, 0)));
+        assert_eq!(part_lengths(&parts), [119, 2 * 60]);
+    }
+
+    fn minutes(bucket: &HashMap<Uuid, Duration>, project_id: Uuid) -> i64 {
+        bucket.get(&project_id).map_or(0, |v| v.num_minutes())
+    }
+
+    #[test]
+    fn breakdown_splits_records_at_hour_edges() {
+        let history = history_of(&[record(WRITING, (9, 40), (11, 10))]);
+
+        let breakdown = history.breakdown((at(0, 0), at(23, 59)));
+        let hours = breakdown
+            .hours
+            .iter()
+            .map(|v| minutes(v, PROJECT))
+            .collect::<Vec<_>>();
+
+        assert_eq!(hours[9..12], [20, 60, 10]);
+        assert_eq!(hours.iter().sum::<i64>(), 90);
+
+        // 2023-03-14 is a Tuesday.
+        assert_eq!(minutes(&breakdown.weekdays[1], PROJECT), 90);
+        assert!(breakdown
+            .weekdays
+            .iter()
+            .enumerate()
+            .all(|(i, v)| i == 1 || v.is_empty()));
+    }
+
+    #[test]
+    fn breakdown_splits_records_at_midnight_by_weekday() {
+        let history = history_of(&[
+            record(WRITING, (23, 30), (0, 45)).shifted(Duration::zero(), Duration::days(1))
+        ]);
+
+        let breakdown = history.breakdown((at(0, 0), at(0, 0) + Duration::days(2)));
+
+        assert_eq!(minutes(&breakdown.hours[23], PROJECT), 30);
+        assert_eq!(minutes(&breakdown.hours[0], PROJECT), 45);
+        assert_eq!(minutes(&breakdown.weekdays[1], PROJECT), 30);
+        assert_eq!(minutes(&breakdown.weekdays[2], PROJECT), 45);
+    }
+
+    #[test]
+    fn breakdown_clips_to_the_range_and_keeps_projects_apart() {
+        let mut other = record(REVIEW, (10, 0), (10, 30));
+        other.project_id = Uuid::from_u128(6);
+
+        let history = history_of(&[record(WRITING, (9, 0), (11, 0)), other]);
+
+        let breakdown = history.breakdown((at(9, 30), at(10, 15)));
+
+        assert_eq!(minutes(&breakdown.hours[9], PROJECT), 30);
+        assert_eq!(minutes(&breakdown.hours[10], PROJECT), 15);
+        assert_eq!(minutes(&breakdown.hours[10], other.project_id), 15);
+        assert_eq!(minutes(&breakdown.weekdays[1], PROJECT), 45);
+        assert_eq!(minutes(&breakdown.weekdays[1], other.project_id), 15);
+    }
+
+    fn whole_day() -> (DateTime<Local>, DateTime<Local>) {
+        (at(0, 0), at(0, 0) + Duration::days(1))
+    }
+
+    #[test]
+    fn coalesce_merges_records_inserted_out_of_order() {
+        let seconds = Duration::seconds;
+        let first = record(WRITING, (9, 0), (9, 30));
+        let second = record(WRITING, (9, 30), (10, 0)).shifted(seconds(20), Duration::zero());
+        let third = record(WRITING, (10, 0), (10, 30)).shifted(seconds(15), Duration::zero());
+        // Past the gap, so it stays on its own.
+        let fourth = record(WRITING, (10, 31), (11, 0));
+
+        let mut history = history_of(&[fourth, third, first, second]);
+
+        assert_eq!(history.coalesce_preview(whole_day(), seconds(30)), 2);
+
+        let merged = history.coalesce(whole_day(), seconds(30));
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].absorbed, 2);
+        assert_eq!(merged[0].gained, seconds(35));
+        assert_eq!(merged[0].record.id, third.id);
+        assert_eq!(
+            spans(history.get_records(whole_day())),
+            [(at(9, 0), at(10, 30)), (at(10, 31), at(11, 0))]
+        );
+        assert_eq!(history.coalesce_preview(whole_day(), seconds(30)), 0);
+    }
+
+    #[test]
+    fn coalesce_never_merges_different_subjects() {
+        let gap = Duration::seconds(10);
+        let mut history = history_of(&[
+            record(WRITING, (10, 0), (10, 30)).shifted(gap, Duration::zero()),
+            record(REVIEW, (9, 30), (10, 0)),
+            record(WRITING, (9, 0), (9, 30)),
+            // Same subject under another sub-project.
+            HistoryRecord {
+                sub_project_id: Uuid::from_u128(5),
+                ..record(WRITING, (10, 30), (11, 0))
+            },
+        ]);
+
+        assert_eq!(
+            history.coalesce_preview(whole_day(), Duration::minutes(1)),
+            0
+        );
+        assert!(history
+            .coalesce(whole_day(), Duration::minutes(1))
+            .is_empty());
+        assert_eq!(history.get_records(whole_day()).len(), 4);
+    }
+
+    #[test]
+    fn last_note_is_found_walking_the_days_back() {
+        let day = Duration::days(1);
+        let noted = record(WRITING, (9, 0), (10, 0)).shifted(-day * 3, -day * 3);
+        let older = record(WRITING, (8, 0), (8, 30)).shifted(-day * 3, -day * 3);
+        // Newer, but without a note or of another subject.
+        let plain = record(WRITING, (9, 0), (10, 0)).shifted(-day, -day);
+        let other = record(REVIEW, (11, 0), (12, 0));
+
+        let mut history = history_of(&[noted, older, plain, other]);
+
+        assert_eq!(history.last_note_for_subject(WRITING), None);
+
+        assert!(history.set_note(older.id, "Outline"));
+        assert!(history.set_note(noted.id, "  Left off at the parser  "));
+        assert!(history.set_note(other.id, "Review"));
+
+        assert_eq!(history.note(&noted.id), Some("Left off at the parser"));
+        assert_eq!(
+            history.last_note_for_subject(WRITING),
+            Some("Left off at the parser")
+        );
+        assert_eq!(history.last_note_for_subject(REVIEW), Some("Review"));
+
+        assert!(history.set_note(plain.id, "Tests"));
+        assert_eq!(history.last_note_for_subject(WRITING), Some("Tests"));
+
+        // Removing the note or the record goes back to the one before.
+        assert!(history.set_note(plain.id, " "));
+        assert_eq!(history.note(&plain.id), None);
+        history.remove(&noted.id);
+        assert_eq!(history.last_note_for_subject(WRITING), Some("Outline"));
+
+        assert!(!history.set_note(noted.id, "Gone"));
+        assert_eq!(history.note(&noted.id), None);
+    }
+
+    #[test]
+    fn notes_load_back_and_are_left_out_when_there_are_none() {
+        let first = record(WRITING, (9, 0), (10, 0));
+        let mut history = history_of(&[first, record(REVIEW, (10, 0), (11, 0))]);
+
+        assert!(!ron::to_string(&history).unwrap().contains("notes"));
+
+        history.set_note(first.id, "Left off at the parser");
+
+        let loaded: History = ron::from_str(&ron::to_string(&history).unwrap()).unwrap();
+
+        assert_eq!(loaded.note(&first.id), Some("Left off at the parser"));
+        assert_eq!(loaded.records().count(), 2);
+    }
+
+    #[test]
+    fn coalesced_records_keep_every_note() {
+        let first = record(WRITING, (9, 0), (9, 30));
+        let second = record(WRITING, (9, 30), (10, 0));
+        let third = record(WRITING, (10, 0), (10, 30));
+
+        let mut history = history_of(&[first, second, third]);
+        history.set_note(first.id, "Outline");
+        history.set_note(third.id, "Draft");
+
+        history.coalesce(whole_day(), Duration::minutes(1));
+
+        assert_eq!(history.note(&third.id), Some("Outline\nDraft"));
+        assert_eq!(history.note(&first.id), None);
     }
 }
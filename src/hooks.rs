@@ -0,0 +1,70 @@
+//! Shell commands run when a session starts or stops, to set a chat status or a light.
+//!
+//! The session is passed in `RUH_EVENT` (`start` or `stop`), `RUH_PROJECT`,
+//! `RUH_SUB_PROJECT`, `RUH_SUBJECT` and, on stop, `RUH_DURATION_SECONDS`.
+
+use crate::backend::RecordPath;
+use std::process::{Command, Stdio};
+
+#[derive(Copy, Clone)]
+pub enum HookEvent {
+    Start,
+    Stop { duration: chrono::Duration },
+}
+
+/// Starts `command` in the platform shell without waiting for it, failures are logged.
+pub fn run(command: &str, event: HookEvent, path: &RecordPath) {
+    let command = command.trim();
+
+    if command.is_empty() {
+        return;
+    }
+
+    #[cfg(windows)]
+    let mut shell = {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    };
+
+    #[cfg(not(windows))]
+    let mut shell = {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    };
+
+    shell
+        .env("RUH_PROJECT", &path.project)
+        .env("RUH_SUB_PROJECT", &path.sub_project)
+        .env("RUH_SUBJECT", &path.subject)
+        .stdin(Stdio::null());
+
+    match event {
+        HookEvent::Start => {
+            shell.env("RUH_EVENT", "start");
+        }
+        HookEvent::Stop { duration } => {
+            shell
+                .env("RUH_EVENT", "stop")
+                .env("RUH_DURATION_SECONDS", duration.num_seconds().to_string());
+        }
+    }
+
+    let mut child = match shell.spawn() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("failed to run hook \"{command}\": {e}");
+            return;
+        }
+    };
+
+    let command = command.to_string();
+
+    // Waited on elsewhere so a slow command never holds up tracking.
+    std::thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => eprintln!("hook \"{command}\" failed: {status}"),
+        Err(e) => eprintln!("hook \"{command}\" failed: {e}"),
+        _ => {}
+    });
+}
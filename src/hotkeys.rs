@@ -0,0 +1,278 @@
+use crate::i18n::tr;
+use eframe::egui::{InputState, Key, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Keys that can be bound, anything else is ignored while rebinding.
+const BINDABLE_KEYS: [Key; 49] = [
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::Num0,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::Space,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+];
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum HotkeyAction {
+    TimeView,
+    StatisticView,
+    TodoView,
+    MinimalView,
+    StartPause,
+    Notes,
+    Palette,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 7] = [
+        HotkeyAction::TimeView,
+        HotkeyAction::StatisticView,
+        HotkeyAction::TodoView,
+        HotkeyAction::MinimalView,
+        HotkeyAction::StartPause,
+        HotkeyAction::Notes,
+        HotkeyAction::Palette,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::TimeView => tr!("Time view"),
+            HotkeyAction::StatisticView => tr!("Statistic view"),
+            HotkeyAction::TodoView => tr!("Todo view"),
+            HotkeyAction::MinimalView => tr!("Minimal view"),
+            HotkeyAction::StartPause => tr!("Start / pause"),
+            HotkeyAction::Notes => tr!("Notes"),
+            HotkeyAction::Palette => tr!("Go to subject"),
+        }
+    }
+
+    /// Name the binding is saved under, must never change.
+    fn id(self) -> &'static str {
+        match self {
+            HotkeyAction::TimeView => "time_view",
+            HotkeyAction::StatisticView => "statistic_view",
+            HotkeyAction::TodoView => "todo_view",
+            HotkeyAction::MinimalView => "minimal_view",
+            HotkeyAction::StartPause => "start_pause",
+            HotkeyAction::Notes => "notes",
+            HotkeyAction::Palette => "palette",
+        }
+    }
+
+    fn default_hotkey(self) -> Hotkey {
+        let (key, modifiers) = match self {
+            HotkeyAction::TimeView => (Key::Q, Modifiers::NONE),
+            HotkeyAction::StatisticView => (Key::W, Modifiers::NONE),
+            HotkeyAction::TodoView => (Key::E, Modifiers::NONE),
+            HotkeyAction::MinimalView => (Key::D, Modifiers::NONE),
+            HotkeyAction::StartPause => (Key::Space, Modifiers::NONE),
+            HotkeyAction::Notes => (Key::N, Modifiers::NONE),
+            HotkeyAction::Palette => (Key::K, Modifiers::COMMAND),
+        };
+
+        Hotkey::new(key, modifiers)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Hotkey {
+    pub(crate) key: Key,
+    pub(crate) modifiers: Modifiers,
+}
+
+impl Hotkey {
+    /// Only ctrl, alt and shift are kept, so a binding means the same on every platform.
+    pub fn new(key: Key, modifiers: Modifiers) -> Self {
+        Self {
+            key,
+            modifiers: Modifiers {
+                alt: modifiers.alt,
+                ctrl: modifiers.ctrl || modifiers.command,
+                shift: modifiers.shift,
+                mac_cmd: false,
+                command: modifiers.ctrl || modifiers.command,
+            },
+        }
+    }
+
+    pub fn is_bindable(key: Key) -> bool {
+        BINDABLE_KEYS.contains(&key)
+    }
+
+    pub fn pressed(&self, input: &InputState) -> bool {
+        input.key_pressed(self.key)
+            && input.modifiers.alt == self.modifiers.alt
+            && input.modifiers.command == self.modifiers.command
+            && input.modifiers.shift == self.modifiers.shift
+    }
+
+    /// Parses the `Ctrl+Shift+Q` form written by [`Display`](fmt::Display).
+    fn parse(value: &str) -> Option<Self> {
+        let mut modifiers = Modifiers::NONE;
+        let mut parts = value.split('+').map(str::trim).collect::<Vec<_>>();
+        let key = parts.pop()?;
+
+        for part in parts {
+            match part {
+                "Ctrl" => modifiers.ctrl = true,
+                "Alt" => modifiers.alt = true,
+                "Shift" => modifiers.shift = true,
+                _ => return None,
+            }
+        }
+
+        let key = BINDABLE_KEYS.into_iter().find(|v| v.name() == key)?;
+
+        Some(Self::new(key, modifiers))
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+
+        if self.modifiers.alt {
+            write!(f, "Alt+")?;
+        }
+
+        if self.modifiers.shift {
+            write!(f, "Shift+")?;
+        }
+
+        write!(f, "{}", self.key.name())
+    }
+}
+
+/// Saved as action id to binding text. Unknown actions are dropped and bindings that no
+/// longer parse fall back to the default of their action.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(transparent)]
+struct HotkeysData(BTreeMap<String, String>);
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(from = "HotkeysData", into = "HotkeysData")]
+pub struct Hotkeys {
+    bindings: HashMap<HotkeyAction, Hotkey>,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self {
+            bindings: HotkeyAction::ALL
+                .into_iter()
+                .map(|v| (v, v.default_hotkey()))
+                .collect(),
+        }
+    }
+}
+
+impl From<HotkeysData> for Hotkeys {
+    fn from(data: HotkeysData) -> Self {
+        let mut hotkeys = Hotkeys::default();
+
+        for action in HotkeyAction::ALL {
+            let Some(hotkey) = data.0.get(action.id()).and_then(|v| Hotkey::parse(v)) else {
+                continue;
+            };
+
+            if hotkeys.conflict(action, hotkey).is_none() {
+                hotkeys.bindings.insert(action, hotkey);
+            }
+        }
+
+        hotkeys
+    }
+}
+
+impl From<Hotkeys> for HotkeysData {
+    fn from(hotkeys: Hotkeys) -> Self {
+        HotkeysData(
+            hotkeys
+                .bindings
+                .into_iter()
+                .map(|(k, v)| (k.id().to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+}
+
+impl Hotkeys {
+    pub fn get(&self, action: HotkeyAction) -> Hotkey {
+        self.bindings
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_hotkey())
+    }
+
+    /// Other action already bound to `hotkey`, if any.
+    pub fn conflict(&self, action: HotkeyAction, hotkey: Hotkey) -> Option<HotkeyAction> {
+        HotkeyAction::ALL
+            .into_iter()
+            .find(|v| *v != action && self.get(*v) == hotkey)
+    }
+
+    /// Binds `hotkey` to `action` unless another action uses it, returning that action.
+    pub fn set(&mut self, action: HotkeyAction, hotkey: Hotkey) -> Result<(), HotkeyAction> {
+        if let Some(other) = self.conflict(action, hotkey) {
+            return Err(other);
+        }
+
+        self.bindings.insert(action, hotkey);
+
+        Ok(())
+    }
+
+    /// First action whose binding was pressed this frame.
+    pub fn pressed(&self, input: &InputState) -> Option<HotkeyAction> {
+        HotkeyAction::ALL
+            .into_iter()
+            .find(|v| self.get(*v).pressed(input))
+    }
+}
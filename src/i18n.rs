@@ -0,0 +1,198 @@
+//! Translations of the interface. The English text in the code is the key of every string,
+//! a catalog maps those keys to another language and anything it lacks stays English.
+//!
+//! Catalogs are RON files like `locales/de.ron`. The bundled ones are built in, further ones
+//! put into a `locales` folder in the working directory show up in the settings, and one
+//! with the code of a bundled catalog replaces it. Each is read once, when its language is
+//! first used.
+
+use chrono::{Month, Weekday};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+const LOCALES_DIR: &str = "./locales";
+
+const BUNDLED: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en.ron")),
+    ("de", include_str!("../locales/de.ron")),
+];
+
+#[derive(Deserialize)]
+struct CatalogFile {
+    /// Name of the language in that language.
+    name: String,
+    strings: HashMap<String, String>,
+}
+
+type Catalog = HashMap<String, &'static str>;
+
+/// Catalog in use. Catalogs are leaked when loaded so translations live as long as their
+/// keys, each language is loaded at most once.
+static CATALOG: RwLock<Option<&'static Catalog>> = RwLock::new(None);
+
+/// Catalogs loaded so far by language code, `None` for those that could not be read.
+static LOADED: Mutex<Option<HashMap<String, Option<&'static Catalog>>>> = Mutex::new(None);
+
+pub struct Language {
+    pub(crate) code: String,
+    pub(crate) name: String,
+}
+
+/// Translation of `key`, or `key` itself when the catalog has none.
+pub fn translate(key: &str) -> &str {
+    let catalog = *CATALOG.read().unwrap();
+
+    catalog.and_then(|v| v.get(key).copied()).unwrap_or(key)
+}
+
+/// Replaces every `{name}` of `text` with its value.
+pub fn fill(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter().fold(text.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{name}}}"), &value.to_string())
+    })
+}
+
+/// Translates a string literal. Placeholders are named and their values passed like
+/// `tr!("{n} records merged", n = merged)`, so a translation may reorder them.
+macro_rules! tr {
+    ($key:literal) => {
+        $crate::i18n::translate($key)
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::fill(
+            $crate::i18n::translate($key),
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+
+pub(crate) use tr;
+
+fn read_catalog(code: &str) -> Option<CatalogFile> {
+    let path = Path::new(LOCALES_DIR).join(format!("{code}.ron"));
+
+    let text = match std::fs::read_to_string(path) {
+        Ok(v) => v,
+        Err(_) => BUNDLED.iter().find(|v| v.0 == code)?.1.to_string(),
+    };
+
+    ron::from_str(&text)
+        .map_err(|e| eprintln!("failed to read the \"{code}\" catalog: {e}"))
+        .ok()
+}
+
+/// Switches the interface to the language with this code, English when it has no readable
+/// catalog.
+pub fn set_language(code: &str) {
+    let mut loaded = LOADED.lock().unwrap();
+
+    let catalog = *loaded
+        .get_or_insert_with(HashMap::new)
+        .entry(code.to_string())
+        .or_insert_with(|| {
+            read_catalog(code).map(|file| {
+                let catalog = file
+                    .strings
+                    .into_iter()
+                    .map(|(k, v)| (k, &*Box::leak(v.into_boxed_str())))
+                    .collect::<Catalog>();
+
+                &*Box::leak(Box::new(catalog))
+            })
+        });
+
+    *CATALOG.write().unwrap() = catalog;
+}
+
+/// Bundled languages and those in the locales folder, by name.
+pub fn languages() -> Vec<Language> {
+    let mut codes = BUNDLED.iter().map(|v| v.0.to_string()).collect::<Vec<_>>();
+
+    if let Ok(entries) = std::fs::read_dir(LOCALES_DIR) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            if path.extension().is_some_and(|v| v == "ron") {
+                if let Some(code) = path.file_stem().and_then(|v| v.to_str()) {
+                    if !codes.iter().any(|v| v == code) {
+                        codes.push(code.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    let mut languages = codes
+        .into_iter()
+        .filter_map(|code| {
+            let name = read_catalog(&code)?.name;
+            Some(Language { code, name })
+        })
+        .collect::<Vec<_>>();
+
+    languages.sort_by_key(|v| v.name.to_lowercase());
+
+    languages
+}
+
+pub fn month_name(month: Month) -> &'static str {
+    translate(month.name())
+}
+
+/// `Jan` to `Dec` by month number, 1 based.
+pub fn month_short(month: u32) -> &'static str {
+    const SHORT: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    translate(SHORT[(month as usize).clamp(1, 12) - 1])
+}
+
+pub fn weekday_name(day: Weekday) -> &'static str {
+    const NAMES: [&str; 7] = [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ];
+
+    translate(NAMES[day.num_days_from_monday() as usize])
+}
+
+pub fn weekday_short(day: Weekday) -> &'static str {
+    const SHORT: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+    translate(SHORT[day.num_days_from_monday() as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_catalogs_read() {
+        for (code, text) in BUNDLED {
+            assert!(ron::from_str::<CatalogFile>(text).is_ok(), "{code}");
+        }
+    }
+
+    /// English, so other tests running meanwhile still see the keys.
+    #[test]
+    fn catalogs_load_once() {
+        set_language(DEFAULT_LANGUAGE);
+        let first = CATALOG.read().unwrap().unwrap();
+
+        set_language(DEFAULT_LANGUAGE);
+        let second = CATALOG.read().unwrap().unwrap();
+
+        assert!(std::ptr::eq(first, second));
+    }
+}
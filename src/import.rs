@@ -0,0 +1,207 @@
+//! Reader for the detailed report CSV export of Toggl Track.
+
+use crate::util::to_local;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// One time entry of the export, mapped onto the project tree. Project becomes the project,
+/// task the sub project and description the subject.
+pub struct TogglEntry {
+    pub(crate) names: [String; 3],
+    pub(crate) start: DateTime<Local>,
+    pub(crate) end: DateTime<Local>,
+}
+
+pub struct TogglImport {
+    pub(crate) entries: Vec<TogglEntry>,
+    /// Rows whose dates could not be read.
+    pub(crate) invalid_rows: usize,
+}
+
+/// Names standing in for the empty columns of entries without project, task or description.
+const MISSING_NAMES: [&str; 3] = ["No project", "No task", "No description"];
+
+/// Splits CSV text into rows of fields, following the quoting rules of RFC 4180.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows
+}
+
+fn parse_date_time(date: &str, time: &str) -> Option<DateTime<Local>> {
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()?;
+    let time = NaiveTime::parse_from_str(time.trim(), "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time.trim(), "%H:%M"))
+        .ok()?;
+
+    Some(to_local(NaiveDateTime::new(date, time)))
+}
+
+/// Reads a detailed report export, `Err` when a needed column is missing.
+pub fn parse_toggl(text: &str) -> Result<TogglImport, String> {
+    let mut rows = parse_csv(text).into_iter();
+
+    let header = rows.next().unwrap_or_default();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|v| v.trim().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("the file has no \"{name}\" column"))
+    };
+
+    let project = column("Project")?;
+    let task = column("Task").ok();
+    let description = column("Description")?;
+    let start_date = column("Start date")?;
+    let start_time = column("Start time")?;
+    let end_date = column("End date")?;
+    let end_time = column("End time")?;
+
+    let mut import = TogglImport {
+        entries: Vec::new(),
+        invalid_rows: 0,
+    };
+
+    for row in rows {
+        if row.iter().all(|v| v.trim().is_empty()) {
+            continue;
+        }
+
+        let field = |i: usize| row.get(i).map_or("", |v| v.as_str());
+
+        let start = parse_date_time(field(start_date), field(start_time));
+        let end = parse_date_time(field(end_date), field(end_time));
+
+        let (Some(start), Some(end)) = (start, end) else {
+            import.invalid_rows += 1;
+            continue;
+        };
+
+        if end <= start {
+            import.invalid_rows += 1;
+            continue;
+        }
+
+        let names = [field(project), task.map_or("", field), field(description)];
+
+        import.entries.push(TogglEntry {
+            names: std::array::from_fn(|i| match names[i].trim() {
+                "" => MISSING_NAMES[i].to_string(),
+                name => name.to_string(),
+            }),
+            start,
+            end,
+        });
+    }
+
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const HEADER: &str =
+        "User,Email,Project,Task,Description,Start date,Start time,End date,End time\n";
+
+    fn at(h: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, 5, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn quoted_fields_keep_commas_quotes_and_line_breaks() {
+        let rows = parse_csv("a,\"b, c\",\"say \"\"hi\"\"\"\r\n\"two\nlines\",,\n");
+
+        assert_eq!(
+            rows,
+            [vec!["a", "b, c", "say \"hi\""], vec!["two\nlines", "", ""],]
+        );
+        assert_eq!(parse_csv("last,row"), [vec!["last", "row"]]);
+        assert!(parse_csv("").is_empty());
+    }
+
+    #[test]
+    fn entries_map_onto_the_tree() {
+        let import = parse_toggl(&format!(
+            "\u{feff}{HEADER}\
+            Ann,a@b.c,\"Client, Inc.\",Website,\"Fix \"\"navbar\"\"\",2024-03-05,09:00:00,2024-03-05,10:30\n\
+            Ann,a@b.c,,,,2024-03-05,11:00,2024-03-05,11:15\n\
+            ,,,,,,,,\n"
+        ))
+        .unwrap();
+
+        assert_eq!(import.invalid_rows, 0);
+
+        let [first, second] = &import.entries[..] else {
+            panic!("expected two entries");
+        };
+
+        assert_eq!(first.names, ["Client, Inc.", "Website", "Fix \"navbar\""]);
+        assert_eq!((first.start, first.end), (at(9, 0), at(10, 30)));
+        assert_eq!(second.names, MISSING_NAMES.map(String::from));
+    }
+
+    #[test]
+    fn task_column_is_optional() {
+        let import = parse_toggl(
+            "Project,Description,Start date,Start time,End date,End time\n\
+            Client,Navbar,2024-03-05,09:00,2024-03-05,10:00\n",
+        )
+        .unwrap();
+
+        assert_eq!(import.entries[0].names, ["Client", "No task", "Navbar"]);
+    }
+
+    #[test]
+    fn other_missing_columns_are_errors() {
+        let error = parse_toggl("Project,Task,Start date,Start time,End date,End time\n")
+            .err()
+            .unwrap();
+
+        assert!(error.contains("\"Description\""), "{error}");
+        assert!(parse_toggl("").is_err());
+    }
+
+    #[test]
+    fn rows_with_unreadable_or_reversed_times_are_counted() {
+        let import = parse_toggl(&format!(
+            "{HEADER}\
+            Ann,a@b.c,Client,Website,Navbar,2024-03-05,09:00,2024-03-05,10:00\n\
+            Ann,a@b.c,Client,Website,Navbar,05/03/2024,09:00,2024-03-05,10:00\n\
+            Ann,a@b.c,Client,Website,Navbar,2024-03-05,9 am,2024-03-05,10:00\n\
+            Ann,a@b.c,Client,Website,Navbar,2024-03-05,11:00,2024-03-05,10:00\n\
+            Ann,a@b.c,Client,Website,Navbar,2024-03-05,11:00,2024-03-05,11:00\n\
+            Ann,a@b.c,Client\n"
+        ))
+        .unwrap();
+
+        assert_eq!(import.entries.len(), 1);
+        assert_eq!(import.invalid_rows, 5);
+    }
+}
@@ -0,0 +1,368 @@
+//! Lock file telling other processes that a window owns the data file.
+//!
+//! It holds the process id of the window and the loopback port it listens on, a second
+//! window sends `raise` there instead of opening.
+
+use crate::tray::TrayCommand;
+use eframe::egui;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+pub const LOCK_PATH: &str = "./data.ron.lock";
+
+const RAISE: &str = "raise";
+
+/// How long a peer gets to connect or to send its message, and a new lock file to get its
+/// contents.
+const PEER_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Held by the window for as long as it runs, the lock file is removed on drop.
+pub struct InstanceLock {
+    pid: u32,
+    path: PathBuf,
+    listener: Option<TcpListener>,
+}
+
+/// Process holding the lock.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Owner {
+    pub(crate) pid: u32,
+    port: Option<u16>,
+}
+
+impl Owner {
+    fn connect(&self) -> Option<TcpStream> {
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, self.port?));
+
+        TcpStream::connect_timeout(&address, PEER_TIMEOUT).ok()
+    }
+
+    /// Asks the owner to show its window, false when it could not be reached.
+    pub fn raise(&self) -> bool {
+        self.connect()
+            .is_some_and(|mut v| writeln!(v, "{RAISE}").is_ok())
+    }
+}
+
+impl InstanceLock {
+    /// Takes the lock for this process, `Err` with the owner when another running process
+    /// holds it. A lock left behind by a process that is gone is taken over.
+    pub fn acquire() -> Result<Self, Owner> {
+        Self::acquire_at(Path::new(LOCK_PATH))
+    }
+
+    fn acquire_at(path: &Path) -> Result<Self, Owner> {
+        let pid = std::process::id();
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).ok();
+        let port = listener
+            .as_ref()
+            .and_then(|v| v.local_addr().ok())
+            .map(|v| v.port());
+
+        let text = match port {
+            Some(port) => format!("{pid} {port}"),
+            None => pid.to_string(),
+        };
+
+        let lock = Self {
+            pid,
+            path: path.to_path_buf(),
+            listener,
+        };
+
+        // Created only when there is none, so of two instances starting at once one wins.
+        // A lock of a process found to be gone is removed for another try.
+        for _ in 0..3 {
+            let created = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+                .and_then(|mut v| v.write_all(text.as_bytes()));
+
+            match created {
+                Ok(()) => return Ok(lock),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let holder = read_written_lock(path);
+
+                    if let Some(owner) = holder.as_ref().filter(|v| is_alive(v)) {
+                        return Err(*owner);
+                    }
+
+                    // Unless another instance took it over in the meantime.
+                    if read_lock(path) == holder {
+                        let _ = std::fs::remove_file(path);
+                    }
+                }
+                // Without a lock file nothing stops a second instance, this one still works.
+                Err(e) => {
+                    eprintln!("failed to write {}: {e}", path.display());
+                    return Ok(lock);
+                }
+            }
+        }
+
+        match owner_at(path) {
+            Some(owner) => Err(owner),
+            None => {
+                eprintln!("failed to take over {}", path.display());
+                Ok(lock)
+            }
+        }
+    }
+
+    /// Forwards `raise` requests of later instances as [`TrayCommand::OpenWindow`].
+    pub fn listen(&mut self, sender: Sender<TrayCommand>, ctx: egui::Context) {
+        let Some(listener) = self.listener.take() else {
+            return;
+        };
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+
+                let _ = stream.set_read_timeout(Some(PEER_TIMEOUT));
+
+                let mut line = String::new();
+
+                if BufReader::new(stream).read_line(&mut line).is_err() || line.trim() != RAISE {
+                    continue;
+                }
+
+                if sender.send(TrayCommand::OpenWindow).is_err() {
+                    return;
+                }
+
+                ctx.request_repaint();
+            }
+        });
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        if read_lock(&self.path).is_some_and(|v| v.pid == self.pid) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn read_lock(path: &Path) -> Option<Owner> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let mut parts = text.split_whitespace();
+
+    Some(Owner {
+        pid: parts.next()?.parse().ok()?,
+        port: parts.next().and_then(|v| v.parse().ok()),
+    })
+}
+
+/// Like [`read_lock`], but gives a lock file just created by another instance the time to
+/// get its contents. One still empty then was left behind by a process that died.
+fn read_written_lock(path: &Path) -> Option<Owner> {
+    let start = Instant::now();
+
+    loop {
+        let owner = read_lock(path);
+
+        if owner.is_some() || !path.exists() || start.elapsed() > PEER_TIMEOUT {
+            return owner;
+        }
+
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+/// Process holding the lock, `None` when it is free or its holder is gone.
+pub fn owner() -> Option<Owner> {
+    owner_at(Path::new(LOCK_PATH))
+}
+
+fn owner_at(path: &Path) -> Option<Owner> {
+    read_lock(path).filter(is_alive)
+}
+
+/// A holder that listens has to answer, so a reused process id does not keep a stale lock
+/// alive.
+fn is_alive(owner: &Owner) -> bool {
+    owner.pid != std::process::id()
+        && is_running(owner.pid)
+        && (owner.port.is_none() || owner.connect().is_some())
+}
+
+/// Whether a process with this id exists. Only known on Linux, elsewhere every process
+/// counts as running.
+fn is_running(pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    return Path::new(&format!("/proc/{pid}")).exists();
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use tempfile::TempDir;
+
+    /// A process that always runs and is never this one.
+    const INIT: u32 = 1;
+
+    /// Another window, listening for `raise` and handing on what it got.
+    fn fake_peer() -> (u16, std::sync::mpsc::Receiver<String>) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = channel();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut line = String::new();
+                let _ = BufReader::new(stream).read_line(&mut line);
+
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+
+        (port, receiver)
+    }
+
+    fn lock_path(dir: &TempDir) -> PathBuf {
+        dir.path().join("data.ron.lock")
+    }
+
+    fn holder(path: &Path) -> Option<u32> {
+        read_lock(path).map(|v| v.pid)
+    }
+
+    #[test]
+    fn free_lock_is_taken() {
+        let dir = TempDir::new().unwrap();
+        let path = lock_path(&dir);
+
+        let lock = InstanceLock::acquire_at(&path).unwrap();
+
+        assert_eq!(holder(&path), Some(std::process::id()));
+        assert!(read_lock(&path).unwrap().port.is_some());
+
+        drop(lock);
+
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn running_peer_is_raised() {
+        let dir = TempDir::new().unwrap();
+        let path = lock_path(&dir);
+        let (port, received) = fake_peer();
+        std::fs::write(&path, format!("{INIT} {port}")).unwrap();
+
+        let Err(owner) = InstanceLock::acquire_at(&path) else {
+            panic!("the lock of a running peer was taken");
+        };
+
+        assert_eq!(
+            owner,
+            Owner {
+                pid: INIT,
+                port: Some(port)
+            }
+        );
+        assert!(owner.raise());
+        // The check whether it is alive connected too, without a message.
+        let messages = received.iter().take(2).collect::<Vec<_>>();
+        assert_eq!(messages, ["", "raise\n"]);
+        assert_eq!(holder(&path), Some(INIT));
+    }
+
+    #[test]
+    fn silent_peer_is_taken_over() {
+        let dir = TempDir::new().unwrap();
+        let path = lock_path(&dir);
+
+        // A reused process id, whatever runs under it does not listen on the port.
+        let port = {
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        std::fs::write(&path, format!("{INIT} {port}")).unwrap();
+
+        let _lock = InstanceLock::acquire_at(&path).unwrap();
+
+        assert_eq!(holder(&path), Some(std::process::id()));
+    }
+
+    // Elsewhere every process id counts as running.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn lock_of_a_gone_process_is_taken_over() {
+        let dir = TempDir::new().unwrap();
+        let path = lock_path(&dir);
+        std::fs::write(&path, format!("{}", u32::MAX - 1)).unwrap();
+
+        let _lock = InstanceLock::acquire_at(&path).unwrap();
+
+        assert_eq!(holder(&path), Some(std::process::id()));
+    }
+
+    #[test]
+    fn lock_being_written_is_waited_for() {
+        let dir = TempDir::new().unwrap();
+        let path = lock_path(&dir);
+        let (port, _received) = fake_peer();
+        std::fs::File::create(&path).unwrap();
+
+        let writing = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(100));
+                std::fs::write(&path, format!("{INIT} {port}")).unwrap();
+            })
+        };
+
+        let Err(owner) = InstanceLock::acquire_at(&path) else {
+            panic!("the lock of a running peer was taken");
+        };
+        writing.join().unwrap();
+
+        assert_eq!(owner.pid, INIT);
+    }
+
+    #[test]
+    fn empty_lock_left_behind_is_taken_over() {
+        let dir = TempDir::new().unwrap();
+        let path = lock_path(&dir);
+        std::fs::File::create(&path).unwrap();
+
+        let _lock = InstanceLock::acquire_at(&path).unwrap();
+
+        assert_eq!(holder(&path), Some(std::process::id()));
+    }
+
+    #[test]
+    fn raise_reaches_the_window() {
+        let dir = TempDir::new().unwrap();
+        let path = lock_path(&dir);
+        let mut lock = InstanceLock::acquire_at(&path).unwrap();
+        let (sender, commands) = channel();
+
+        lock.listen(sender, egui::Context::default());
+
+        // What a second instance finds in the lock file.
+        let owner = read_lock(&path).unwrap();
+        assert!(owner.raise());
+
+        let command = commands.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(command, TrayCommand::OpenWindow));
+    }
+}
@@ -0,0 +1,153 @@
+//! Control socket for scripts, media keys and stream decks while the window runs.
+//!
+//! Each line sent to the socket is a JSON request answered by one JSON line:
+//! `{"cmd":"status"}`, `{"cmd":"start","path":"Client/Web/Fix navbar"}` or `{"cmd":"stop"}`.
+//! Requests are answered by the window in its next frame.
+
+use eframe::egui;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::mpsc::{Receiver, Sender};
+
+pub const SOCKET_PATH: &str = "./ruh_time_tracker.sock";
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+pub enum IpcCommand {
+    Status,
+    Start { path: String },
+    Stop,
+}
+
+pub struct IpcRequest {
+    pub(crate) command: IpcCommand,
+    pub(crate) reply: Sender<Value>,
+}
+
+/// Listens while it is alive, the socket is removed on drop.
+pub struct IpcServer {
+    pub(crate) requests: Receiver<IpcRequest>,
+    #[cfg(unix)]
+    _socket: unix::Socket,
+}
+
+pub fn error_reply(error: &str) -> Value {
+    json!({ "ok": false, "error": error })
+}
+
+/// Starts listening, `None` when the socket cannot be created or the platform has none.
+pub fn listen(ctx: egui::Context) -> Option<IpcServer> {
+    let (sender, requests) = std::sync::mpsc::channel();
+
+    #[cfg(unix)]
+    return unix::Socket::bind(sender, ctx).map(|v| IpcServer {
+        requests,
+        _socket: v,
+    });
+
+    #[cfg(not(unix))]
+    {
+        let _ = (sender, requests, ctx);
+        None
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::{error_reply, IpcCommand, IpcRequest, SOCKET_PATH};
+    use eframe::egui;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::{channel, Sender};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// How long a request waits for the window, which answers within a frame unless it is
+    /// hidden and not drawing.
+    const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub struct Socket {
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl Socket {
+        pub fn bind(sender: Sender<IpcRequest>, ctx: egui::Context) -> Option<Self> {
+            // The instance lock makes any socket left behind a stale one.
+            let _ = std::fs::remove_file(SOCKET_PATH);
+
+            let listener = UnixListener::bind(SOCKET_PATH)
+                .map_err(|e| eprintln!("failed to listen on {SOCKET_PATH}: {e}"))
+                .ok()?;
+
+            let stopped = Arc::new(AtomicBool::new(false));
+            let stop = stopped.clone();
+
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::Relaxed) {
+                        return;
+                    }
+
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+
+                    let sender = sender.clone();
+                    let ctx = ctx.clone();
+
+                    std::thread::spawn(move || serve(stream, sender, ctx));
+                }
+            });
+
+            Some(Self { stopped })
+        }
+    }
+
+    impl Drop for Socket {
+        fn drop(&mut self) {
+            self.stopped.store(true, Ordering::Relaxed);
+
+            // Wakes the listener up so it sees the flag.
+            let _ = UnixStream::connect(SOCKET_PATH);
+            let _ = std::fs::remove_file(SOCKET_PATH);
+        }
+    }
+
+    fn serve(stream: UnixStream, sender: Sender<IpcRequest>, ctx: egui::Context) {
+        let Ok(mut writer) = stream.try_clone() else {
+            return;
+        };
+
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                return;
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let reply = match serde_json::from_str::<IpcCommand>(&line) {
+                Ok(command) => {
+                    let (reply, receiver) = channel();
+
+                    if sender.send(IpcRequest { command, reply }).is_err() {
+                        return;
+                    }
+
+                    ctx.request_repaint();
+
+                    receiver
+                        .recv_timeout(REPLY_TIMEOUT)
+                        .unwrap_or_else(|_| error_reply("the window did not answer"))
+                }
+                Err(e) => error_reply(&format!("invalid request: {e}")),
+            };
+
+            if writeln!(writer, "{reply}").is_err() {
+                return;
+            }
+        }
+    }
+}
@@ -3,68 +3,242 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
 mod backend;
+mod cli;
+mod clock;
+mod crypto;
 mod frontend;
 mod history;
+mod hooks;
+mod hotkeys;
+mod i18n;
+mod import;
+mod instance;
+mod ipc;
+mod merge;
+mod palette;
+mod profiles;
+mod report;
+mod settings;
+mod sqlite;
+mod storage;
+mod system_events;
+mod test_data;
+mod tray;
 mod util;
+mod window_title;
 
-use crate::frontend::{DisplayMode, Frontend};
+use crate::frontend::{DisplayMode, Launcher};
+use crate::instance::InstanceLock;
+use crate::profiles::Profiles;
+use crate::settings::WindowState;
 use eframe::egui;
 use eframe::egui::Ui;
 
 fn main() -> Result<(), eframe::Error> {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+
+    // `--profile <name>` goes first and picks the data everything below works on, without it
+    // the profile used last.
+    let profile = match args.first().map(String::as_str) {
+        Some(profiles::FLAG) if args.len() > 1 => Some(args.drain(..2).nth(1).unwrap()),
+        Some(profiles::FLAG) => {
+            eprintln!("usage: --profile <name> [command]");
+            std::process::exit(2);
+        }
+        _ => None,
+    };
+
+    if let Err(e) = Profiles::load().activate(profile.as_deref()) {
+        eprintln!("{e}");
+        std::process::exit(2);
+    }
+
+    if args.first().map(String::as_str) == Some(test_data::FLAG) {
+        if let Err(e) = test_data::run(&args[1..]) {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+
+        return Ok(());
+    }
+
+    if args
+        .first()
+        .is_some_and(|v| cli::COMMANDS.contains(&v.as_str()))
+    {
+        if let Err(e) = cli::run(&args) {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+
+        return Ok(());
+    }
+
+    if args.first().map(String::as_str) == Some(report::FLAG) {
+        if let Err(e) = report::run(&args[1..]) {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+
+        return Ok(());
+    }
+
+    // A second window would overwrite the data of the first, it shows the first instead.
+    let instance_lock = match InstanceLock::acquire() {
+        Ok(v) => v,
+        Err(owner) if owner.raise() => return Ok(()),
+        Err(owner) => {
+            eprintln!(
+                "another tracker (process {}) owns the data file, remove {} if it is not running",
+                owner.pid,
+                instance::LOCK_PATH
+            );
+            std::process::exit(1);
+        }
+    };
+
+    // An encrypted data file is loaded once the window got its passphrase, one that fails to
+    // load is shown in the window instead.
+    let backend = frontend::load_unencrypted();
+    let window_state = backend
+        .as_ref()
+        .ok()
+        .and_then(Option::as_ref)
+        .map(|v| v.window_state.clone())
+        .unwrap_or_default();
+
     let options = eframe::NativeOptions {
         // Hide the OS-specific "chrome" around the window:
         decorated: false,
         // To have rounded corners we need transparency:
         transparent: true,
         resizable: false,
-        initial_window_size: Some(egui::vec2(800.0, 400.0)),
+        initial_window_size: Some(window_size(window_state.display_mode, &window_state)),
+        initial_window_pos: window_state.position.map(|(x, y)| egui::pos2(x, y)),
+        always_on_top: window_state.display_mode == DisplayMode::Minimal,
+        // Keeps `IntegrationInfo::system_theme` up to date for `Theme::System`.
+        follow_system_theme: true,
         ..Default::default()
     };
 
     eframe::run_native(
         "Ruh Time Tracker", // unused title
         options,
-        Box::new(|cc| Box::<Frontend>::new(Frontend::init(cc))),
+        Box::new(|cc| Box::new(Launcher::new(cc, backend, instance_lock))),
     )
 }
 
+fn title_bar_height(display_mode: DisplayMode) -> f32 {
+    match display_mode {
+        DisplayMode::Minimal => 8.,
+        _ => 24.,
+    }
+}
+
+/// Smallest size a resizable window can be dragged down to.
+const MIN_RESIZABLE_SIZE: egui::Vec2 = egui::vec2(800., 500.);
+
+/// Room left for panels and docks when a window would not fit the screen.
+const SCREEN_MARGIN: egui::Vec2 = egui::vec2(0., 48.);
+
+fn is_resizable(display_mode: DisplayMode) -> bool {
+    display_mode == DisplayMode::Statistic
+}
+
+/// Size the window takes in `display_mode`, the one the user picked when it can be resized.
+fn window_size(display_mode: DisplayMode, window_state: &WindowState) -> egui::Vec2 {
+    if is_resizable(display_mode) {
+        if let Some((width, height)) = window_state.sizes.get(&display_mode) {
+            return egui::vec2(*width, *height).max(MIN_RESIZABLE_SIZE);
+        }
+    }
+
+    let content = match display_mode {
+        DisplayMode::Statistic => egui::vec2(1200., 800.),
+        DisplayMode::Time | DisplayMode::Todo => egui::vec2(954., 400.),
+        DisplayMode::Minimal => egui::vec2(105., 80.),
+    };
+
+    content + egui::vec2(0., title_bar_height(display_mode))
+}
+
+/// Grip in the bottom right corner of resizable windows, grows `size` while dragged.
+fn resize_grip_ui(ui: &mut Ui, app_rect: egui::Rect, size: &mut egui::Vec2) {
+    use egui::*;
+
+    let rect = Rect::from_min_max(
+        app_rect.right_bottom() - vec2(16., 16.),
+        app_rect.right_bottom(),
+    );
+    let response = ui.interact(rect, Id::new("resize_grip"), Sense::drag());
+
+    if response.hovered() || response.dragged() {
+        ui.ctx().set_cursor_icon(CursorIcon::ResizeNwSe);
+    }
+
+    if response.dragged() {
+        *size = (*size + response.drag_delta()).max(MIN_RESIZABLE_SIZE);
+    }
+
+    let stroke = ui.visuals().widgets.noninteractive.fg_stroke;
+
+    for offset in [4., 8., 12.] {
+        ui.painter().line_segment(
+            [
+                rect.right_bottom() - vec2(offset, 2.),
+                rect.right_bottom() - vec2(2., offset),
+            ],
+            stroke,
+        );
+    }
+}
+
+/// Draws the window with a title bar to move it by, returns true when close was clicked.
+/// `opacity` only fades the background, the contents stay fully opaque. `size` is applied to
+/// the window and updated when a resizable window is dragged by its grip.
 fn custom_window_frame(
     ctx: &egui::Context,
     frame: &mut eframe::Frame,
-    _title: &str,
+    title: &str,
     display_mode: DisplayMode,
+    opacity: f32,
+    size: &mut egui::Vec2,
     add_contents: impl FnOnce(&mut Ui),
-) {
+) -> bool {
     use egui::*;
 
     let panel_frame = Frame {
-        fill: ctx.style().visuals.window_fill(),
+        fill: ctx.style().visuals.window_fill().gamma_multiply(opacity),
         rounding: 8.0.into(),
         stroke: ctx.style().visuals.widgets.noninteractive.fg_stroke,
         outer_margin: 0.5.into(), // so the stroke is within the bounds
         ..Default::default()
     };
 
-    match display_mode {
-        DisplayMode::Statistic => {
-            frame.set_window_size(Vec2::new(1200., 800.));
-        }
-
-        DisplayMode::Time | DisplayMode::Todo => {
-            frame.set_window_size(Vec2::new(954., 400.));
+    if is_resizable(display_mode) {
+        if let Some(monitor) = frame.info().window_info.monitor_size {
+            *size = size.min(monitor - SCREEN_MARGIN);
         }
+    }
 
-        DisplayMode::Minimal => {
-            frame.set_window_size(Vec2::new(105., 60.));
-            frame.set_always_on_top(true);
-        }
+    if display_mode == DisplayMode::Minimal {
+        frame.set_always_on_top(true);
     }
 
+    let mut close_requested = false;
+
     CentralPanel::default().frame(panel_frame).show(ctx, |ui| {
         let app_rect = ui.max_rect();
 
+        let title_bar_rect = {
+            let mut rect = app_rect;
+            rect.max.y = rect.min.y + title_bar_height(display_mode);
+            rect
+        };
+        close_requested = title_bar_ui(ui, frame, title_bar_rect, title, display_mode);
+
         let mut c = (*ctx.style()).clone();
         c.text_styles.insert(
             TextStyle::Button,
@@ -75,9 +249,100 @@ fn custom_window_frame(
         ctx.set_style(c);
 
         // Add the contents:
-        let content_rect = { app_rect }.shrink(4.0);
+        let content_rect = {
+            let mut rect = app_rect;
+            rect.min.y = title_bar_rect.max.y;
+            rect
+        }
+        .shrink(4.0);
 
         let mut content_ui = ui.child_ui(content_rect, *ui.layout());
         add_contents(&mut content_ui);
+
+        if is_resizable(display_mode) {
+            resize_grip_ui(ui, app_rect, size);
+        }
+    });
+
+    frame.set_window_size(*size);
+
+    close_requested
+}
+
+fn title_bar_ui(
+    ui: &mut Ui,
+    frame: &mut eframe::Frame,
+    title_bar_rect: egui::Rect,
+    title: &str,
+    display_mode: DisplayMode,
+) -> bool {
+    use egui::*;
+
+    let painter = ui.painter();
+
+    let title_bar_response = ui.interact(
+        title_bar_rect,
+        Id::new("title_bar"),
+        Sense::click_and_drag(),
+    );
+
+    if title_bar_response.is_pointer_button_down_on() {
+        frame.drag_window();
+    }
+
+    if display_mode == DisplayMode::Minimal {
+        // Just a grip to hold the tiny window by.
+        painter.hline(
+            title_bar_rect.center().x - 12.0..=title_bar_rect.center().x + 12.0,
+            title_bar_rect.center().y,
+            ui.visuals().widgets.noninteractive.fg_stroke,
+        );
+
+        return false;
+    }
+
+    painter.text(
+        title_bar_rect.center(),
+        Align2::CENTER_CENTER,
+        title,
+        FontId::proportional(14.0),
+        ui.style().visuals.text_color(),
+    );
+
+    painter.line_segment(
+        [
+            title_bar_rect.left_bottom() + vec2(1.0, 0.0),
+            title_bar_rect.right_bottom() + vec2(-1.0, 0.0),
+        ],
+        ui.visuals().widgets.noninteractive.bg_stroke,
+    );
+
+    let mut close_requested = false;
+
+    ui.allocate_ui_at_rect(title_bar_rect, |ui| {
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.add_space(8.0);
+
+            let button_height = 12.0;
+
+            if ui
+                .add(Button::new(RichText::new("❌").size(button_height)))
+                .on_hover_text("Close the window")
+                .clicked()
+            {
+                close_requested = true;
+            }
+
+            if ui
+                .add(Button::new(RichText::new("🗕").size(button_height)))
+                .on_hover_text("Minimize the window")
+                .clicked()
+            {
+                frame.set_minimized(true);
+            }
+        });
     });
+
+    close_requested
 }
@@ -0,0 +1,714 @@
+//! Merging a second copy of the data into this one, for data files synced between machines
+//! or brought over from another one.
+//!
+//! History records are united by id, a record both sides have keeps our span. Entries of the
+//! trees are matched by id, then by name below entries that matched, so the same project
+//! added on two machines becomes one. One only the other side has is added with everything
+//! below it. One both sides have takes the fields of the side that changed it last as far
+//! as its times tell, which are when it was created, deleted or checked off, and ours on a
+//! tie.
+
+use crate::backend::{Backend, IdType, PContainer, Subject, TodoSubject};
+use crate::history::HistoryRecord;
+use chrono::Duration;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+#[derive(Default, Debug, PartialEq)]
+pub struct MergeSummary {
+    /// Projects, sub projects and subjects of either tree only the other side had.
+    pub(crate) added_entries: usize,
+    /// Entries both sides had that took the fields of the other side.
+    pub(crate) updated_entries: usize,
+    pub(crate) added_records: usize,
+    /// Entries of theirs matched with one of ours by name rather than id.
+    pub(crate) matched_by_name: usize,
+}
+
+/// What merging the other side does, worked out without changing ours.
+pub struct MergePlan {
+    projects: Chain<Subject>,
+    todos: Chain<TodoSubject>,
+    /// Their records ours lacks, with the ids of the entries they were matched with.
+    records: Vec<HistoryRecord>,
+    /// Notes of those records, by record id.
+    notes: HashMap<Uuid, String>,
+    pub(crate) summary: MergeSummary,
+}
+
+type Chain<L> = PContainer<PContainer<PContainer<Arc<Mutex<L>>, IdType>, IdType>, IdType>;
+
+/// Their ids of entries matched by name, mapped to ours.
+type IdMap = HashMap<Uuid, Uuid>;
+
+/// What merging needs to know of an entry.
+trait Merge {
+    /// Latest change its times tell of.
+    fn touched(&self) -> SystemTime;
+
+    /// Takes the fields of `theirs`, which changed later.
+    fn update_from(&mut self, theirs: Self);
+
+    fn name(&self) -> &str;
+
+    fn is_deleted(&self) -> bool;
+
+    fn set_id(&mut self, id: Uuid);
+}
+
+fn latest(created_at: SystemTime, others: &[Option<SystemTime>]) -> SystemTime {
+    others.iter().flatten().fold(created_at, |a, b| a.max(*b))
+}
+
+impl Merge for Subject {
+    fn touched(&self) -> SystemTime {
+        latest(self.created_at, &[self.deleted_at])
+    }
+
+    /// The duration stays, it is made up of our records plus the ones merged in.
+    fn update_from(&mut self, theirs: Self) {
+        *self = Self {
+            duration: self.duration,
+            ..theirs
+        };
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+
+    fn set_id(&mut self, id: Uuid) {
+        self.id = id;
+    }
+}
+
+impl Merge for TodoSubject {
+    fn touched(&self) -> SystemTime {
+        latest(self.created_at, &[self.deleted_at, self.completed_at])
+    }
+
+    fn update_from(&mut self, theirs: Self) {
+        *self = theirs;
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+
+    fn set_id(&mut self, id: Uuid) {
+        self.id = id;
+    }
+}
+
+/// An entry of a tree level as matching by name sees it.
+trait Named {
+    /// `None` when deleted, those only match by id.
+    fn live_name(&self) -> Option<String>;
+
+    fn set_id(&mut self, id: Uuid);
+}
+
+impl<T> Named for PContainer<T, IdType> {
+    fn live_name(&self) -> Option<String> {
+        (!self.is_deleted).then(|| self.name.clone())
+    }
+
+    fn set_id(&mut self, id: Uuid) {
+        self.id = id;
+    }
+}
+
+impl<L: Merge> Named for Arc<Mutex<L>> {
+    fn live_name(&self) -> Option<String> {
+        let leaf = self.lock().unwrap();
+
+        (!leaf.is_deleted()).then(|| leaf.name().to_string())
+    }
+
+    fn set_id(&mut self, id: Uuid) {
+        self.lock().unwrap().set_id(id);
+    }
+}
+
+/// Merges `theirs` into `ours`, which is left dirty to be saved.
+pub fn merge(ours: &mut Backend, mut theirs: Backend) -> MergeSummary {
+    ours.history.load_all();
+    theirs.history.load_all();
+
+    let plan = plan_merge(ours, theirs);
+
+    apply_merge(ours, plan)
+}
+
+/// Works out merging `theirs` into `ours`, with a summary to show before applying it.
+/// Expects the whole history of both loaded.
+pub fn plan_merge(ours: &Backend, mut theirs: Backend) -> MergePlan {
+    let mut summary = MergeSummary::default();
+
+    let mut ids = IdMap::new();
+
+    match_chain(&ours.projects, &mut theirs.projects, &mut ids);
+    match_chain(&ours.todos, &mut theirs.todos, &mut ids);
+
+    summary.matched_by_name = ids.len();
+
+    let records = theirs
+        .history
+        .records()
+        .filter(|v| ours.history.get(&v.id).is_none())
+        .map(|v| {
+            let id = |v: Uuid| ids.get(&v).copied().unwrap_or(v);
+
+            HistoryRecord {
+                project_id: id(v.project_id),
+                sub_project_id: id(v.sub_project_id),
+                subject_id: id(v.subject_id),
+                ..*v
+            }
+        })
+        .collect::<Vec<_>>();
+
+    summary.added_records = records.len();
+
+    let notes = records
+        .iter()
+        .filter_map(|v| Some((v.id, theirs.history.note(&v.id)?.to_string())))
+        .collect();
+
+    count_chain(&ours.projects, &theirs.projects, &mut summary);
+    count_chain(&ours.todos, &theirs.todos, &mut summary);
+
+    MergePlan {
+        projects: theirs.projects,
+        todos: theirs.todos,
+        records,
+        notes,
+        summary,
+    }
+}
+
+/// Applies a plan made for `ours`, which is left dirty to be saved. The summary returned
+/// tells what it did, which differs from the planned one when ours changed since.
+pub fn apply_merge(ours: &mut Backend, plan: MergePlan) -> MergeSummary {
+    let mut summary = MergeSummary {
+        matched_by_name: plan.summary.matched_by_name,
+        ..MergeSummary::default()
+    };
+
+    // Subjects only they had bring their duration along, records they add to ours do not.
+    let our_subjects = subject_ids(&ours.projects);
+
+    let mut added = HashMap::<Uuid, Duration>::new();
+
+    for record in plan.records {
+        if ours.history.get(&record.id).is_some() {
+            continue;
+        }
+
+        ours.history.insert(record);
+        summary.added_records += 1;
+
+        if let Some(note) = plan.notes.get(&record.id) {
+            ours.history.set_note(record.id, note);
+        }
+
+        if our_subjects.contains(&record.subject_id) {
+            let total = added
+                .entry(record.subject_id)
+                .or_insert_with(Duration::zero);
+            *total = *total + record.get_duration();
+        }
+    }
+
+    merge_chain(&mut ours.projects, plan.projects, &mut summary);
+    merge_chain(&mut ours.todos, plan.todos, &mut summary);
+
+    for project in ours.projects.inner.values() {
+        for sub_project in project.inner.values() {
+            for (id, subject) in &sub_project.inner {
+                if let Some(duration) = added.get(id).and_then(|v| v.to_std().ok()) {
+                    subject.lock().unwrap().duration += duration;
+                }
+            }
+        }
+    }
+
+    ours.dirty();
+
+    summary
+}
+
+fn subject_ids(projects: &Chain<Subject>) -> HashSet<Uuid> {
+    projects
+        .inner
+        .values()
+        .flat_map(|v| v.inner.values())
+        .flat_map(|v| v.inner.keys().copied())
+        .collect()
+}
+
+fn merge_chain<L: Merge + Clone>(
+    ours: &mut Chain<L>,
+    theirs: Chain<L>,
+    summary: &mut MergeSummary,
+) {
+    // The root only holds the projects, it is the same on both sides.
+    merge_entries(ours, theirs.inner, summary, &|ours, theirs, summary| {
+        merge_level(ours, theirs, summary, &|ours, theirs, summary| {
+            merge_level(ours, theirs, summary, &merge_leaf)
+        })
+    });
+}
+
+fn container_touched<T>(container: &PContainer<T, IdType>) -> SystemTime {
+    latest(container.created_at, &[container.deleted_at])
+}
+
+/// Merges the fields of the container itself, then the entries in it.
+fn merge_level<T>(
+    ours: &mut PContainer<T, IdType>,
+    theirs: PContainer<T, IdType>,
+    summary: &mut MergeSummary,
+    merge_inner: &dyn Fn(&mut T, T, &mut MergeSummary),
+) {
+    if container_touched(&theirs) > container_touched(ours) {
+        // Their times too, or the same file merged again would count as changed again.
+        ours.name = theirs.name;
+        ours.created_at = theirs.created_at;
+        ours.is_deleted = theirs.is_deleted;
+        ours.deleted_at = theirs.deleted_at;
+        ours.color = theirs.color;
+        ours.goal = theirs.goal;
+        ours.is_archived = theirs.is_archived;
+        ours.hourly_rate = theirs.hourly_rate;
+        ours.currency = theirs.currency;
+        ours.color_override = theirs.color_override;
+        summary.updated_entries += 1;
+    }
+
+    merge_entries(ours, theirs.inner, summary, merge_inner);
+}
+
+/// Adds the entries only they have and merges the ones both have.
+fn merge_entries<T>(
+    ours: &mut PContainer<T, IdType>,
+    theirs: HashMap<IdType, T>,
+    summary: &mut MergeSummary,
+    merge_inner: &dyn Fn(&mut T, T, &mut MergeSummary),
+) {
+    for (id, entry) in theirs {
+        match ours.inner.entry(id) {
+            Entry::Vacant(v) => {
+                v.insert(entry);
+                summary.added_entries += 1;
+            }
+            Entry::Occupied(mut v) => merge_inner(v.get_mut(), entry, summary),
+        }
+    }
+}
+
+fn merge_leaf<L: Merge + Clone>(
+    ours: &mut Arc<Mutex<L>>,
+    theirs: Arc<Mutex<L>>,
+    summary: &mut MergeSummary,
+) {
+    let theirs = theirs.lock().unwrap().clone();
+    let mut ours = ours.lock().unwrap();
+
+    if theirs.touched() > ours.touched() {
+        ours.update_from(theirs);
+        summary.updated_entries += 1;
+    }
+}
+
+fn match_chain<L: Merge>(ours: &Chain<L>, theirs: &mut Chain<L>, ids: &mut IdMap) {
+    match_level(ours, theirs, ids, &|ours, theirs, ids| {
+        match_level(ours, theirs, ids, &|ours, theirs, ids| {
+            match_level(ours, theirs, ids, &|_, _, _| {})
+        })
+    });
+}
+
+/// Gives entries of theirs no entry of ours has the id of, the id of one with their name
+/// that no other entry of theirs has the id of, then matches below the entries both have.
+fn match_level<T: Named>(
+    ours: &PContainer<T, IdType>,
+    theirs: &mut PContainer<T, IdType>,
+    ids: &mut IdMap,
+    match_inner: &dyn Fn(&T, &mut T, &mut IdMap),
+) {
+    let unmatched = theirs
+        .inner
+        .keys()
+        .filter(|v| !ours.inner.contains_key(v))
+        .copied()
+        .collect::<Vec<_>>();
+
+    for id in unmatched {
+        let Some(name) = theirs.inner[&id].live_name() else {
+            continue;
+        };
+
+        let Some(our_id) = ours
+            .inner
+            .iter()
+            .filter(|(k, _)| !theirs.inner.contains_key(k))
+            .find(|(_, v)| v.live_name().as_ref() == Some(&name))
+            .map(|(k, _)| *k)
+        else {
+            continue;
+        };
+
+        let mut entry = theirs.inner.remove(&id).unwrap();
+        entry.set_id(our_id);
+        theirs.inner.insert(our_id, entry);
+
+        if theirs.current_inner_id == Some(id) {
+            theirs.current_inner_id = Some(our_id);
+        }
+
+        ids.insert(id, our_id);
+    }
+
+    for (id, entry) in &mut theirs.inner {
+        if let Some(ours) = ours.inner.get(id) {
+            match_inner(ours, entry, ids);
+        }
+    }
+}
+
+/// Counts what [`merge_chain`] would change.
+fn count_chain<L: Merge>(ours: &Chain<L>, theirs: &Chain<L>, summary: &mut MergeSummary) {
+    count_entries(ours, theirs, summary, &|ours, theirs, summary| {
+        count_level(ours, theirs, summary, &|ours, theirs, summary| {
+            count_level(ours, theirs, summary, &|ours, theirs, summary| {
+                if theirs.lock().unwrap().touched() > ours.lock().unwrap().touched() {
+                    summary.updated_entries += 1;
+                }
+            })
+        })
+    });
+}
+
+fn count_level<T>(
+    ours: &PContainer<T, IdType>,
+    theirs: &PContainer<T, IdType>,
+    summary: &mut MergeSummary,
+    count_inner: &dyn Fn(&T, &T, &mut MergeSummary),
+) {
+    if container_touched(theirs) > container_touched(ours) {
+        summary.updated_entries += 1;
+    }
+
+    count_entries(ours, theirs, summary, count_inner);
+}
+
+fn count_entries<T>(
+    ours: &PContainer<T, IdType>,
+    theirs: &PContainer<T, IdType>,
+    summary: &mut MergeSummary,
+    count_inner: &dyn Fn(&T, &T, &mut MergeSummary),
+) {
+    for (id, entry) in &theirs.inner {
+        match ours.inner.get(id) {
+            None => summary.added_entries += 1,
+            Some(v) => count_inner(v, entry, summary),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{EntryPath, Tree};
+    use chrono::{Local, TimeZone};
+
+    fn backend() -> Backend {
+        Backend::unsaved()
+    }
+
+    /// Another side with the same data, as a synced copy of the data file has it.
+    fn copy(backend: &Backend) -> Backend {
+        let mut copy = self::backend();
+
+        copy.projects = ron::from_str(&ron::to_string(&backend.projects).unwrap()).unwrap();
+        copy.todos = ron::from_str(&ron::to_string(&backend.todos).unwrap()).unwrap();
+
+        for record in backend.history.records() {
+            copy.history.insert(*record);
+        }
+
+        copy
+    }
+
+    fn subject(backend: &Backend, path: EntryPath) -> Subject {
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+
+        backend.projects.inner[&p].inner[&s].inner[&x]
+            .lock()
+            .unwrap()
+            .clone()
+    }
+
+    /// Adds a record of `minutes` on the 5th of March at `hour`.
+    fn add_record(backend: &mut Backend, path: EntryPath, hour: u32, minutes: i64) -> Uuid {
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+        let start = Local.with_ymd_and_hms(2024, 3, 5, hour, 0, 0).unwrap();
+
+        backend
+            .add_manual_record(p, s, x, start, start + Duration::minutes(minutes))
+            .unwrap()
+    }
+
+    fn later(time: SystemTime) -> SystemTime {
+        time + std::time::Duration::from_secs(60)
+    }
+
+    #[test]
+    fn same_ids_merge_into_one() {
+        let mut ours = backend();
+        let path = ours.add_path("Client/Website/Navbar", Tree::Time).unwrap();
+        let theirs = copy(&ours);
+
+        let summary = merge(&mut ours, theirs);
+
+        assert_eq!(summary, MergeSummary::default());
+        assert_eq!(subject_ids(&ours.projects).len(), 1);
+        assert_eq!(subject(&ours, path).name, "Navbar");
+    }
+
+    #[test]
+    fn same_names_match() {
+        let mut ours = backend();
+        let path = ours.add_path("Client/Website/Navbar", Tree::Time).unwrap();
+        let mut theirs = backend();
+        theirs
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+        theirs
+            .add_path("Client/Website/Footer", Tree::Time)
+            .unwrap();
+
+        let summary = merge(&mut ours, theirs);
+
+        assert_eq!(summary.matched_by_name, 3);
+        assert_eq!(summary.added_entries, 1);
+        assert_eq!(ours.projects.inner.len(), 1);
+        assert_eq!(subject_ids(&ours.projects).len(), 2);
+        assert_eq!(subject(&ours, path).name, "Navbar");
+    }
+
+    #[test]
+    fn deleted_entries_only_match_by_id() {
+        let mut ours = backend();
+        ours.add_path("Client/Website/Navbar", Tree::Time).unwrap();
+        let mut theirs = backend();
+        let deleted = theirs.add_path("Client", Tree::Time).unwrap();
+        theirs.delete_entry(Tree::Time, deleted);
+
+        let summary = merge(&mut ours, theirs);
+
+        assert_eq!(summary.matched_by_name, 0);
+        assert_eq!(summary.added_entries, 1);
+        assert_eq!(ours.projects.inner.len(), 2);
+        assert_eq!(
+            ours.projects
+                .inner
+                .values()
+                .filter(|v| v.is_deleted)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn deletion_on_their_side_wins() {
+        let mut ours = backend();
+        let path = ours.add_path("Client/Website/Navbar", Tree::Time).unwrap();
+        let mut theirs = copy(&ours);
+        theirs.delete_entry(Tree::Time, path);
+
+        let summary = merge(&mut ours, theirs);
+
+        assert_eq!(summary.updated_entries, 1);
+        assert!(subject(&ours, path).is_deleted);
+    }
+
+    #[test]
+    fn ties_keep_ours() {
+        let mut ours = backend();
+        let path = ours.add_path("Client/Website/Navbar", Tree::Time).unwrap();
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+        let mut theirs = copy(&ours);
+        theirs.projects.inner.get_mut(&p).unwrap().name = "Theirs".to_string();
+        theirs.projects.inner[&p].inner[&s].inner[&x]
+            .lock()
+            .unwrap()
+            .name = "Theirs".to_string();
+
+        let summary = merge(&mut ours, theirs);
+
+        assert_eq!(summary.updated_entries, 0);
+        assert_eq!(ours.projects.inner[&p].name, "Client");
+        assert_eq!(subject(&ours, path).name, "Navbar");
+    }
+
+    #[test]
+    fn later_touch_takes_their_fields() {
+        let mut ours = backend();
+        let path = ours.add_path("Client/Website/Navbar", Tree::Time).unwrap();
+        let EntryPath::Subject(p, ..) = path else {
+            unreachable!();
+        };
+        let mut theirs = copy(&ours);
+        let project = theirs.projects.inner.get_mut(&p).unwrap();
+        project.name = "Customer".to_string();
+        project.created_at = later(project.created_at);
+
+        let summary = merge(&mut ours, theirs);
+
+        assert_eq!(summary.updated_entries, 1);
+        assert_eq!(ours.projects.inner[&p].name, "Customer");
+    }
+
+    #[test]
+    fn root_is_not_merged() {
+        let mut ours = backend();
+        ours.add_path("Client/Website/Navbar", Tree::Time).unwrap();
+        let mut theirs = copy(&ours);
+        theirs.projects.name = "Theirs".to_string();
+        theirs.projects.created_at = later(ours.projects.created_at);
+        theirs.todos.created_at = later(ours.todos.created_at);
+
+        let plan = plan_merge(&ours, copy(&theirs));
+        let summary = merge(&mut ours, theirs);
+
+        assert_eq!(plan.summary, MergeSummary::default());
+        assert_eq!(summary, MergeSummary::default());
+        assert_eq!(ours.projects.name, "root");
+    }
+
+    #[test]
+    fn merged_records_add_to_durations() {
+        let mut ours = backend();
+        let navbar = ours.add_path("Client/Website/Navbar", Tree::Time).unwrap();
+        add_record(&mut ours, navbar, 9, 60);
+
+        let mut theirs = backend();
+        let their_navbar = theirs
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+        let footer = theirs
+            .add_path("Client/Website/Footer", Tree::Time)
+            .unwrap();
+        add_record(&mut theirs, their_navbar, 11, 30);
+        add_record(&mut theirs, footer, 13, 15);
+
+        let summary = merge(&mut ours, theirs);
+
+        let (EntryPath::Subject(p, s, _), EntryPath::Subject(.., x)) = (navbar, footer) else {
+            unreachable!();
+        };
+        let footer = EntryPath::Subject(p, s, x);
+
+        assert_eq!(summary.added_records, 2);
+        assert_eq!(
+            subject(&ours, navbar).duration,
+            std::time::Duration::from_secs(90 * 60)
+        );
+        // Brought along with the subject, not added a second time.
+        assert_eq!(
+            subject(&ours, footer).duration,
+            std::time::Duration::from_secs(15 * 60)
+        );
+    }
+
+    #[test]
+    fn records_take_the_matched_ids() {
+        let mut ours = backend();
+        let EntryPath::Subject(p, s, x) =
+            ours.add_path("Client/Website/Navbar", Tree::Time).unwrap()
+        else {
+            unreachable!();
+        };
+        let mut theirs = backend();
+        let path = theirs
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+        let id = add_record(&mut theirs, path, 9, 60);
+
+        merge(&mut ours, theirs);
+
+        let record = ours.history.get(&id).unwrap();
+        assert_eq!(
+            (record.project_id, record.sub_project_id, record.subject_id),
+            (p, s, x)
+        );
+    }
+
+    #[test]
+    fn merging_again_changes_nothing() {
+        let mut ours = backend();
+        let navbar = ours.add_path("Client/Website/Navbar", Tree::Time).unwrap();
+        add_record(&mut ours, navbar, 9, 60);
+
+        let mut theirs = backend();
+        let path = theirs
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+        add_record(&mut theirs, path, 11, 30);
+        theirs.add_path("Home/Garden/Mow", Tree::Todo).unwrap();
+
+        merge(&mut ours, copy(&theirs));
+        let projects = ron::to_string(&ours.projects).unwrap();
+        let todos = ron::to_string(&ours.todos).unwrap();
+
+        let summary = merge(&mut ours, theirs);
+
+        assert_eq!(
+            summary,
+            MergeSummary {
+                matched_by_name: 3,
+                ..MergeSummary::default()
+            }
+        );
+        assert_eq!(ours.history.records().count(), 2);
+        assert_eq!(ron::to_string(&ours.projects).unwrap(), projects);
+        assert_eq!(ron::to_string(&ours.todos).unwrap(), todos);
+    }
+
+    #[test]
+    fn merged_records_bring_their_notes() {
+        let mut ours = backend();
+        let navbar = ours.add_path("Client/Website/Navbar", Tree::Time).unwrap();
+        let kept = add_record(&mut ours, navbar, 9, 60);
+        ours.set_session_note(kept, "Ours");
+
+        let mut theirs = copy(&ours);
+        theirs.set_session_note(kept, "Theirs");
+        let added = add_record(&mut theirs, navbar, 11, 30);
+        theirs.set_session_note(added, "Left off at the parser");
+
+        merge(&mut ours, theirs);
+
+        assert_eq!(ours.history.note(&kept), Some("Ours"));
+        assert_eq!(ours.history.note(&added), Some("Left off at the parser"));
+    }
+}
@@ -0,0 +1,126 @@
+//! Colors given to new projects, and keeping chain colors visible on the background.
+//!
+//! The colorblind safe palette is the one of Okabe and Ito without its black, its colors stay
+//! apart with every common kind of color blindness. The high contrast one takes saturated
+//! colors far apart in hue and lightness.
+
+use crate::i18n::tr;
+use eframe::egui::Color32;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug, Default)]
+pub enum Palette {
+    /// Random colors.
+    #[default]
+    Default,
+    ColorblindSafe,
+    HighContrast,
+}
+
+const OKABE_ITO: [(u8, u8, u8); 7] = [
+    (230, 159, 0),
+    (86, 180, 233),
+    (0, 158, 115),
+    (240, 228, 66),
+    (0, 114, 178),
+    (213, 94, 0),
+    (204, 121, 167),
+];
+
+const HIGH_CONTRAST: [(u8, u8, u8); 8] = [
+    (230, 25, 75),
+    (60, 180, 75),
+    (255, 225, 25),
+    (0, 130, 200),
+    (245, 130, 48),
+    (145, 30, 180),
+    (70, 240, 240),
+    (240, 50, 230),
+];
+
+/// Contrast a chain color keeps against the background. Below the 3:1 the WCAG asks of
+/// graphics, so palette colors stay recognizable and only near invisible ones change.
+const MIN_CONTRAST: f32 = 2.;
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [Self::Default, Self::ColorblindSafe, Self::HighContrast];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Default => tr!("Random"),
+            Self::ColorblindSafe => tr!("Colorblind safe"),
+            Self::HighContrast => tr!("High contrast"),
+        }
+    }
+
+    fn colors(self) -> &'static [(u8, u8, u8)] {
+        match self {
+            Self::Default => &[],
+            Self::ColorblindSafe => &OKABE_ITO,
+            Self::HighContrast => &HIGH_CONTRAST,
+        }
+    }
+
+    /// Color for a project next to projects colored `used`, the palette color used least, the
+    /// earliest of them on a tie.
+    pub fn next_color(self, used: &[(u8, u8, u8)]) -> (u8, u8, u8) {
+        let colors = self.colors();
+
+        if colors.is_empty() {
+            let mut rng = thread_rng();
+            return (rng.gen(), rng.gen(), rng.gen());
+        }
+
+        *colors
+            .iter()
+            .min_by_key(|v| used.iter().filter(|u| u == v).count())
+            .unwrap()
+    }
+}
+
+/// Relative luminance as the WCAG defines it.
+fn luminance(color: Color32) -> f32 {
+    let channel = |v: u8| {
+        let v = v as f32 / 255.;
+
+        if v <= 0.03928 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        }
+    };
+
+    0.2126 * channel(color.r()) + 0.7152 * channel(color.g()) + 0.0722 * channel(color.b())
+}
+
+fn contrast(a: Color32, b: Color32) -> f32 {
+    let (a, b) = (luminance(a), luminance(b));
+
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// `color` lightened on dark backgrounds or darkened on light ones just enough to stand out
+/// of `background`.
+pub fn with_contrast(color: Color32, background: Color32) -> Color32 {
+    let target = if luminance(background) < 0.18 {
+        Color32::WHITE
+    } else {
+        Color32::BLACK
+    };
+
+    let mix = |a: u8, b: u8, t: f32| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+
+    (0..=10)
+        .map(|step| {
+            let t = step as f32 / 10.;
+
+            Color32::from_rgb(
+                mix(color.r(), target.r(), t),
+                mix(color.g(), target.g(), t),
+                mix(color.b(), target.b(), t),
+            )
+        })
+        .find(|v| contrast(*v, background) >= MIN_CONTRAST)
+        .unwrap_or(target)
+}
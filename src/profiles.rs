@@ -0,0 +1,131 @@
+//! Profiles keep apart data that should not mix, like work and personal tracking.
+//!
+//! A profile is a folder holding its own data file, history and database, the default
+//! profile uses the working directory. Other profiles are listed by name in `profiles.ron`
+//! in the working directory, which also remembers the profile used last.
+
+use crate::storage::write_atomically;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+pub const FLAG: &str = "--profile";
+
+pub const DEFAULT_PROFILE: &str = "Default";
+
+const CONFIG_PATH: &str = "./profiles.ron";
+
+/// Where profiles added in the window get their folder.
+const PROFILES_DIR: &str = "./profiles";
+
+/// Name and folder of the profile in use, the default profile when unset.
+static ACTIVE: RwLock<Option<(String, PathBuf)>> = RwLock::new(None);
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Profiles {
+    /// Data folder of every profile besides the default one.
+    #[serde(default)]
+    pub(crate) folders: BTreeMap<String, PathBuf>,
+    /// Profile opened when none is asked for.
+    #[serde(default)]
+    pub(crate) last: Option<String>,
+}
+
+impl Profiles {
+    /// The saved profiles, none when the file is missing or unreadable.
+    pub fn load() -> Self {
+        let Ok(text) = std::fs::read_to_string(CONFIG_PATH) else {
+            return Self::default();
+        };
+
+        ron::from_str(&text)
+            .map_err(|e| eprintln!("failed to parse {CONFIG_PATH}: {e}"))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let data = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+
+        write_atomically(Path::new(CONFIG_PATH), &data)
+            .map_err(|e| format!("failed to save {CONFIG_PATH}: {e}"))
+    }
+
+    /// Every profile, the default one first.
+    pub fn names(&self) -> Vec<&str> {
+        std::iter::once(DEFAULT_PROFILE)
+            .chain(self.folders.keys().map(String::as_str))
+            .collect()
+    }
+
+    pub fn folder(&self, name: &str) -> Option<PathBuf> {
+        match name {
+            DEFAULT_PROFILE => Some(PathBuf::from(".")),
+            name => self.folders.get(name).cloned(),
+        }
+    }
+
+    /// Adds a profile with a folder of its own in [`PROFILES_DIR`].
+    pub fn add(&mut self, name: &str) -> Result<(), String> {
+        let name = name.trim();
+
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(format!("\"{name}\" cannot name a profile"));
+        }
+
+        if self.folder(name).is_some() {
+            return Err(format!("a profile named \"{name}\" already exists"));
+        }
+
+        let folder = Path::new(PROFILES_DIR).join(name);
+
+        std::fs::create_dir_all(&folder)
+            .map_err(|e| format!("failed to create {}: {e}", folder.display()))?;
+
+        self.folders.insert(name.to_string(), folder);
+
+        self.save()
+    }
+
+    /// Uses the profile `name` from now on, or the last used one when `None`.
+    pub fn activate(&self, name: Option<&str>) -> Result<(), String> {
+        let name = match name {
+            Some(v) => v,
+            // One removed from the file by hand falls back to the default profile.
+            None => self
+                .last
+                .as_deref()
+                .filter(|v| self.folder(v).is_some())
+                .unwrap_or(DEFAULT_PROFILE),
+        };
+
+        let Some(folder) = self.folder(name) else {
+            return Err(format!(
+                "there is no profile named \"{name}\", add it to {CONFIG_PATH}"
+            ));
+        };
+
+        *ACTIVE.write().unwrap() = Some((name.to_string(), folder));
+
+        Ok(())
+    }
+}
+
+/// Name of the profile in use.
+pub fn active_name() -> String {
+    ACTIVE
+        .read()
+        .unwrap()
+        .as_ref()
+        .map_or(DEFAULT_PROFILE.to_string(), |v| v.0.clone())
+}
+
+/// Folder the data of the profile in use is kept in.
+pub fn data_dir() -> PathBuf {
+    ACTIVE
+        .read()
+        .unwrap()
+        .as_ref()
+        .map_or(PathBuf::from("."), |v| v.1.clone())
+}
@@ -0,0 +1,705 @@
+//! Plain text reports of tracked time. They are Markdown tables, so they read fine as
+//! text and render when pasted into chats or issue trackers.
+//!
+//! `ruh_time_tracker --report week [YYYY-MM-DD]` writes the week report of the ISO week
+//! containing the day, today by default, into the working directory.
+
+use crate::backend::{record_path, Backend, ProjectChain};
+use crate::history::History;
+use crate::storage::write_atomically;
+use crate::util::{
+    days_in_range, export_timestamp, format_chrono_duration, round_duration, start_of_day,
+    start_of_week, DurationFormat, ExportZone, RoundingMode, DATE_FORMAT,
+};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Weekday};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+pub const FLAG: &str = "--report";
+
+const USAGE: &str = "usage: --report week [YYYY-MM-DD]";
+
+/// Tracked time of one project, sub project or subject in a report.
+pub struct ReportEntry {
+    pub(crate) name: String,
+    pub(crate) duration: chrono::Duration,
+}
+
+fn total(entries: &[ReportEntry]) -> chrono::Duration {
+    entries
+        .iter()
+        .fold(chrono::Duration::zero(), |s, v| s + v.duration)
+}
+
+/// Share of `total`, rounded to the nearest percent.
+fn percent(duration: chrono::Duration, total: chrono::Duration) -> i64 {
+    match total.num_seconds() {
+        0 => 0,
+        total => (duration.num_seconds() * 100 + total / 2) / total,
+    }
+}
+
+/// Table cell text, a `|` would end the cell early.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+/// Table of `entries` in the given order followed by their total.
+fn table(text: &mut String, column: &str, entries: &[ReportEntry], format: DurationFormat) {
+    let total = total(entries);
+
+    let _ = writeln!(text, "| {column} | Time | Share |");
+    let _ = writeln!(text, "| --- | ---: | ---: |");
+
+    for entry in entries {
+        let _ = writeln!(
+            text,
+            "| {} | {} | {}% |",
+            cell(&entry.name),
+            format_chrono_duration(entry.duration, format),
+            percent(entry.duration, total)
+        );
+    }
+
+    let _ = writeln!(
+        text,
+        "| **Total** | **{}** | {}% |",
+        format_chrono_duration(total, format),
+        percent(total, total)
+    );
+}
+
+/// Report of the days `from` to `to` with `projects` in the given order, and the sub
+/// projects of one project when `breakdown` is set. `zone` names the time zone the days
+/// were cut in, see [`ExportZone::name`].
+pub fn range_report(
+    from: NaiveDate,
+    to: NaiveDate,
+    projects: &[ReportEntry],
+    breakdown: Option<(&str, &[ReportEntry])>,
+    format: DurationFormat,
+    zone: &str,
+) -> String {
+    let mut text = if from == to {
+        format!("## Time report {}\n\n", from.format(DATE_FORMAT))
+    } else {
+        format!(
+            "## Time report {} to {}\n\n",
+            from.format(DATE_FORMAT),
+            to.format(DATE_FORMAT)
+        )
+    };
+
+    let _ = writeln!(text, "Days in {zone}\n");
+
+    table(&mut text, "Project", projects, format);
+
+    if let Some((project, sub_projects)) = breakdown.filter(|v| !v.1.is_empty()) {
+        let _ = writeln!(text, "\n### {}\n", project.trim());
+        table(&mut text, "Sub project", sub_projects, format);
+    }
+
+    text
+}
+
+/// Parses the arguments following [`FLAG`] and writes the report.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let day = match args {
+        [kind] if kind == "week" => Local::now().date_naive(),
+        [kind, day] if kind == "week" => NaiveDate::parse_from_str(day, DATE_FORMAT)
+            .map_err(|_| format!("day must look like 2024-01-31, got \"{day}\""))?,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let mut backend = Backend::try_load()?;
+    let path = write_week_report(
+        day,
+        &mut backend.history,
+        &backend.projects,
+        backend.settings.duration_format,
+    )?;
+
+    println!("{}", path.display());
+
+    Ok(())
+}
+
+/// Monday to Sunday of the ISO week containing `day`.
+fn iso_week_range(day: NaiveDate) -> (DateTime<Local>, DateTime<Local>) {
+    let monday = start_of_week(day, Weekday::Mon);
+
+    (
+        start_of_day(monday),
+        start_of_day(monday + chrono::Duration::days(7)) - chrono::Duration::milliseconds(100),
+    )
+}
+
+/// Writes the report of the ISO week containing `day` to `report-YYYY-Www.md` in the working
+/// directory, next to the data file.
+pub fn write_week_report(
+    day: NaiveDate,
+    history: &mut History,
+    projects: &ProjectChain,
+    format: DurationFormat,
+) -> Result<PathBuf, String> {
+    let week = day.iso_week();
+    let path = PathBuf::from(format!("report-{}-W{:02}.md", week.year(), week.week()));
+    let range = iso_week_range(day);
+
+    history.load_range(range.0.date_naive(), range.1.date_naive());
+
+    write_atomically(&path, week_report(range, history, projects, format))
+        .map_err(|e| format!("failed to write \"{}\": {e}", path.display()))?;
+
+    Ok(path)
+}
+
+/// Tracked time of one project within a week report.
+struct WeekRow {
+    name: String,
+    days: Vec<chrono::Duration>,
+    /// `sub project / subject` label and time by sub project and subject id.
+    subjects: HashMap<(Uuid, Uuid), (String, chrono::Duration)>,
+}
+
+impl WeekRow {
+    fn total(&self) -> chrono::Duration {
+        self.days
+            .iter()
+            .fold(chrono::Duration::zero(), |s, v| s + *v)
+    }
+}
+
+/// Report of `range` with a table of projects by days, the subjects of every project and
+/// the totals. Meant for a week, works for any range.
+pub fn week_report(
+    range: (DateTime<Local>, DateTime<Local>),
+    history: &History,
+    projects: &ProjectChain,
+    format: DurationFormat,
+) -> String {
+    let days = days_in_range(range.0, range.1);
+    let mut rows: HashMap<Uuid, WeekRow> = HashMap::new();
+
+    for (i, bucket) in history.get_ordered_records(range).iter().enumerate() {
+        for record in bucket {
+            let path = record_path(projects, record);
+            let duration = record.get_duration();
+
+            let row = rows.entry(record.project_id).or_insert_with(|| WeekRow {
+                name: path.project,
+                days: vec![chrono::Duration::zero(); days.len()],
+                subjects: HashMap::new(),
+            });
+
+            row.days[i] = row.days[i] + duration;
+
+            let subject = row
+                .subjects
+                .entry((record.sub_project_id, record.subject_id))
+                .or_insert_with(|| {
+                    (
+                        format!("{} / {}", path.sub_project, path.subject),
+                        chrono::Duration::zero(),
+                    )
+                });
+
+            subject.1 = subject.1 + duration;
+        }
+    }
+
+    let mut rows = rows.into_values().collect::<Vec<_>>();
+    rows.sort_by_key(|v| (Reverse(v.total()), v.name.to_lowercase()));
+
+    let cells = |durations: &mut dyn Iterator<Item = chrono::Duration>| {
+        durations
+            .map(|v| {
+                if v.is_zero() {
+                    " |".to_string()
+                } else {
+                    format!(" {} |", format_chrono_duration(v, format))
+                }
+            })
+            .collect::<String>()
+    };
+
+    let mut text = String::new();
+
+    if let Some(first) = days.first() {
+        let week = first.iso_week();
+        let _ = writeln!(text, "# Week {}-W{:02}\n", week.year(), week.week());
+    }
+
+    if let (Some(first), Some(last)) = (days.first(), days.last()) {
+        let _ = writeln!(
+            text,
+            "{} to {}, days in {}\n",
+            first.format(DATE_FORMAT),
+            last.format(DATE_FORMAT),
+            ExportZone::Local.name((&range.0, &range.1))
+        );
+    }
+
+    let _ = writeln!(
+        text,
+        "| Project |{} Total |",
+        days.iter()
+            .map(|v| format!(" {} |", v.format("%a %d")))
+            .collect::<String>()
+    );
+    let _ = writeln!(text, "| --- |{} ---: |", " ---: |".repeat(days.len()));
+
+    for row in &rows {
+        let _ = writeln!(
+            text,
+            "| {} |{} **{}** |",
+            cell(&row.name),
+            cells(&mut row.days.iter().copied()),
+            format_chrono_duration(row.total(), format)
+        );
+    }
+
+    let day_totals = (0..days.len()).map(|i| {
+        rows.iter()
+            .fold(chrono::Duration::zero(), |s, v| s + v.days[i])
+    });
+    let total = rows
+        .iter()
+        .fold(chrono::Duration::zero(), |s, v| s + v.total());
+
+    let _ = writeln!(
+        text,
+        "| **Total** |{} **{}** |",
+        cells(&mut day_totals.into_iter()),
+        format_chrono_duration(total, format)
+    );
+
+    for row in &rows {
+        let _ = writeln!(
+            text,
+            "\n## {} ({})\n",
+            row.name.trim(),
+            format_chrono_duration(row.total(), format)
+        );
+
+        let mut subjects = row.subjects.values().collect::<Vec<_>>();
+        subjects.sort_by_key(|v| (Reverse(v.1), v.0.to_lowercase()));
+
+        for (name, duration) in subjects {
+            let _ = writeln!(
+                text,
+                "- {}: {}",
+                name.trim(),
+                format_chrono_duration(*duration, format)
+            );
+        }
+    }
+
+    text
+}
+
+/// CSV field, quoted when it holds a separator, a quote or a line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Time of one subject in a CSV report, with the first start and last end of its records.
+struct CsvRow {
+    names: [String; 3],
+    duration: chrono::Duration,
+    first_start: DateTime<Local>,
+    last_end: DateTime<Local>,
+}
+
+/// Time per subject within `range` as CSV with a header line, hours with two decimals. The
+/// first start and last end of the records of each subject are RFC 3339 timestamps in `zone`.
+/// Each record is rounded with `rounding` before it is added, as in the statistic summary.
+pub fn csv_report(
+    range: (DateTime<Local>, DateTime<Local>),
+    history: &History,
+    projects: &ProjectChain,
+    zone: ExportZone,
+    rounding: RoundingMode,
+) -> String {
+    let mut subjects: HashMap<(Uuid, Uuid, Uuid), CsvRow> = HashMap::new();
+
+    for record in history.get_records(range) {
+        let key = (record.project_id, record.sub_project_id, record.subject_id);
+
+        let subject = subjects.entry(key).or_insert_with(|| {
+            let path = record_path(projects, &record);
+
+            CsvRow {
+                names: [path.project, path.sub_project, path.subject],
+                duration: chrono::Duration::zero(),
+                first_start: record.start_date,
+                last_end: record.end_date,
+            }
+        });
+
+        subject.duration = subject.duration + round_duration(record.get_duration(), rounding);
+        subject.first_start = subject.first_start.min(record.start_date);
+        subject.last_end = subject.last_end.max(record.end_date);
+    }
+
+    let mut rows = subjects.into_values().collect::<Vec<_>>();
+    rows.sort_by_key(|v| v.names.clone().map(|v| v.to_lowercase()));
+
+    let mut text = "project,sub project,subject,hours,first start,last end\n".to_string();
+
+    for row in rows {
+        let _ = writeln!(
+            text,
+            "{},{:.2},{},{}",
+            row.names.map(|v| csv_field(&v)).join(","),
+            row.duration.num_seconds() as f64 / 3600.,
+            export_timestamp(&row.first_start, zone),
+            export_timestamp(&row.last_end, zone)
+        );
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn entry(name: &str, minutes: i64) -> ReportEntry {
+        ReportEntry {
+            name: name.to_string(),
+            duration: chrono::Duration::minutes(minutes),
+        }
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, d).unwrap()
+    }
+
+    #[test]
+    fn range_report_of_a_day() {
+        let projects = [entry("Client", 90), entry("Home", 30)];
+
+        let text = range_report(
+            day(5),
+            day(5),
+            &projects,
+            None,
+            DurationFormat::default(),
+            "UTC",
+        );
+
+        assert_eq!(
+            text,
+            "\
+## Time report 2024-03-05
+
+Days in UTC
+
+| Project | Time | Share |
+| --- | ---: | ---: |
+| Client | 1:30 | 75% |
+| Home | 0:30 | 25% |
+| **Total** | **2:00** | 100% |
+"
+        );
+    }
+
+    #[test]
+    fn range_report_with_breakdown() {
+        let projects = [entry("Client", 120), entry("Home", 60)];
+        let sub_projects = [entry("Website", 80), entry("App | iOS", 40)];
+
+        let text = range_report(
+            day(4),
+            day(10),
+            &projects,
+            Some(("Client ", &sub_projects)),
+            DurationFormat::default(),
+            "UTC",
+        );
+
+        assert_eq!(
+            text,
+            "\
+## Time report 2024-03-04 to 2024-03-10
+
+Days in UTC
+
+| Project | Time | Share |
+| --- | ---: | ---: |
+| Client | 2:00 | 67% |
+| Home | 1:00 | 33% |
+| **Total** | **3:00** | 100% |
+
+### Client
+
+| Sub project | Time | Share |
+| --- | ---: | ---: |
+| Website | 1:20 | 67% |
+| App \\| iOS | 0:40 | 33% |
+| **Total** | **2:00** | 100% |
+"
+        );
+    }
+
+    #[test]
+    fn empty_breakdown_is_left_out() {
+        let projects = [entry("Client", 60)];
+
+        let text = range_report(
+            day(5),
+            day(5),
+            &projects,
+            Some(("Client", &[])),
+            DurationFormat::default(),
+            "UTC",
+        );
+
+        assert!(!text.contains("###"));
+    }
+
+    #[test]
+    fn range_report_without_time() {
+        let text = range_report(day(5), day(5), &[], None, DurationFormat::default(), "UTC");
+
+        assert_eq!(
+            text,
+            "\
+## Time report 2024-03-05
+
+Days in UTC
+
+| Project | Time | Share |
+| --- | ---: | ---: |
+| **Total** | **0:00** | 0% |
+"
+        );
+    }
+
+    #[test]
+    fn range_report_in_decimal_hours() {
+        let projects = [entry("Client", 105)];
+        let format = DurationFormat {
+            time: crate::util::TimeFormat::DecimalHours,
+            days: false,
+        };
+
+        let text = range_report(day(5), day(5), &projects, None, format, "UTC");
+
+        assert!(text.contains("| Client | 1.75h | 100% |"), "{text}");
+    }
+
+    #[test]
+    fn shares_round_to_nearest() {
+        let total = chrono::Duration::minutes(3);
+
+        assert_eq!(percent(chrono::Duration::minutes(1), total), 33);
+        assert_eq!(percent(chrono::Duration::minutes(2), total), 67);
+        assert_eq!(
+            percent(chrono::Duration::zero(), chrono::Duration::zero()),
+            0
+        );
+    }
+
+    /// Subjects with records in the week of 2024-03-04 and one in the week after.
+    fn week_backend() -> Backend {
+        let mut backend = Backend::unsaved();
+
+        let mut add = |path: &str, start: (u32, u32, u32), minutes: i64| {
+            let path = backend.add_path(path, crate::backend::Tree::Time).unwrap();
+            let crate::backend::EntryPath::Subject(p, s, x) = path else {
+                unreachable!();
+            };
+            let start = start_of_day(day(start.0))
+                + chrono::Duration::hours(start.1 as i64)
+                + chrono::Duration::minutes(start.2 as i64);
+
+            backend
+                .add_manual_record(p, s, x, start, start + chrono::Duration::minutes(minutes))
+                .unwrap();
+        };
+
+        add("Client/Website/Navbar", (4, 9, 0), 90);
+        add("Client/Website/Navbar", (5, 9, 0), 60);
+        add("Client/App/Login", (6, 14, 0), 60);
+        add("Client/Website/Navbar", (8, 17, 0), 30);
+        add("Client/Website/Navbar", (9, 10, 0), 30);
+        add("Home/Garden/Mow", (10, 10, 0), 30);
+        add("Home/Garden/Mow", (11, 10, 0), 45);
+
+        backend
+    }
+
+    #[test]
+    fn week_report_tables_projects_by_day() {
+        let backend = week_backend();
+        let range = iso_week_range(day(7));
+        // Depends on the time zone the tests run in.
+        let zone = ExportZone::Local.name((&range.0, &range.1));
+
+        let text = week_report(
+            range,
+            &backend.history,
+            &backend.projects,
+            DurationFormat::default(),
+        );
+
+        assert_eq!(
+            text,
+            format!(
+                "\
+# Week 2024-W10
+
+2024-03-04 to 2024-03-10, days in {zone}
+
+| Project | Mon 04 | Tue 05 | Wed 06 | Thu 07 | Fri 08 | Sat 09 | Sun 10 | Total |
+| --- | ---: | ---: | ---: | ---: | ---: | ---: | ---: | ---: |
+| Client | 1:30 | 1:00 | 1:00 | | 0:30 | 0:30 | | **4:30** |
+| Home | | | | | | | 0:30 | **0:30** |
+| **Total** | 1:30 | 1:00 | 1:00 | | 0:30 | 0:30 | 0:30 | **5:00** |
+
+## Client (4:30)
+
+- Website / Navbar: 3:30
+- App / Login: 1:00
+
+## Home (0:30)
+
+- Garden / Mow: 0:30
+"
+            )
+        );
+    }
+
+    #[test]
+    fn week_report_without_records() {
+        let backend = Backend::unsaved();
+
+        let text = week_report(
+            iso_week_range(day(7)),
+            &backend.history,
+            &backend.projects,
+            DurationFormat::default(),
+        );
+
+        assert!(
+            text.ends_with("| **Total** | | | | | | | | **0:00** |\n"),
+            "{text}"
+        );
+        assert!(!text.contains("## "));
+    }
+
+    #[test]
+    fn iso_week_starts_on_monday() {
+        for d in 4..=10 {
+            let (start, end) = iso_week_range(day(d));
+
+            assert_eq!(start, start_of_day(day(4)));
+            assert_eq!(end.date_naive(), day(10));
+        }
+    }
+
+    #[test]
+    fn csv_report_quotes_names_and_writes_utc_timestamps() {
+        let mut backend = Backend::unsaved();
+        let utc = |h, m| {
+            Utc.with_ymd_and_hms(2024, 3, 5, h, m, 0)
+                .unwrap()
+                .with_timezone(&Local)
+        };
+
+        let mut add = |path: &str, start, end| {
+            let path = backend.add_path(path, crate::backend::Tree::Time).unwrap();
+            let crate::backend::EntryPath::Subject(p, s, x) = path else {
+                unreachable!();
+            };
+
+            backend.add_manual_record(p, s, x, start, end).unwrap();
+        };
+
+        add("Acme, Inc/Website/Navbar \"v2\"", utc(9, 0), utc(10, 30));
+        add("Acme, Inc/Website/Navbar \"v2\"", utc(13, 0), utc(13, 45));
+        add("Home/Garden/Mow", utc(16, 0), utc(16, 20));
+
+        let text = csv_report(
+            (utc(0, 0), utc(23, 59)),
+            &backend.history,
+            &backend.projects,
+            ExportZone::Utc,
+            RoundingMode::default(),
+        );
+
+        assert_eq!(
+            text,
+            "\
+project,sub project,subject,hours,first start,last end
+\"Acme, Inc\",Website,\"Navbar \"\"v2\"\"\",2.25,2024-03-05T09:00:00Z,2024-03-05T13:45:00Z
+Home,Garden,Mow,0.33,2024-03-05T16:00:00Z,2024-03-05T16:20:00Z
+"
+        );
+    }
+
+    #[test]
+    fn csv_report_rounds_each_record() {
+        let mut backend = Backend::unsaved();
+        let path = backend
+            .add_path("Client/Website/Navbar", crate::backend::Tree::Time)
+            .unwrap();
+        let crate::backend::EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+        let start = start_of_day(day(5)) + chrono::Duration::hours(9);
+
+        for (from, minutes) in [(0, 10), (60, 20)] {
+            let from = start + chrono::Duration::minutes(from);
+
+            backend
+                .add_manual_record(p, s, x, from, from + chrono::Duration::minutes(minutes))
+                .unwrap();
+        }
+
+        let hours = |rounding| {
+            let text = csv_report(
+                (start_of_day(day(5)), start_of_day(day(6))),
+                &backend.history,
+                &backend.projects,
+                ExportZone::Utc,
+                rounding,
+            );
+
+            text.lines()
+                .nth(1)
+                .unwrap()
+                .split(',')
+                .nth(3)
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(hours(RoundingMode::default()), "0.50");
+        // 10 and 20 minutes each round up to a quarter, not their 30 minutes together.
+        assert_eq!(
+            hours(RoundingMode {
+                minutes: 15,
+                up: true
+            }),
+            "0.75"
+        );
+        assert_eq!(
+            hours(RoundingMode {
+                minutes: 15,
+                up: false
+            }),
+            "0.50"
+        );
+    }
+}
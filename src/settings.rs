@@ -0,0 +1,167 @@
+use crate::frontend::DisplayMode;
+use crate::hotkeys::Hotkeys;
+use crate::i18n::DEFAULT_LANGUAGE;
+use crate::palette::Palette;
+use crate::storage::{sorted_map, sorted_set};
+use crate::util::{DurationFormat, RoundingMode};
+use chrono::Weekday;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug, Default)]
+pub enum TodoLayout {
+    #[default]
+    Tree,
+    Columns,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug, Default)]
+pub enum Theme {
+    Light,
+    Dark,
+    #[default]
+    System,
+}
+
+/// Which tracked time the Time view columns show next to each entry.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug, Default)]
+pub enum ColumnTime {
+    Today,
+    Total,
+    #[default]
+    Both,
+}
+
+/// Focused windows whose title matches `pattern` suggest tracking `path`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct WindowRule {
+    pub(crate) pattern: String,
+    /// `project`, `project/sub project` or a whole subject path.
+    pub(crate) path: String,
+}
+
+/// What happens to a session stopped by the screen lock once the screen is unlocked.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug, Default)]
+pub enum UnlockResume {
+    Off,
+    #[default]
+    Ask,
+    Automatic,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub(crate) theme: Theme,
+    /// Colors new projects get and Re-assign colors hands out.
+    pub(crate) palette: Palette,
+    /// Code of the interface language, the name of its catalog.
+    pub(crate) language: String,
+    pub(crate) first_day_of_week: Weekday,
+    pub(crate) todo_layout: TodoLayout,
+    /// Done todos stay among the open ones instead of a collapsed section below them.
+    pub(crate) todo_done_inline: bool,
+    #[serde(serialize_with = "sorted_set")]
+    pub(crate) todo_expanded: HashSet<Uuid>,
+    pub(crate) pause_on_display_off: bool,
+    pub(crate) pause_on_lock: bool,
+    pub(crate) resume_on_unlock: UnlockResume,
+    pub(crate) monthly_goal_hours: u32,
+    /// Working window the Statistic view reports untracked time within, minutes of the day.
+    pub(crate) work_day_start_minutes: u32,
+    pub(crate) work_day_end_minutes: u32,
+    pub(crate) pomodoro: bool,
+    pub(crate) pomodoro_work_minutes: u32,
+    pub(crate) pomodoro_break_minutes: u32,
+    pub(crate) pomodoro_track_breaks: bool,
+    pub(crate) column_time: ColumnTime,
+    pub(crate) duration_format: DurationFormat,
+    pub(crate) notify_goal_reached: bool,
+    pub(crate) notify_auto_pause: bool,
+    pub(crate) notify_pomodoro: bool,
+    /// Minutes of continuous tracking between stretch reminders, 0 turns them off.
+    pub(crate) stretch_reminder_minutes: u32,
+    pub(crate) end_of_day_summary: bool,
+    /// Clears the notes at the start of each day, keeping the old ones in the journal.
+    pub(crate) daily_notes: bool,
+    /// Pausing asks for a note on the session, filled with the note template of its subject.
+    pub(crate) session_notes: bool,
+    /// When the end of day summary shows up, minutes of the day.
+    pub(crate) end_of_day_minutes: u32,
+    /// Serves the JSON control socket while the window runs.
+    pub(crate) control_socket: bool,
+    /// Shell commands run when a session starts and stops, empty for none.
+    pub(crate) on_start_command: String,
+    pub(crate) on_stop_command: String,
+    /// Watches the focused window title and offers to start the first matching rule.
+    pub(crate) window_suggestions: bool,
+    pub(crate) window_rules: Vec<WindowRule>,
+    /// Applied to every session in the statistic summaries, the records are kept exact.
+    pub(crate) report_rounding: RoundingMode,
+    /// Opacity of the minimal window background, from 0.2 to 1.
+    pub(crate) minimal_opacity: f32,
+    /// Sessions stopped before this many seconds are dropped, 0 keeps every session.
+    pub(crate) min_session_seconds: u32,
+    pub(crate) save_period_seconds: u64,
+    pub(crate) tracking_repaint_ms: u64,
+    pub(crate) idle_repaint_seconds: u64,
+    pub(crate) hotkeys: Hotkeys,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            palette: Palette::default(),
+            language: DEFAULT_LANGUAGE.to_string(),
+            first_day_of_week: Weekday::Mon,
+            todo_layout: TodoLayout::default(),
+            todo_done_inline: false,
+            todo_expanded: HashSet::new(),
+            pause_on_display_off: true,
+            pause_on_lock: true,
+            resume_on_unlock: UnlockResume::default(),
+            monthly_goal_hours: 0,
+            work_day_start_minutes: 9 * 60,
+            work_day_end_minutes: 18 * 60,
+            pomodoro: false,
+            pomodoro_work_minutes: 25,
+            pomodoro_break_minutes: 5,
+            pomodoro_track_breaks: false,
+            column_time: ColumnTime::default(),
+            duration_format: DurationFormat::default(),
+            notify_goal_reached: false,
+            notify_auto_pause: false,
+            notify_pomodoro: false,
+            stretch_reminder_minutes: 0,
+            end_of_day_summary: true,
+            daily_notes: false,
+            session_notes: false,
+            end_of_day_minutes: 18 * 60,
+            control_socket: false,
+            on_start_command: String::new(),
+            on_stop_command: String::new(),
+            window_suggestions: false,
+            window_rules: Vec::new(),
+            report_rounding: RoundingMode::default(),
+            minimal_opacity: 1.0,
+            min_session_seconds: 30,
+            save_period_seconds: 10,
+            tracking_repaint_ms: 1000,
+            idle_repaint_seconds: 30,
+            hotkeys: Hotkeys::default(),
+        }
+    }
+}
+
+/// Where and in which mode the window was left, restored on the next launch.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct WindowState {
+    pub(crate) display_mode: DisplayMode,
+    pub(crate) position: Option<(f32, f32)>,
+    /// Size chosen by the user for the modes that can be resized.
+    #[serde(serialize_with = "sorted_map")]
+    pub(crate) sizes: HashMap<DisplayMode, (f32, f32)>,
+}
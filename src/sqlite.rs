@@ -0,0 +1,765 @@
+//! Storage in an SQLite database, for histories too long to read and write as text.
+//!
+//! Each level of the trees has a table, rows hold the ids leading to them and the entry
+//! itself as RON without its children. Records are rows of `history_records` and are read
+//! a month at a time by their start, the rest of the data is one RON text in `state`.
+//!
+//! Saves are written by a worker thread, which only touches the rows and records that
+//! changed since what it knows to be in the database.
+
+use crate::backend::{Backend, IdType, Leaf, PContainer};
+use crate::history::{next_month, HistoryRecord};
+use crate::storage::{move_aside, HistoryStore, Saver, Storage, StorageKind, Writer};
+use crate::util::start_of_day;
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+/// The database, in the folder of the profile.
+pub const SQLITE_FILE: &str = "data.sqlite";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS state (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS projects (
+        id TEXT PRIMARY KEY, tree TEXT NOT NULL, data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS sub_projects (
+        id TEXT PRIMARY KEY, tree TEXT NOT NULL, project_id TEXT NOT NULL, data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS subjects (
+        id TEXT PRIMARY KEY, project_id TEXT NOT NULL, sub_project_id TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS todos (
+        id TEXT PRIMARY KEY, project_id TEXT NOT NULL, sub_project_id TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS history_records (
+        id TEXT PRIMARY KEY, start_date INTEGER NOT NULL, end_date INTEGER NOT NULL,
+        project_id TEXT NOT NULL, sub_project_id TEXT NOT NULL, subject_id TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS history_records_start ON history_records (start_date);
+";
+
+/// Tables of the trees with their columns besides `id`.
+const TREE_TABLES: [(&str, &str); 4] = [
+    ("projects", "tree, data"),
+    ("sub_projects", "tree, project_id, data"),
+    ("subjects", "project_id, sub_project_id, data"),
+    ("todos", "project_id, sub_project_id, data"),
+];
+
+/// How long a connection waits for the one of the worker to finish writing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Chain of three levels ending in leaves, the shape of both trees.
+type Chain<L> = PContainer<PContainer<PContainer<Arc<Mutex<L>>, IdType>, IdType>, IdType>;
+
+/// Table and id of a tree row.
+type RowKey = (&'static str, Uuid);
+
+/// Columns of a tree row besides the id, in the order of [`TREE_TABLES`].
+type Rows = HashMap<RowKey, Vec<String>>;
+
+/// Records of months written by the history, waiting for the next save.
+type Months = BTreeMap<NaiveDate, Vec<HistoryRecord>>;
+
+fn open_connection(path: &Path) -> Result<Connection, String> {
+    let connection =
+        Connection::open(path).map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+
+    connection
+        .busy_timeout(BUSY_TIMEOUT)
+        .and_then(|_| connection.execute_batch(SCHEMA))
+        .map_err(|e| format!("failed to set up {}: {e}", path.display()))?;
+
+    Ok(connection)
+}
+
+/// Nanoseconds since the epoch, what `history_records` keeps times as.
+fn to_nanos(date: DateTime<Local>) -> i64 {
+    date.timestamp() * 1_000_000_000 + date.timestamp_subsec_nanos() as i64
+}
+
+fn from_nanos(nanos: i64) -> Option<DateTime<Local>> {
+    Utc.timestamp_opt(
+        nanos.div_euclid(1_000_000_000),
+        nanos.rem_euclid(1_000_000_000) as u32,
+    )
+    .single()
+    .map(|v| v.with_timezone(&Local))
+}
+
+fn parse_id(text: &str) -> rusqlite::Result<Uuid> {
+    Uuid::parse_str(text).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn to_text<T: Serialize>(value: &T) -> Result<String, String> {
+    ron::to_string(value).map_err(|e| e.to_string())
+}
+
+fn from_text<T: DeserializeOwned>(text: &str) -> Result<T, String> {
+    ron::from_str(text).map_err(|e| e.to_string())
+}
+
+pub struct SqliteStorage {
+    path: PathBuf,
+    connection: Connection,
+    saver: Saver<SqliteWriter>,
+    /// Filled by the history store, emptied by the next save.
+    unsaved_months: Arc<Mutex<Months>>,
+}
+
+impl SqliteStorage {
+    /// Opens the database in `dir`, creating it and its tables as needed.
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(SQLITE_FILE);
+
+        Ok(Self {
+            connection: open_connection(&path)?,
+            saver: Saver::new(SqliteWriter {
+                path: path.clone(),
+                connection: None,
+                rows: None,
+                months: HashMap::new(),
+                failed: None,
+            }),
+            unsaved_months: Arc::default(),
+            path,
+        })
+    }
+
+    /// Rows of the entries of `chain`, each without its children.
+    fn tree_rows<L: Leaf + Serialize>(
+        rows: &mut Rows,
+        tree: &str,
+        leaf_table: &'static str,
+        chain: &Chain<L>,
+    ) -> Result<(), String> {
+        for project in chain.inner.values() {
+            rows.insert(
+                ("projects", project.id),
+                vec![tree.to_string(), to_text(&project.without_inner())?],
+            );
+
+            for sub_project in project.inner.values() {
+                rows.insert(
+                    ("sub_projects", sub_project.id),
+                    vec![
+                        tree.to_string(),
+                        project.id.to_string(),
+                        to_text(&sub_project.without_inner())?,
+                    ],
+                );
+
+                for (id, leaf) in &sub_project.inner {
+                    rows.insert(
+                        (leaf_table, *id),
+                        vec![
+                            project.id.to_string(),
+                            sub_project.id.to_string(),
+                            to_text(&*leaf.lock().unwrap())?,
+                        ],
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fills the trees of `chain` from the tables. Rows whose parent is gone are skipped.
+    fn load_tree<L: Leaf + DeserializeOwned>(
+        &self,
+        tree: &str,
+        leaf_table: &str,
+        chain: &mut Chain<L>,
+    ) -> Result<(), String> {
+        let error = |e: rusqlite::Error| e.to_string();
+
+        let mut statement = self
+            .connection
+            .prepare("SELECT data FROM projects WHERE tree = ?1")
+            .map_err(error)?;
+        let rows = statement
+            .query_map([tree], |row| row.get::<_, String>(0))
+            .map_err(error)?;
+
+        for data in rows {
+            let project: PContainer<_, IdType> = from_text(&data.map_err(error)?)?;
+            chain.inner.insert(project.id, project);
+        }
+
+        let mut statement = self
+            .connection
+            .prepare("SELECT project_id, data FROM sub_projects WHERE tree = ?1")
+            .map_err(error)?;
+        let rows = statement
+            .query_map([tree], |row| {
+                Ok((
+                    parse_id(&row.get::<_, String>(0)?)?,
+                    row.get::<_, String>(1)?,
+                ))
+            })
+            .map_err(error)?;
+
+        for row in rows {
+            let (project_id, data) = row.map_err(error)?;
+            let sub_project: PContainer<_, IdType> = from_text(&data)?;
+
+            if let Some(project) = chain.inner.get_mut(&project_id) {
+                project.inner.insert(sub_project.id, sub_project);
+            }
+        }
+
+        let mut statement = self
+            .connection
+            .prepare(&format!(
+                "SELECT project_id, sub_project_id, data FROM {leaf_table}"
+            ))
+            .map_err(error)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    parse_id(&row.get::<_, String>(0)?)?,
+                    parse_id(&row.get::<_, String>(1)?)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(error)?;
+
+        for row in rows {
+            let (project_id, sub_project_id, data) = row.map_err(error)?;
+            let leaf: L = from_text(&data)?;
+
+            if let Some(sub_project) = chain
+                .inner
+                .get_mut(&project_id)
+                .and_then(|v| v.inner.get_mut(&sub_project_id))
+            {
+                sub_project
+                    .inner
+                    .insert(leaf.id(), Arc::new(Mutex::new(leaf)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Sqlite
+    }
+
+    fn load(&mut self) -> Result<Option<Backend>, String> {
+        let state = self
+            .connection
+            .query_row("SELECT value FROM state WHERE key = 'backend'", [], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()
+            .map_err(|e| format!("failed to read {}: {e}", self.path.display()))?;
+
+        let Some(state) = state else {
+            return Ok(None);
+        };
+
+        let mut backend = from_text::<Backend>(&state)
+            .map_err(|e| format!("failed to parse {}: {e}", self.path.display()))?;
+
+        self.load_tree("time", "subjects", &mut backend.projects)
+            .and_then(|_| self.load_tree("todo", "todos", &mut backend.todos))
+            .map_err(|e| format!("failed to read {}: {e}", self.path.display()))?;
+
+        Ok(Some(backend))
+    }
+
+    fn history_store(&mut self) -> Option<Box<dyn HistoryStore>> {
+        match open_connection(&self.path) {
+            Ok(connection) => Some(Box::new(SqliteHistory {
+                connection,
+                unsaved_months: self.unsaved_months.clone(),
+            })),
+            Err(e) => {
+                eprintln!("{e}");
+                None
+            }
+        }
+    }
+
+    /// Hands the rows of the trees, the state and the months the history wrote to the
+    /// worker. The trees are swapped for their roots while the state is serialized, their
+    /// entries are in the tables.
+    fn save(&mut self, backend: &mut Backend) -> Result<(), String> {
+        let roots = (
+            backend.projects.without_inner(),
+            backend.todos.without_inner(),
+        );
+        let projects = std::mem::replace(&mut backend.projects, roots.0);
+        let todos = std::mem::replace(&mut backend.todos, roots.1);
+
+        let state = backend.to_ron();
+
+        backend.projects = projects;
+        backend.todos = todos;
+
+        let state = state.map_err(|e| e.to_string())?;
+
+        let mut rows = Rows::new();
+
+        Self::tree_rows(&mut rows, "time", "subjects", &backend.projects)
+            .and_then(|_| Self::tree_rows(&mut rows, "todo", "todos", &backend.todos))
+            .map_err(|e| format!("failed to save {}: {e}", self.path.display()))?;
+
+        self.saver.save(SqliteSnapshot {
+            state,
+            rows,
+            months: std::mem::take(&mut *self.unsaved_months.lock().unwrap()),
+        });
+
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        self.saver.flush();
+    }
+
+    fn save_outcome(&mut self) -> Option<Result<(), String>> {
+        self.saver.take_outcome()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.connection
+            .query_row("SELECT COUNT(*) FROM state", [], |row| row.get::<_, i64>(0))
+            .is_ok_and(|v| v == 0)
+    }
+
+    /// Other processes writing the database is not something the tracker does or expects.
+    fn changed_outside(&mut self) -> bool {
+        false
+    }
+
+    fn reopen(&self) -> Result<Box<dyn Storage>, String> {
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+
+        Ok(Box::new(Self::open(dir)?))
+    }
+
+    fn encrypted(&self) -> bool {
+        false
+    }
+
+    fn set_passphrase(&mut self, passphrase: Option<&str>) -> Result<(), String> {
+        match passphrase {
+            Some(_) => Err("the SQLite database cannot be encrypted, use RON files".to_string()),
+            None => Ok(()),
+        }
+    }
+
+    fn retire(&mut self) -> Result<(), String> {
+        self.saver.flush();
+
+        move_aside(&self.path)
+    }
+}
+
+/// What one save hands the worker.
+struct SqliteSnapshot {
+    state: String,
+    rows: Rows,
+    months: Months,
+}
+
+impl SqliteSnapshot {
+    /// `newer` on top of this, months only one of them has are kept.
+    fn merge(mut self, newer: Self) -> Self {
+        self.months.extend(newer.months);
+
+        Self {
+            state: newer.state,
+            rows: newer.rows,
+            months: self.months,
+        }
+    }
+}
+
+/// Writes snapshots as the changes to what is in the database.
+struct SqliteWriter {
+    path: PathBuf,
+    /// Opened on the first write.
+    connection: Option<Connection>,
+    /// Tree rows in the database, read on the first write.
+    rows: Option<Rows>,
+    /// Records in the database of each month written so far.
+    months: HashMap<NaiveDate, HashMap<Uuid, HistoryRecord>>,
+    /// Snapshot whose write failed, written together with the next one.
+    failed: Option<SqliteSnapshot>,
+}
+
+impl Writer for SqliteWriter {
+    type Snapshot = SqliteSnapshot;
+
+    fn write(&mut self, snapshots: Vec<SqliteSnapshot>) -> Result<Option<SystemTime>, String> {
+        let Some(snapshot) = self
+            .failed
+            .take()
+            .into_iter()
+            .chain(snapshots)
+            .reduce(SqliteSnapshot::merge)
+        else {
+            return Ok(None);
+        };
+
+        if let Err(e) = self.write_changes(&snapshot) {
+            self.failed = Some(snapshot);
+
+            return Err(format!("failed to save {}: {e}", self.path.display()));
+        }
+
+        for (month, records) in snapshot.months {
+            let ids = records.iter().map(|v| v.id).collect::<Vec<_>>();
+
+            // A record moved to another month is no longer in the one it was.
+            for (_, stored) in self.months.iter_mut().filter(|(v, _)| **v != month) {
+                for id in &ids {
+                    stored.remove(id);
+                }
+            }
+
+            self.months
+                .insert(month, records.into_iter().map(|v| (v.id, v)).collect());
+        }
+
+        self.rows = Some(snapshot.rows);
+
+        Ok(None)
+    }
+}
+
+impl SqliteWriter {
+    fn write_changes(&mut self, snapshot: &SqliteSnapshot) -> Result<(), String> {
+        let error = |e: rusqlite::Error| e.to_string();
+
+        if self.connection.is_none() {
+            self.connection = Some(open_connection(&self.path)?);
+        }
+
+        let Some(connection) = &mut self.connection else {
+            return Ok(());
+        };
+
+        if self.rows.is_none() {
+            self.rows = Some(read_rows(connection).map_err(error)?);
+        }
+
+        for month in snapshot.months.keys() {
+            if !self.months.contains_key(month) {
+                let records = read_records(connection, *month).map_err(error)?;
+
+                self.months
+                    .insert(*month, records.into_iter().map(|v| (v.id, v)).collect());
+            }
+        }
+
+        let stored = self.rows.as_ref().unwrap_or(&snapshot.rows);
+
+        let transaction = connection.transaction().map_err(error)?;
+
+        for (table, id) in stored.keys().filter(|v| !snapshot.rows.contains_key(v)) {
+            transaction
+                .execute(
+                    &format!("DELETE FROM {table} WHERE id = ?1"),
+                    [id.to_string()],
+                )
+                .map_err(error)?;
+        }
+
+        for ((table, id), row) in &snapshot.rows {
+            if stored.get(&(*table, *id)) == Some(row) {
+                continue;
+            }
+
+            let columns = tree_columns(table);
+
+            transaction
+                .execute(
+                    &format!(
+                        "INSERT OR REPLACE INTO {table} (id, {columns}) VALUES (?1{})",
+                        (2..=row.len() + 1)
+                            .map(|v| format!(", ?{v}"))
+                            .collect::<String>()
+                    ),
+                    params_from_iter(std::iter::once(id.to_string()).chain(row.iter().cloned())),
+                )
+                .map_err(error)?;
+        }
+
+        // Removed first, so a record moving between months is not removed after it moved.
+        for (month, records) in &snapshot.months {
+            let (from, to) = month_bounds(*month);
+
+            for id in self.months[month]
+                .keys()
+                .filter(|id| !records.iter().any(|v| v.id == **id))
+            {
+                transaction
+                    .execute(
+                        "DELETE FROM history_records \
+                        WHERE id = ?1 AND start_date >= ?2 AND start_date < ?3",
+                        params![id.to_string(), from, to],
+                    )
+                    .map_err(error)?;
+            }
+        }
+
+        for (month, records) in &snapshot.months {
+            let stored = &self.months[month];
+
+            for record in records
+                .iter()
+                .filter(|v| !stored.get(&v.id).is_some_and(|s| s.same_fields(v)))
+            {
+                transaction
+                    .execute(
+                        "INSERT OR REPLACE INTO history_records \
+                        (id, start_date, end_date, project_id, sub_project_id, subject_id) \
+                        VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![
+                            record.id.to_string(),
+                            to_nanos(record.start_date),
+                            to_nanos(record.end_date),
+                            record.project_id.to_string(),
+                            record.sub_project_id.to_string(),
+                            record.subject_id.to_string()
+                        ],
+                    )
+                    .map_err(error)?;
+            }
+        }
+
+        transaction
+            .execute(
+                "INSERT OR REPLACE INTO state (key, value) VALUES ('backend', ?1)",
+                [&snapshot.state],
+            )
+            .map_err(error)?;
+
+        transaction.commit().map_err(error)
+    }
+}
+
+fn tree_columns(table: &str) -> &'static str {
+    TREE_TABLES
+        .iter()
+        .find(|v| v.0 == table)
+        .map_or("", |v| v.1)
+}
+
+/// Every tree row in the database.
+fn read_rows(connection: &Connection) -> rusqlite::Result<Rows> {
+    let mut rows = Rows::new();
+
+    for (table, columns) in TREE_TABLES {
+        let count = columns.split(',').count();
+
+        let mut statement = connection.prepare(&format!("SELECT id, {columns} FROM {table}"))?;
+        let table_rows = statement.query_map([], |row| {
+            let values = (1..=count)
+                .map(|i| row.get::<_, String>(i))
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            Ok((parse_id(&row.get::<_, String>(0)?)?, values))
+        })?;
+
+        for row in table_rows {
+            let (id, values) = row?;
+            rows.insert((table, id), values);
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Records starting in the month.
+fn read_records(connection: &Connection, month: NaiveDate) -> rusqlite::Result<Vec<HistoryRecord>> {
+    let (from, to) = month_bounds(month);
+
+    let mut statement = connection.prepare(
+        "SELECT id, start_date, end_date, project_id, sub_project_id, subject_id \
+        FROM history_records WHERE start_date >= ?1 AND start_date < ?2",
+    )?;
+
+    let rows = statement.query_map([from, to], |row| {
+        let date = |i: usize| {
+            let nanos = row.get::<_, i64>(i)?;
+
+            from_nanos(nanos).ok_or(rusqlite::Error::IntegralValueOutOfRange(i, nanos))
+        };
+
+        Ok(HistoryRecord {
+            id: parse_id(&row.get::<_, String>(0)?)?,
+            start_date: date(1)?,
+            end_date: date(2)?,
+            project_id: parse_id(&row.get::<_, String>(3)?)?,
+            sub_project_id: parse_id(&row.get::<_, String>(4)?)?,
+            subject_id: parse_id(&row.get::<_, String>(5)?)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// Months of `history_records`, each read by a range query on the start. Written months
+/// go to the worker of the storage with its next save.
+struct SqliteHistory {
+    connection: Connection,
+    unsaved_months: Arc<Mutex<Months>>,
+}
+
+/// Start and end of the month in the nanoseconds `history_records` keeps.
+fn month_bounds(month: NaiveDate) -> (i64, i64) {
+    (
+        to_nanos(start_of_day(month)),
+        to_nanos(start_of_day(next_month(month))),
+    )
+}
+
+impl HistoryStore for SqliteHistory {
+    fn months(&mut self) -> Result<BTreeSet<NaiveDate>, String> {
+        let error = |e: rusqlite::Error| e.to_string();
+
+        // Months by local time, the way the history buckets records.
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT DISTINCT strftime('%Y-%m-01', start_date / 1000000000, 'unixepoch', \
+                'localtime') FROM history_records",
+            )
+            .map_err(error)?;
+        let rows = statement
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(error)?;
+
+        let mut months = BTreeSet::new();
+
+        for month in rows {
+            let month = month.map_err(error)?;
+
+            if let Ok(month) = NaiveDate::parse_from_str(&month, "%Y-%m-%d") {
+                months.insert(month);
+            }
+        }
+
+        Ok(months)
+    }
+
+    fn read_month(&mut self, month: NaiveDate) -> Result<Vec<HistoryRecord>, String> {
+        read_records(&self.connection, month).map_err(|e| e.to_string())
+    }
+
+    fn write_month(&mut self, month: NaiveDate, records: &[&HistoryRecord]) -> Result<(), String> {
+        self.unsaved_months
+            .lock()
+            .unwrap()
+            .insert(month, records.iter().map(|v| **v).collect());
+
+        Ok(())
+    }
+
+    fn keeps_current_month(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{EntryPath, Tree};
+    use chrono::Duration;
+    use tempfile::TempDir;
+
+    fn save(backend: &mut Backend) {
+        backend.dirty();
+        backend.dump();
+        backend.flush();
+        assert_eq!(backend.save_error, None);
+    }
+
+    #[test]
+    fn unchanged_rows_are_not_rewritten() {
+        let dir = TempDir::new().unwrap();
+        let open = || Backend::load_from(Box::new(SqliteStorage::open(dir.path()).unwrap()));
+
+        let mut backend = open().unwrap();
+        let navbar = backend
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+        let footer = backend
+            .add_path("Client/Website/Footer", Tree::Time)
+            .unwrap();
+        let Some(EntryPath::Subject(.., logo)) =
+            backend.add_path("Client/Website/Logo", Tree::Time)
+        else {
+            unreachable!();
+        };
+        let EntryPath::Subject(p, s, x) = navbar else {
+            unreachable!();
+        };
+        let now = Local::now();
+        let record = backend
+            .add_manual_record(p, s, x, now - Duration::hours(2), now - Duration::hours(1))
+            .unwrap();
+        save(&mut backend);
+
+        // Marks the worker does not know about, only rewriting the rows removes them.
+        let connection = open_connection(&dir.path().join(SQLITE_FILE)).unwrap();
+        connection
+            .execute_batch(&format!(
+                "UPDATE subjects SET data = data || ' ' WHERE id = '{logo}';
+                 UPDATE history_records SET end_date = end_date + 1 WHERE id = '{record}';"
+            ))
+            .unwrap();
+
+        backend.rename_entry(Tree::Time, footer, "Header");
+        let start = now - Duration::minutes(30);
+        backend
+            .add_manual_record(p, s, x, start, start + Duration::minutes(10))
+            .unwrap();
+        save(&mut backend);
+
+        let data: String = connection
+            .query_row(
+                "SELECT data FROM subjects WHERE id = ?1",
+                [logo.to_string()],
+                |v| v.get(0),
+            )
+            .unwrap();
+        let end: i64 = connection
+            .query_row(
+                "SELECT end_date FROM history_records WHERE id = ?1",
+                [record.to_string()],
+                |v| v.get(0),
+            )
+            .unwrap();
+        let records: i64 = connection
+            .query_row("SELECT COUNT(*) FROM history_records", [], |v| v.get(0))
+            .unwrap();
+
+        assert!(data.ends_with(' '));
+        assert_eq!(end, to_nanos(now - Duration::hours(1)) + 1);
+        assert_eq!(records, 2);
+        assert_eq!(
+            open().unwrap().entry_name(Tree::Time, footer).as_deref(),
+            Some("Header")
+        );
+    }
+}
@@ -0,0 +1,1565 @@
+use crate::backend::Backend;
+use crate::crypto::{self, Cipher};
+use crate::history::HistoryRecord;
+use crate::i18n::tr;
+use crate::profiles::data_dir;
+use crate::sqlite::{SqliteStorage, SQLITE_FILE};
+use chrono::{Local, NaiveDate};
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::SystemTime;
+
+/// The data file, in the folder of the profile.
+pub const DATA_FILE: &str = "data.ron";
+
+/// Folder of the history of past months, one file per month.
+pub const HISTORY_DIR: &str = "history";
+
+/// Copy of a data file holding the whole history, made before it is first split by month.
+const UNSPLIT_BACKUP_FILE: &str = "data.ron.unsplit.bak";
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum StorageKind {
+    Ron,
+    Sqlite,
+}
+
+impl StorageKind {
+    pub const ALL: [StorageKind; 2] = [StorageKind::Ron, StorageKind::Sqlite];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StorageKind::Ron => tr!("RON files"),
+            StorageKind::Sqlite => tr!("SQLite database"),
+        }
+    }
+
+    /// Command line name, as taken by `convert-storage`.
+    pub fn name(self) -> &'static str {
+        match self {
+            StorageKind::Ron => "ron",
+            StorageKind::Sqlite => "sqlite",
+        }
+    }
+}
+
+/// Where the data lives between runs.
+pub trait Storage {
+    fn kind(&self) -> StorageKind;
+
+    /// The saved data, `None` when nothing was saved yet.
+    fn load(&mut self) -> Result<Option<Backend>, String>;
+
+    /// Where past months of the history go, `None` keeps them with the rest.
+    fn history_store(&mut self) -> Option<Box<dyn HistoryStore>>;
+
+    /// Writes everything besides the months the history store keeps, maybe on another
+    /// thread.
+    fn save(&mut self, backend: &mut Backend) -> Result<(), String>;
+
+    /// Blocks until every save is written.
+    fn flush(&mut self);
+
+    /// How the latest write on another thread went, `None` when nothing was written since
+    /// the last call.
+    fn save_outcome(&mut self) -> Option<Result<(), String>>;
+
+    /// Whether nothing of this format exists yet, so converting into it overwrites nothing.
+    fn is_empty(&self) -> bool;
+
+    /// Whether something else wrote the data since it was loaded or saved here, like a sync
+    /// tool bringing over the data of another machine.
+    fn changed_outside(&mut self) -> bool;
+
+    /// A fresh storage of the same data, to load what is there now.
+    fn reopen(&self) -> Result<Box<dyn Storage>, String>;
+
+    fn encrypted(&self) -> bool;
+
+    /// Encrypts the data with `passphrase` from the next save on, or stops encrypting it
+    /// when `None`.
+    fn set_passphrase(&mut self, passphrase: Option<&str>) -> Result<(), String>;
+
+    /// Moves the data aside once it was converted to another format.
+    fn retire(&mut self) -> Result<(), String>;
+}
+
+/// Months of history, read and written one month at a time.
+pub trait HistoryStore {
+    /// Months with records, by their first day.
+    fn months(&mut self) -> Result<BTreeSet<NaiveDate>, String>;
+
+    /// Records starting in the month.
+    fn read_month(&mut self, month: NaiveDate) -> Result<Vec<HistoryRecord>, String>;
+
+    /// Replaces the records of the month, without any the month is removed. A store may
+    /// leave the writing to the next save of the data.
+    fn write_month(&mut self, month: NaiveDate, records: &[&HistoryRecord]) -> Result<(), String>;
+
+    /// Whether the current month is kept here too instead of with the main data.
+    fn keeps_current_month(&self) -> bool;
+}
+
+/// The storage the data of the profile in use is in, the SQLite database when there is one
+/// holding data. One left empty by a failed conversion is passed over.
+pub fn open_storage() -> Result<Box<dyn Storage>, String> {
+    let dir = data_dir();
+
+    if dir.join(SQLITE_FILE).exists() {
+        let storage = SqliteStorage::open(&dir)?;
+
+        if !storage.is_empty() {
+            return Ok(Box::new(storage));
+        }
+    }
+
+    Ok(Box::new(RonStorage::new(&dir)))
+}
+
+/// An empty storage of `kind` to convert into, next to the data of the profile in use.
+pub fn new_storage(kind: StorageKind) -> Result<Box<dyn Storage>, String> {
+    let dir = data_dir();
+
+    let (storage, paths): (Box<dyn Storage>, _) = match kind {
+        StorageKind::Ron => (
+            Box::new(RonStorage::new(&dir)),
+            format!(
+                "{} or {}",
+                dir.join(DATA_FILE).display(),
+                dir.join(HISTORY_DIR).display()
+            ),
+        ),
+        StorageKind::Sqlite => (
+            Box::new(SqliteStorage::open(&dir)?),
+            dir.join(SQLITE_FILE).display().to_string(),
+        ),
+    };
+
+    if !storage.is_empty() {
+        return Err(format!("{paths} already holds data, move it away first"));
+    }
+
+    Ok(storage)
+}
+
+/// Whether the data file in `dir` is encrypted and needs a passphrase to load.
+pub fn is_encrypted(dir: &Path) -> bool {
+    file_is_encrypted(&dir.join(DATA_FILE))
+}
+
+fn file_is_encrypted(path: &Path) -> bool {
+    let mut start = [0; 64];
+
+    File::open(path)
+        .and_then(|mut v| v.read(&mut start))
+        .is_ok_and(|n| crypto::is_encrypted(&start[..n]))
+}
+
+/// Key of an encrypted data file, shared with the month files written along with it.
+type SharedCipher = Arc<Mutex<Option<Cipher>>>;
+
+/// Reads a file written along with the data file, decrypting it with `cipher` when encrypted.
+fn read_file(path: &Path, cipher: Option<&Cipher>) -> Result<Vec<u8>, String> {
+    let contents =
+        std::fs::read(path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+
+    if !crypto::is_encrypted(&contents) {
+        return Ok(contents);
+    }
+
+    let Some(cipher) = cipher else {
+        return Err(format!("{} is encrypted", path.display()));
+    };
+
+    cipher
+        .decrypt(&contents)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))
+}
+
+fn seal(data: Vec<u8>, cipher: Option<&Cipher>) -> Result<Vec<u8>, String> {
+    match cipher {
+        Some(cipher) => cipher.encrypt(&data),
+        None => Ok(data),
+    }
+}
+
+/// Writes the files again encrypted with `to`, or not at all when `None`. When one fails,
+/// those already written go back to `from`, so all of them keep opening with one key.
+fn rewrite_files(
+    paths: &[PathBuf],
+    from: Option<&Cipher>,
+    to: Option<&Cipher>,
+) -> Result<(), String> {
+    let contents = paths
+        .iter()
+        .map(|v| read_file(v, from))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let write = |path: &Path, data: &[u8], cipher: Option<&Cipher>| {
+        seal(data.to_vec(), cipher)
+            .and_then(|v| write_atomically(path, v).map_err(|e| format!("{}: {e}", path.display())))
+    };
+
+    for (i, (path, data)) in paths.iter().zip(&contents).enumerate() {
+        if let Err(e) = write(path, data, to) {
+            for (path, data) in paths.iter().zip(&contents).take(i) {
+                if let Err(e) = write(path, data, from) {
+                    eprintln!("{e}");
+                }
+            }
+
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a data file from anywhere along with the history split off next to it, to merge
+/// it into ours. Nothing is ever saved to it.
+pub fn read_data_file(path: &Path) -> Result<Backend, String> {
+    let contents =
+        std::fs::read(path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+
+    if crypto::is_encrypted(&contents) {
+        return Err(format!(
+            "{} is encrypted, turn its encryption off first",
+            path.display()
+        ));
+    }
+
+    let contents = String::from_utf8(contents)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+
+    let mut backend = ron::from_str::<Backend>(&contents)
+        .map_err(|e| format!("failed to parse {}: {e}", path.display()))?;
+
+    let history_dir = path.with_file_name(HISTORY_DIR);
+
+    if history_dir.is_dir() {
+        backend.history.open_archive(Box::new(MonthFiles {
+            dir: history_dir,
+            cipher: SharedCipher::default(),
+        }));
+    }
+
+    backend.history.load_all();
+
+    Ok(backend)
+}
+
+/// Renames `path` to `<path>.converted.bak`, when it exists.
+pub fn move_aside(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".converted.bak");
+    let backup = PathBuf::from(backup);
+
+    std::fs::rename(path, &backup).map_err(|e| {
+        format!(
+            "failed to move {} to {}: {e}",
+            path.display(),
+            backup.display()
+        )
+    })
+}
+
+/// The data file plus a file per past month in [`HISTORY_DIR`].
+pub struct RonStorage {
+    /// Folder of the profile the data belongs to.
+    dir: PathBuf,
+    saver: Saver<FileWriter>,
+    /// Opens the data file when encrypted, kept to load it again after changes elsewhere.
+    passphrase: Option<String>,
+    /// Encrypts every save and the month files while set.
+    cipher: SharedCipher,
+    /// Whether past months go to their own files. A data file from before month files
+    /// stays whole when it cannot be backed up before the split.
+    split: bool,
+}
+
+impl RonStorage {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            saver: Saver::new(FileWriter {
+                path: dir.join(DATA_FILE),
+            }),
+            passphrase: None,
+            cipher: SharedCipher::default(),
+            split: true,
+        }
+    }
+
+    /// Like [`new`](Self::new), for an encrypted data file.
+    pub fn with_passphrase(dir: &Path, passphrase: &str) -> Self {
+        Self {
+            passphrase: Some(passphrase.to_string()),
+            ..Self::new(dir)
+        }
+    }
+
+    fn data_path(&self) -> PathBuf {
+        self.dir.join(DATA_FILE)
+    }
+
+    fn history_dir(&self) -> PathBuf {
+        self.dir.join(HISTORY_DIR)
+    }
+
+    /// Month files and the backup from before they were split off, which are encrypted
+    /// like the data file.
+    fn side_files(&self) -> Vec<PathBuf> {
+        let months = std::fs::read_dir(self.history_dir())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|v| v.path())
+            .filter(|v| v.extension().is_some_and(|v| v == "ron"));
+
+        let mut files = months.collect::<Vec<_>>();
+        files.sort();
+        files.push(self.dir.join(UNSPLIT_BACKUP_FILE));
+        files.retain(|v| v.is_file());
+
+        files
+    }
+}
+
+impl Storage for RonStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Ron
+    }
+
+    fn load(&mut self) -> Result<Option<Backend>, String> {
+        let config = self.data_path();
+
+        if !config.exists() {
+            return Ok(None);
+        }
+
+        // Taken before reading, a change while reading is then noticed by the next check.
+        let modified = modified(&config);
+
+        let mut contents = std::fs::read(&config)
+            .map_err(|e| format!("failed to read {}: {e}", config.display()))?;
+
+        if crypto::is_encrypted(&contents) {
+            let Some(passphrase) = &self.passphrase else {
+                return Err(format!(
+                    "{} is encrypted, open it in the window to enter the passphrase",
+                    config.display()
+                ));
+            };
+
+            let (cipher, plain) = Cipher::open(passphrase, &contents)?;
+
+            // Left unencrypted by versions that only encrypted the data file.
+            let plain_files = self
+                .side_files()
+                .into_iter()
+                .filter(|v| !file_is_encrypted(v))
+                .collect::<Vec<_>>();
+
+            if let Err(e) = rewrite_files(&plain_files, None, Some(&cipher)) {
+                eprintln!("failed to encrypt the history: {e}");
+            }
+
+            *self.cipher.lock().unwrap() = Some(cipher);
+            contents = plain;
+        }
+
+        let contents = String::from_utf8(contents)
+            .map_err(|e| format!("failed to read {}: {e}", config.display()))?;
+
+        let mut backend = ron::from_str::<Backend>(&contents)
+            .map_err(|e| format!("failed to parse {}: {e}", config.display()))?;
+
+        *self.saver.written.lock().unwrap() = modified;
+
+        let today = Local::now().date_naive();
+
+        if !self.history_dir().exists() && backend.history.starts_before_month(today) {
+            let backup = self.dir.join(UNSPLIT_BACKUP_FILE);
+
+            match std::fs::copy(&config, &backup) {
+                Ok(_) => backend.dirty(),
+                Err(e) => {
+                    eprintln!(
+                        "failed to back up {} to {}: {e}",
+                        config.display(),
+                        backup.display()
+                    );
+                    self.split = false;
+                }
+            }
+        }
+
+        Ok(Some(backend))
+    }
+
+    fn history_store(&mut self) -> Option<Box<dyn HistoryStore>> {
+        self.split.then(|| {
+            Box::new(MonthFiles {
+                dir: self.history_dir(),
+                cipher: self.cipher.clone(),
+            }) as Box<dyn HistoryStore>
+        })
+    }
+
+    fn save(&mut self, backend: &mut Backend) -> Result<(), String> {
+        let data = backend.to_ron().map_err(|e| e.to_string())?;
+
+        let data = seal(data.into_bytes(), self.cipher.lock().unwrap().as_ref())?;
+
+        self.saver.save(data);
+
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        self.saver.flush();
+    }
+
+    fn save_outcome(&mut self) -> Option<Result<(), String>> {
+        self.saver.take_outcome()
+    }
+
+    fn is_empty(&self) -> bool {
+        !self.data_path().exists() && !self.history_dir().exists()
+    }
+
+    /// Compares the modification time of the data file with the one of the last read or
+    /// write. A file that went missing counts as unchanged, loading it would give empty data.
+    fn changed_outside(&mut self) -> bool {
+        let written = self.saver.written.lock().unwrap();
+        let modified = modified(&self.data_path());
+
+        modified.is_some() && modified != *written
+    }
+
+    fn reopen(&self) -> Result<Box<dyn Storage>, String> {
+        Ok(Box::new(Self {
+            passphrase: self.passphrase.clone(),
+            ..Self::new(&self.dir)
+        }))
+    }
+
+    fn encrypted(&self) -> bool {
+        self.cipher.lock().unwrap().is_some()
+    }
+
+    /// Rewrites the month files and the backup right away, the data file is left to the
+    /// save that follows.
+    fn set_passphrase(&mut self, passphrase: Option<&str>) -> Result<(), String> {
+        let cipher = passphrase.map(Cipher::new).transpose()?;
+        let mut current = self.cipher.lock().unwrap();
+
+        rewrite_files(&self.side_files(), current.as_ref(), cipher.as_ref())?;
+
+        *current = cipher;
+        self.passphrase = passphrase.map(str::to_string);
+
+        Ok(())
+    }
+
+    fn retire(&mut self) -> Result<(), String> {
+        self.saver.flush();
+
+        move_aside(&self.data_path())?;
+        move_aside(&self.history_dir())
+    }
+}
+
+/// Month files like `2024-03.ron` holding the records starting in that month, encrypted
+/// along with the data file.
+struct MonthFiles {
+    dir: PathBuf,
+    cipher: SharedCipher,
+}
+
+impl MonthFiles {
+    fn path(&self, month: NaiveDate) -> PathBuf {
+        self.dir.join(format!("{}.ron", month.format("%Y-%m")))
+    }
+}
+
+impl HistoryStore for MonthFiles {
+    fn months(&mut self) -> Result<BTreeSet<NaiveDate>, String> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(v) => v,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeSet::new()),
+            Err(e) => return Err(format!("failed to read {}: {e}", self.dir.display())),
+        };
+
+        Ok(entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name();
+                let month = name.to_str()?.strip_suffix(".ron")?;
+
+                NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d").ok()
+            })
+            .collect())
+    }
+
+    fn read_month(&mut self, month: NaiveDate) -> Result<Vec<HistoryRecord>, String> {
+        let path = self.path(month);
+
+        let contents = read_file(&path, self.cipher.lock().unwrap().as_ref())?;
+
+        String::from_utf8(contents)
+            .map_err(|e| e.to_string())
+            .and_then(|v| ron::from_str(&v).map_err(|e| e.to_string()))
+            .map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    fn write_month(&mut self, month: NaiveDate, records: &[&HistoryRecord]) -> Result<(), String> {
+        let path = self.path(month);
+
+        let written = if records.is_empty() {
+            match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            }
+        } else {
+            let data = ron::ser::to_string_pretty(records, ron::ser::PrettyConfig::default())
+                .map_err(|e| e.to_string())
+                .and_then(|v| seal(v.into_bytes(), self.cipher.lock().unwrap().as_ref()))
+                .map_err(std::io::Error::other);
+
+            std::fs::create_dir_all(&self.dir)
+                .and(data)
+                .and_then(|v| write_atomically(&path, &v))
+        };
+
+        written.map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    fn keeps_current_month(&self) -> bool {
+        false
+    }
+}
+
+/// Writes `data` to a temporary file next to `path` and moves it into place, so a crash
+/// mid-write never leaves a truncated file behind.
+pub fn write_atomically(path: &Path, data: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut file = File::create(&tmp)?;
+    file.write_all(data.as_ref())?;
+    file.sync_all()?;
+
+    std::fs::rename(tmp, path)
+}
+
+/// Serializes a map in key order instead of hash order, so saving unchanged data writes the
+/// same text. Reading it back needs nothing special.
+pub fn sorted_map<K: Serialize + Ord, V: Serialize, S: Serializer>(
+    map: &HashMap<K, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut entries = map.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|v| v.0);
+
+    serializer.collect_map(entries)
+}
+
+pub fn sorted_set<T: Serialize + Ord, S: Serializer>(
+    set: &HashSet<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut values = set.iter().collect::<Vec<_>>();
+    values.sort();
+
+    serializer.collect_seq(values)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|v| v.modified()).ok()
+}
+
+/// Writes what a [`Saver`] is handed, on its worker thread.
+pub trait Writer: Send {
+    type Snapshot: Send;
+
+    /// Writes the snapshots queued since the previous call, oldest first. Returns the
+    /// modification time of the file written, for storage that watches one.
+    fn write(&mut self, snapshots: Vec<Self::Snapshot>) -> Result<Option<SystemTime>, String>;
+}
+
+/// Writes the whole data file, only the newest snapshot matters.
+struct FileWriter {
+    path: PathBuf,
+}
+
+impl Writer for FileWriter {
+    type Snapshot = Vec<u8>;
+
+    fn write(&mut self, snapshots: Vec<Vec<u8>>) -> Result<Option<SystemTime>, String> {
+        let Some(data) = snapshots.last() else {
+            return Ok(modified(&self.path));
+        };
+
+        write_atomically(&self.path, data)
+            .map(|_| modified(&self.path))
+            .map_err(|e| format!("failed to save {}: {e}", self.path.display()))
+    }
+}
+
+/// Writes snapshots on a worker thread. Snapshots queued while a write is in progress are
+/// handed to the next write together.
+pub struct Saver<W: Writer> {
+    /// Handed to the worker while it runs.
+    writer: Option<W>,
+    sender: Option<Sender<W::Snapshot>>,
+    worker: Option<JoinHandle<W>>,
+    /// Modification time of the file after the last write, held while writing so a check
+    /// never sees the file of a write without its time.
+    written: Arc<Mutex<Option<SystemTime>>>,
+    /// How the latest write went, until it is taken.
+    outcome: Arc<Mutex<Option<Result<(), String>>>>,
+}
+
+impl<W: Writer + 'static> Saver<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Some(writer),
+            sender: None,
+            worker: None,
+            written: Arc::default(),
+            outcome: Arc::default(),
+        }
+    }
+
+    pub fn save(&mut self, snapshot: W::Snapshot) {
+        if self.sender.is_none() {
+            let Some(mut writer) = self.writer.take() else {
+                eprintln!("the saver stopped, nothing is saved anymore");
+                return;
+            };
+
+            let (sender, receiver) = channel::<W::Snapshot>();
+            let written = self.written.clone();
+            let outcome = self.outcome.clone();
+
+            self.worker = Some(std::thread::spawn(move || {
+                while let Ok(snapshot) = receiver.recv() {
+                    let snapshots = std::iter::once(snapshot)
+                        .chain(receiver.try_iter())
+                        .collect();
+
+                    let mut written = written.lock().unwrap();
+
+                    let result = writer.write(snapshots).map(|v| *written = v);
+
+                    if let Err(e) = &result {
+                        eprintln!("{e}");
+                    }
+
+                    *outcome.lock().unwrap() = Some(result);
+                }
+
+                writer
+            }));
+
+            self.sender = Some(sender);
+        }
+
+        if let Some(sender) = &self.sender {
+            // The worker only stops once the sender is gone, so this cannot fail.
+            let _ = sender.send(snapshot);
+        }
+    }
+
+    pub fn take_outcome(&self) -> Option<Result<(), String>> {
+        self.outcome.lock().unwrap().take()
+    }
+
+    /// Blocks until every queued snapshot is written.
+    pub fn flush(&mut self) {
+        self.sender = None;
+
+        if let Some(worker) = self.worker.take() {
+            self.writer = worker.join().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{EntryPath, Goal, GoalPeriod, Issue, Priority, Tree};
+    use crate::frontend::DisplayMode;
+    use chrono::{DateTime, Duration};
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    type Open = fn(&Path) -> Box<dyn Storage>;
+
+    /// Every kind of storage, the suite below runs against each.
+    const STORAGES: [(StorageKind, Open); 2] = [
+        (StorageKind::Ron, |dir| Box::new(RonStorage::new(dir))),
+        (StorageKind::Sqlite, |dir| {
+            Box::new(SqliteStorage::open(dir).unwrap())
+        }),
+    ];
+
+    fn load(open: Open, dir: &Path) -> Backend {
+        let mut backend = Backend::load_from(open(dir)).unwrap();
+        backend.history.load_all();
+        backend
+    }
+
+    fn save(backend: &mut Backend) {
+        backend.dirty();
+        backend.dump();
+        backend.flush();
+        assert_eq!(backend.save_error, None);
+    }
+
+    /// What has to survive a save, in a form that compares.
+    fn contents(backend: &Backend) -> (String, String, String, Vec<String>) {
+        let mut records = backend
+            .history
+            .records()
+            .map(|v| {
+                format!(
+                    "{} {} {} {} {} {}",
+                    v.id,
+                    v.start_date.timestamp_nanos(),
+                    v.end_date.timestamp_nanos(),
+                    v.project_id,
+                    v.sub_project_id,
+                    v.subject_id
+                )
+            })
+            .collect::<Vec<_>>();
+        records.sort();
+
+        (
+            ron::to_string(&backend.projects).unwrap(),
+            ron::to_string(&backend.todos).unwrap(),
+            ron::to_string(&backend.settings).unwrap(),
+            records,
+        )
+    }
+
+    /// A subject with a record this month and one two months back, plus a todo.
+    fn fill(backend: &mut Backend) -> (EntryPath, Uuid, Uuid) {
+        let path = backend
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+        backend.add_path("Home/Garden/Mow", Tree::Todo).unwrap();
+        backend.scratchpad = "Call back".to_string();
+        backend.settings.min_session_seconds = 5;
+
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+
+        let now = Local::now();
+        let recent = backend
+            .add_manual_record(p, s, x, now - Duration::hours(2), now - Duration::hours(1))
+            .unwrap();
+        let start = now - Duration::days(62);
+        let old = backend
+            .add_manual_record(p, s, x, start, start + Duration::minutes(45))
+            .unwrap();
+
+        (path, recent, old)
+    }
+
+    #[test]
+    fn new_storage_is_empty() {
+        for (kind, open) in STORAGES {
+            let dir = TempDir::new().unwrap();
+            let mut storage = open(dir.path());
+
+            assert!(storage.is_empty(), "{kind:?}");
+            assert!(storage.load().unwrap().is_none(), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn saved_data_loads_back() {
+        for (kind, open) in STORAGES {
+            let dir = TempDir::new().unwrap();
+
+            let mut backend = load(open, dir.path());
+            fill(&mut backend);
+            save(&mut backend);
+
+            let loaded = load(open, dir.path());
+
+            assert_eq!(contents(&loaded), contents(&backend), "{kind:?}");
+            assert_eq!(loaded.scratchpad, "Call back", "{kind:?}");
+            assert!(!open(dir.path()).is_empty(), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn later_changes_load_back() {
+        for (kind, open) in STORAGES {
+            let dir = TempDir::new().unwrap();
+
+            let mut backend = load(open, dir.path());
+            let (path, recent, old) = fill(&mut backend);
+            save(&mut backend);
+
+            let mut backend = load(open, dir.path());
+
+            backend.rename_entry(Tree::Time, path, "Footer");
+            assert!(backend.delete_record(recent));
+
+            // Into the current month, out of the one it was in.
+            let start = Local::now() - Duration::minutes(30);
+            backend.set_record_times(old, start, start + Duration::minutes(10));
+
+            let EntryPath::Subject(p, s, _) = path else {
+                unreachable!();
+            };
+            backend
+                .add_path("Client/Website/Header", Tree::Time)
+                .unwrap();
+            let header = backend.find_subject("Client/Website/Header").unwrap();
+            let EntryPath::Subject(_, _, x) = header else {
+                unreachable!();
+            };
+            let start = Local::now() - Duration::days(40);
+            backend
+                .add_manual_record(p, s, x, start, start + Duration::minutes(5))
+                .unwrap();
+
+            save(&mut backend);
+
+            let loaded = load(open, dir.path());
+
+            assert_eq!(contents(&loaded), contents(&backend), "{kind:?}");
+            assert_eq!(loaded.history.records().count(), 2, "{kind:?}");
+            assert_eq!(
+                loaded.entry_name(Tree::Time, path).as_deref(),
+                Some("Footer"),
+                "{kind:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn saving_unchanged_data_changes_nothing() {
+        for (kind, open) in STORAGES {
+            let dir = TempDir::new().unwrap();
+
+            let mut backend = load(open, dir.path());
+            fill(&mut backend);
+            save(&mut backend);
+            save(&mut backend);
+
+            let mut loaded = load(open, dir.path());
+            save(&mut loaded);
+
+            assert_eq!(
+                contents(&load(open, dir.path())),
+                contents(&backend),
+                "{kind:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn deleted_entries_are_removed() {
+        for (kind, open) in STORAGES {
+            let dir = TempDir::new().unwrap();
+
+            let mut backend = load(open, dir.path());
+            fill(&mut backend);
+            let path = backend.add_path("Home/Garden/Water", Tree::Todo).unwrap();
+            save(&mut backend);
+
+            let mut backend = load(open, dir.path());
+            backend.delete_entry(Tree::Todo, path);
+            assert_eq!(backend.empty_trash(), 1);
+            save(&mut backend);
+
+            let loaded = load(open, dir.path());
+
+            assert_eq!(loaded.entry_name(Tree::Todo, path), None, "{kind:?}");
+            assert_eq!(contents(&loaded), contents(&backend), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn dates_keep_their_instant() {
+        let date = DateTime::parse_from_rfc3339("2024-03-31T01:30:00.123456789+01:00")
+            .unwrap()
+            .with_timezone(&Local);
+
+        for (kind, open) in STORAGES {
+            let dir = TempDir::new().unwrap();
+
+            let mut backend = load(open, dir.path());
+            let (path, ..) = fill(&mut backend);
+            let EntryPath::Subject(p, s, x) = path else {
+                unreachable!();
+            };
+            let id = backend
+                .add_manual_record(p, s, x, date, date + Duration::minutes(1))
+                .unwrap();
+            save(&mut backend);
+
+            let loaded = load(open, dir.path());
+
+            assert_eq!(
+                loaded.history.get(&id).unwrap().start_date,
+                date,
+                "{kind:?}"
+            );
+        }
+    }
+
+    /// A data file with an hour recorded this month, which stays in the file itself.
+    fn saved_hour(dir: &Path) -> (Uuid, Uuid) {
+        let mut backend = load(STORAGES[0].1, dir);
+        let path = backend
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+        let now = Local::now();
+        let record = backend
+            .add_manual_record(p, s, x, now - Duration::hours(2), now - Duration::hours(1))
+            .unwrap();
+        save(&mut backend);
+
+        (x, record)
+    }
+
+    /// Edits the data file by hand.
+    fn corrupt(dir: &Path, edit: impl FnOnce(String) -> String) {
+        let path = dir.join(DATA_FILE);
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, edit(text)).unwrap();
+    }
+
+    /// Repairs everything found and checks nothing is left once saved and loaded again.
+    fn repair(dir: &Path, mut backend: Backend) -> Backend {
+        backend.repair_all();
+        assert!(backend.check_integrity().is_empty());
+        save(&mut backend);
+
+        let mut loaded = load(STORAGES[0].1, dir);
+        assert!(loaded.check_integrity().is_empty());
+
+        loaded
+    }
+
+    #[test]
+    fn intact_file_has_no_issues() {
+        let dir = TempDir::new().unwrap();
+        saved_hour(dir.path());
+
+        assert!(load(STORAGES[0].1, dir.path()).check_integrity().is_empty());
+    }
+
+    #[test]
+    fn missing_subject_is_repaired() {
+        let dir = TempDir::new().unwrap();
+        let (subject, record) = saved_hour(dir.path());
+
+        // The key and id of the subject, not the selection or the record pointing at it.
+        corrupt(dir.path(), |v| {
+            v.replacen(&subject.to_string(), &Uuid::new_v4().to_string(), 2)
+        });
+
+        let mut backend = load(STORAGES[0].1, dir.path());
+        let issues = backend.check_integrity();
+
+        assert_eq!(issues.len(), 3);
+        assert!(matches!(issues[0], Issue::MissingEntry { record: v } if v == record));
+        assert!(matches!(
+            issues[1],
+            Issue::DanglingSelection {
+                tree: Tree::Time,
+                container: Some(EntryPath::SubProject(..))
+            }
+        ));
+        assert!(matches!(issues[2], Issue::DurationMismatch { .. }));
+
+        let backend = repair(dir.path(), backend);
+
+        let record = backend.history.get(&record).unwrap();
+        let recovered = backend.find_subject("Recovered/Recovered/Missing subject");
+        assert_eq!(
+            recovered,
+            Some(EntryPath::Subject(
+                record.project_id,
+                record.sub_project_id,
+                record.subject_id
+            ))
+        );
+    }
+
+    #[test]
+    fn negative_record_is_repaired() {
+        let dir = TempDir::new().unwrap();
+        let (_, record) = saved_hour(dir.path());
+
+        corrupt(dir.path(), |v| {
+            let start = v.find("start_date: ").unwrap();
+            let end = v.find("end_date: ").unwrap();
+            let date = |at: usize| {
+                let from = v[at..].find('"').unwrap() + at;
+                let to = v[from + 1..].find('"').unwrap() + from + 2;
+                (from, to)
+            };
+            let (start, end) = (date(start), date(end));
+
+            format!(
+                "{}{}{}{}{}",
+                &v[..start.0],
+                &v[end.0..end.1],
+                &v[start.1..end.0],
+                &v[start.0..start.1],
+                &v[end.1..]
+            )
+        });
+
+        let mut backend = load(STORAGES[0].1, dir.path());
+        let issues = backend.check_integrity();
+
+        assert_eq!(issues.len(), 2);
+        assert!(matches!(issues[0], Issue::NegativeRecord { record: v } if v == record));
+        assert!(matches!(issues[1], Issue::DurationMismatch { .. }));
+
+        let backend = repair(dir.path(), backend);
+
+        let record = backend.history.get(&record).unwrap();
+        assert_eq!(record.start_date, record.end_date);
+    }
+
+    #[test]
+    fn wrong_duration_is_repaired() {
+        let dir = TempDir::new().unwrap();
+        let (subject, _) = saved_hour(dir.path());
+
+        corrupt(dir.path(), |v| v.replacen("secs: 3600,", "secs: 7200,", 1));
+
+        let mut backend = load(STORAGES[0].1, dir.path());
+        let issues = backend.check_integrity();
+
+        let [Issue::DurationMismatch {
+            path: EntryPath::Subject(.., id),
+            stored,
+            history,
+        }] = issues[..]
+        else {
+            panic!("expected one duration mismatch");
+        };
+        assert_eq!(id, subject);
+        assert_eq!(stored, std::time::Duration::from_secs(7200));
+        assert_eq!(history, std::time::Duration::from_secs(3600));
+
+        let backend = repair(dir.path(), backend);
+
+        let Some(EntryPath::Subject(p, s, x)) = backend.find_subject("Client/Website/Navbar")
+        else {
+            panic!("the subject is gone");
+        };
+        let duration = backend.projects.inner[&p].inner[&s].inner[&x]
+            .lock()
+            .unwrap()
+            .duration;
+        assert_eq!(duration, std::time::Duration::from_secs(3600));
+    }
+
+    /// Data with every optional and newer field set, the way a long used file has it.
+    fn full_backend() -> Backend {
+        let mut backend = Backend::unsaved();
+
+        let navbar = backend
+            .add_path("Client/Website/Navbar", Tree::Time)
+            .unwrap();
+        backend
+            .add_path("Client/Website/Footer", Tree::Time)
+            .unwrap();
+        backend.add_path("Home/Garden/Mow", Tree::Todo).unwrap();
+        backend.add_path("Home/Garden/Water", Tree::Todo).unwrap();
+
+        let EntryPath::Subject(p, s, x) = navbar else {
+            unreachable!();
+        };
+        let now = Local::now();
+        for days in 1..4 {
+            let start = now - Duration::days(days);
+            let id = backend
+                .add_manual_record(p, s, x, start, start + Duration::minutes(20))
+                .unwrap();
+            backend.set_session_note(id, &format!("Day {days}"));
+        }
+
+        let project = backend.projects.inner.get_mut(&p).unwrap();
+        project.goal = Some(Goal {
+            period: GoalPeriod::Week,
+            minutes: 600,
+        });
+        project.hourly_rate = Some(85.5);
+        project.currency = "EUR".to_string();
+        let sub_project = project.inner.get_mut(&s).unwrap();
+        sub_project.color_override = Some((1, 2, 3));
+        let mut subject = sub_project.inner[&x].lock().unwrap();
+        subject.is_pinned = true;
+        subject.link = Some("https://example.com/issues/7".to_string());
+        subject.color = Some((4, 5, 6));
+        subject.note_template = Some("Next:\n- ".to_string());
+        drop(subject);
+
+        let project = backend.todos.inner.values_mut().next().unwrap();
+        let sub_project = project.inner.values_mut().next().unwrap();
+        for todo in sub_project.inner.values() {
+            let mut todo = todo.lock().unwrap();
+            todo.due = Some(now);
+            todo.priority = Priority::High;
+            todo.completed_at = Some(SystemTime::now());
+            todo.is_done = true;
+            todo.notes = "Front and back".to_string();
+            todo.link = Some("https://example.com".to_string());
+            backend.settings.todo_expanded.insert(todo.id);
+        }
+
+        backend.scratchpad = "Call back\nTomorrow".to_string();
+        backend.scratchpad_day = Some(now.date_naive());
+        backend.journal.insert(
+            now.date_naive().pred_opt().unwrap(),
+            "Order paper".to_string(),
+        );
+        backend.end_of_day_shown = Some(now.date_naive());
+        backend.detached_session = Some(Uuid::new_v4());
+        backend.window_state.position = Some((10.0, 20.0));
+        backend
+            .window_state
+            .sizes
+            .insert(DisplayMode::Todo, (300.0, 400.0));
+        backend
+            .window_state
+            .sizes
+            .insert(DisplayMode::Statistic, (500.0, 600.0));
+        for _ in 0..3 {
+            backend.settings.todo_expanded.insert(Uuid::new_v4());
+        }
+
+        backend
+    }
+
+    #[test]
+    fn dump_loads_back_unchanged() {
+        let backend = full_backend();
+        let text = backend.to_ron().unwrap();
+
+        let loaded = ron::from_str::<Backend>(&text).unwrap();
+
+        assert_eq!(loaded.to_ron().unwrap(), text);
+        assert_eq!(contents(&loaded), contents(&backend));
+        assert_eq!(loaded.scratchpad, backend.scratchpad);
+        assert_eq!(loaded.scratchpad_day, backend.scratchpad_day);
+        assert_eq!(loaded.journal, backend.journal);
+        assert_eq!(loaded.end_of_day_shown, backend.end_of_day_shown);
+        assert_eq!(loaded.detached_session, backend.detached_session);
+        assert_eq!(
+            loaded.settings.todo_expanded,
+            backend.settings.todo_expanded
+        );
+        assert_eq!(loaded.window_state.sizes, backend.window_state.sizes);
+
+        let project = loaded.projects.inner.values().next().unwrap();
+        assert_eq!(project.hourly_rate, Some(85.5));
+        assert_eq!(project.goal.map(|v| v.minutes), Some(600));
+    }
+
+    #[test]
+    fn session_notes_and_templates_load_back() {
+        for (kind, open) in STORAGES {
+            let dir = TempDir::new().unwrap();
+
+            let mut backend = load(open, dir.path());
+            let (path, recent, old) = fill(&mut backend);
+            backend.set_note_template(path, "Next:\n- ");
+            backend.set_session_note(recent, "Left off at the parser");
+            backend.set_session_note(old, "Outline");
+            save(&mut backend);
+
+            let loaded = load(open, dir.path());
+
+            assert_eq!(
+                loaded.note_template(path).as_deref(),
+                Some("Next:\n- "),
+                "{kind:?}"
+            );
+            assert_eq!(
+                loaded.history.note(&recent),
+                Some("Left off at the parser"),
+                "{kind:?}"
+            );
+            assert_eq!(loaded.history.note(&old), Some("Outline"), "{kind:?}");
+            assert_eq!(
+                loaded.last_session_note(path),
+                Some("Left off at the parser"),
+                "{kind:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn unchanged_data_dumps_the_same() {
+        let backend = full_backend();
+        let text = backend.to_ron().unwrap();
+
+        // Other hash orders, as another run of the program has them.
+        let loaded = ron::from_str::<Backend>(&text).unwrap();
+        let reloaded = ron::from_str::<Backend>(&loaded.to_ron().unwrap()).unwrap();
+
+        assert_eq!(backend.to_ron().unwrap(), text);
+        assert_eq!(reloaded.to_ron().unwrap(), text);
+    }
+
+    /// A file saved before entries were sorted and the optional fields were added.
+    const OLD_FILE: &str = r#"(
+        projects: (
+            id: "00000000-0000-0000-0000-000000000000",
+            name: "root",
+            created_at: (secs_since_epoch: 1700000000, nanos_since_epoch: 0),
+            is_deleted: false,
+            color: (0, 0, 0),
+            inner: {
+                "40000000-0000-0000-0000-000000000000": (
+                    id: "40000000-0000-0000-0000-000000000000",
+                    name: "Client",
+                    created_at: (secs_since_epoch: 1700000000, nanos_since_epoch: 0),
+                    is_deleted: false,
+                    color: (10, 20, 30),
+                    inner: {
+                        "30000000-0000-0000-0000-000000000000": (
+                            id: "30000000-0000-0000-0000-000000000000",
+                            name: "Website",
+                            created_at: (secs_since_epoch: 1700000000, nanos_since_epoch: 0),
+                            is_deleted: false,
+                            color: (10, 20, 30),
+                            inner: {
+                                "f0000000-0000-0000-0000-000000000000": (
+                                    id: "f0000000-0000-0000-0000-000000000000",
+                                    name: "Footer",
+                                    created_at: (secs_since_epoch: 1700000000, nanos_since_epoch: 0),
+                                    duration: (secs: 600, nanos: 0),
+                                    is_deleted: false,
+                                ),
+                                "10000000-0000-0000-0000-000000000000": (
+                                    id: "10000000-0000-0000-0000-000000000000",
+                                    name: "Navbar",
+                                    created_at: (secs_since_epoch: 1700000000, nanos_since_epoch: 0),
+                                    duration: (secs: 1800, nanos: 0),
+                                    is_deleted: false,
+                                ),
+                            },
+                            current_inner_id: Some("10000000-0000-0000-0000-000000000000"),
+                        ),
+                    },
+                    current_inner_id: None,
+                ),
+            },
+            current_inner_id: None,
+        ),
+        todos: (
+            id: "00000000-0000-0000-0000-000000000001",
+            name: "root",
+            created_at: (secs_since_epoch: 1700000000, nanos_since_epoch: 0),
+            is_deleted: false,
+            color: (0, 0, 0),
+            inner: {},
+            current_inner_id: None,
+        ),
+        current_session_duration: (secs: 0, nanos: 0),
+        last_session_subject_id: "10000000-0000-0000-0000-000000000000",
+        last_save: (secs_since_epoch: 1700000000, nanos_since_epoch: 0),
+        history: (
+            records: {
+                "b0000000-0000-0000-0000-000000000000": (
+                    id: "b0000000-0000-0000-0000-000000000000",
+                    start_date: "2023-11-15T10:00:00+00:00",
+                    end_date: "2023-11-15T10:10:00+00:00",
+                    project_id: "40000000-0000-0000-0000-000000000000",
+                    sub_project_id: "30000000-0000-0000-0000-000000000000",
+                    subject_id: "f0000000-0000-0000-0000-000000000000",
+                ),
+                "a0000000-0000-0000-0000-000000000000": (
+                    id: "a0000000-0000-0000-0000-000000000000",
+                    start_date: "2023-11-14T09:00:00+00:00",
+                    end_date: "2023-11-14T09:30:00+00:00",
+                    project_id: "40000000-0000-0000-0000-000000000000",
+                    sub_project_id: "30000000-0000-0000-0000-000000000000",
+                    subject_id: "10000000-0000-0000-0000-000000000000",
+                ),
+            },
+        ),
+    )"#;
+
+    #[test]
+    fn old_unordered_file_loads() {
+        let mut backend = ron::from_str::<Backend>(OLD_FILE).unwrap();
+
+        assert!(backend.check_integrity().is_empty());
+        assert_eq!(backend.history.records().count(), 2);
+
+        let sub_project = &backend.projects.inner.values().next().unwrap().inner;
+        let subjects = &sub_project.values().next().unwrap().inner;
+        assert_eq!(subjects.len(), 2);
+        for subject in subjects.values() {
+            let subject = subject.lock().unwrap();
+            assert_eq!(
+                (subject.is_pinned, subject.link.clone(), subject.color),
+                (false, None, None)
+            );
+        }
+
+        // Saved again, entries come in order.
+        let text = backend.to_ron().unwrap();
+        let at = |v: &str| text.find(v).unwrap();
+        assert!(at("\"10000000-") < at("\"f0000000-"));
+        assert!(at("\"a0000000-") < at("\"b0000000-"));
+        assert_eq!(
+            ron::from_str::<Backend>(&text).unwrap().to_ron().unwrap(),
+            text
+        );
+    }
+
+    /// Every file of the data in `dir`, by path relative to it.
+    fn files(dir: &Path) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+
+        for entry in std::fs::read_dir(dir).unwrap().flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                found.extend(files(&path));
+            } else {
+                found.push(path);
+            }
+        }
+
+        found.sort();
+        found
+    }
+
+    /// Saved data with records this month and in two past ones, which go to month files.
+    fn saved_months(dir: &Path) -> Backend {
+        let mut backend = load(STORAGES[0].1, dir);
+        let (path, ..) = fill(&mut backend);
+        let EntryPath::Subject(p, s, x) = path else {
+            unreachable!();
+        };
+        let start = Local::now() - Duration::days(100);
+        backend
+            .add_manual_record(p, s, x, start, start + Duration::minutes(5))
+            .unwrap();
+        save(&mut backend);
+
+        backend
+    }
+
+    #[test]
+    fn encryption_covers_the_month_files() {
+        let dir = TempDir::new().unwrap();
+        let mut backend = saved_months(dir.path());
+
+        backend.set_passphrase(Some("secret")).unwrap();
+
+        let files = files(dir.path());
+        assert!(files.len() >= 3);
+        for path in &files {
+            assert!(file_is_encrypted(path), "{}", path.display());
+        }
+
+        let storage = RonStorage::with_passphrase(dir.path(), "secret");
+        let mut loaded = Backend::load_from(Box::new(storage)).unwrap();
+        loaded.history.load_all();
+
+        assert!(loaded.encrypted());
+        assert_eq!(contents(&loaded), contents(&backend));
+    }
+
+    #[test]
+    fn wrong_passphrase_opens_nothing() {
+        let dir = TempDir::new().unwrap();
+        let mut backend = saved_months(dir.path());
+        backend.set_passphrase(Some("secret")).unwrap();
+
+        let storage = RonStorage::with_passphrase(dir.path(), "guess");
+        let loaded = Backend::load_from(Box::new(storage));
+        assert_eq!(loaded.err().as_deref(), Some(crypto::WRONG_PASSPHRASE));
+
+        let mut months = MonthFiles {
+            dir: dir.path().join(HISTORY_DIR),
+            cipher: Arc::new(Mutex::new(Some(Cipher::new("guess").unwrap()))),
+        };
+        let month = *months.months().unwrap().first().unwrap();
+        let read = months.read_month(month).unwrap_err();
+        assert!(read.ends_with(crypto::WRONG_PASSPHRASE), "{read}");
+
+        // Not even without any passphrase.
+        months.cipher = SharedCipher::default();
+        assert!(months.read_month(month).is_err());
+    }
+
+    #[test]
+    fn decrypting_covers_the_month_files() {
+        let dir = TempDir::new().unwrap();
+        let mut backend = saved_months(dir.path());
+        backend.set_passphrase(Some("secret")).unwrap();
+
+        backend.set_passphrase(None).unwrap();
+
+        for path in files(dir.path()) {
+            assert!(!file_is_encrypted(&path), "{}", path.display());
+        }
+        assert_eq!(
+            contents(&load(STORAGES[0].1, dir.path())),
+            contents(&backend)
+        );
+    }
+
+    #[test]
+    fn new_passphrase_rewrites_the_month_files() {
+        let dir = TempDir::new().unwrap();
+        let mut backend = saved_months(dir.path());
+        backend.set_passphrase(Some("secret")).unwrap();
+
+        backend.set_passphrase(Some("other")).unwrap();
+
+        let storage = RonStorage::with_passphrase(dir.path(), "other");
+        let mut loaded = Backend::load_from(Box::new(storage)).unwrap();
+        loaded.history.load_all();
+
+        assert_eq!(contents(&loaded), contents(&backend));
+    }
+
+    #[test]
+    fn unsplit_backup_is_encrypted() {
+        let dir = TempDir::new().unwrap();
+
+        // A data file from before month files, with a record of a past month.
+        let mut old = Backend::unsaved();
+        let EntryPath::Subject(p, s, x) =
+            old.add_path("Client/Website/Navbar", Tree::Time).unwrap()
+        else {
+            unreachable!();
+        };
+        let start = Local::now() - Duration::days(100);
+        old.add_manual_record(p, s, x, start, start + Duration::minutes(5))
+            .unwrap();
+        std::fs::write(dir.path().join(DATA_FILE), old.to_ron().unwrap()).unwrap();
+
+        let mut backend = load(STORAGES[0].1, dir.path());
+        save(&mut backend);
+        let backup = dir.path().join(UNSPLIT_BACKUP_FILE);
+        assert!(backup.is_file() && !file_is_encrypted(&backup));
+
+        backend.set_passphrase(Some("secret")).unwrap();
+
+        assert!(file_is_encrypted(&backup));
+        let cipher = backend_cipher(dir.path(), "secret");
+        let text = String::from_utf8(read_file(&backup, Some(&cipher)).unwrap()).unwrap();
+        assert_eq!(text, old.to_ron().unwrap());
+    }
+
+    #[test]
+    fn plain_month_files_are_encrypted_on_load() {
+        let dir = TempDir::new().unwrap();
+        let backend = saved_months(dir.path());
+
+        // Encrypted before the month files were.
+        let data = dir.path().join(DATA_FILE);
+        let cipher = Cipher::new("secret").unwrap();
+        let text = std::fs::read(&data).unwrap();
+        std::fs::write(&data, cipher.encrypt(&text).unwrap()).unwrap();
+
+        let storage = RonStorage::with_passphrase(dir.path(), "secret");
+        let mut loaded = Backend::load_from(Box::new(storage)).unwrap();
+        loaded.history.load_all();
+
+        for path in files(dir.path()) {
+            assert!(file_is_encrypted(&path), "{}", path.display());
+        }
+        assert_eq!(contents(&loaded), contents(&backend));
+    }
+
+    /// The key of the encrypted data file in `dir`.
+    fn backend_cipher(dir: &Path, passphrase: &str) -> Cipher {
+        let data = std::fs::read(dir.join(DATA_FILE)).unwrap();
+
+        Cipher::open(passphrase, &data).unwrap().0
+    }
+
+    /// Writes the data file the way a sync tool does, with data another copy saved.
+    fn write_elsewhere(dir: &Path, edit: impl FnOnce(&mut Backend)) {
+        let mut other = load(|dir| Box::new(RonStorage::new(dir)), dir);
+        edit(&mut other);
+        save(&mut other);
+
+        // A coarse file system clock could give the write the time of the one before.
+        let file = std::fs::File::options()
+            .write(true)
+            .open(dir.join(DATA_FILE))
+            .unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(60))
+            .unwrap();
+    }
+
+    #[test]
+    fn only_writes_elsewhere_count_as_changed_outside() {
+        let dir = TempDir::new().unwrap();
+        let mut backend = load(|dir| Box::new(RonStorage::new(dir)), dir.path());
+
+        // Nothing there yet, loading it again would give empty data.
+        assert!(!backend.changed_outside());
+
+        fill(&mut backend);
+        save(&mut backend);
+
+        assert!(!backend.changed_outside());
+
+        write_elsewhere(dir.path(), |other| {
+            other.add_path("Shop/Front/Cart", Tree::Time).unwrap();
+        });
+
+        assert!(backend.changed_outside());
+
+        let mut theirs = backend.reload().unwrap();
+        theirs.history.load_all();
+
+        assert_eq!(theirs.projects.inner.len(), 2);
+        assert_eq!(theirs.history.records().count(), 2);
+        assert!(!theirs.changed_outside());
+
+        // Keeping ours saves over theirs.
+        save(&mut backend);
+
+        assert!(!backend.changed_outside());
+        assert_eq!(
+            load(|dir| Box::new(RonStorage::new(dir)), dir.path())
+                .projects
+                .inner
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use chrono::{DateTime, Local};
+use eframe::egui;
+use std::sync::mpsc::Sender;
+
+pub enum SystemEvent {
+    /// The screen saver engaged or the display went to sleep.
+    DisplayOff(DateTime<Local>),
+    ScreenLocked(DateTime<Local>),
+    ScreenUnlocked,
+}
+
+pub trait SystemEvents {
+    fn listen(self: Box<Self>, sender: Sender<SystemEvent>, ctx: egui::Context);
+}
+
+/// Listens to `org.freedesktop.ScreenSaver.ActiveChanged` through `dbus-monitor`. Does nothing
+/// when the tool or a session bus is not available.
+#[cfg(target_os = "linux")]
+pub struct ScreenSaverListener;
+
+#[cfg(target_os = "linux")]
+impl SystemEvents for ScreenSaverListener {
+    fn listen(self: Box<Self>, sender: Sender<SystemEvent>, ctx: egui::Context) {
+        use std::io::{BufRead, BufReader};
+        use std::process::{Command, Stdio};
+        use std::time::SystemTime;
+
+        std::thread::spawn(move || {
+            let Ok(mut child) = Command::new("dbus-monitor")
+                .arg("--session")
+                .arg("type='signal',interface='org.freedesktop.ScreenSaver',member='ActiveChanged'")
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+            else {
+                return;
+            };
+
+            let Some(stdout) = child.stdout.take() else {
+                return;
+            };
+
+            let mut in_signal = false;
+
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                if line.contains("member=ActiveChanged") {
+                    in_signal = true;
+                } else if in_signal {
+                    in_signal = false;
+
+                    if line.trim() == "boolean true" {
+                        let event = SystemEvent::DisplayOff(DateTime::from(SystemTime::now()));
+
+                        if sender.send(event).is_err() {
+                            break;
+                        }
+
+                        ctx.request_repaint();
+                    }
+                }
+            }
+
+            let _ = child.kill();
+        });
+    }
+}
+
+/// Follows the `Lock` and `Unlock` signals and the `LockedHint` property of the logind
+/// session. Does nothing without a system bus or logind.
+#[cfg(target_os = "linux")]
+pub struct LogindLockListener;
+
+#[cfg(target_os = "linux")]
+impl SystemEvents for LogindLockListener {
+    fn listen(self: Box<Self>, sender: Sender<SystemEvent>, ctx: egui::Context) {
+        use std::collections::HashMap;
+        use std::time::SystemTime;
+        use zbus::blocking::{Connection, MessageIterator, Proxy};
+        use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+        use zbus::{MatchRule, MessageType};
+
+        let session_iterator = || -> zbus::Result<MessageIterator> {
+            let connection = Connection::system()?;
+
+            let manager = Proxy::new(
+                &connection,
+                "org.freedesktop.login1",
+                "/org/freedesktop/login1",
+                "org.freedesktop.login1.Manager",
+            )?;
+
+            let session: OwnedObjectPath = manager.call("GetSession", &("auto",))?;
+
+            let rule = MatchRule::builder()
+                .msg_type(MessageType::Signal)
+                .sender("org.freedesktop.login1")?
+                .path(session)?
+                .build();
+
+            MessageIterator::for_match_rule(rule, &connection, None)
+        };
+
+        std::thread::spawn(move || {
+            let Ok(messages) = session_iterator() else {
+                return;
+            };
+
+            // Lockers both send the signal and set the hint, only changes are passed on.
+            let mut locked = false;
+
+            for message in messages {
+                let Ok(message) = message else {
+                    continue;
+                };
+
+                let now_locked = match message.member().as_ref().map(|v| v.as_str()) {
+                    Some("Lock") => true,
+                    Some("Unlock") => false,
+                    Some("PropertiesChanged") => {
+                        let Ok((_, changed, _)) =
+                            message.body::<(String, HashMap<String, OwnedValue>, Vec<String>)>()
+                        else {
+                            continue;
+                        };
+
+                        match changed
+                            .get("LockedHint")
+                            .and_then(|v| v.downcast_ref::<bool>())
+                        {
+                            Some(hint) => *hint,
+                            None => continue,
+                        }
+                    }
+                    _ => continue,
+                };
+
+                if now_locked == locked {
+                    continue;
+                }
+
+                locked = now_locked;
+
+                let event = if locked {
+                    SystemEvent::ScreenLocked(DateTime::from(SystemTime::now()))
+                } else {
+                    SystemEvent::ScreenUnlocked
+                };
+
+                if sender.send(event).is_err() {
+                    break;
+                }
+
+                ctx.request_repaint();
+            }
+        });
+    }
+}
+
+/// Polls whether the input desktop can be switched to, which it cannot while the workstation
+/// is locked. Session notifications would need the window procedure, which winit owns.
+#[cfg(windows)]
+pub struct InputDesktopListener;
+
+#[cfg(windows)]
+impl SystemEvents for InputDesktopListener {
+    fn listen(self: Box<Self>, sender: Sender<SystemEvent>, ctx: egui::Context) {
+        const POLL_PERIOD: std::time::Duration = std::time::Duration::from_secs(2);
+
+        std::thread::spawn(move || {
+            let mut locked = false;
+
+            loop {
+                if is_input_desktop_locked() != locked {
+                    locked = !locked;
+
+                    let event = if locked {
+                        SystemEvent::ScreenLocked(Local::now())
+                    } else {
+                        SystemEvent::ScreenUnlocked
+                    };
+
+                    if sender.send(event).is_err() {
+                        return;
+                    }
+
+                    ctx.request_repaint();
+                }
+
+                std::thread::sleep(POLL_PERIOD);
+            }
+        });
+    }
+}
+
+#[cfg(windows)]
+fn is_input_desktop_locked() -> bool {
+    #[link(name = "user32")]
+    extern "system" {
+        fn OpenInputDesktop(flags: u32, inherit: i32, desired_access: u32) -> isize;
+        fn SwitchDesktop(desktop: isize) -> i32;
+        fn CloseDesktop(desktop: isize) -> i32;
+    }
+
+    const DESKTOP_SWITCHDESKTOP: u32 = 0x0100;
+
+    // SAFETY: the handle is only used between opening and closing it.
+    unsafe {
+        let desktop = OpenInputDesktop(0, 0, DESKTOP_SWITCHDESKTOP);
+
+        if desktop == 0 {
+            return true;
+        }
+
+        let switched = SwitchDesktop(desktop) != 0;
+        CloseDesktop(desktop);
+
+        !switched
+    }
+}
+
+/// Shows a desktop notification through `notify-send` on Linux and `osascript` on macOS.
+pub fn notify(summary: &str, body: &str) {
+    #[cfg(target_os = "linux")]
+    {
+        let child = std::process::Command::new("notify-send")
+            .arg("--app-name=Ruh Time Tracker")
+            .arg(summary)
+            .arg(body)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn();
+
+        if let Ok(mut child) = child {
+            std::thread::spawn(move || child.wait());
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let quote = |v: &str| format!("\"{}\"", v.replace('\\', "\\\\").replace('"', "\\\""));
+
+        let child = std::process::Command::new("osascript")
+            .arg("-e")
+            .arg(format!(
+                "display notification {} with title {}",
+                quote(body),
+                quote(summary)
+            ))
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn();
+
+        if let Ok(mut child) = child {
+            std::thread::spawn(move || child.wait());
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    let _ = (summary, body);
+}
+
+/// Listeners of this platform, none where nothing is known.
+pub fn platform_listeners() -> Vec<Box<dyn SystemEvents>> {
+    #[cfg(target_os = "linux")]
+    return vec![Box::new(ScreenSaverListener), Box::new(LogindLockListener)];
+
+    #[cfg(windows)]
+    return vec![Box::new(InputDesktopListener)];
+
+    #[cfg(not(any(target_os = "linux", windows)))]
+    Vec::new()
+}
+
+/// Sends its events as soon as it listens, standing in for the platform listeners in tests.
+#[cfg(test)]
+pub struct MockEvents(pub Vec<SystemEvent>);
+
+#[cfg(test)]
+impl SystemEvents for MockEvents {
+    fn listen(self: Box<Self>, sender: Sender<SystemEvent>, ctx: egui::Context) {
+        for event in self.0 {
+            let _ = sender.send(event);
+        }
+
+        ctx.request_repaint();
+    }
+}
@@ -0,0 +1,314 @@
+//! Synthetic data for performance work and bug reproduction.
+//!
+//! `ruh_time_tracker --generate-test-data <days> <projects> <output> [seed]` writes a
+//! backend with plausible history ending today. The same seed yields the same data.
+
+use crate::backend::{Backend, PContainer, Priority, Subject, TodoSubProject, TodoSubject};
+use crate::history::HistoryRecord;
+use crate::profiles::data_dir;
+use crate::storage::DATA_FILE;
+use crate::util::start_of_day;
+use chrono::{DateTime, Datelike, Duration, Local, Weekday};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+pub const FLAG: &str = "--generate-test-data";
+
+const USAGE: &str = "usage: --generate-test-data <days> <projects> <output> [seed]";
+
+const MAX_DAYS: u32 = 3650;
+const MAX_PROJECTS: u32 = 50;
+
+const PROJECT_NAMES: [&str; 8] = [
+    "Website", "Backend", "Research", "Ops", "Mobile", "Docs", "Hiring", "Support",
+];
+const SUB_PROJECT_NAMES: [&str; 5] = ["Design", "Development", "Review", "Meetings", "Planning"];
+
+/// Parses the arguments following [`FLAG`] and writes the generated data.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let [days, projects, output, rest @ ..] = args else {
+        return Err(USAGE.to_string());
+    };
+
+    let days = parse_in_range(days, "days", MAX_DAYS)?;
+    let projects = parse_in_range(projects, "projects", MAX_PROJECTS)?;
+
+    let seed = match rest {
+        [] => 0,
+        [seed] => seed
+            .parse::<u64>()
+            .map_err(|_| format!("seed must be a non-negative integer, got \"{seed}\""))?,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let Some(target) = resolve(Path::new(output)) else {
+        return Err(format!("output directory of \"{output}\" does not exist"));
+    };
+
+    if resolve(&data_dir().join(DATA_FILE)).as_ref() == Some(&target) {
+        return Err("refusing to overwrite the tracker data file".to_string());
+    }
+
+    generate(days, projects, seed, Local::now())
+        .write_to(&target)
+        .map_err(|e| format!("failed to write \"{output}\": {e}"))
+}
+
+fn parse_in_range(value: &str, name: &str, max: u32) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(v) if (1..=max).contains(&v) => Ok(v),
+        _ => Err(format!(
+            "{name} must be between 1 and {max}, got \"{value}\""
+        )),
+    }
+}
+
+fn resolve(path: &Path) -> Option<PathBuf> {
+    let path = std::env::current_dir().ok()?.join(path);
+
+    Some(path.parent()?.canonicalize().ok()?.join(path.file_name()?))
+}
+
+fn uuid(rng: &mut StdRng) -> Uuid {
+    uuid::Builder::from_random_bytes(rng.gen()).into_uuid()
+}
+
+fn container<T>(rng: &mut StdRng, name: String, created_at: SystemTime) -> PContainer<T, Uuid> {
+    PContainer {
+        id: uuid(rng),
+        name,
+        created_at,
+        is_deleted: false,
+        deleted_at: None,
+        color: (rng.gen(), rng.gen(), rng.gen()),
+        inner: HashMap::new(),
+        current_inner_id: None,
+        goal: None,
+        is_archived: false,
+        hourly_rate: None,
+        currency: String::new(),
+        color_override: None,
+    }
+}
+
+fn numbered(names: &[&str], i: usize) -> String {
+    match i / names.len() {
+        0 => names[i].to_string(),
+        n => format!("{} {}", names[i % names.len()], n + 1),
+    }
+}
+
+/// Builds a backend with `projects` projects and `days` days of history ending at `now`.
+pub fn generate(days: u32, projects: u32, seed: u64, now: DateTime<Local>) -> Backend {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut backend = Backend::default();
+
+    let today = now.date_naive();
+    let first_day = today - Duration::days(days as i64 - 1);
+    let created_at = SystemTime::from(start_of_day(first_day));
+
+    backend.projects = container(&mut rng, "root".to_string(), created_at);
+    backend.todos = container(&mut rng, "root".to_string(), created_at);
+    backend.last_session_subject_id = uuid(&mut rng);
+    backend.last_save = created_at;
+
+    // (project, sub project, subject) of every subject records can point at.
+    let mut chains = Vec::new();
+
+    for i in 0..projects as usize {
+        let mut project = container(&mut rng, numbered(&PROJECT_NAMES, i), created_at);
+
+        for j in 0..rng.gen_range(1..=4) {
+            let mut sub_project = container(&mut rng, numbered(&SUB_PROJECT_NAMES, j), created_at);
+
+            for k in 0..rng.gen_range(1..=5) {
+                let subject = Subject {
+                    id: uuid(&mut rng),
+                    name: format!("Task {}", k + 1),
+                    created_at,
+                    duration: std::time::Duration::ZERO,
+                    is_deleted: rng.gen_bool(0.05),
+                    deleted_at: None,
+                    is_pinned: false,
+                    link: None,
+                    color: None,
+                    note_template: None,
+                };
+
+                chains.push((project.id, sub_project.id, subject.id));
+                sub_project
+                    .inner
+                    .insert(subject.id, Arc::new(Mutex::new(subject)));
+            }
+
+            project.inner.insert(sub_project.id, sub_project);
+        }
+
+        backend.projects.inner.insert(project.id, project);
+
+        let mut todo_project = container(&mut rng, numbered(&PROJECT_NAMES, i), created_at);
+
+        for j in 0..rng.gen_range(1..=3) {
+            let mut sub_project: TodoSubProject =
+                container(&mut rng, numbered(&SUB_PROJECT_NAMES, j), created_at);
+
+            for k in 0..rng.gen_range(2..=6) {
+                let is_done = rng.gen_bool(0.4);
+
+                let subject = TodoSubject {
+                    id: uuid(&mut rng),
+                    name: format!("Todo {}", k + 1),
+                    created_at,
+                    is_deleted: rng.gen_bool(0.1),
+                    deleted_at: None,
+                    is_done,
+                    due: None,
+                    priority: Priority::default(),
+                    completed_at: is_done.then_some(created_at),
+                    notes: String::new(),
+                    link: None,
+                };
+
+                sub_project
+                    .inner
+                    .insert(subject.id, Arc::new(Mutex::new(subject)));
+            }
+
+            todo_project.inner.insert(sub_project.id, sub_project);
+        }
+
+        backend.todos.inner.insert(todo_project.id, todo_project);
+    }
+
+    for day in first_day.iter_days().take(days as usize) {
+        let weekend = matches!(day.weekday(), Weekday::Sat | Weekday::Sun);
+
+        if rng.gen_bool(if weekend { 0.8 } else { 0.05 }) {
+            continue;
+        }
+
+        let mut start = start_of_day(day) + Duration::minutes(rng.gen_range(7 * 60..11 * 60));
+        let mut previous: Option<HistoryRecord> = None;
+
+        for i in 0..rng.gen_range(3..=8) {
+            let (project_id, sub_project_id, subject_id) = chains[rng.gen_range(0..chains.len())];
+
+            // An occasional late session runs past midnight.
+            if i == 0 && rng.gen_bool(0.05) {
+                start = start_of_day(day) + Duration::minutes(rng.gen_range(22 * 60..24 * 60 - 5));
+            }
+
+            // An occasional session overlaps the previous one.
+            if let Some(previous) = previous.filter(|_| rng.gen_bool(0.03)) {
+                start = previous.end_date - Duration::minutes(rng.gen_range(5..30));
+            }
+
+            let end = start + Duration::minutes(rng.gen_range(10..120));
+
+            if end > now {
+                break;
+            }
+
+            let record = HistoryRecord {
+                id: uuid(&mut rng),
+                start_date: start,
+                end_date: end,
+                project_id,
+                sub_project_id,
+                subject_id,
+            };
+
+            if let Some(subject) = backend
+                .projects
+                .inner
+                .get(&project_id)
+                .and_then(|v| v.inner.get(&sub_project_id))
+                .and_then(|v| v.inner.get(&subject_id))
+            {
+                subject.lock().unwrap().duration += record.get_duration().to_std().unwrap();
+            }
+
+            backend.history.insert(record);
+            previous = Some(record);
+
+            start = end + Duration::minutes(rng.gen_range(0..45));
+
+            if start.date_naive() != day {
+                break;
+            }
+        }
+    }
+
+    backend
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, 5, 15, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn generated_data_passes_the_integrity_checks() {
+        for seed in 0..4 {
+            let mut backend = generate(120, 6, seed, now());
+
+            assert!(backend.history.records().count() > 100, "seed {seed}");
+            assert!(
+                backend.check_integrity().is_empty(),
+                "seed {seed} has issues"
+            );
+            assert!(backend.history.records().all(|v| v.end_date <= now()
+                && v.start_date.date_naive() > now().date_naive() - Duration::days(120)));
+        }
+    }
+
+    #[test]
+    fn generated_data_loads_back() {
+        let backend = generate(30, 3, 1, now());
+        let text = backend.to_ron().unwrap();
+
+        let mut loaded = ron::from_str::<Backend>(&text).unwrap();
+
+        assert!(loaded.check_integrity().is_empty());
+        assert_eq!(loaded.to_ron().unwrap(), text);
+    }
+
+    #[test]
+    fn seed_reproduces_identical_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, seed: u64| {
+            let path = dir.path().join(name);
+            generate(90, 4, seed, now()).write_to(&path).unwrap();
+            std::fs::read(path).unwrap()
+        };
+
+        assert_eq!(write("first.ron", 7), write("second.ron", 7));
+        assert_ne!(write("first.ron", 7), write("other.ron", 8));
+    }
+
+    #[test]
+    fn arguments_are_validated() {
+        let args = |v: &[&str]| v.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        for bad in [
+            &["10"][..],
+            &["0", "2", "out.ron"],
+            &["3651", "2", "out.ron"],
+            &["10", "51", "out.ron"],
+            &["10", "2", "out.ron", "-1"],
+            &["10", "2", "out.ron", "1", "extra"],
+            &["10", "2", "missing-dir/out.ron"],
+        ] {
+            assert!(run(&args(bad)).is_err(), "{bad:?}");
+        }
+    }
+}
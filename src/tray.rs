@@ -0,0 +1,367 @@
+use eframe::egui;
+use std::sync::mpsc::Sender;
+
+pub enum TrayCommand {
+    StartPause,
+    OpenWindow,
+    Minimal,
+    Quit,
+}
+
+/// What the tray icon shows.
+#[derive(Clone, PartialEq, Default)]
+pub struct TrayState {
+    pub(crate) running: bool,
+    pub(crate) work_name: String,
+    pub(crate) session: String,
+}
+
+pub trait Tray {
+    fn update(&self, state: &TrayState);
+}
+
+/// Status notifier item with a dbusmenu, as understood by KDE, most GNOME extensions and
+/// other freedesktop panels. `None` when there is no session bus or no tray to register with.
+pub fn platform_tray(sender: Sender<TrayCommand>, ctx: egui::Context) -> Option<Box<dyn Tray>> {
+    #[cfg(target_os = "linux")]
+    return linux::StatusNotifierTray::register(sender, ctx).map(|v| Box::new(v) as Box<dyn Tray>);
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (sender, ctx);
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{Tray, TrayCommand, TrayState};
+    use crate::i18n::tr;
+    use eframe::egui;
+    use std::collections::HashMap;
+    use std::sync::mpsc::Sender;
+    use zbus::blocking::{Connection, ConnectionBuilder};
+    use zbus::zvariant::{ObjectPath, OwnedValue, StructureBuilder, Value};
+    use zbus::{dbus_interface, SignalContext};
+
+    const ITEM_PATH: &str = "/StatusNotifierItem";
+    const MENU_PATH: &str = "/MenuBar";
+
+    const START_PAUSE_ID: i32 = 1;
+    const OPEN_ID: i32 = 2;
+    const MINIMAL_ID: i32 = 3;
+    const SEPARATOR_ID: i32 = 4;
+    const QUIT_ID: i32 = 5;
+
+    type ToolTip = (String, Vec<(i32, i32, Vec<u8>)>, String, String);
+
+    /// Id, properties and children of a menu item.
+    type MenuLayout = (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>);
+
+    struct Item {
+        state: TrayState,
+        sender: Sender<TrayCommand>,
+        ctx: egui::Context,
+    }
+
+    #[dbus_interface(name = "org.kde.StatusNotifierItem")]
+    impl Item {
+        fn activate(&self, _x: i32, _y: i32) {
+            let _ = self.sender.send(TrayCommand::OpenWindow);
+            self.ctx.request_repaint();
+        }
+
+        fn secondary_activate(&self, _x: i32, _y: i32) {
+            let _ = self.sender.send(TrayCommand::StartPause);
+            self.ctx.request_repaint();
+        }
+
+        fn context_menu(&self, _x: i32, _y: i32) {}
+
+        fn scroll(&self, _delta: i32, _orientation: &str) {}
+
+        #[dbus_interface(property)]
+        fn category(&self) -> &str {
+            "ApplicationStatus"
+        }
+
+        #[dbus_interface(property)]
+        fn id(&self) -> &str {
+            "ruh-time-tracker"
+        }
+
+        #[dbus_interface(property)]
+        fn title(&self) -> &str {
+            "Ruh Time Tracker"
+        }
+
+        #[dbus_interface(property)]
+        fn status(&self) -> &str {
+            "Active"
+        }
+
+        #[dbus_interface(property)]
+        fn icon_name(&self) -> &str {
+            if self.state.running {
+                "media-playback-start"
+            } else {
+                "media-playback-pause"
+            }
+        }
+
+        #[dbus_interface(property)]
+        fn tool_tip(&self) -> ToolTip {
+            let body = if self.state.running {
+                format!("{}, {}", self.state.work_name, self.state.session)
+            } else {
+                tr!("Not tracking").to_string()
+            };
+
+            (String::new(), vec![], "Ruh Time Tracker".to_string(), body)
+        }
+
+        #[dbus_interface(property)]
+        fn item_is_menu(&self) -> bool {
+            false
+        }
+
+        #[dbus_interface(property)]
+        fn menu(&self) -> ObjectPath<'_> {
+            ObjectPath::from_static_str_unchecked(MENU_PATH)
+        }
+
+        #[dbus_interface(property)]
+        fn window_id(&self) -> i32 {
+            0
+        }
+
+        #[dbus_interface(signal)]
+        async fn new_icon(ctx: &SignalContext<'_>) -> zbus::Result<()>;
+
+        #[dbus_interface(signal)]
+        async fn new_tool_tip(ctx: &SignalContext<'_>) -> zbus::Result<()>;
+    }
+
+    struct Menu {
+        running: bool,
+        revision: u32,
+        sender: Sender<TrayCommand>,
+        ctx: egui::Context,
+    }
+
+    impl Menu {
+        fn item_properties(&self, id: i32) -> HashMap<String, OwnedValue> {
+            let label = match id {
+                START_PAUSE_ID if self.running => tr!("Pause"),
+                START_PAUSE_ID => tr!("Start"),
+                OPEN_ID => tr!("Open main window"),
+                MINIMAL_ID => tr!("Minimal mode"),
+                QUIT_ID => tr!("Quit"),
+                _ => "",
+            };
+
+            let mut properties = HashMap::new();
+
+            if id == SEPARATOR_ID {
+                properties.insert("type".to_string(), Value::from("separator").into());
+            } else if id != 0 {
+                properties.insert("label".to_string(), Value::from(label).into());
+            } else {
+                properties.insert(
+                    "children-display".to_string(),
+                    Value::from("submenu").into(),
+                );
+            }
+
+            properties
+        }
+
+        fn layout(&self, id: i32) -> MenuLayout {
+            let children = if id == 0 {
+                [START_PAUSE_ID, OPEN_ID, MINIMAL_ID, SEPARATOR_ID, QUIT_ID]
+                    .into_iter()
+                    .map(|id| {
+                        let child = StructureBuilder::new()
+                            .add_field(id)
+                            .add_field(self.item_properties(id))
+                            .add_field(Vec::<OwnedValue>::new())
+                            .build();
+
+                        OwnedValue::from(Value::from(child))
+                    })
+                    .collect()
+            } else {
+                vec![]
+            };
+
+            (id, self.item_properties(id), children)
+        }
+    }
+
+    #[dbus_interface(name = "com.canonical.dbusmenu")]
+    impl Menu {
+        fn get_layout(
+            &self,
+            parent_id: i32,
+            _recursion_depth: i32,
+            _property_names: Vec<String>,
+        ) -> (u32, MenuLayout) {
+            (self.revision, self.layout(parent_id))
+        }
+
+        fn get_group_properties(
+            &self,
+            ids: Vec<i32>,
+            _property_names: Vec<String>,
+        ) -> Vec<(i32, HashMap<String, OwnedValue>)> {
+            ids.into_iter()
+                .map(|id| (id, self.item_properties(id)))
+                .collect()
+        }
+
+        fn get_property(&self, id: i32, name: &str) -> OwnedValue {
+            self.item_properties(id)
+                .remove(name)
+                .unwrap_or_else(|| Value::from("").into())
+        }
+
+        fn event(&self, id: i32, event_id: &str, _data: OwnedValue, _timestamp: u32) {
+            if event_id != "clicked" {
+                return;
+            }
+
+            let command = match id {
+                START_PAUSE_ID => TrayCommand::StartPause,
+                OPEN_ID => TrayCommand::OpenWindow,
+                MINIMAL_ID => TrayCommand::Minimal,
+                QUIT_ID => TrayCommand::Quit,
+                _ => return,
+            };
+
+            let _ = self.sender.send(command);
+            self.ctx.request_repaint();
+        }
+
+        fn event_group(&self, events: Vec<(i32, String, OwnedValue, u32)>) -> Vec<i32> {
+            for (id, event_id, data, timestamp) in events {
+                self.event(id, &event_id, data, timestamp);
+            }
+
+            vec![]
+        }
+
+        fn about_to_show(&self, _id: i32) -> bool {
+            false
+        }
+
+        fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+            (vec![], vec![])
+        }
+
+        #[dbus_interface(property)]
+        fn version(&self) -> u32 {
+            3
+        }
+
+        #[dbus_interface(property)]
+        fn text_direction(&self) -> &str {
+            "ltr"
+        }
+
+        #[dbus_interface(property)]
+        fn status(&self) -> &str {
+            "normal"
+        }
+
+        #[dbus_interface(property)]
+        fn icon_theme_path(&self) -> Vec<String> {
+            vec![]
+        }
+
+        #[dbus_interface(signal)]
+        async fn layout_updated(
+            ctx: &SignalContext<'_>,
+            revision: u32,
+            parent: i32,
+        ) -> zbus::Result<()>;
+    }
+
+    pub struct StatusNotifierTray {
+        connection: Connection,
+    }
+
+    impl StatusNotifierTray {
+        pub fn register(sender: Sender<TrayCommand>, ctx: egui::Context) -> Option<Self> {
+            let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+
+            let item = Item {
+                state: TrayState::default(),
+                sender: sender.clone(),
+                ctx: ctx.clone(),
+            };
+
+            let menu = Menu {
+                running: false,
+                revision: 1,
+                sender,
+                ctx,
+            };
+
+            let connection = ConnectionBuilder::session()
+                .ok()?
+                .name(name.as_str())
+                .ok()?
+                .serve_at(ITEM_PATH, item)
+                .ok()?
+                .serve_at(MENU_PATH, menu)
+                .ok()?
+                .build()
+                .ok()?;
+
+            connection
+                .call_method(
+                    Some("org.kde.StatusNotifierWatcher"),
+                    "/StatusNotifierWatcher",
+                    Some("org.kde.StatusNotifierWatcher"),
+                    "RegisterStatusNotifierItem",
+                    &(name.as_str(),),
+                )
+                .ok()?;
+
+            Some(Self { connection })
+        }
+    }
+
+    impl Tray for StatusNotifierTray {
+        fn update(&self, state: &TrayState) {
+            let server = self.connection.object_server();
+
+            let Ok(item) = server.interface::<_, Item>(ITEM_PATH) else {
+                return;
+            };
+
+            let Ok(menu) = server.interface::<_, Menu>(MENU_PATH) else {
+                return;
+            };
+
+            item.get_mut().state = state.clone();
+
+            let _ = zbus::block_on(Item::new_icon(item.signal_context()));
+            let _ = zbus::block_on(Item::new_tool_tip(item.signal_context()));
+
+            let revision = {
+                let mut menu = menu.get_mut();
+
+                if menu.running == state.running {
+                    return;
+                }
+
+                menu.running = state.running;
+                menu.revision += 1;
+                menu.revision
+            };
+
+            let _ = zbus::block_on(Menu::layout_updated(menu.signal_context(), revision, 0));
+        }
+    }
+}
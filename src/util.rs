@@ -1,34 +1,166 @@
-use chrono::{DateTime, Datelike, Local, NaiveDate};
-use std::ops::Rem;
+use crate::i18n::tr;
+use chrono::{
+    DateTime, Datelike, Days, Local, LocalResult, NaiveDate, NaiveDateTime, SecondsFormat,
+    TimeZone, Timelike, Weekday,
+};
+use eframe::egui::{Color32, FontId, Ui};
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
 
-pub fn format_duration(duration: Duration) -> String {
-    const HOUR_S: f64 = 60.0 * 60.0;
+pub const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
 
-    let spent = duration.as_secs_f64();
+pub fn format_date_time(date: DateTime<Local>) -> String {
+    date.format(DATE_TIME_FORMAT).to_string()
+}
 
-    let hours = (spent / HOUR_S).trunc();
-    let minutes = (spent.rem(&HOUR_S) / 60.0).trunc();
+pub fn parse_date_time(text: &str) -> Option<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(text.trim(), DATE_TIME_FORMAT).ok()?;
 
-    format!(
-        " {}:{}",
-        format_number(hours as u32),
-        format_number(minutes as u32)
-    )
+    Some(to_local(naive))
 }
-pub fn format_chrono_duration(duration: chrono::Duration) -> String {
-    const HOUR_S: f64 = 60.0 * 60.0;
 
-    let spent = duration.num_seconds() as f64;
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
 
-    let hours = (spent / HOUR_S).trunc();
-    let minutes = (spent.rem(&HOUR_S) / 60.0).trunc();
+/// Accepts a date time or a bare date, which means the last minute of that day.
+pub fn parse_due(text: &str) -> Option<DateTime<Local>> {
+    if let Some(date_time) = parse_date_time(text) {
+        return Some(date_time);
+    }
 
-    format!(
-        " {}:{}",
-        format_number(hours as u32),
-        format_number(minutes as u32)
-    )
+    let date = NaiveDate::parse_from_str(text.trim(), DATE_FORMAT).ok()?;
+
+    Some(to_local(date.and_hms_opt(23, 59, 0)?))
+}
+
+/// Inverse of [`parse_due`], dates due at the last minute of the day are shown bare.
+pub fn format_due(due: DateTime<Local>) -> String {
+    if due.hour() == 23 && due.minute() == 59 {
+        due.format(DATE_FORMAT).to_string()
+    } else {
+        format_date_time(due)
+    }
+}
+
+/// Resolves a wall clock time in the local timezone. Times repeated when clocks go back map
+/// to their earliest instant, times skipped when clocks go forward roll on to the first
+/// valid minute.
+pub fn to_local(naive: NaiveDateTime) -> DateTime<Local> {
+    resolve_local(&Local, naive)
+}
+
+/// [`to_local`] in any timezone.
+pub fn resolve_local<Tz: TimeZone>(tz: &Tz, naive: NaiveDateTime) -> DateTime<Tz> {
+    let mut candidate = naive;
+
+    // Real world gaps are at most a couple of hours.
+    for _ in 0..=24 * 60 {
+        match tz.from_local_datetime(&candidate) {
+            LocalResult::Single(v) => return v,
+            LocalResult::Ambiguous(a, b) => return a.min(b),
+            LocalResult::None => candidate += chrono::Duration::minutes(1),
+        }
+    }
+
+    tz.from_utc_datetime(&naive)
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug, Default)]
+pub enum TimeFormat {
+    /// `1:45`
+    #[default]
+    HoursMinutes,
+    /// `1:45:30`
+    HoursMinutesSeconds,
+    /// `1.75h`
+    DecimalHours,
+}
+
+/// How durations are written everywhere in the app and its reports.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct DurationFormat {
+    pub(crate) time: TimeFormat,
+    /// A day or more is written as `2d 04:30`, decimal hours stay as they are.
+    pub(crate) days: bool,
+}
+
+pub fn format_duration(duration: Duration, format: DurationFormat) -> String {
+    format_seconds(duration.as_secs().try_into().unwrap_or(i64::MAX), format)
+}
+
+pub fn format_chrono_duration(duration: chrono::Duration, format: DurationFormat) -> String {
+    format_seconds(duration.num_seconds(), format)
+}
+
+fn format_seconds(seconds: i64, format: DurationFormat) -> String {
+    const DAY_S: u64 = 24 * 60 * 60;
+
+    let sign = if seconds < 0 { "-" } else { "" };
+    let spent = seconds.unsigned_abs();
+
+    if format.time == TimeFormat::DecimalHours {
+        return format!("{sign}{:.2}h", spent as f64 / 3600.);
+    }
+
+    let (days, spent) = if format.days && spent >= DAY_S {
+        (Some(spent / DAY_S), spent % DAY_S)
+    } else {
+        (None, spent)
+    };
+
+    let hours = spent / 3600;
+    let minutes = format_number((spent % 3600 / 60) as u32);
+
+    let mut text = match days {
+        Some(days) => format!("{sign}{days}d {}:{minutes}", format_number(hours as u32)),
+        None => format!("{sign}{hours}:{minutes}"),
+    };
+
+    if format.time == TimeFormat::HoursMinutesSeconds {
+        text.push(':');
+        text.push_str(&format_number((spent % 60) as u32));
+    }
+
+    text
+}
+
+/// Increment reported durations are rounded to. A `minutes` of 0 keeps them exact.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug, Default)]
+pub struct RoundingMode {
+    pub(crate) minutes: u32,
+    /// Always round up instead of to the nearest increment.
+    pub(crate) up: bool,
+}
+
+impl RoundingMode {
+    pub const MINUTES: [u32; 4] = [0, 5, 10, 15];
+
+    pub fn label(minutes: u32) -> String {
+        match minutes {
+            0 => tr!("None").to_string(),
+            v => tr!("{n} min", n = v),
+        }
+    }
+}
+
+/// Rounds `duration` to the increment of `mode`, halves round up when rounding to nearest.
+/// Zero stays zero either way.
+pub fn round_duration(duration: chrono::Duration, mode: RoundingMode) -> chrono::Duration {
+    if mode.minutes == 0 {
+        return duration;
+    }
+
+    let step = mode.minutes as i64 * 60;
+    let seconds = duration.num_seconds();
+
+    let steps = if mode.up {
+        (seconds + step - 1).div_euclid(step)
+    } else {
+        (seconds + step / 2).div_euclid(step)
+    };
+
+    chrono::Duration::seconds(steps * step)
 }
 
 pub fn format_number<T>(number: T) -> String
@@ -61,9 +193,642 @@ pub fn get_days_from_month(year: i32, month: u32) -> u32 {
     .num_days() as u32
 }
 
-pub fn calendar_days_count(from: DateTime<Local>, to: DateTime<Local>) -> u32 {
-    let rf = NaiveDate::from_ymd_opt(from.year(), from.month(), from.day()).unwrap();
-    let rt = NaiveDate::from_ymd_opt(to.year(), to.month(), to.day()).unwrap();
+pub fn days_in_range(from: DateTime<Local>, to: DateTime<Local>) -> Vec<NaiveDate> {
+    let mut res = Vec::new();
+
+    let mut day = from.date_naive();
+    let last = to.date_naive();
+
+    while day <= last {
+        res.push(day);
+
+        let Some(next) = day.checked_add_days(Days::new(1)) else {
+            break;
+        };
+
+        day = next;
+    }
+
+    res
+}
+
+pub fn start_of_day(date: NaiveDate) -> DateTime<Local> {
+    to_local(date.and_hms_opt(0, 0, 0).unwrap())
+}
+
+/// Time zone exported reports cut their days in and write their timestamps in.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum ExportZone {
+    /// The local time zone, its offset written along.
+    #[default]
+    Local,
+    Utc,
+}
+
+impl ExportZone {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "local" => Some(Self::Local),
+            "utc" => Some(Self::Utc),
+            _ => None,
+        }
+    }
+
+    /// First instant of `day` in this zone, given in `tz`.
+    pub fn day_start<Tz: TimeZone>(self, tz: &Tz, day: NaiveDate) -> DateTime<Tz> {
+        let midnight = day.and_hms_opt(0, 0, 0).unwrap();
+
+        match self {
+            Self::Local => resolve_local(tz, midnight),
+            Self::Utc => tz.from_utc_datetime(&midnight),
+        }
+    }
+
+    /// How a report header names the zone of a range, like `UTC` or `local time, UTC+01:00`.
+    /// Both offsets are named when the range spans a DST change.
+    pub fn name<Tz: TimeZone>(self, range: (&DateTime<Tz>, &DateTime<Tz>)) -> String
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        if self == Self::Utc {
+            return "UTC".to_string();
+        }
+
+        let offsets = [range.0, range.1].map(|v| v.format("%:z").to_string());
+
+        if offsets[0] == offsets[1] {
+            format!("local time, UTC{}", offsets[0])
+        } else {
+            format!("local time, UTC{} to UTC{}", offsets[0], offsets[1])
+        }
+    }
+}
+
+/// RFC 3339 timestamp for exports, with the offset of `time` or in UTC ending in `Z`.
+pub fn export_timestamp<Tz: TimeZone>(time: &DateTime<Tz>, zone: ExportZone) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    match zone {
+        ExportZone::Local => time.to_rfc3339_opts(SecondsFormat::Secs, false),
+        ExportZone::Utc => time
+            .with_timezone(&chrono::Utc)
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+    }
+}
+
+pub fn start_of_week(date: NaiveDate, first_day: Weekday) -> NaiveDate {
+    let offset = (date.weekday().num_days_from_monday() + 7 - first_day.num_days_from_monday()) % 7;
+
+    date.checked_sub_days(Days::new(offset as u64)).unwrap()
+}
+
+pub fn start_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap()
+}
+
+pub fn end_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(get_days_from_month(date.year(), date.month()))
+        .unwrap()
+}
+
+/// Shortens `text` to at most `max_chars` grapheme clusters by replacing its middle with an
+/// ellipsis. Works on logical order, so combining marks, emoji sequences and RTL runs are never
+/// split apart.
+pub fn truncate_middle(text: &str, max_chars: usize) -> String {
+    let graphemes: Vec<&str> = text.graphemes(true).collect();
+
+    if graphemes.len() <= max_chars {
+        return text.to_string();
+    }
+
+    if max_chars == 0 {
+        return String::new();
+    }
+
+    let kept = max_chars - 1;
+    let head = kept.div_ceil(2);
+    let tail = kept - head;
+
+    format!(
+        "{}…{}",
+        graphemes[..head].concat(),
+        graphemes[graphemes.len() - tail..].concat()
+    )
+}
+
+/// How well `query` matches `text` as a subsequence, ignoring case and the spaces of the
+/// query. Adjacent characters and word starts score higher, skipped characters lower.
+/// `None` when a character of the query is missing.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+
+    for c in query.to_lowercase().chars().filter(|v| !v.is_whitespace()) {
+        let i = next + text[next..].iter().position(|v| *v == c)?;
+
+        score += 1;
+
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+
+        match previous {
+            Some(p) if p + 1 == i => score += 5,
+            Some(p) => score -= (i - p - 1).min(5) as i32,
+            None => score -= i.min(5) as i32,
+        }
+
+        previous = Some(i);
+        next = i + 1;
+    }
+
+    Some(score)
+}
+
+/// Same as [`truncate_middle`], but fits the text into `max_width` points of the given font.
+pub fn truncate_middle_to_width(ui: &Ui, text: &str, font: FontId, max_width: f32) -> String {
+    let width = |t: &str| {
+        ui.fonts(|f| {
+            f.layout_no_wrap(t.to_string(), font.clone(), Color32::WHITE)
+                .size()
+                .x
+        })
+    };
+
+    if width(text) <= max_width {
+        return text.to_string();
+    }
+
+    let mut low = 0;
+    let mut high = text.graphemes(true).count();
+
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+
+        if width(&truncate_middle(text, mid)) <= max_width {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    truncate_middle(text, low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(seconds: i64) -> chrono::Duration {
+        chrono::Duration::seconds(seconds)
+    }
+
+    fn mode(minutes: u32, up: bool) -> RoundingMode {
+        RoundingMode { minutes, up }
+    }
+
+    #[test]
+    fn no_rounding_keeps_durations() {
+        for up in [false, true] {
+            for value in [0, 1, 59, 60, 7 * 60 + 29, 3600, 100 * 3600 + 1] {
+                assert_eq!(round_duration(seconds(value), mode(0, up)), seconds(value));
+            }
+        }
+    }
+
+    #[test]
+    fn zero_stays_zero() {
+        for minutes in RoundingMode::MINUTES {
+            for up in [false, true] {
+                assert_eq!(round_duration(seconds(0), mode(minutes, up)), seconds(0));
+            }
+        }
+    }
+
+    #[test]
+    fn boundaries_stay() {
+        for minutes in RoundingMode::MINUTES {
+            let step = minutes as i64 * 60;
+
+            for up in [false, true] {
+                for steps in 1..5 {
+                    let value = seconds(steps * step);
+
+                    assert_eq!(round_duration(value, mode(minutes, up)), value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rounds_to_nearest_with_halves_up() {
+        let quarter = mode(15, false);
+
+        assert_eq!(round_duration(seconds(1), quarter), seconds(0));
+        assert_eq!(round_duration(seconds(7 * 60 + 29), quarter), seconds(0));
+        assert_eq!(
+            round_duration(seconds(7 * 60 + 30), quarter),
+            seconds(15 * 60)
+        );
+        assert_eq!(
+            round_duration(seconds(15 * 60 + 1), quarter),
+            seconds(15 * 60)
+        );
+        assert_eq!(
+            round_duration(seconds(22 * 60 + 30), quarter),
+            seconds(30 * 60)
+        );
+        assert_eq!(
+            round_duration(seconds(2 * 60 + 30), mode(5, false)),
+            seconds(5 * 60)
+        );
+        assert_eq!(
+            round_duration(seconds(4 * 60 + 59), mode(10, false)),
+            seconds(0)
+        );
+    }
+
+    #[test]
+    fn rounds_up() {
+        let quarter = mode(15, true);
+
+        assert_eq!(round_duration(seconds(1), quarter), seconds(15 * 60));
+        assert_eq!(
+            round_duration(seconds(15 * 60 - 1), quarter),
+            seconds(15 * 60)
+        );
+        assert_eq!(
+            round_duration(seconds(15 * 60 + 1), quarter),
+            seconds(30 * 60)
+        );
+        assert_eq!(round_duration(seconds(1), mode(5, true)), seconds(5 * 60));
+    }
+
+    /// Every second of the first hours against what rounding means.
+    #[test]
+    fn every_duration_rounds_to_a_close_increment() {
+        for minutes in RoundingMode::MINUTES.into_iter().filter(|v| *v > 0) {
+            let step = minutes as i64 * 60;
+
+            for value in 0..=3 * 3600 {
+                let nearest = round_duration(seconds(value), mode(minutes, false)).num_seconds();
+                let up = round_duration(seconds(value), mode(minutes, true)).num_seconds();
+
+                assert_eq!(nearest % step, 0, "{value} s to {minutes} min");
+                assert_eq!(up % step, 0, "{value} s up to {minutes} min");
+
+                assert!((up - value) >= 0 && up - value < step, "{value} s up");
+                assert!(
+                    (nearest - value).abs() * 2 <= step,
+                    "{value} s to {minutes} min"
+                );
+                // A half goes up.
+                if (nearest - value).abs() * 2 == step {
+                    assert!(nearest > value, "{value} s to {minutes} min");
+                }
+            }
+        }
+    }
+
+    fn format(time: TimeFormat, days: bool) -> DurationFormat {
+        DurationFormat { time, days }
+    }
+
+    /// 59 s, 60 s, 24 h and 100 h in a format.
+    fn boundaries(format: DurationFormat) -> [String; 4] {
+        [59, 60, 24 * 3600, 100 * 3600].map(|v| format_seconds(v, format))
+    }
+
+    #[test]
+    fn formats_hours_minutes() {
+        assert_eq!(
+            boundaries(format(TimeFormat::HoursMinutes, false)),
+            ["0:00", "0:01", "24:00", "100:00"]
+        );
+        assert_eq!(
+            boundaries(format(TimeFormat::HoursMinutes, true)),
+            ["0:00", "0:01", "1d 00:00", "4d 04:00"]
+        );
+    }
+
+    #[test]
+    fn formats_hours_minutes_seconds() {
+        assert_eq!(
+            boundaries(format(TimeFormat::HoursMinutesSeconds, false)),
+            ["0:00:59", "0:01:00", "24:00:00", "100:00:00"]
+        );
+        assert_eq!(
+            boundaries(format(TimeFormat::HoursMinutesSeconds, true)),
+            ["0:00:59", "0:01:00", "1d 00:00:00", "4d 04:00:00"]
+        );
+    }
+
+    #[test]
+    fn formats_decimal_hours() {
+        for days in [false, true] {
+            assert_eq!(
+                boundaries(format(TimeFormat::DecimalHours, days)),
+                ["0.02h", "0.02h", "24.00h", "100.00h"]
+            );
+        }
+
+        assert_eq!(
+            format_seconds(6300, format(TimeFormat::DecimalHours, false)),
+            "1.75h"
+        );
+    }
+
+    #[test]
+    fn formats_negative_durations() {
+        assert_eq!(
+            format_seconds(-90 * 60, format(TimeFormat::HoursMinutes, false)),
+            "-1:30"
+        );
+        assert_eq!(
+            format_seconds(-25 * 3600, format(TimeFormat::HoursMinutes, true)),
+            "-1d 01:00"
+        );
+        assert_eq!(
+            format_seconds(-6300, format(TimeFormat::DecimalHours, false)),
+            "-1.75h"
+        );
+    }
+
+    #[test]
+    fn formats_without_padding() {
+        for time in [
+            TimeFormat::HoursMinutes,
+            TimeFormat::HoursMinutesSeconds,
+            TimeFormat::DecimalHours,
+        ] {
+            let text = format_duration(Duration::from_secs(3600), format(time, false));
+
+            assert_eq!(text, text.trim());
+        }
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn days(from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        days_in_range(
+            start_of_day(from),
+            start_of_day(to) + chrono::Duration::hours(23),
+        )
+    }
+
+    #[test]
+    fn day_list_crosses_the_year_end() {
+        assert_eq!(
+            days(date(2022, 12, 30), date(2023, 1, 2)),
+            [
+                date(2022, 12, 30),
+                date(2022, 12, 31),
+                date(2023, 1, 1),
+                date(2023, 1, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn day_list_follows_leap_februaries() {
+        assert_eq!(
+            days(date(2024, 2, 27), date(2024, 3, 1)),
+            [
+                date(2024, 2, 27),
+                date(2024, 2, 28),
+                date(2024, 2, 29),
+                date(2024, 3, 1),
+            ]
+        );
+        assert_eq!(
+            days(date(2023, 2, 27), date(2023, 3, 1)),
+            [date(2023, 2, 27), date(2023, 2, 28), date(2023, 3, 1)]
+        );
+        assert_eq!(days(date(2024, 1, 1), date(2024, 12, 31)).len(), 366);
+        assert_eq!(days(date(2100, 1, 1), date(2100, 12, 31)).len(), 365);
+    }
+
+    #[test]
+    fn day_list_of_one_day_or_reversed_range() {
+        let day = start_of_day(date(2024, 2, 29));
+
+        assert_eq!(days_in_range(day, day), [date(2024, 2, 29)]);
+        assert!(days_in_range(day + chrono::Duration::days(1), day).is_empty());
+    }
+
+    #[test]
+    fn short_text_is_not_truncated() {
+        assert_eq!(truncate_middle("Navbar", 6), "Navbar");
+        assert_eq!(truncate_middle("Navbar", 20), "Navbar");
+        assert_eq!(truncate_middle("", 0), "");
+        assert_eq!(truncate_middle("Navbar", 0), "");
+        assert_eq!(truncate_middle("Navbar", 1), "…");
+    }
+
+    #[test]
+    fn truncation_keeps_both_ends() {
+        assert_eq!(truncate_middle("Client/Website/Navbar", 9), "Clie…vbar");
+        assert_eq!(truncate_middle("Client/Website/Navbar", 8), "Clie…bar");
+    }
+
+    #[test]
+    fn truncation_keeps_combining_marks_with_their_letter() {
+        // Every letter carries a combining acute accent.
+        let text = "a\u{301}e\u{301}i\u{301}o\u{301}u\u{301}y\u{301}";
 
-    rt.signed_duration_since(rf).num_days() as u32
+        assert_eq!(
+            truncate_middle(text, 5),
+            "a\u{301}e\u{301}…u\u{301}y\u{301}"
+        );
+    }
+
+    #[test]
+    fn truncation_keeps_emoji_sequences_whole() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let flag = "🇩🇪";
+        let thumb = "👍🏽";
+        let text = format!("{family}{thumb}ab{flag}{thumb}");
+
+        assert_eq!(
+            truncate_middle(&text, 4),
+            format!("{family}{thumb}…{thumb}")
+        );
+        assert_eq!(truncate_middle(&text, 3), format!("{family}…{thumb}"));
+    }
+
+    #[test]
+    fn truncation_of_rtl_text_works_in_logical_order() {
+        let text = "שלום עולם";
+
+        assert_eq!(truncate_middle(text, 5), "של…לם");
+        assert_eq!(truncate_middle("مرحبا بالعالم", 7), "مرح…الم");
+    }
+
+    fn wall(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<chrono::Utc> {
+        chrono::Utc.from_utc_datetime(&wall(y, m, d, h, min))
+    }
+
+    #[test]
+    fn skipped_times_roll_forward() {
+        let berlin = chrono_tz::Europe::Berlin;
+
+        // 2:00 to 3:00 does not exist on the night clocks go forward.
+        let resolved = resolve_local(&berlin, wall(2024, 3, 31, 2, 30));
+
+        assert_eq!(resolved.naive_local(), wall(2024, 3, 31, 3, 0));
+        assert_eq!(resolved, utc(2024, 3, 31, 1, 0));
+
+        // Midnight itself is skipped.
+        let sao_paulo = chrono_tz::America::Sao_Paulo;
+        let resolved = resolve_local(&sao_paulo, wall(2018, 11, 4, 0, 0));
+
+        assert_eq!(resolved.naive_local(), wall(2018, 11, 4, 1, 0));
+    }
+
+    #[test]
+    fn repeated_times_take_the_earliest_instant() {
+        let berlin = chrono_tz::Europe::Berlin;
+
+        // 2:00 to 3:00 happens twice on the night clocks go back, first in summer time.
+        assert_eq!(
+            resolve_local(&berlin, wall(2024, 10, 27, 2, 30)),
+            utc(2024, 10, 27, 0, 30)
+        );
+        assert_eq!(
+            resolve_local(&berlin, wall(2024, 10, 27, 3, 0)),
+            utc(2024, 10, 27, 2, 0)
+        );
+    }
+
+    #[test]
+    fn ordinary_times_resolve_as_they_are() {
+        let berlin = chrono_tz::Europe::Berlin;
+
+        for naive in [
+            wall(2024, 3, 31, 1, 59),
+            wall(2024, 7, 1, 12, 0),
+            wall(2024, 12, 31, 0, 0),
+        ] {
+            assert_eq!(resolve_local(&berlin, naive).naive_local(), naive);
+            assert_eq!(to_local(naive).naive_local(), naive);
+        }
+    }
+
+    #[test]
+    fn fuzzy_score_needs_every_character_in_order() {
+        assert!(fuzzy_score("nav", "Client / Website / Navbar").is_some());
+        assert!(fuzzy_score("NAVBAR", "client / website / navbar").is_some());
+        assert!(fuzzy_score("web nav", "Client / Website / Navbar").is_some());
+        assert!(fuzzy_score("", "Client").is_some());
+
+        assert_eq!(fuzzy_score("vna", "Navbar"), None);
+        assert_eq!(fuzzy_score("navbars", "Navbar"), None);
+        assert_eq!(fuzzy_score("x", ""), None);
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_adjacent_characters_and_word_starts() {
+        let score = |query| fuzzy_score(query, "Client / Website / Navbar").unwrap();
+
+        // Word starts beat the same letters inside words.
+        assert!(score("cwn") > score("lbv"));
+        // Adjacent characters beat scattered ones.
+        assert!(score("nav") > score("nvr"));
+        // A match near the start beats one far along the text.
+        assert!(fuzzy_score("nav", "Navbar").unwrap() > fuzzy_score("nav", "x Navbar").unwrap());
+
+        let mut texts = [
+            "Home / Garden / Mow",
+            "Client / Website / Navbar",
+            "Nova / Art / Vase",
+        ];
+        texts.sort_by_key(|v| std::cmp::Reverse(fuzzy_score("navbar", v)));
+
+        assert_eq!(texts[0], "Client / Website / Navbar");
+        assert_eq!(fuzzy_score("navbar", "Home / Garden / Mow"), None);
+    }
+
+    #[test]
+    fn export_timestamps_carry_the_offset_or_utc() {
+        let berlin = chrono_tz::Europe::Berlin;
+        // Before and after clocks went forward on 2024-03-31 at 02:00.
+        let before = berlin.with_ymd_and_hms(2024, 3, 31, 1, 30, 0).unwrap();
+        let after = berlin.with_ymd_and_hms(2024, 3, 31, 3, 30, 0).unwrap();
+
+        assert_eq!(
+            export_timestamp(&before, ExportZone::Local),
+            "2024-03-31T01:30:00+01:00"
+        );
+        assert_eq!(
+            export_timestamp(&after, ExportZone::Local),
+            "2024-03-31T03:30:00+02:00"
+        );
+        assert_eq!(
+            export_timestamp(&before, ExportZone::Utc),
+            "2024-03-31T00:30:00Z"
+        );
+        assert_eq!(
+            export_timestamp(&after, ExportZone::Utc),
+            "2024-03-31T01:30:00Z"
+        );
+
+        // An hour apart either way.
+        let parse = |v: &str| DateTime::parse_from_rfc3339(v).unwrap();
+
+        for zone in [ExportZone::Local, ExportZone::Utc] {
+            assert_eq!(
+                parse(&export_timestamp(&after, zone)) - parse(&export_timestamp(&before, zone)),
+                chrono::Duration::hours(1)
+            );
+        }
+    }
+
+    #[test]
+    fn export_zone_names_both_offsets_across_a_dst_change() {
+        let berlin = chrono_tz::Europe::Berlin;
+        let day =
+            |d| ExportZone::Local.day_start(&berlin, NaiveDate::from_ymd_opt(2024, 3, d).unwrap());
+
+        assert_eq!(
+            ExportZone::Local.name((&day(25), &day(30))),
+            "local time, UTC+01:00"
+        );
+        assert_eq!(
+            ExportZone::Local.name((&day(25), &day(31).with_hour(12).unwrap())),
+            "local time, UTC+01:00 to UTC+02:00"
+        );
+        assert_eq!(ExportZone::Utc.name((&day(25), &day(31))), "UTC");
+    }
+
+    #[test]
+    fn export_zone_cuts_days_in_its_zone() {
+        let berlin = chrono_tz::Europe::Berlin;
+        let day = NaiveDate::from_ymd_opt(2024, 7, 1).unwrap();
+
+        assert_eq!(
+            ExportZone::Local.day_start(&berlin, day),
+            berlin.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            ExportZone::Utc.day_start(&berlin, day),
+            berlin.with_ymd_and_hms(2024, 7, 1, 2, 0, 0).unwrap()
+        );
+
+        assert_eq!(ExportZone::parse(" UTC "), Some(ExportZone::Utc));
+        assert_eq!(ExportZone::parse("local"), Some(ExportZone::Local));
+        assert_eq!(ExportZone::parse("gmt"), None);
+    }
 }
@@ -0,0 +1,163 @@
+//! Title of the focused window, sampled to suggest what to track.
+//!
+//! X11 is read through `xprop` and Windows through user32. Wayland has no common way to tell
+//! the focused window, there and on other platforms no title is ever read.
+
+use eframe::egui;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::time::Duration;
+
+const SAMPLE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Samples the title on its own thread while it is alive.
+pub struct TitleWatcher {
+    /// The title whenever it changed, `None` when none could be read.
+    pub(crate) titles: Receiver<Option<String>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl TitleWatcher {
+    pub fn spawn(ctx: egui::Context) -> Self {
+        let (sender, titles) = std::sync::mpsc::channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stop = stopped.clone();
+
+        std::thread::spawn(move || {
+            let mut last = None;
+
+            while !stop.load(Ordering::Relaxed) {
+                let title = active_title();
+
+                if title != last {
+                    if sender.send(title.clone()).is_err() {
+                        return;
+                    }
+
+                    ctx.request_repaint();
+                    last = title;
+                }
+
+                std::thread::sleep(SAMPLE_PERIOD);
+            }
+        });
+
+        Self { titles, stopped }
+    }
+}
+
+impl Drop for TitleWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Whether `title` fits `pattern`, ignoring case. `*` stands for any text and `?` for one
+/// character, a pattern without either only has to appear somewhere in the title.
+pub fn matches(pattern: &str, title: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    let title = title.to_lowercase();
+
+    if pattern.is_empty() {
+        return false;
+    }
+
+    if !pattern.contains(['*', '?']) {
+        return title.contains(&pattern);
+    }
+
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let title = title.chars().collect::<Vec<_>>();
+
+    // Whether the pattern so far matches each prefix of the title.
+    let mut row = vec![false; title.len() + 1];
+    row[0] = true;
+
+    for p in pattern {
+        let mut next = vec![false; title.len() + 1];
+
+        for i in 0..=title.len() {
+            next[i] = match p {
+                '*' => row[i] || (i > 0 && next[i - 1]),
+                '?' => i > 0 && row[i - 1],
+                c => i > 0 && row[i - 1] && title[i - 1] == c,
+            };
+        }
+
+        row = next;
+    }
+
+    row[title.len()]
+}
+
+pub fn active_title() -> Option<String> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    return x11_title();
+
+    #[cfg(windows)]
+    return win32_title();
+
+    #[cfg(not(any(all(unix, not(target_os = "macos")), windows)))]
+    None
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn x11_title() -> Option<String> {
+    std::env::var_os("DISPLAY")?;
+
+    let xprop = |args: &[&str]| {
+        let output = std::process::Command::new("xprop")
+            .args(args)
+            .stderr(std::process::Stdio::null())
+            .output()
+            .ok()?;
+
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    // "_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007"
+    let active = xprop(&["-root", "_NET_ACTIVE_WINDOW"])?;
+    let id = active.rsplit(' ').next()?;
+
+    if !id.starts_with("0x") || id == "0x0" {
+        return None;
+    }
+
+    // "_NET_WM_NAME(UTF8_STRING) = \"title\"", or "WM_NAME(STRING) = ..." for old clients.
+    let names = xprop(&["-id", id, "_NET_WM_NAME", "WM_NAME"])?;
+
+    names.lines().find_map(|line| {
+        let (_, value) = line.split_once(" = ")?;
+        let value = value.strip_prefix('"')?.strip_suffix('"')?;
+
+        Some(value.replace("\\\"", "\"").replace("\\\\", "\\"))
+    })
+}
+
+#[cfg(windows)]
+fn win32_title() -> Option<String> {
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetForegroundWindow() -> isize;
+        fn GetWindowTextW(window: isize, text: *mut u16, max_count: i32) -> i32;
+    }
+
+    let mut text = [0u16; 512];
+
+    // SAFETY: the buffer outlives the call and its length is passed along.
+    let len = unsafe {
+        let window = GetForegroundWindow();
+
+        if window == 0 {
+            return None;
+        }
+
+        GetWindowTextW(window, text.as_mut_ptr(), text.len() as i32)
+    };
+
+    (len > 0).then(|| String::from_utf16_lossy(&text[..len as usize]))
+}